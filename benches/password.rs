@@ -0,0 +1,59 @@
+//! 密码生成与验证性能基准
+//!
+//! 运行：`cargo bench --bench password`
+
+use chrono::{TimeZone, Utc};
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+use nekohouse_v3_visitor_registration::gen_password::{
+    KeeLoqCrypto, KeeLoqKey, ManualClock, PasswordType, UnifiedPasswordGenerator,
+};
+
+const ADMIN_PWD: &str = "123456";
+
+/// KeeLoq 单次加解密
+fn bench_keeloq(c: &mut Criterion) {
+    let crypto = KeeLoqCrypto::new();
+    let key = KeeLoqKey::new(ADMIN_PWD);
+
+    let mut group = c.benchmark_group("keeloq");
+    group.bench_function("crypt_usercode", |b| {
+        b.iter(|| crypto.crypt_usercode(black_box(429_306_000), black_box(ADMIN_PWD)))
+    });
+    group.bench_function("encrypt", |b| b.iter(|| crypto.encrypt(black_box(429_306_000), &key)));
+    group.bench_function("decrypt", |b| b.iter(|| crypto.decrypt(black_box(1_234_567_890), &key)));
+    group.finish();
+}
+
+/// 各类型密码的生成、验证和检查
+///
+/// 验证和检查走统一入口，与机器人实际使用的路径一致：非临时密码需要先排除排在前面的类型。
+fn bench_password_types(c: &mut Criterion) {
+    let now = Utc.with_ymd_and_hms(2024, 6, 1, 12, 0, 0).unwrap();
+    let generator = UnifiedPasswordGenerator::new().with_clock(ManualClock::new(now).shared());
+
+    let password_types = [
+        ("temporary", PasswordType::Temporary),
+        ("times", PasswordType::Times(5)),
+        ("limited", PasswordType::Limited(2, 30)),
+        ("period", PasswordType::Period(2024, 6, 8, 18)),
+    ];
+
+    for (name, password_type) in password_types {
+        let password = generator.generate(ADMIN_PWD, password_type.clone()).unwrap().password;
+
+        let mut group = c.benchmark_group(name);
+        group.bench_function("generate", |b| {
+            b.iter(|| generator.generate(black_box(ADMIN_PWD), password_type.clone()).unwrap())
+        });
+        group.bench_function("verify", |b| {
+            b.iter(|| generator.verify(black_box(&password), black_box(ADMIN_PWD)).unwrap())
+        });
+        group.bench_function("inspect", |b| {
+            b.iter(|| generator.inspect(black_box(&password), black_box(ADMIN_PWD), now))
+        });
+        group.finish();
+    }
+}
+
+criterion_group!(benches, bench_keeloq, bench_password_types);
+criterion_main!(benches);
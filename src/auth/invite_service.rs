@@ -0,0 +1,244 @@
+//! 邀请码服务模块 - 处理邀请码的创建、撤销和使用
+
+use crate::database::{AuditRepository, Database, InviteRepository, RecordRepository};
+use crate::error::Result;
+use crate::types::{AuditAction, AuditEvent, AuthPreset, Invite, InviteState, Record};
+use crate::utils::gen_password::{SharedClock, SystemClock};
+use chrono::Duration;
+
+/// 创建邀请码的参数
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct InviteOptions {
+    /// 名称
+    pub label: Option<String>,
+    /// 最大使用次数
+    pub max_uses: Option<i64>,
+    /// 有效期
+    pub validity: Option<Duration>,
+    /// 预设授权，设置后访客申请时自动批准
+    pub preset: Option<AuthPreset>,
+}
+
+/// 邀请码服务
+#[derive(Clone)]
+pub struct InviteService {
+    database: Database,
+    clock: SharedClock,
+}
+
+impl InviteService {
+    /// 创建新的邀请码服务实例
+    pub fn new(database: Database) -> Self {
+        Self {
+            database,
+            clock: SystemClock::shared(),
+        }
+    }
+
+    /// 使用指定的时钟判断邀请码是否过期
+    pub fn with_clock(mut self, clock: SharedClock) -> Self {
+        self.clock = clock;
+        self
+    }
+
+    /// 为管理员创建邀请码
+    pub async fn create(&self, actor_id: i64, admin_id: i64, options: &InviteOptions) -> Result<Invite> {
+        let mut tx = self.database.begin_transaction().await?;
+        let invite = Self::create_in_tx(&mut tx, actor_id, admin_id, options).await?;
+        tx.commit().await?;
+
+        log::info!("管理员 {} 创建了邀请码 #{}", admin_id, invite.unique_id);
+        Ok(invite)
+    }
+
+    async fn create_in_tx(
+        tx: &mut sqlx::Transaction<'_, sqlx::Sqlite>,
+        actor_id: i64,
+        admin_id: i64,
+        options: &InviteOptions,
+    ) -> Result<Invite> {
+        let mut invite = Invite::new(admin_id);
+        invite.label = options.label.clone();
+        invite.max_uses = options.max_uses;
+        invite.expires_at = options.validity.map(|validity| invite.created_at + validity);
+        invite.preset = options.preset.clone();
+        invite.unique_id = InviteRepository::create(tx, &invite).await?;
+
+        let event = AuditEvent::new(actor_id, AuditAction::CreateInvite)
+            .with_detail(format!("#{} {}", invite.unique_id, invite.display_name()));
+        AuditRepository::record(tx, &event).await?;
+
+        Ok(invite)
+    }
+
+    /// 获取管理员的所有邀请码
+    pub async fn list_by_admin(&self, admin_id: i64) -> Result<Vec<Invite>> {
+        InviteRepository::find_by_admin(self.database.pool(), admin_id).await
+    }
+
+    /// 通过邀请码查找
+    pub async fn find_by_code(&self, code: &str) -> Result<Option<Invite>> {
+        InviteRepository::find_by_code(self.database.pool(), code).await
+    }
+
+    /// 撤销管理员自己的邀请码
+    pub async fn revoke(&self, actor_id: i64, admin_id: i64, invite_id: i64) -> Result<bool> {
+        let invite = match InviteRepository::find_by_id(self.database.pool(), invite_id).await? {
+            Some(invite) if invite.admin_id == admin_id => invite,
+            _ => return Ok(false),
+        };
+
+        let mut tx = self.database.begin_transaction().await?;
+        let revoked = InviteRepository::revoke(&mut tx, invite_id).await?;
+        if revoked {
+            let event = AuditEvent::new(actor_id, AuditAction::RevokeInvite)
+                .with_detail(format!("#{} {}", invite.unique_id, invite.display_name()));
+            AuditRepository::record(&mut tx, &event).await?;
+        }
+        tx.commit().await?;
+
+        Ok(revoked)
+    }
+
+    /// 更换邀请码：撤销原邀请码并以相同的名称、次数、有效时长和预设授权创建新邀请码
+    ///
+    /// 邀请码不存在、不属于该管理员或已被撤销时返回None。
+    pub async fn regenerate(&self, actor_id: i64, admin_id: i64, invite_id: i64) -> Result<Option<Invite>> {
+        let old = match InviteRepository::find_by_id(self.database.pool(), invite_id).await? {
+            Some(invite) if invite.admin_id == admin_id && invite.state() != InviteState::Revoked => invite,
+            _ => return Ok(None),
+        };
+
+        let options = InviteOptions {
+            label: old.label.clone(),
+            max_uses: old.max_uses,
+            validity: old.expires_at.map(|expires_at| expires_at - old.created_at),
+            preset: old.preset.clone(),
+        };
+
+        let mut tx = self.database.begin_transaction().await?;
+        if InviteRepository::revoke(&mut tx, invite_id).await? {
+            let event = AuditEvent::new(actor_id, AuditAction::RevokeInvite)
+                .with_detail(format!("#{} {}", old.unique_id, old.display_name()));
+            AuditRepository::record(&mut tx, &event).await?;
+        }
+        let invite = Self::create_in_tx(&mut tx, actor_id, admin_id, &options).await?;
+        tx.commit().await?;
+
+        Ok(Some(invite))
+    }
+
+    /// 使用邀请码创建待审批的访客记录
+    ///
+    /// 邀请码不可用（已撤销、过期或次数用完）时返回None，不创建记录。
+    pub async fn create_request(&self, invite: &Invite, vis_id: i64, vis_name: &str) -> Result<Option<i64>> {
+        if invite.expires_at.is_some_and(|expires_at| self.clock.now() > expires_at) {
+            return Ok(None);
+        }
+
+        let mut tx = self.database.begin_transaction().await?;
+        if !InviteRepository::redeem(&mut tx, invite.unique_id).await? {
+            return Ok(None);
+        }
+
+        let record = Record::new(vis_id, invite.admin_id)
+            .with_invite(invite.unique_id)
+            .with_visitor_name(vis_name);
+        let record_id = RecordRepository::create(&mut tx, &record).await?;
+        tx.commit().await?;
+
+        Ok(Some(record_id))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::database::AdminRepository;
+    use crate::types::{Admin, AuthType};
+    use tempfile::NamedTempFile;
+
+    /// 返回的临时文件需在测试期间保持存活，否则数据库文件会被提前删除
+    async fn setup_test_service() -> Result<(InviteService, Database, i64, NamedTempFile)> {
+        let temp_file = NamedTempFile::new()?;
+        let db_url = format!("sqlite:{}", temp_file.path().to_str().unwrap());
+        let database = Database::new(&db_url).await?;
+
+        let mut tx = database.begin_transaction().await?;
+        let admin_id = AdminRepository::create(&mut tx, &Admin::new(123456789)).await?;
+        tx.commit().await?;
+
+        Ok((InviteService::new(database.clone()), database, admin_id, temp_file))
+    }
+
+    #[tokio::test]
+    async fn test_create_request_enforces_limits() -> Result<()> {
+        let (service, db, admin_id, _temp_file) = setup_test_service().await?;
+
+        let options = InviteOptions {
+            label: Some("家人".to_string()),
+            max_uses: Some(1),
+            validity: Some(Duration::days(7)),
+            preset: None,
+        };
+        let invite = service.create(123456789, admin_id, &options).await?;
+        assert!(invite.expires_at.is_some());
+
+        let record_id = service.create_request(&invite, 987654321, "访客甲").await?;
+        assert!(record_id.is_some());
+        let record = RecordRepository::find_by_id(db.pool(), record_id.unwrap()).await?.unwrap();
+        assert_eq!(record.invite_id, Some(invite.unique_id));
+        assert_eq!(record.vis_name.as_deref(), Some("访客甲"));
+        assert_eq!(record.inviter, admin_id);
+
+        // 次数用完
+        assert_eq!(service.create_request(&invite, 555666777, "访客乙").await?, None);
+        let invite = service.find_by_code(&invite.code).await?.unwrap();
+        assert_eq!(invite.state(), InviteState::Exhausted);
+
+        // 过期
+        let expired = InviteOptions { validity: Some(Duration::seconds(-1)), ..Default::default() };
+        let invite = service.create(123456789, admin_id, &expired).await?;
+        assert_eq!(service.create_request(&invite, 555666777, "访客乙").await?, None);
+
+        db.close().await;
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_revoke_and_regenerate() -> Result<()> {
+        let (service, db, admin_id, _temp_file) = setup_test_service().await?;
+
+        let mut preset = AuthPreset::new(AuthType::Times);
+        preset.times = Some(2);
+        let options = InviteOptions {
+            label: Some("保洁".to_string()),
+            max_uses: Some(5),
+            validity: Some(Duration::hours(12)),
+            preset: Some(preset.clone()),
+        };
+        let invite = service.create(123456789, admin_id, &options).await?;
+
+        // 不能撤销其他管理员的邀请码
+        assert!(!service.revoke(111, admin_id + 1, invite.unique_id).await?);
+
+        let renewed = service.regenerate(123456789, admin_id, invite.unique_id).await?.unwrap();
+        assert_ne!(renewed.code, invite.code);
+        assert_eq!(renewed.label.as_deref(), Some("保洁"));
+        assert_eq!(renewed.max_uses, Some(5));
+        assert_eq!(renewed.expires_at.map(|t| t - renewed.created_at), Some(Duration::hours(12)));
+        assert_eq!(renewed.preset, Some(preset));
+
+        let old = service.find_by_code(&invite.code).await?.unwrap();
+        assert_eq!(old.state(), InviteState::Revoked);
+        assert_eq!(service.create_request(&old, 987654321, "访客甲").await?, None);
+        // 已撤销的邀请码不能再更换出新邀请码
+        assert!(service.regenerate(123456789, admin_id, invite.unique_id).await?.is_none());
+
+        assert!(service.revoke(123456789, admin_id, renewed.unique_id).await?);
+        assert!(!service.revoke(123456789, admin_id, renewed.unique_id).await?);
+
+        db.close().await;
+        Ok(())
+    }
+}
@@ -0,0 +1,167 @@
+//! 门锁服务模块 - 管理门锁及访客记录与门锁的关联
+
+use crate::config::AppConfig;
+use crate::database::{AuditRepository, Database, LockRepository, RecordRepository};
+use crate::error::Result;
+use crate::types::{AuditAction, AuditEvent, AuthStatus, AuthType, Lock, DEFAULT_LOCK_NAME};
+
+/// 门锁服务
+#[derive(Clone)]
+pub struct LockService {
+    database: Database,
+}
+
+impl LockService {
+    /// 创建新的门锁服务实例
+    pub fn new(database: Database) -> Self {
+        Self { database }
+    }
+
+    /// 还没有门锁时按配置文件的时间偏移和时区创建默认门锁，返回创建的门锁ID
+    pub async fn ensure_default(&self, config: &AppConfig) -> Result<Option<i64>> {
+        let lock = Lock::new(DEFAULT_LOCK_NAME)
+            .with_time_offset(config.time_offset)
+            .with_timezone(config.lock_timezone);
+
+        let mut tx = self.database.begin_transaction().await?;
+        let created = LockRepository::ensure_default(&mut tx, &lock).await?;
+        tx.commit().await?;
+
+        if let Some(lock_id) = created {
+            log::info!("已根据配置创建默认门锁 #{}，已有记录均关联到该门锁", lock_id);
+        }
+        Ok(created)
+    }
+
+    /// 获取所有门锁（默认门锁在前）
+    pub async fn list(&self) -> Result<Vec<Lock>> {
+        LockRepository::find_all(self.database.pool()).await
+    }
+
+    /// 通过名称查找门锁
+    pub async fn find_by_name(&self, name: &str) -> Result<Option<Lock>> {
+        LockRepository::find_by_name(self.database.pool(), name).await
+    }
+
+    /// 获取访客记录关联的门锁
+    pub async fn locks_for_record(&self, record_id: i64) -> Result<Vec<Lock>> {
+        LockRepository::find_by_record(self.database.pool(), record_id).await
+    }
+
+    /// 保存门锁（`unique_id` 为0时新建）并写入审计日志，返回保存后的门锁
+    pub async fn save(&self, actor_id: i64, lock: &Lock) -> Result<Lock> {
+        let mut saved = lock.clone();
+        let mut tx = self.database.begin_transaction().await?;
+
+        if saved.unique_id == 0 {
+            saved.unique_id = LockRepository::create(&mut tx, &saved).await?;
+        } else {
+            LockRepository::update(&mut tx, &saved).await?;
+        }
+
+        let event = AuditEvent::new(actor_id, AuditAction::EditLock).with_detail(format!(
+            "#{} {}：时间偏移 {} 秒，时区 {}，授权类型 {}",
+            saved.unique_id,
+            saved.name,
+            saved.time_offset,
+            saved.timezone,
+            AuthType::join_list(&saved.auth_types)
+        ));
+        AuditRepository::record(&mut tx, &event).await?;
+
+        tx.commit().await?;
+        log::info!("用户 {} 保存了门锁 #{} {}", actor_id, saved.unique_id, saved.name);
+        Ok(saved)
+    }
+
+    /// 切换待审批记录是否关联门锁，返回切换后是否关联；记录不存在或已处理时返回None
+    pub async fn toggle_record_lock(&self, record_id: i64, lock_id: i64) -> Result<Option<bool>> {
+        let mut tx = self.database.begin_transaction().await?;
+
+        let pending = RecordRepository::find_by_id_in_tx(&mut tx, record_id)
+            .await?
+            .is_some_and(|record| record.status == AuthStatus::Pending);
+        if !pending {
+            return Ok(None);
+        }
+
+        let linked = if LockRepository::unlink_record(&mut tx, record_id, lock_id).await? {
+            false
+        } else {
+            LockRepository::link_record(&mut tx, record_id, lock_id).await?
+        };
+
+        tx.commit().await?;
+        Ok(Some(linked))
+    }
+
+    /// 关联访客记录和一组门锁
+    pub async fn link_record(&self, record_id: i64, lock_ids: &[i64]) -> Result<()> {
+        let mut tx = self.database.begin_transaction().await?;
+        for &lock_id in lock_ids {
+            LockRepository::link_record(&mut tx, record_id, lock_id).await?;
+        }
+        tx.commit().await?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::database::{AdminRepository, AuditFilter};
+    use crate::types::{Admin, Record};
+    use tempfile::NamedTempFile;
+
+    #[tokio::test]
+    async fn test_save_and_toggle_locks() -> Result<()> {
+        let temp_file = NamedTempFile::new()?;
+        let db_url = format!("sqlite:{}", temp_file.path().to_str().unwrap());
+        let database = Database::new(&db_url).await?;
+        let service = LockService::new(database.clone());
+
+        let mut config = AppConfig::default();
+        config.time_offset = 120;
+        assert!(service.ensure_default(&config).await?.is_some());
+        assert!(service.ensure_default(&config).await?.is_none());
+
+        let gate = service.save(123456789, &Lock::new("大门").with_time_offset(-30)).await?;
+        assert!(gate.unique_id > 0);
+
+        let locks = service.list().await?;
+        assert_eq!(locks.len(), 2);
+        assert_eq!(locks[0].name, DEFAULT_LOCK_NAME);
+        assert_eq!(locks[0].time_offset, 120);
+
+        // 修改已有门锁并记录审计日志
+        let mut updated = gate.clone();
+        updated.auth_types = vec![AuthType::Temp];
+        service.save(123456789, &updated).await?;
+        assert_eq!(service.find_by_name("大门").await?.unwrap().auth_types, vec![AuthType::Temp]);
+
+        let events = AuditRepository::query(database.pool(), &AuditFilter::default(), 10, 0).await?;
+        assert_eq!(events.len(), 2);
+        assert_eq!(events[0].action, AuditAction::EditLock);
+        assert!(events[0].detail.as_deref().unwrap().contains("授权类型 temp"));
+
+        // 只有待审批的记录可以切换门锁
+        let mut tx = database.begin_transaction().await?;
+        let admin_id = AdminRepository::create(&mut tx, &Admin::new(123456789)).await?;
+        let record_id = RecordRepository::create(&mut tx, &Record::new(987654321, admin_id)).await?;
+        tx.commit().await?;
+
+        assert_eq!(service.toggle_record_lock(record_id, gate.unique_id).await?, Some(true));
+        assert_eq!(service.locks_for_record(record_id).await?.len(), 1);
+        assert_eq!(service.toggle_record_lock(record_id, gate.unique_id).await?, Some(false));
+        assert!(service.locks_for_record(record_id).await?.is_empty());
+        assert_eq!(service.toggle_record_lock(record_id + 1, gate.unique_id).await?, None);
+
+        let mut tx = database.begin_transaction().await?;
+        RecordRepository::update_status(&mut tx, record_id, AuthStatus::Rejected).await?;
+        tx.commit().await?;
+        assert_eq!(service.toggle_record_lock(record_id, gate.unique_id).await?, None);
+
+        database.close().await;
+        Ok(())
+    }
+}
@@ -1,8 +1,14 @@
 //! 认证和授权模块
 
+pub mod invite_service;
+pub mod lock_service;
 pub mod password_service;
+pub mod record_service;
 pub mod user_service;
 
 // 重新导出主要组件
+pub use invite_service::{InviteOptions, InviteService};
+pub use lock_service::LockService;
 pub use password_service::PasswordService;
-pub use user_service::UserService;
\ No newline at end of file
+pub use record_service::RecordService;
+pub use user_service::{AdminRemoval, AdminRemovalSummary, UserService};
\ No newline at end of file
@@ -2,16 +2,16 @@
 
 use crate::config::AppConfig;
 use crate::error::{AppError, Result};
-use crate::types::{AuthType, PasswordRequest};
+use crate::types::{AuthPreset, AuthType, Lock, PasswordRequest};
 use crate::utils::gen_password::{
-    UnifiedPasswordGenerator, PasswordType, PasswordResult,
+    UnifiedPasswordGenerator, PasswordInspection, PasswordType, PasswordResult, SharedClock, SystemClock,
 };
 use chrono::{DateTime, Utc};
 use std::collections::HashMap;
 
 /// 密码服务
 pub struct PasswordService {
-    _generator: UnifiedPasswordGenerator,
+    clock: SharedClock,
     /// 用于跟踪长期临时密码的最后生成时间
     longtime_temp_cache: HashMap<i64, DateTime<Utc>>,
 }
@@ -20,52 +20,104 @@ impl PasswordService {
     /// 创建新的密码服务实例
     pub fn new() -> Self {
         Self {
-            _generator: UnifiedPasswordGenerator::new(),
+            clock: SystemClock::shared(),
             longtime_temp_cache: HashMap::new(),
         }
     }
 
-    /// 生成密码
+    /// 使用指定的时钟获取当前时间
+    pub fn with_clock(mut self, clock: SharedClock) -> Self {
+        self.clock = clock;
+        self
+    }
+
+    /// 创建使用服务时钟、门锁时间偏移和时区的生成器
+    fn generator(&self, lock: &Lock) -> UnifiedPasswordGenerator {
+        UnifiedPasswordGenerator::new()
+            .with_clock(self.clock.clone())
+            .with_time_offset(lock.time_offset)
+            .with_timezone(lock.timezone)
+    }
+
+    /// 为指定门锁生成密码
     pub fn generate_password(
         &mut self,
         request: &PasswordRequest,
-        config: &AppConfig,
+        lock: &Lock,
     ) -> Result<PasswordResult> {
         // 时间偏移不应该修改管理员密码，而是在时间戳计算时应用
         match request.auth_type {
-            AuthType::Temp => self.generate_temp_password(&request.admin_password, config.time_offset as i32),
-            AuthType::Times => self.generate_times_password(&request.admin_password, request.times, config.time_offset as i32),
-            AuthType::Limited => self.generate_limited_password(&request.admin_password, request.hours, request.minutes, config.time_offset as i32),
-            AuthType::Period => self.generate_period_password(&request.admin_password, request, config.time_offset as i32),
-            AuthType::LongtimeTemp => self.generate_longtime_temp_password(&request.admin_password, request, config.time_offset as i32),
+            AuthType::Temp => self.generate_temp_password(&request.admin_password, lock),
+            AuthType::Times => self.generate_times_password(&request.admin_password, request.times, lock),
+            AuthType::Limited => self.generate_limited_password(&request.admin_password, request.hours, request.minutes, lock),
+            AuthType::Period => self.generate_period_password(&request.admin_password, request, lock),
+            AuthType::LongtimeTemp => self.generate_longtime_temp_password(&request.admin_password, request, lock),
         }
     }
 
-    /// 验证密码
+    /// 验证密码当前是否被指定门锁接受
     pub fn verify_password(
         &self,
         password: &str,
         admin_password: &str,
-        config: &AppConfig,
+        lock: &Lock,
     ) -> Result<bool> {
         // 验证时也需要考虑时间偏移
-        Ok(self.verify_password_with_offset(password, admin_password, config.time_offset as i32).is_some())
+        Ok(self.generator(lock).verify(password, admin_password).is_some())
     }
 
-    /// 获取密码剩余有效时间
+    /// 获取密码在指定门锁上的剩余有效时间
     pub fn get_remaining_time(
         &self,
         password: &str,
         admin_password: &str,
-        config: &AppConfig,
+        lock: &Lock,
     ) -> Result<Option<String>> {
-        Ok(self.get_remaining_time_with_offset(password, admin_password, config.time_offset as i32))
+        Ok(self.generator(lock).get_remaining_time(password, admin_password))
+    }
+
+    /// 按指定门锁检查密码的类型、参数和有效期
+    ///
+    /// 门锁时间为真实时间加上时间偏移，返回的有效期已换算回真实时间，消息按门锁时区显示。
+    /// 需要搜索大量时间窗口，耗时较长，应在阻塞线程中调用。
+    pub fn inspect_password(
+        password: &str,
+        admin_password: &str,
+        lock: &Lock,
+        at: DateTime<Utc>,
+    ) -> PasswordInspection {
+        UnifiedPasswordGenerator::new()
+            .with_time_offset(lock.time_offset)
+            .with_timezone(lock.timezone)
+            .inspect(password, admin_password, at)
+    }
+
+    /// 在多个门锁上检查密码，返回匹配的门锁下标和检查结果
+    ///
+    /// 优先返回当前有效的匹配，其次返回第一个匹配；都不匹配时返回 `(None, NotOurs)`。
+    pub fn inspect_on_locks(
+        password: &str,
+        admin_password: &str,
+        locks: &[Lock],
+        at: DateTime<Utc>,
+    ) -> (Option<usize>, PasswordInspection) {
+        let mut found = (None, PasswordInspection::NotOurs);
+        for (index, lock) in locks.iter().enumerate() {
+            let inspection = Self::inspect_password(password, admin_password, lock, at);
+            match inspection {
+                PasswordInspection::Valid(_) => return (Some(index), inspection),
+                PasswordInspection::NotOurs => {}
+                _ if found.0.is_none() => found = (Some(index), inspection),
+                _ => {}
+            }
+        }
+        found
     }
 
     /// 检查长期临时密码是否可以生成（5分钟限制）
     pub fn can_generate_longtime_temp(&mut self, user_id: i64) -> bool {
         if let Some(last_generated) = self.longtime_temp_cache.get(&user_id) {
-            let now = Utc::now();
+            let now = self.clock.now();
             let elapsed = now.signed_duration_since(*last_generated);
             elapsed.num_minutes() >= 5
         } else {
@@ -75,12 +127,12 @@ impl PasswordService {
 
     /// 标记长期临时密码已生成
     pub fn mark_longtime_temp_generated(&mut self, user_id: i64) {
-        self.longtime_temp_cache.insert(user_id, Utc::now());
+        self.longtime_temp_cache.insert(user_id, self.clock.now());
     }
 
     /// 清理过期的长期临时密码缓存
     pub fn cleanup_longtime_temp_cache(&mut self) {
-        let now = Utc::now();
+        let now = self.clock.now();
         self.longtime_temp_cache.retain(|_, last_time| {
             let elapsed = now.signed_duration_since(*last_time);
             elapsed.num_minutes() < 60 // 保留1小时内的记录
@@ -93,25 +145,25 @@ impl PasswordService {
     }
 
     /// 生成临时密码（10分钟有效）
-    fn generate_temp_password(&self, admin_pwd: &str, time_offset: i32) -> Result<PasswordResult> {
-        let result = self.generate_password_with_offset(admin_pwd, PasswordType::Temporary, time_offset)?;
+    fn generate_temp_password(&self, admin_pwd: &str, lock: &Lock) -> Result<PasswordResult> {
+        let result = self.generate_password_for_lock(admin_pwd, PasswordType::Temporary, lock)?;
         Ok(result)
     }
 
     /// 生成次数限制密码（2小时有效）
-    fn generate_times_password(&self, admin_pwd: &str, times: Option<u32>, time_offset: i32) -> Result<PasswordResult> {
+    fn generate_times_password(&self, admin_pwd: &str, times: Option<u32>, lock: &Lock) -> Result<PasswordResult> {
         let use_times = times.ok_or_else(|| AppError::validation("次数密码必须指定使用次数"))?;
         
         if use_times < 1 || use_times > 31 {
             return Err(AppError::validation("使用次数必须在1-31之间"));
         }
 
-        let result = self.generate_password_with_offset(admin_pwd, PasswordType::Times(use_times), time_offset)?;
+        let result = self.generate_password_for_lock(admin_pwd, PasswordType::Times(use_times), lock)?;
         Ok(result)
     }
 
     /// 生成限时密码
-    fn generate_limited_password(&self, admin_pwd: &str, hours: Option<u32>, minutes: Option<u32>, time_offset: i32) -> Result<PasswordResult> {
+    fn generate_limited_password(&self, admin_pwd: &str, hours: Option<u32>, minutes: Option<u32>, lock: &Lock) -> Result<PasswordResult> {
         let hours = hours.ok_or_else(|| AppError::validation("限时密码必须指定小时数"))?;
         let minutes = minutes.unwrap_or(0);
 
@@ -123,12 +175,12 @@ impl PasswordService {
             return Err(AppError::validation("分钟数只能是0或30"));
         }
 
-        let result = self.generate_password_with_offset(admin_pwd, PasswordType::Limited(hours, minutes), time_offset)?;
+        let result = self.generate_password_for_lock(admin_pwd, PasswordType::Limited(hours, minutes), lock)?;
         Ok(result)
     }
 
     /// 生成周期密码（指定过期时间）
-    fn generate_period_password(&self, admin_pwd: &str, request: &PasswordRequest, time_offset: i32) -> Result<PasswordResult> {
+    fn generate_period_password(&self, admin_pwd: &str, request: &PasswordRequest, lock: &Lock) -> Result<PasswordResult> {
         let year = request.end_year.ok_or_else(|| AppError::validation("周期密码必须指定年份"))?;
         let month = request.end_month.ok_or_else(|| AppError::validation("周期密码必须指定月份"))?;
         let day = request.end_day.ok_or_else(|| AppError::validation("周期密码必须指定日期"))?;
@@ -151,132 +203,28 @@ impl PasswordService {
             return Err(AppError::validation("小时必须在0-23之间"));
         }
 
-        let result = self.generate_password_with_offset(admin_pwd, PasswordType::Period(year, month, day, hour), time_offset)?;
+        let result = self.generate_password_for_lock(admin_pwd, PasswordType::Period(year, month, day, hour), lock)?;
         Ok(result)
     }
 
     /// 生成长期临时密码（使用临时密码算法）
-    fn generate_longtime_temp_password(&self, admin_pwd: &str, _request: &PasswordRequest, time_offset: i32) -> Result<PasswordResult> {
+    fn generate_longtime_temp_password(&self, admin_pwd: &str, _request: &PasswordRequest, lock: &Lock) -> Result<PasswordResult> {
         // 长期临时密码实际上就是一个临时密码，但有特殊的使用限制
         // 在5分钟内只能生成一次，超过5分钟可以重新申请
-        let result = self.generate_password_with_offset(admin_pwd, PasswordType::Temporary, time_offset)?;
+        let result = self.generate_password_for_lock(admin_pwd, PasswordType::Temporary, lock)?;
         Ok(result)
     }
 
-    /// 使用时间偏移生成密码的内部方法
-    fn generate_password_with_offset(&self, admin_pwd: &str, password_type: PasswordType, time_offset: i32) -> Result<PasswordResult> {
-        // 创建带偏移的生成器实例
-        let temp_gen = crate::utils::gen_password::TempPasswordGeneratorWithOffset::new(time_offset);
-        let times_gen = crate::utils::gen_password::TimesPasswordGeneratorWithOffset::new(time_offset);
-        let limited_gen = crate::utils::gen_password::LimitedPasswordGeneratorWithOffset::new(time_offset);
-        let period_gen = crate::utils::gen_password::PeriodPasswordGeneratorWithOffset::new(time_offset);
-        
-        match password_type {
-            PasswordType::Temporary => {
-                let (password, expire_time, message) = temp_gen.generate(admin_pwd)
-                    .map_err(|e| AppError::password_generation(e))?;
-                Ok(PasswordResult {
-                    password,
-                    expire_time,
-                    message,
-                    password_type: PasswordType::Temporary,
-                })
-            }
-            PasswordType::Times(count) => {
-                let (password, expire_time, message) = times_gen.generate(admin_pwd, count)
-                    .map_err(|e| AppError::password_generation(e))?;
-                Ok(PasswordResult {
-                    password,
-                    expire_time,
-                    message,
-                    password_type: PasswordType::Times(count),
-                })
-            }
-            PasswordType::Limited(hours, minutes) => {
-                let (password, expire_time, message) = limited_gen.generate(admin_pwd, hours, minutes)
-                    .map_err(|e| AppError::password_generation(e))?;
-                Ok(PasswordResult {
-                    password,
-                    expire_time,
-                    message,
-                    password_type: PasswordType::Limited(hours, minutes),
-                })
-            }
-            PasswordType::Period(year, month, day, hour) => {
-                let (password, expire_time, message) = period_gen.generate(admin_pwd, year, month, day, hour)
-                    .map_err(|e| AppError::password_generation(e))?;
-                Ok(PasswordResult {
-                    password,
-                    expire_time,
-                    message,
-                    password_type: PasswordType::Period(year, month, day, hour),
-                })
-            }
-        }
-    }
-
-    /// 使用时间偏移验证密码
-    fn verify_password_with_offset(&self, password: &str, admin_pwd: &str, time_offset: i32) -> Option<PasswordType> {
-        // 创建带偏移的生成器实例进行验证
-        let temp_gen = crate::utils::gen_password::TempPasswordGeneratorWithOffset::new(time_offset);
-        let times_gen = crate::utils::gen_password::TimesPasswordGeneratorWithOffset::new(time_offset);
-        let limited_gen = crate::utils::gen_password::LimitedPasswordGeneratorWithOffset::new(time_offset);
-        let period_gen = crate::utils::gen_password::PeriodPasswordGeneratorWithOffset::new(time_offset);
-
-        // 尝试验证临时密码
-        if temp_gen.verify(password, admin_pwd, 150) {
-            return Some(PasswordType::Temporary);
-        }
-
-        // 尝试验证次数密码
-        if let Some(times) = times_gen.verify(password, admin_pwd, 0, 2) {
-            return Some(PasswordType::Times(times));
-        }
-
-        // 尝试验证限时密码
-        if let Some((hours, minutes)) = limited_gen.verify(password, admin_pwd, 2) {
-            return Some(PasswordType::Limited(hours, minutes));
-        }
-
-        // 尝试验证周期密码
-        if let Some(_expire_time) = period_gen.verify(password, admin_pwd, 1) {
-            return Some(PasswordType::Period(0, 0, 0, 0));
-        }
-
-        None
-    }
-
-    /// 使用时间偏移获取密码剩余时间
-    fn get_remaining_time_with_offset(&self, password: &str, admin_pwd: &str, time_offset: i32) -> Option<String> {
-        let temp_gen = crate::utils::gen_password::TempPasswordGeneratorWithOffset::new(time_offset);
-        let times_gen = crate::utils::gen_password::TimesPasswordGeneratorWithOffset::new(time_offset);
-        let limited_gen = crate::utils::gen_password::LimitedPasswordGeneratorWithOffset::new(time_offset);
-        let period_gen = crate::utils::gen_password::PeriodPasswordGeneratorWithOffset::new(time_offset);
-
-        // 尝试不同类型的剩余时间检查
-        if let Some(remaining) = temp_gen.check_remaining_time(password, admin_pwd) {
-            let remaining_minutes = remaining / (1000 * 60);
-            return Some(format!("{}分钟", remaining_minutes));
-        }
-
-        if let Some((remaining_str, _times)) = times_gen.check_remaining_time(password, admin_pwd) {
-            return Some(remaining_str);
-        }
-
-        if let Some((remaining_str, _hours, _minutes)) = limited_gen.check_remaining_time(password, admin_pwd) {
-            return Some(remaining_str);
-        }
-
-        if let Some((remaining_str, _expire_time)) = period_gen.check_remaining_time(password, admin_pwd) {
-            return Some(remaining_str);
-        }
-
-        None
+    /// 按门锁时间偏移和时区生成密码的内部方法
+    fn generate_password_for_lock(&self, admin_pwd: &str, password_type: PasswordType, lock: &Lock) -> Result<PasswordResult> {
+        self.generator(lock)
+            .generate(admin_pwd, password_type)
+            .map_err(AppError::password_generation)
     }
 
     /// 根据授权类型获取默认过期时间
     pub fn get_default_expiry_time(&self, auth_type: AuthType) -> Option<DateTime<Utc>> {
-        let now = Utc::now();
+        let now = self.clock.now();
         match auth_type {
             AuthType::Temp => Some(now + chrono::Duration::minutes(10)),
             AuthType::Times => Some(now + chrono::Duration::hours(2)),
@@ -325,6 +273,38 @@ impl PasswordService {
 
         Ok(())
     }
+
+    /// 验证邀请码的预设授权
+    ///
+    /// 在 `validate_request` 的基础上，额外要求时效密码时长大于0、结束时间在未来，
+    /// 且周期密码的结束时间为门锁时区的整点（周期密码只精确到小时）。
+    pub fn validate_preset(&self, preset: &AuthPreset, config: &AppConfig) -> Result<()> {
+        self.validate_request(&preset.to_password_request(&config.lock_timezone))?;
+
+        match preset.auth_type {
+            AuthType::Limited => {
+                if preset.hours.unwrap_or(0) == 0 && preset.minutes.unwrap_or(0) == 0 {
+                    return Err(AppError::validation("限时密码时长必须大于0"));
+                }
+            }
+            AuthType::Period | AuthType::LongtimeTemp => {
+                let end_time = preset
+                    .end_time
+                    .ok_or_else(|| AppError::validation("必须指定结束时间"))?;
+                if end_time <= self.clock.now() {
+                    return Err(AppError::validation("结束时间必须晚于当前时间"));
+                }
+                if preset.auth_type == AuthType::Period
+                    && config.lock_timezone.local_timestamp(end_time) % 3600 != 0
+                {
+                    return Err(AppError::validation("周期密码的结束时间必须是整点"));
+                }
+            }
+            AuthType::Temp | AuthType::Times => {}
+        }
+
+        Ok(())
+    }
 }
 
 impl Default for PasswordService {
@@ -337,11 +317,14 @@ impl Default for PasswordService {
 mod tests {
     use super::*;
     use crate::config::AppConfig;
+    use crate::utils::gen_password::{Clock, ManualClock};
+    use chrono::TimeZone;
 
     #[test]
     fn test_password_generation() {
-        let mut service = PasswordService::new();
-        let config = AppConfig::default();
+        let clock = ManualClock::new(Utc.with_ymd_and_hms(2024, 1, 1, 10, 0, 1).unwrap());
+        let mut service = PasswordService::new().with_clock(clock.shared());
+        let lock = Lock::new("大门");
 
         // 测试临时密码
         let request = PasswordRequest {
@@ -357,12 +340,86 @@ mod tests {
             start_time: None,
         };
 
-        let result = service.generate_password(&request, &config);
+        let result = service.generate_password(&request, &lock);
         assert!(result.is_ok());
         
+        // 密码为 5000000000 加上32位加密结果，首位不一定是5
         let password_result = result.unwrap();
-        assert!(!password_result.password.is_empty());
-        assert!(password_result.password.starts_with('5'));
+        assert_eq!(password_result.password.len(), 10);
+        assert!(password_result.password.parse::<u64>().unwrap() >= 5000000000);
+        assert_eq!(password_result.valid_from, Utc.with_ymd_and_hms(2024, 1, 1, 10, 0, 0).unwrap());
+
+        // 相同时刻生成的密码相同
+        assert_eq!(service.generate_password(&request, &lock).unwrap(), password_result);
+    }
+
+    #[test]
+    fn test_inspect_password_with_offset() {
+        let clock = ManualClock::new(Utc.with_ymd_and_hms(2024, 1, 1, 10, 0, 1).unwrap());
+        let mut service = PasswordService::new().with_clock(clock.shared());
+        let lock = Lock::new("大门").with_time_offset(120);
+
+        let request = PasswordRequest {
+            admin_password: "123456".to_string(),
+            auth_type: AuthType::Temp,
+            times: None,
+            hours: None,
+            minutes: None,
+            end_year: None,
+            end_month: None,
+            end_day: None,
+            end_hour: None,
+            start_time: None,
+        };
+        let generated = service.generate_password(&request, &lock).unwrap();
+
+        // 检查结果与生成结果完全一致（有效期均为真实时间）
+        let inspection = PasswordService::inspect_password(&generated.password, "123456", &lock, clock.now());
+        assert_eq!(inspection, PasswordInspection::Valid(generated.clone()));
+
+        // 门锁时间快2分钟，有效期按真实时间提前2分钟
+        assert_eq!(generated.valid_until, Utc.with_ymd_and_hms(2024, 1, 1, 10, 10, 0).unwrap());
+        assert!(service.verify_password(&generated.password, "123456", &lock).unwrap());
+        assert!(!service.verify_password(&generated.password, "123456", &Lock::new("公寓门")).unwrap());
+    }
+
+    #[test]
+    fn test_generate_for_multiple_locks() {
+        let clock = ManualClock::new(Utc.with_ymd_and_hms(2024, 1, 1, 10, 0, 1).unwrap());
+        let mut service = PasswordService::new().with_clock(clock.shared());
+        let gate = Lock::new("大门").with_time_offset(-300);
+        let door = Lock::new("公寓门").with_time_offset(600);
+
+        let request = PasswordRequest {
+            admin_password: "123456".to_string(),
+            auth_type: AuthType::Temp,
+            times: None,
+            hours: None,
+            minutes: None,
+            end_year: None,
+            end_month: None,
+            end_day: None,
+            end_hour: None,
+            start_time: None,
+        };
+        let gate_password = service.generate_password(&request, &gate).unwrap();
+        let door_password = service.generate_password(&request, &door).unwrap();
+        assert_ne!(gate_password.password, door_password.password);
+
+        // 每个门锁只接受按自己时钟生成的密码
+        assert!(service.verify_password(&gate_password.password, "123456", &gate).unwrap());
+        assert!(!service.verify_password(&gate_password.password, "123456", &door).unwrap());
+        assert!(service.verify_password(&door_password.password, "123456", &door).unwrap());
+        assert!(!service.verify_password(&door_password.password, "123456", &gate).unwrap());
+
+        // 多个门锁时优先返回当前有效的门锁
+        let locks = [gate, door];
+        let (index, inspection) = PasswordService::inspect_on_locks(&door_password.password, "123456", &locks, clock.now());
+        assert_eq!(index, Some(1));
+        assert_eq!(inspection, PasswordInspection::Valid(door_password));
+        let (index, inspection) = PasswordService::inspect_on_locks("0000000000", "123456", &locks, clock.now());
+        assert_eq!(index, None);
+        assert_eq!(inspection, PasswordInspection::NotOurs);
     }
 
     #[test]
@@ -395,6 +452,48 @@ mod tests {
         assert!(service.validate_request(&request).is_err());
     }
 
+    #[test]
+    fn test_preset_validation() {
+        let service = PasswordService::new();
+        let mut config = AppConfig::default();
+
+        // 与普通请求相同的参数限制
+        let mut preset = AuthPreset::new(AuthType::Times);
+        assert!(service.validate_preset(&preset, &config).is_err());
+        preset.times = Some(32);
+        assert!(service.validate_preset(&preset, &config).is_err());
+        preset.times = Some(3);
+        assert!(service.validate_preset(&preset, &config).is_ok());
+
+        let mut preset = AuthPreset::new(AuthType::Limited);
+        preset.hours = Some(0);
+        preset.minutes = Some(0);
+        assert!(service.validate_preset(&preset, &config).is_err());
+        preset.minutes = Some(15);
+        assert!(service.validate_preset(&preset, &config).is_err());
+        preset.minutes = Some(30);
+        assert!(service.validate_preset(&preset, &config).is_ok());
+
+        // 结束时间必须在未来，周期密码必须是整点
+        let next_hour = Utc::now().timestamp() / 3600 * 3600 + 7200;
+        let mut preset = AuthPreset::new(AuthType::Period);
+        assert!(service.validate_preset(&preset, &config).is_err());
+        preset.end_time = DateTime::from_timestamp(next_hour - 86400, 0);
+        assert!(service.validate_preset(&preset, &config).is_err());
+        preset.end_time = DateTime::from_timestamp(next_hour + 60, 0);
+        assert!(service.validate_preset(&preset, &config).is_err());
+        preset.end_time = DateTime::from_timestamp(next_hour, 0);
+        assert!(service.validate_preset(&preset, &config).is_ok());
+
+        assert!(service.validate_preset(&AuthPreset::new(AuthType::Temp), &config).is_ok());
+
+        // 整点按门锁时区判断
+        config.lock_timezone = "+05:30".parse().unwrap();
+        assert!(service.validate_preset(&preset, &config).is_err());
+        preset.end_time = DateTime::from_timestamp(next_hour + 1800, 0);
+        assert!(service.validate_preset(&preset, &config).is_ok());
+    }
+
     #[tokio::test]
     async fn test_longtime_temp_cache() {
         let mut service = PasswordService::new();
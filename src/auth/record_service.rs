@@ -0,0 +1,465 @@
+//! 访客记录服务模块 - 处理授权状态变更并在同一事务中写入审计日志
+
+use crate::database::{AuditRepository, Database, LockRepository, RecordRepository};
+use crate::error::{AppError, Result};
+use crate::types::{AuditAction, AuditEvent, AuthPreset, AuthStatus, AuthType, Record};
+use crate::utils::gen_password::{SharedClock, SystemClock};
+use chrono::{DateTime, Duration, Utc};
+
+/// 系统自动操作（如到期清理）在审计日志中使用的操作者ID
+pub const SYSTEM_ACTOR_ID: i64 = 0;
+
+/// 访客记录服务
+#[derive(Clone)]
+pub struct RecordService {
+    database: Database,
+    clock: SharedClock,
+}
+
+impl RecordService {
+    /// 创建新的记录服务实例
+    pub fn new(database: Database) -> Self {
+        Self {
+            database,
+            clock: SystemClock::shared(),
+        }
+    }
+
+    /// 使用指定的时钟判断授权是否到期
+    pub fn with_clock(mut self, clock: SharedClock) -> Self {
+        self.clock = clock;
+        self
+    }
+
+    /// 批准授权
+    ///
+    /// 记录尚未关联门锁时（如直接发送文本批准、预设未指定门锁）关联默认门锁；
+    /// 关联的门锁未启用该授权类型时返回错误，不做任何修改。
+    pub async fn approve(
+        &self,
+        actor_id: i64,
+        record_id: i64,
+        auth_type: AuthType,
+        start_time: Option<DateTime<Utc>>,
+        ended_time: Option<DateTime<Utc>>,
+        times: Option<i32>,
+    ) -> Result<bool> {
+        let mut tx = self.database.begin_transaction().await?;
+        let approved =
+            Self::approve_in_tx(&mut tx, actor_id, record_id, auth_type, start_time, ended_time, times, None).await?;
+        tx.commit().await?;
+        Ok(approved)
+    }
+
+    /// 按邀请码的预设授权自动批准请求
+    ///
+    /// 预设的门锁与批准在同一事务中关联，未能批准时不会留下关联；
+    /// 审计日志记为系统操作，并注明来源邀请码。
+    pub async fn approve_with_preset(&self, invite_id: i64, record_id: i64, preset: &AuthPreset) -> Result<bool> {
+        let (start_time, ended_time, times) = preset.authorization_window(self.clock.now());
+
+        let mut tx = self.database.begin_transaction().await?;
+        for &lock_id in &preset.locks {
+            LockRepository::link_record(&mut tx, record_id, lock_id).await?;
+        }
+
+        let note = format!("邀请码 #{}", invite_id);
+        let approved = Self::approve_in_tx(
+            &mut tx,
+            SYSTEM_ACTOR_ID,
+            record_id,
+            preset.auth_type,
+            start_time,
+            ended_time,
+            times,
+            Some(&note),
+        )
+        .await?;
+        if approved {
+            tx.commit().await?;
+        }
+        Ok(approved)
+    }
+
+    /// 在事务中批准授权并写入审计日志，`note`会附加在审计详情末尾
+    #[allow(clippy::too_many_arguments)]
+    async fn approve_in_tx(
+        tx: &mut sqlx::Transaction<'_, sqlx::Sqlite>,
+        actor_id: i64,
+        record_id: i64,
+        auth_type: AuthType,
+        start_time: Option<DateTime<Utc>>,
+        ended_time: Option<DateTime<Utc>>,
+        times: Option<i32>,
+        note: Option<&str>,
+    ) -> Result<bool> {
+        let record = match RecordRepository::find_by_id_in_tx(tx, record_id).await? {
+            Some(record) => record,
+            None => return Ok(false),
+        };
+
+        let mut locks = LockRepository::find_by_record_in_tx(tx, record_id).await?;
+        if record.status == AuthStatus::Pending {
+            if locks.is_empty() {
+                if let Some(default_lock) = LockRepository::find_default_in_tx(tx).await? {
+                    LockRepository::link_record(tx, record_id, default_lock.unique_id).await?;
+                    locks.push(default_lock);
+                }
+            }
+            if let Some(lock) = locks.iter().find(|lock| !lock.supports(auth_type)) {
+                return Err(AppError::business(format!(
+                    "门锁「{}」未启用{}",
+                    lock.name,
+                    auth_type.description()
+                )));
+            }
+        }
+
+        let approved = RecordRepository::approve_authorization(
+            tx, record_id, auth_type, start_time, ended_time, times,
+        )
+        .await?;
+
+        if approved {
+            let mut parts = vec![auth_type.as_str().to_string()];
+            if !locks.is_empty() {
+                let lock_names: Vec<&str> = locks.iter().map(|lock| lock.name.as_str()).collect();
+                parts.push(lock_names.join("、"));
+            }
+            parts.extend(note.map(str::to_string));
+            let event = AuditEvent::for_record(actor_id, AuditAction::Approve, &record, AuthStatus::Auth)
+                .with_detail(parts.join(" · "));
+            AuditRepository::record(tx, &event).await?;
+        }
+
+        Ok(approved)
+    }
+
+    /// 拒绝待审批的授权请求
+    pub async fn reject(&self, actor_id: i64, record_id: i64) -> Result<bool> {
+        let mut tx = self.database.begin_transaction().await?;
+
+        let record = match RecordRepository::find_by_id_in_tx(&mut tx, record_id).await? {
+            Some(record) if record.status == AuthStatus::Pending => record,
+            _ => return Ok(false),
+        };
+
+        let rejected = RecordRepository::update_status(&mut tx, record_id, AuthStatus::Rejected).await?;
+
+        if rejected {
+            let event = AuditEvent::for_record(actor_id, AuditAction::Reject, &record, AuthStatus::Rejected);
+            AuditRepository::record(&mut tx, &event).await?;
+        }
+
+        tx.commit().await?;
+        Ok(rejected)
+    }
+
+    /// 撤销指定记录（待审批的请求记为取消）
+    pub async fn revoke(&self, actor_id: i64, record_id: i64) -> Result<bool> {
+        let mut tx = self.database.begin_transaction().await?;
+
+        let record = match RecordRepository::find_by_id_in_tx(&mut tx, record_id).await? {
+            Some(record) => record,
+            None => return Ok(false),
+        };
+
+        let revoked = RecordRepository::revoke_by_id(&mut tx, record_id).await?;
+
+        if revoked {
+            let event = AuditEvent::for_record(actor_id, AuditAction::Revoke, &record, record.revocation_status());
+            AuditRepository::record(&mut tx, &event).await?;
+        }
+
+        tx.commit().await?;
+        Ok(revoked)
+    }
+
+    /// 将可延长的授权延长指定时长（已过结束时间的从当前时间起算），返回新的结束时间
+    pub async fn extend(&self, actor_id: i64, record_id: i64, duration: Duration) -> Result<Option<DateTime<Utc>>> {
+        let mut tx = self.database.begin_transaction().await?;
+
+        let record = match RecordRepository::find_by_id_in_tx(&mut tx, record_id).await? {
+            Some(record) if record.can_extend() => record,
+            _ => return Ok(None),
+        };
+
+        let now = self.clock.now();
+        let ended_time = record.ended_time.map_or(now, |ended_time| ended_time.max(now)) + duration;
+        if !RecordRepository::extend_authorization(&mut tx, record_id, ended_time).await? {
+            return Ok(None);
+        }
+
+        let event = AuditEvent::for_record(actor_id, AuditAction::Extend, &record, AuthStatus::Auth)
+            .with_detail(format!("结束时间延长至 {}", ended_time.format("%Y-%m-%d %H:%M UTC")));
+        AuditRepository::record(&mut tx, &event).await?;
+
+        tx.commit().await?;
+        Ok(Some(ended_time))
+    }
+
+    /// 撤销访客的所有已授权记录，返回撤销数量
+    pub async fn revoke_all_by_visitor(&self, actor_id: i64, vis_id: i64) -> Result<usize> {
+        let mut tx = self.database.begin_transaction().await?;
+
+        let records = RecordRepository::find_authorized_by_visitor_in_tx(&mut tx, vis_id).await?;
+        let mut revoked_count = 0;
+
+        for record in records {
+            if RecordRepository::revoke_by_id(&mut tx, record.unique_id).await? {
+                let event = AuditEvent::for_record(actor_id, AuditAction::Revoke, &record, AuthStatus::Revoked);
+                AuditRepository::record(&mut tx, &event).await?;
+                revoked_count += 1;
+            }
+        }
+
+        tx.commit().await?;
+        Ok(revoked_count)
+    }
+
+    /// 将所有已到结束时间的授权标记为过期，返回本次过期的记录
+    pub async fn expire_due(&self) -> Result<Vec<Record>> {
+        let due = RecordRepository::find_expired_authorizations(self.database.pool(), self.clock.now()).await?;
+        if due.is_empty() {
+            return Ok(due);
+        }
+
+        let mut tx = self.database.begin_transaction().await?;
+        let mut expired = Vec::with_capacity(due.len());
+
+        for record in due {
+            // 状态条件更新，已被撤销或并发处理的记录会被跳过
+            if RecordRepository::expire_by_id(&mut tx, record.unique_id).await? {
+                let event = AuditEvent::for_record(SYSTEM_ACTOR_ID, AuditAction::Expire, &record, AuthStatus::Expired);
+                AuditRepository::record(&mut tx, &event).await?;
+                expired.push(record);
+            }
+        }
+
+        tx.commit().await?;
+        Ok(expired)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::database::{AdminRepository, AuditFilter};
+    use crate::types::{Admin, Lock};
+    use crate::utils::gen_password::{Clock, ManualClock};
+    use chrono::TimeZone;
+    use tempfile::NamedTempFile;
+
+    /// 返回的临时文件需在测试期间保持存活，否则数据库文件会被提前删除
+    async fn setup_test_service() -> Result<(RecordService, Database, i64, NamedTempFile)> {
+        let temp_file = NamedTempFile::new()?;
+        let db_url = format!("sqlite:{}", temp_file.path().to_str().unwrap());
+        let database = Database::new(&db_url).await?;
+
+        let mut tx = database.begin_transaction().await?;
+        let admin_id = AdminRepository::create(&mut tx, &Admin::new(123456789)).await?;
+        tx.commit().await?;
+
+        Ok((RecordService::new(database.clone()), database, admin_id, temp_file))
+    }
+
+    #[tokio::test]
+    async fn test_status_changes_are_audited() -> Result<()> {
+        let (service, db, admin_id, _temp_file) = setup_test_service().await?;
+        let pool = db.pool();
+
+        let mut tx = db.begin_transaction().await?;
+        let approved_id = RecordRepository::create(&mut tx, &Record::new(987654321, admin_id)).await?;
+        let rejected_id = RecordRepository::create(&mut tx, &Record::new(555666777, admin_id)).await?;
+        tx.commit().await?;
+
+        assert!(service.approve(
+            123456789,
+            approved_id,
+            AuthType::Temp,
+            Some(Utc::now()),
+            Some(Utc::now() + chrono::Duration::minutes(10)),
+            None,
+        ).await?);
+        assert!(service.reject(123456789, rejected_id).await?);
+        // 已处理的请求不能再次拒绝
+        assert!(!service.reject(123456789, rejected_id).await?);
+        assert_eq!(service.revoke_all_by_visitor(123456789, 987654321).await?, 1);
+
+        let events = AuditRepository::query(pool, &AuditFilter::default(), 10, 0).await?;
+        let actions: Vec<AuditAction> = events.iter().map(|e| e.action).collect();
+        assert_eq!(actions, vec![AuditAction::Revoke, AuditAction::Reject, AuditAction::Approve]);
+
+        let approve = &events[2];
+        assert_eq!(approve.actor_id, 123456789);
+        assert_eq!(approve.target_record, Some(approved_id));
+        assert_eq!(approve.target_user, Some(987654321));
+        assert_eq!(approve.before_status, Some(AuthStatus::Pending));
+        assert_eq!(approve.after_status, Some(AuthStatus::Auth));
+
+        let revoke = &events[0];
+        assert_eq!(revoke.before_status, Some(AuthStatus::Auth));
+        assert_eq!(revoke.after_status, Some(AuthStatus::Revoked));
+
+        let reject = &events[1];
+        assert_eq!(reject.after_status, Some(AuthStatus::Rejected));
+
+        db.close().await;
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_approve_links_locks() -> Result<()> {
+        let (service, db, admin_id, _temp_file) = setup_test_service().await?;
+        let pool = db.pool();
+
+        let mut tx = db.begin_transaction().await?;
+        let default_id = LockRepository::create(&mut tx, &Lock::new("大门")).await?;
+        let door_id = LockRepository::create(
+            &mut tx,
+            &Lock::new("公寓门").with_auth_types(vec![AuthType::Temp]),
+        )
+        .await?;
+        let plain_id = RecordRepository::create(&mut tx, &Record::new(987654321, admin_id)).await?;
+        let door_record_id = RecordRepository::create(&mut tx, &Record::new(555666777, admin_id)).await?;
+        LockRepository::link_record(&mut tx, door_record_id, door_id).await?;
+        tx.commit().await?;
+
+        // 未选择门锁时关联默认门锁
+        assert!(service.approve(123456789, plain_id, AuthType::Times, None, None, Some(3)).await?);
+        let linked = LockRepository::find_by_record(pool, plain_id).await?;
+        assert_eq!(linked.iter().map(|l| l.unique_id).collect::<Vec<_>>(), vec![default_id]);
+
+        // 门锁未启用的授权类型不能批准
+        let result = service.approve(123456789, door_record_id, AuthType::Times, None, None, Some(3)).await;
+        assert!(matches!(result, Err(AppError::Business(_))));
+        let record = RecordRepository::find_by_id(pool, door_record_id).await?.unwrap();
+        assert_eq!(record.status, AuthStatus::Pending);
+
+        assert!(service.approve(123456789, door_record_id, AuthType::Temp, None, None, None).await?);
+        let events = AuditRepository::query(pool, &AuditFilter::default(), 10, 0).await?;
+        assert_eq!(events[0].detail.as_deref(), Some("temp · 公寓门"));
+        assert_eq!(events[1].detail.as_deref(), Some("times · 大门"));
+
+        db.close().await;
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_approve_with_preset() -> Result<()> {
+        let (service, db, admin_id, _temp_file) = setup_test_service().await?;
+        let pool = db.pool();
+
+        let mut tx = db.begin_transaction().await?;
+        LockRepository::create(&mut tx, &Lock::new("大门")).await?;
+        let door_id = LockRepository::create(
+            &mut tx,
+            &Lock::new("公寓门").with_auth_types(vec![AuthType::Temp]),
+        )
+        .await?;
+        let record_id = RecordRepository::create(&mut tx, &Record::new(987654321, admin_id)).await?;
+        tx.commit().await?;
+
+        // 门锁不支持预设的授权类型时不批准，也不留下门锁关联
+        let mut preset = AuthPreset::new(AuthType::Times);
+        preset.times = Some(3);
+        preset.locks = vec![door_id];
+        let result = service.approve_with_preset(7, record_id, &preset).await;
+        assert!(matches!(result, Err(AppError::Business(_))));
+        assert!(LockRepository::find_by_record(pool, record_id).await?.is_empty());
+
+        let mut preset = AuthPreset::new(AuthType::Temp);
+        preset.locks = vec![door_id];
+        assert!(service.approve_with_preset(7, record_id, &preset).await?);
+        let linked = LockRepository::find_by_record(pool, record_id).await?;
+        assert_eq!(linked.iter().map(|l| l.unique_id).collect::<Vec<_>>(), vec![door_id]);
+
+        let events = AuditRepository::query(pool, &AuditFilter::default(), 10, 0).await?;
+        assert_eq!(events[0].actor_id, SYSTEM_ACTOR_ID);
+        assert_eq!(events[0].detail.as_deref(), Some("temp · 公寓门 · 邀请码 #7"));
+
+        db.close().await;
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_expire_due() -> Result<()> {
+        let (service, db, admin_id, _temp_file) = setup_test_service().await?;
+        let pool = db.pool();
+
+        let ended_time = Utc.with_ymd_and_hms(2024, 1, 1, 12, 0, 0).unwrap();
+        let clock = ManualClock::new(ended_time - chrono::Duration::minutes(1));
+        let service = service.with_clock(clock.shared());
+
+        let mut tx = db.begin_transaction().await?;
+        let mut record = Record::new(987654321, admin_id);
+        record.status = AuthStatus::Auth;
+        record.ended_time = Some(ended_time);
+        let record_id = RecordRepository::create(&mut tx, &record).await?;
+        tx.commit().await?;
+
+        // 未到结束时间
+        assert!(service.expire_due().await?.is_empty());
+
+        clock.advance(chrono::Duration::minutes(2));
+        let expired = service.expire_due().await?;
+        assert_eq!(expired.len(), 1);
+        assert_eq!(expired[0].unique_id, record_id);
+        assert!(service.expire_due().await?.is_empty());
+
+        let record = RecordRepository::find_by_id(pool, record_id).await?.unwrap();
+        assert_eq!(record.status, AuthStatus::Expired);
+
+        let events = AuditRepository::query(pool, &AuditFilter::default(), 10, 0).await?;
+        assert_eq!(events.len(), 1);
+        assert_eq!(events[0].action, AuditAction::Expire);
+        assert_eq!(events[0].actor_id, SYSTEM_ACTOR_ID);
+        assert_eq!(events[0].after_status, Some(AuthStatus::Expired));
+
+        db.close().await;
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_extend() -> Result<()> {
+        let (service, db, admin_id, _temp_file) = setup_test_service().await?;
+        let pool = db.pool();
+
+        let ended_time = Utc.with_ymd_and_hms(2024, 1, 1, 12, 0, 0).unwrap();
+        let clock = ManualClock::new(ended_time - chrono::Duration::hours(1));
+        let service = service.with_clock(clock.shared());
+
+        let mut tx = db.begin_transaction().await?;
+        let mut record = Record::new(987654321, admin_id);
+        record.status = AuthStatus::Auth;
+        record.auth_type = AuthType::LongtimeTemp;
+        record.ended_time = Some(ended_time);
+        let longtime_id = RecordRepository::create(&mut tx, &record).await?;
+        record.auth_type = AuthType::Limited;
+        let limited_id = RecordRepository::create(&mut tx, &record).await?;
+        tx.commit().await?;
+
+        // 未到结束时间时从原结束时间顺延
+        let extended = service.extend(123456789, longtime_id, chrono::Duration::days(1)).await?;
+        assert_eq!(extended, Some(ended_time + chrono::Duration::days(1)));
+        let record = RecordRepository::find_by_id(pool, longtime_id).await?.unwrap();
+        assert_eq!(record.ended_time, extended);
+
+        // 已过结束时间时从当前时间起算
+        clock.advance(chrono::Duration::days(3));
+        let extended = service.extend(123456789, longtime_id, chrono::Duration::days(1)).await?;
+        assert_eq!(extended, Some(clock.now() + chrono::Duration::days(1)));
+
+        // 密码本身带有效期的授权不能延长
+        assert_eq!(service.extend(123456789, limited_id, chrono::Duration::days(1)).await?, None);
+
+        let events = AuditRepository::query(pool, &AuditFilter::default(), 10, 0).await?;
+        assert_eq!(events.len(), 2);
+        assert_eq!(events[0].action, AuditAction::Extend);
+        assert_eq!(events[0].target_record, Some(longtime_id));
+        assert!(events[1].detail.as_deref().unwrap().contains("2024-01-02 12:00"));
+
+        db.close().await;
+        Ok(())
+    }
+}
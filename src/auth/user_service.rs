@@ -1,11 +1,29 @@
 //! 用户服务模块 - 处理用户身份验证和权限管理
 
 use crate::config::AppConfig;
-use crate::database::{AdminRepository, Database};
+use crate::database::{AdminRepository, AuditRepository, Database, InviteRepository, RecordRepository};
 use crate::error::{AppError, Result};
-use crate::types::{Admin, UserInfo, UserRole};
+use crate::types::{Admin, AuditAction, AuditEvent, UserInfo, UserRole};
 use teloxide::types::User;
 
+/// 删除管理员时对其访客记录和邀请码的处理方式
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AdminRemoval {
+    /// 撤销已授权的记录、取消待审批的请求并撤销邀请码
+    Revoke,
+    /// 将所有访客记录和邀请码转交给另一位管理员（Telegram ID）
+    Transfer(i64),
+}
+
+/// 删除管理员时撤销或转交的数量
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct AdminRemovalSummary {
+    /// 访客记录数量（撤销时只统计待审批和已授权的记录）
+    pub records: u64,
+    /// 邀请码数量
+    pub invites: u64,
+}
+
 /// 用户服务
 pub struct UserService {
     database: Database,
@@ -18,6 +36,11 @@ impl UserService {
         Self { database, config }
     }
 
+    /// 替换配置（运行中修改超级管理员后由BotState同步）
+    pub fn set_config(&mut self, config: AppConfig) {
+        self.config = config;
+    }
+
     /// 获取用户信息和角色
     pub async fn get_user_info(&self, user: &User) -> Result<UserInfo> {
         let user_id = user.id.0 as i64;
@@ -68,6 +91,25 @@ impl UserService {
         AdminRepository::exists_by_telegram_id(self.database.pool(), user_id).await
     }
 
+    /// 获取可接收审批升级的管理员
+    ///
+    /// 邀请管理员已经收到过审批消息，不再重复发送；未注册为管理员的代理人
+    /// 无法操作审批按钮，同样跳过。
+    pub async fn escalation_recipients(&self, inviter_id: Option<i64>) -> Result<Vec<i64>> {
+        let mut recipients = Vec::new();
+        for &target in self.config.escalation_targets() {
+            if Some(target) == inviter_id {
+                continue;
+            }
+            if self.is_admin(target).await? {
+                recipients.push(target);
+            } else {
+                log::debug!("审批升级接收人 {} 不是管理员，已跳过", target);
+            }
+        }
+        Ok(recipients)
+    }
+
     /// 获取管理员信息（通过Telegram ID）
     pub async fn get_admin_info(&self, user_id: i64) -> Result<Option<Admin>> {
         AdminRepository::find_by_telegram_id(self.database.pool(), user_id).await
@@ -94,6 +136,8 @@ impl UserService {
         let mut tx = self.database.begin_transaction().await?;
         let admin = Admin::new(target_user_id);
         let admin_id = AdminRepository::create(&mut tx, &admin).await?;
+        let event = AuditEvent::new(operator_id, AuditAction::AddAdmin).with_target_user(target_user_id);
+        AuditRepository::record(&mut tx, &event).await?;
         tx.commit().await?;
 
         log::info!("超级管理员 {} 添加了新管理员 {}", operator_id, target_user_id);
@@ -101,7 +145,15 @@ impl UserService {
     }
 
     /// 删除管理员（只有超级管理员可以操作）
-    pub async fn remove_admin(&self, operator_id: i64, target_user_id: i64) -> Result<bool> {
+    ///
+    /// 按 `removal` 撤销或转交该管理员的访客记录和邀请码，与删除在同一事务中完成；
+    /// 管理员只标记为已删除，历史记录仍然引用原管理员。
+    pub async fn remove_admin(
+        &self,
+        operator_id: i64,
+        target_user_id: i64,
+        removal: AdminRemoval,
+    ) -> Result<AdminRemovalSummary> {
         // 检查操作者权限
         if !self.config.is_super_admin(operator_id) {
             return Err(AppError::permission("只有超级管理员可以删除管理员"));
@@ -113,21 +165,81 @@ impl UserService {
         }
 
         // 查找要删除的管理员
-        let admin = AdminRepository::find_by_telegram_id(self.database.pool(), target_user_id).await?;
-        
-        if let Some(admin) = admin {
-            let mut tx = self.database.begin_transaction().await?;
-            let removed = AdminRepository::delete(&mut tx, admin.unique_id).await?;
-            tx.commit().await?;
+        let pool = self.database.pool();
+        let admin = AdminRepository::find_by_telegram_id(pool, target_user_id)
+            .await?
+            .ok_or_else(|| AppError::business("目标用户不是管理员"))?;
+
+        let successor = match removal {
+            AdminRemoval::Revoke => None,
+            AdminRemoval::Transfer(successor_id) => {
+                if successor_id == target_user_id {
+                    return Err(AppError::business("不能转交给要删除的管理员"));
+                }
+                let successor = AdminRepository::find_by_telegram_id(pool, successor_id)
+                    .await?
+                    .ok_or_else(|| AppError::business("接收的用户不是管理员"))?;
+                Some(successor)
+            }
+        };
 
-            if removed {
-                log::info!("超级管理员 {} 删除了管理员 {}", operator_id, target_user_id);
+        let mut tx = self.database.begin_transaction().await?;
+        let mut summary = AdminRemovalSummary::default();
+
+        let detail = match successor {
+            None => {
+                for record in RecordRepository::find_open_by_inviter_in_tx(&mut tx, admin.unique_id).await? {
+                    if RecordRepository::revoke_by_id(&mut tx, record.unique_id).await? {
+                        let event = AuditEvent::for_record(
+                            operator_id,
+                            AuditAction::Revoke,
+                            &record,
+                            record.revocation_status(),
+                        );
+                        AuditRepository::record(&mut tx, &event).await?;
+                        summary.records += 1;
+                    }
+                }
+                summary.invites = InviteRepository::revoke_by_admin(&mut tx, admin.unique_id).await?;
+                format!("撤销 {} 条访客记录、{} 个邀请码", summary.records, summary.invites)
             }
-            
-            Ok(removed)
-        } else {
-            Err(AppError::business("目标用户不是管理员"))
+            Some(successor) => {
+                summary.records =
+                    RecordRepository::transfer_inviter(&mut tx, admin.unique_id, successor.unique_id).await?;
+                summary.invites =
+                    InviteRepository::transfer_admin(&mut tx, admin.unique_id, successor.unique_id).await?;
+                format!(
+                    "{} 条访客记录、{} 个邀请码转交给 {}",
+                    summary.records, summary.invites, successor.id
+                )
+            }
+        };
+
+        if !AdminRepository::mark_removed(&mut tx, admin.unique_id).await? {
+            return Err(AppError::business("管理员已被删除"));
         }
+        let event = AuditEvent::new(operator_id, AuditAction::RemoveAdmin)
+            .with_target_user(target_user_id)
+            .with_detail(detail);
+        AuditRepository::record(&mut tx, &event).await?;
+        tx.commit().await?;
+
+        log::info!("超级管理员 {} 删除了管理员 {}", operator_id, target_user_id);
+        Ok(summary)
+    }
+
+    /// 记录超级管理员变更的审计日志（配置由BotState写回配置文件）
+    pub async fn record_super_admin_change(
+        &self,
+        operator_id: i64,
+        action: AuditAction,
+        target_user_id: i64,
+    ) -> Result<()> {
+        let mut tx = self.database.begin_transaction().await?;
+        let event = AuditEvent::new(operator_id, action).with_target_user(target_user_id);
+        AuditRepository::record(&mut tx, &event).await?;
+        tx.commit().await?;
+        Ok(())
     }
 
     /// 更新管理员密码
@@ -137,8 +249,18 @@ impl UserService {
             return Err(AppError::validation("密码必须是4-10位数字"));
         }
 
+        let admin = match AdminRepository::find_by_unique_id(self.database.pool(), admin_id).await? {
+            Some(admin) => admin,
+            None => return Ok(false),
+        };
+
         let mut tx = self.database.begin_transaction().await?;
         let updated = AdminRepository::update_password(&mut tx, admin_id, new_password).await?;
+        if updated {
+            // 审计日志中不记录密码内容
+            let event = AuditEvent::new(admin.id, AuditAction::EditPassword).with_target_user(admin.id);
+            AuditRepository::record(&mut tx, &event).await?;
+        }
         tx.commit().await?;
 
         if updated {
@@ -158,21 +280,6 @@ impl UserService {
         AdminRepository::has_password(self.database.pool(), admin_id).await
     }
 
-    /// 生成管理员邀请码
-    pub async fn generate_admin_invite_code(&self, admin_id: i64) -> Result<String> {
-        let mut tx = self.database.begin_transaction().await?;
-        let invite_code = AdminRepository::generate_invite_code(&mut tx, admin_id).await?;
-        tx.commit().await?;
-
-        log::info!("管理员 {} 生成了新的邀请码", admin_id);
-        Ok(invite_code)
-    }
-
-    /// 通过邀请码查找管理员
-    pub async fn find_admin_by_invite_code(&self, invite_code: &str) -> Result<Option<Admin>> {
-        AdminRepository::find_by_invite_code(self.database.pool(), invite_code).await
-    }
-
     /// 获取所有管理员列表（只有超级管理员可以查看）
     pub async fn list_all_admins(&self, operator_id: i64) -> Result<Vec<Admin>> {
         if !self.config.is_super_admin(operator_id) {
@@ -248,7 +355,7 @@ impl UserService {
                 matches!(role, UserRole::SuperAdmin)
             }
             // 管理员操作
-            "editpasswd" | "geninvite" | "revoke" | "approve" => {
+            "editpasswd" | "geninvite" | "invites" | "revoke" | "approve" => {
                 matches!(role, UserRole::Admin | UserRole::SuperAdmin)
             }
             // 访客操作
@@ -267,7 +374,8 @@ mod tests {
     use crate::database::Database;
     use tempfile::NamedTempFile;
 
-    async fn setup_test_service() -> Result<UserService> {
+    /// 返回的临时文件需在测试期间保持存活，否则数据库文件会被提前删除
+    async fn setup_test_service() -> Result<(UserService, NamedTempFile)> {
         let temp_file = NamedTempFile::new()?;
         let db_url = format!("sqlite:{}", temp_file.path().to_str().unwrap());
         let database = Database::new(&db_url).await?;
@@ -276,12 +384,12 @@ mod tests {
         config.telegram.bot_token = "test_token".to_string();
         config.super_admin_ids.push(123456789);
 
-        Ok(UserService::new(database, config))
+        Ok((UserService::new(database, config), temp_file))
     }
 
     #[tokio::test]
     async fn test_user_role_detection() -> Result<()> {
-        let service = setup_test_service().await?;
+        let (service, _temp_file) = setup_test_service().await?;
 
         // 测试超级管理员
         assert!(service.is_super_admin(123456789));
@@ -297,9 +405,32 @@ mod tests {
         Ok(())
     }
 
+    #[tokio::test]
+    async fn test_escalation_recipients() -> Result<()> {
+        let (mut service, _temp_file) = setup_test_service().await?;
+        let super_admin_id = 123456789;
+        let deputy_id = 987654321;
+        let stranger_id = 555666777;
+
+        // 未配置代理人时升级给超级管理员，邀请管理员本人除外
+        assert_eq!(service.escalation_recipients(None).await?, vec![super_admin_id]);
+        assert!(service.escalation_recipients(Some(super_admin_id)).await?.is_empty());
+
+        // 未注册为管理员的代理人收不到升级消息
+        let mut config = service.config.clone();
+        config.escalation_admin_ids = vec![deputy_id, stranger_id];
+        service.set_config(config);
+        assert!(service.escalation_recipients(None).await?.is_empty());
+
+        service.create_admin(super_admin_id, deputy_id).await?;
+        assert_eq!(service.escalation_recipients(None).await?, vec![deputy_id]);
+
+        Ok(())
+    }
+
     #[tokio::test]
     async fn test_admin_creation() -> Result<()> {
-        let service = setup_test_service().await?;
+        let (service, _temp_file) = setup_test_service().await?;
         let super_admin_id = 123456789;
         let new_admin_id = 987654321;
 
@@ -320,7 +451,7 @@ mod tests {
 
     #[tokio::test]
     async fn test_permission_check() -> Result<()> {
-        let service = setup_test_service().await?;
+        let (service, _temp_file) = setup_test_service().await?;
         let super_admin_id = 123456789;
         let visitor_id = 555666777;
 
@@ -341,7 +472,7 @@ mod tests {
 
     #[tokio::test]
     async fn test_action_permissions() -> Result<()> {
-        let service = setup_test_service().await?;
+        let (service, _temp_file) = setup_test_service().await?;
         let super_admin_id = 123456789;
         let visitor_id = 555666777;
 
@@ -359,4 +490,62 @@ mod tests {
 
         Ok(())
     }
-}
\ No newline at end of file
+
+    #[tokio::test]
+    async fn test_remove_admin() -> Result<()> {
+        use crate::database::AuditFilter;
+        use crate::types::{AuthStatus, Invite, Record};
+
+        let (service, _temp_file) = setup_test_service().await?;
+        let super_admin_id = 123456789;
+        let leaving_id = service.create_admin(super_admin_id, 222222222).await?;
+        let successor_id = service.create_admin(super_admin_id, 333333333).await?;
+
+        let db = service.database.clone();
+        let mut tx = db.begin_transaction().await?;
+        let pending_id = RecordRepository::create(&mut tx, &Record::new(987654321, leaving_id)).await?;
+        let mut authorized = Record::new(555666777, leaving_id);
+        authorized.status = AuthStatus::Auth;
+        let authorized_id = RecordRepository::create(&mut tx, &authorized).await?;
+        let invite_id = InviteRepository::create(&mut tx, &Invite::new(leaving_id)).await?;
+        let transferred_id = RecordRepository::create(&mut tx, &authorized.clone()).await?;
+        tx.commit().await?;
+
+        // 不能转交给非管理员或要删除的管理员本人
+        let result = service.remove_admin(super_admin_id, 222222222, AdminRemoval::Transfer(444444444)).await;
+        assert!(matches!(result, Err(AppError::Business(_))));
+        let result = service.remove_admin(super_admin_id, 222222222, AdminRemoval::Transfer(222222222)).await;
+        assert!(matches!(result, Err(AppError::Business(_))));
+        assert!(service.is_admin(222222222).await?);
+
+        // 转交后记录和邀请码归接收的管理员
+        let summary = service.remove_admin(super_admin_id, 222222222, AdminRemoval::Transfer(333333333)).await?;
+        assert_eq!(summary, AdminRemovalSummary { records: 3, invites: 1 });
+        assert!(!service.is_admin(222222222).await?);
+        let record = RecordRepository::find_by_id(db.pool(), transferred_id).await?.unwrap();
+        assert_eq!(record.inviter, successor_id);
+        assert_eq!(record.status, AuthStatus::Auth);
+        assert_eq!(InviteRepository::find_by_id(db.pool(), invite_id).await?.unwrap().admin_id, successor_id);
+
+        // 撤销时取消待审批请求、撤销授权和邀请码，管理员记录保留供追溯
+        let summary = service.remove_admin(super_admin_id, 333333333, AdminRemoval::Revoke).await?;
+        assert_eq!(summary, AdminRemovalSummary { records: 3, invites: 1 });
+        let pending = RecordRepository::find_by_id(db.pool(), pending_id).await?.unwrap();
+        assert_eq!(pending.status, AuthStatus::Cancelled);
+        let authorized = RecordRepository::find_by_id(db.pool(), authorized_id).await?.unwrap();
+        assert_eq!(authorized.status, AuthStatus::Revoked);
+        assert!(InviteRepository::find_by_id(db.pool(), invite_id).await?.unwrap().revoked);
+        assert!(service.get_admin_info_by_unique_id(successor_id).await?.is_some());
+        assert!(service.list_all_admins(super_admin_id).await?.is_empty());
+
+        let events = AuditRepository::query(db.pool(), &AuditFilter::default(), 10, 0).await?;
+        assert_eq!(events[0].action, AuditAction::RemoveAdmin);
+        assert_eq!(events[0].detail.as_deref(), Some("撤销 3 条访客记录、1 个邀请码"));
+
+        // 删除后可以重新添加，原管理密码已清空
+        assert_eq!(service.create_admin(super_admin_id, 222222222).await?, leaving_id);
+        assert!(!service.admin_has_password(leaving_id).await?);
+
+        Ok(())
+    }
+}
@@ -0,0 +1,24 @@
+//! 重新生成密码算法测试向量文件
+//!
+//! 用法：`cargo run --bin gen_password_vectors [输出路径]`，默认覆盖仓库中的测试向量文件。
+
+use nekohouse_v3_visitor_registration::gen_password::vectors::{generate_vector_file, VECTOR_FILE_PATH};
+
+fn main() {
+    let path = std::env::args().nth(1).unwrap_or_else(|| VECTOR_FILE_PATH.to_string());
+
+    let file = match generate_vector_file() {
+        Ok(file) => file,
+        Err(e) => {
+            eprintln!("生成测试向量失败: {}", e);
+            std::process::exit(1);
+        }
+    };
+
+    if let Err(e) = std::fs::write(&path, file.to_json()) {
+        eprintln!("写入 {} 失败: {}", path, e);
+        std::process::exit(1);
+    }
+
+    println!("已写入 {} 条测试向量（版本 {}）到 {}", file.vectors.len(), file.version, path);
+}
@@ -0,0 +1,578 @@
+//! 门锁密码命令行工具
+//!
+//! ```text
+//! nekopass generate times 5
+//! nekopass generate period 2024-06-30 18
+//! nekopass verify 5123456789
+//! nekopass inspect 5123456789 --json
+//! nekopass table --from "2024-06-01 08:00" --to "2024-06-01 12:00" limited 2 30
+//! ```
+//!
+//! 管理密码依次从 `--admin-pwd-file`（首行）、环境变量 `NEKOPASS_ADMIN_PWD`、标准输入读取。
+//! 时间偏移和门锁时区从配置文件读取（`--config` 或 `CONFIG_PATH`，默认 `config.json`），
+//! 可用 `--time-offset`、`--timezone` 临时覆盖。命令行中的时间均为门锁本地时间。
+//!
+//! 退出码：0 成功；1 `verify` 判定门锁不接受该密码；2 参数或运行错误。
+
+use chrono::{DateTime, Datelike, Duration, NaiveDate, NaiveDateTime, Utc};
+use clap::{Parser, Subcommand};
+use nekohouse_v3_visitor_registration::config::AppConfig;
+use nekohouse_v3_visitor_registration::gen_password::{
+    InputValidator, LockTimezone, ManualClock, PasswordInspection, PasswordResult, PasswordType,
+    SharedClock, SystemClock, UnifiedPasswordGenerator,
+};
+use serde::Serialize;
+use std::io::{BufRead, IsTerminal, Write};
+use std::path::{Path, PathBuf};
+use std::process::ExitCode;
+
+/// 管理密码环境变量
+const ADMIN_PWD_ENV: &str = "NEKOPASS_ADMIN_PWD";
+
+/// 未指定配置文件时的默认路径，与机器人一致
+const DEFAULT_CONFIG_PATH: &str = "config.json";
+
+/// 密码表最多输出的行数
+const MAX_TABLE_ROWS: usize = 10_000;
+
+#[derive(Debug, Parser)]
+#[command(name = "nekopass", version, about = "生成、验证和检查门锁密码")]
+struct Cli {
+    /// 配置文件路径，从中读取时间偏移和门锁时区
+    #[arg(long, global = true, env = "CONFIG_PATH")]
+    config: Option<PathBuf>,
+
+    /// 从文件首行读取管理密码
+    #[arg(long, global = true)]
+    admin_pwd_file: Option<PathBuf>,
+
+    /// 覆盖配置中的时间偏移（秒）
+    #[arg(long, global = true, allow_hyphen_values = true)]
+    time_offset: Option<i64>,
+
+    /// 覆盖配置中的门锁时区（IANA时区名或固定偏移）
+    #[arg(long, global = true)]
+    timezone: Option<LockTimezone>,
+
+    /// 以指定时间代替当前时间（门锁本地时间 YYYY-MM-DD HH:MM[:SS]）
+    #[arg(long, global = true)]
+    at: Option<String>,
+
+    /// 以JSON格式输出
+    #[arg(long, global = true)]
+    json: bool,
+
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Debug, Subcommand)]
+enum Command {
+    /// 生成密码
+    Generate {
+        #[command(subcommand)]
+        spec: PasswordSpec,
+    },
+    /// 验证门锁当前是否接受密码
+    Verify {
+        /// 10位密码
+        code: String,
+    },
+    /// 检查密码的类型、参数和有效期
+    Inspect {
+        /// 10位密码
+        code: String,
+    },
+    /// 列出时间范围内每个时间窗口生成的密码
+    Table {
+        /// 起始时间（门锁本地时间）
+        #[arg(long)]
+        from: String,
+        /// 结束时间（门锁本地时间）
+        #[arg(long)]
+        to: String,
+        #[command(subcommand)]
+        spec: PasswordSpec,
+    },
+}
+
+/// 密码类型及参数
+#[derive(Debug, Clone, PartialEq, Subcommand)]
+enum PasswordSpec {
+    /// 临时密码，10分钟内有效
+    Temp,
+    /// 次数密码，20小时内可使用指定次数
+    Times {
+        /// 使用次数（1-31）
+        count: u32,
+    },
+    /// 限时密码，生成后指定时长内有效
+    Limited {
+        /// 小时数（0-127）
+        hours: u32,
+        /// 分钟数（0或30）
+        #[arg(default_value_t = 0)]
+        minutes: u32,
+    },
+    /// 周期密码，有效至指定日期的整点
+    Period {
+        /// 结束日期（YYYY-MM-DD）
+        date: NaiveDate,
+        /// 结束小时（0-23）
+        hour: u32,
+    },
+}
+
+impl PasswordSpec {
+    fn password_type(&self) -> PasswordType {
+        match *self {
+            PasswordSpec::Temp => PasswordType::Temporary,
+            PasswordSpec::Times { count } => PasswordType::Times(count),
+            PasswordSpec::Limited { hours, minutes } => PasswordType::Limited(hours, minutes),
+            PasswordSpec::Period { date, hour } => {
+                PasswordType::Period(date.year() as u32, date.month(), date.day(), hour)
+            }
+        }
+    }
+
+    /// 密码表的采样间隔，不超过该类型时间窗口的长度
+    ///
+    /// 周期密码按门锁本地自然日变化，夏令时切换当天不足24小时，因此按小时采样。
+    fn table_step(&self) -> Duration {
+        match self {
+            PasswordSpec::Temp => Duration::seconds(4),
+            PasswordSpec::Times { .. } => Duration::seconds(4 * 32),
+            PasswordSpec::Limited { .. } => Duration::minutes(30),
+            PasswordSpec::Period { .. } => Duration::hours(1),
+        }
+    }
+}
+
+/// 门锁设置
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct LockSettings {
+    time_offset: i64,
+    timezone: LockTimezone,
+}
+
+impl LockSettings {
+    /// 读取配置文件，命令行参数优先
+    ///
+    /// 显式指定的配置文件必须存在；默认的 `config.json` 不存在时使用默认设置。
+    fn load(cli: &Cli) -> Result<Self, String> {
+        let config = match &cli.config {
+            Some(path) if !path.exists() => return Err(format!("配置文件 {} 不存在", path.display())),
+            Some(path) => Some(load_config(path)?),
+            None if Path::new(DEFAULT_CONFIG_PATH).exists() => Some(load_config(Path::new(DEFAULT_CONFIG_PATH))?),
+            None => None,
+        };
+
+        let config = config.unwrap_or_else(|| {
+            if cli.time_offset.is_none() {
+                eprintln!("⚠️ 未找到配置文件 {}，时间偏移按0计算", DEFAULT_CONFIG_PATH);
+            }
+            AppConfig::default()
+        });
+
+        Ok(Self {
+            time_offset: cli.time_offset.unwrap_or(config.time_offset),
+            timezone: cli.timezone.unwrap_or(config.lock_timezone),
+        })
+    }
+
+    fn generator(&self, clock: SharedClock) -> UnifiedPasswordGenerator {
+        UnifiedPasswordGenerator::new()
+            .with_clock(clock)
+            .with_time_offset(self.time_offset)
+            .with_timezone(self.timezone)
+    }
+}
+
+fn load_config(path: &Path) -> Result<AppConfig, String> {
+    AppConfig::from_file(path).map_err(|e| format!("读取配置文件 {} 失败: {}", path.display(), e))
+}
+
+/// 读取管理密码：文件首行、环境变量、标准输入首行
+fn read_admin_pwd(file: Option<&Path>) -> Result<String, String> {
+    let admin_pwd = if let Some(path) = file {
+        let content = std::fs::read_to_string(path)
+            .map_err(|e| format!("读取管理密码文件 {} 失败: {}", path.display(), e))?;
+        content.lines().next().unwrap_or_default().to_string()
+    } else if let Ok(admin_pwd) = std::env::var(ADMIN_PWD_ENV) {
+        admin_pwd
+    } else {
+        let stdin = std::io::stdin();
+        if stdin.is_terminal() {
+            eprint!("请输入管理密码: ");
+            let _ = std::io::stderr().flush();
+        }
+        let mut line = String::new();
+        stdin
+            .lock()
+            .read_line(&mut line)
+            .map_err(|e| format!("读取管理密码失败: {}", e))?;
+        line
+    };
+
+    let admin_pwd = admin_pwd.trim().to_string();
+    InputValidator::validate_admin_password(&admin_pwd)?;
+    Ok(admin_pwd)
+}
+
+/// 解析门锁本地时间，也接受带时区的RFC 3339时间
+fn parse_local_time(value: &str, timezone: &LockTimezone) -> Result<DateTime<Utc>, String> {
+    if let Ok(time) = DateTime::parse_from_rfc3339(value) {
+        return Ok(time.with_timezone(&Utc));
+    }
+
+    ["%Y-%m-%d %H:%M:%S", "%Y-%m-%d %H:%M"]
+        .iter()
+        .find_map(|format| NaiveDateTime::parse_from_str(value.trim(), format).ok())
+        .map(|local| timezone.from_local(&local))
+        .ok_or_else(|| format!("无效的时间: {}，应为 YYYY-MM-DD HH:MM[:SS]", value))
+}
+
+/// 列出 `[from, to]` 内门锁会接受的各个密码，相邻时间生成的相同密码只列一次
+///
+/// 周期密码过了结束时间就无法生成，表格到此为止。
+fn code_table(
+    admin_pwd: &str,
+    spec: &PasswordSpec,
+    settings: &LockSettings,
+    from: DateTime<Utc>,
+    to: DateTime<Utc>,
+) -> Result<Vec<PasswordResult>, String> {
+    if from > to {
+        return Err("起始时间不能晚于结束时间".to_string());
+    }
+
+    let clock = ManualClock::new(from);
+    let generator = settings.generator(clock.shared());
+    let password_type = spec.password_type();
+
+    let mut rows: Vec<PasswordResult> = Vec::new();
+    let mut at = from;
+    while at <= to {
+        clock.set(at);
+        match generator.generate(admin_pwd, password_type.clone()) {
+            Ok(result) => {
+                if rows.last().is_none_or(|last| last.password != result.password) {
+                    if rows.len() == MAX_TABLE_ROWS {
+                        return Err(format!("时间范围内的密码超过 {} 个，请缩小范围", MAX_TABLE_ROWS));
+                    }
+                    rows.push(result);
+                }
+            }
+            Err(_) if !rows.is_empty() => break,
+            Err(e) => return Err(e),
+        }
+        at += spec.table_step();
+    }
+
+    Ok(rows)
+}
+
+/// `verify` 的输出
+#[derive(Debug, Serialize)]
+struct VerifyOutput {
+    password: String,
+    valid: bool,
+    password_type: Option<PasswordType>,
+    valid_until: Option<DateTime<Utc>>,
+}
+
+/// 格式化生成结果
+fn format_result(result: &PasswordResult, timezone: &LockTimezone) -> String {
+    let mut text = format!(
+        "🔑 密码：{}\n\
+         📋 类型：{}\n\
+         🪟 时间窗口：{}\n\
+         🕐 生效时间：{}\n\
+         ⏰ 失效时间：{}",
+        result.password,
+        result.password_type,
+        result.window,
+        timezone.format(result.valid_from),
+        timezone.format(result.valid_until)
+    );
+    if let Some(uses) = result.remaining_uses {
+        text.push_str(&format!("\n🔢 可用次数：{}", uses));
+    }
+    text
+}
+
+/// 格式化检查结论
+fn format_inspection(password: &str, inspection: &PasswordInspection, timezone: &LockTimezone) -> String {
+    let result = match inspection.result() {
+        Some(result) => result,
+        None => return format!("❓ 密码 {} 不是由该管理密码生成，或生成时间超出检查范围", password),
+    };
+
+    let verdict = match inspection {
+        PasswordInspection::Valid(_) => "✅ 当前有效",
+        PasswordInspection::Expired(_) => "⌛ 已过期",
+        PasswordInspection::NotYetValid(_) => "⏳ 尚未生效",
+        PasswordInspection::NotOurs => unreachable!(),
+    };
+
+    format!("{}\n\n{}", format_result(result, timezone), verdict)
+}
+
+/// 格式化密码表
+fn format_table(spec: &PasswordSpec, rows: &[PasswordResult], timezone: &LockTimezone) -> String {
+    let mut text = format!("{}，门锁时区 {}，共 {} 个\n", spec.password_type(), timezone, rows.len());
+    // 中文标题每个字占两列宽，按字符数补齐时少补4个空格
+    text.push_str(&format!("{:<17}{:<17}{}", "生效时间", "失效时间", "密码"));
+    for row in rows {
+        text.push_str(&format!(
+            "\n{:<21}{:<21}{}",
+            timezone.format(row.valid_from),
+            timezone.format(row.valid_until),
+            row.password
+        ));
+    }
+    text
+}
+
+fn print_json<T: Serialize>(value: &T) -> Result<(), String> {
+    let json = serde_json::to_string_pretty(value).map_err(|e| format!("序列化输出失败: {}", e))?;
+    println!("{}", json);
+    Ok(())
+}
+
+fn run(cli: &Cli) -> Result<ExitCode, String> {
+    let settings = LockSettings::load(cli)?;
+    let timezone = settings.timezone;
+    let clock = match &cli.at {
+        Some(at) => ManualClock::new(parse_local_time(at, &timezone)?).shared(),
+        None => SystemClock::shared(),
+    };
+    let admin_pwd = read_admin_pwd(cli.admin_pwd_file.as_deref())?;
+    let generator = settings.generator(clock.clone());
+
+    match &cli.command {
+        Command::Generate { spec } => {
+            let result = generator.generate(&admin_pwd, spec.password_type())?;
+            if cli.json {
+                print_json(&result)?;
+            } else {
+                println!("{}", format_result(&result, &timezone));
+            }
+        }
+        Command::Verify { code } => {
+            let verified = generator.verify(code, &admin_pwd);
+            let inspection = generator.inspect(code, &admin_pwd, clock.now());
+            let matched = inspection.result().filter(|_| verified.is_some());
+            let output = VerifyOutput {
+                password: code.clone(),
+                valid: verified.is_some(),
+                password_type: matched.map(|result| result.password_type.clone()).or(verified),
+                valid_until: matched.map(|result| result.valid_until),
+            };
+
+            if cli.json {
+                print_json(&output)?;
+            } else if let Some(password_type) = &output.password_type {
+                println!("✅ 门锁接受密码 {}（{}）", code, password_type);
+                if let Some(valid_until) = output.valid_until {
+                    println!("⏰ 失效时间：{}", timezone.format(valid_until));
+                }
+            } else {
+                println!("❌ 门锁不接受密码 {}", code);
+            }
+
+            if !output.valid {
+                return Ok(ExitCode::from(1));
+            }
+        }
+        Command::Inspect { code } => {
+            let inspection = generator.inspect(code, &admin_pwd, clock.now());
+            if cli.json {
+                print_json(&inspection)?;
+            } else {
+                println!("{}", format_inspection(code, &inspection, &timezone));
+            }
+        }
+        Command::Table { from, to, spec } => {
+            let from = parse_local_time(from, &timezone)?;
+            let to = parse_local_time(to, &timezone)?;
+            let rows = code_table(&admin_pwd, spec, &settings, from, to)?;
+            if cli.json {
+                print_json(&rows)?;
+            } else {
+                println!("{}", format_table(spec, &rows, &timezone));
+            }
+        }
+    }
+
+    Ok(ExitCode::SUCCESS)
+}
+
+fn main() -> ExitCode {
+    let cli = Cli::parse();
+    match run(&cli) {
+        Ok(code) => code,
+        Err(e) => {
+            eprintln!("❌ {}", e);
+            ExitCode::from(2)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+    use clap::CommandFactory;
+    use nekohouse_v3_visitor_registration::gen_password::Clock;
+    use std::collections::HashSet;
+
+    const ADMIN_PWD: &str = "123456";
+
+    fn settings() -> LockSettings {
+        LockSettings {
+            time_offset: 0,
+            timezone: LockTimezone::default(),
+        }
+    }
+
+    #[test]
+    fn test_cli_definition() {
+        Cli::command().debug_assert();
+
+        let cli = Cli::try_parse_from(["nekopass", "generate", "limited", "2", "--time-offset", "-30", "--json"]).unwrap();
+        assert_eq!(cli.time_offset, Some(-30));
+        assert!(cli.json);
+        match cli.command {
+            Command::Generate { spec } => assert_eq!(spec.password_type(), PasswordType::Limited(2, 0)),
+            other => panic!("unexpected command: {:?}", other),
+        }
+
+        let cli = Cli::try_parse_from([
+            "nekopass", "--timezone", "Europe/Berlin", "table", "--from", "2024-06-01 08:00", "--to",
+            "2024-06-01 12:00", "period", "2024-06-30", "18",
+        ])
+        .unwrap();
+        assert_eq!(cli.timezone, Some("Europe/Berlin".parse().unwrap()));
+        match cli.command {
+            Command::Table { spec, .. } => assert_eq!(spec.password_type(), PasswordType::Period(2024, 6, 30, 18)),
+            other => panic!("unexpected command: {:?}", other),
+        }
+
+        assert!(Cli::try_parse_from(["nekopass", "generate", "period", "2024-02-30", "18"]).is_err());
+        assert!(Cli::try_parse_from(["nekopass", "--timezone", "Mars/Olympus", "verify", "5000000000"]).is_err());
+    }
+
+    #[test]
+    fn test_parse_local_time() {
+        let berlin: LockTimezone = "Europe/Berlin".parse().unwrap();
+        assert_eq!(
+            parse_local_time("2024-07-01 18:00", &berlin),
+            Ok(Utc.with_ymd_and_hms(2024, 7, 1, 16, 0, 0).unwrap())
+        );
+        assert_eq!(
+            parse_local_time("2024-07-01 18:00:30", &LockTimezone::default()),
+            Ok(Utc.with_ymd_and_hms(2024, 7, 1, 10, 0, 30).unwrap())
+        );
+        assert_eq!(
+            parse_local_time("2024-07-01T18:00:00Z", &berlin),
+            Ok(Utc.with_ymd_and_hms(2024, 7, 1, 18, 0, 0).unwrap())
+        );
+        assert!(parse_local_time("2024-07-01", &berlin).is_err());
+    }
+
+    #[test]
+    fn test_read_admin_pwd_from_file() {
+        let file = tempfile::NamedTempFile::new().unwrap();
+        std::fs::write(file.path(), "123456\r\nignored\n").unwrap();
+        assert_eq!(read_admin_pwd(Some(file.path())), Ok("123456".to_string()));
+
+        std::fs::write(file.path(), "123\n").unwrap();
+        assert!(read_admin_pwd(Some(file.path())).is_err());
+    }
+
+    #[test]
+    fn test_table_lists_each_window_once() {
+        let from = Utc.with_ymd_and_hms(2024, 6, 1, 12, 0, 0).unwrap();
+        let to = from + Duration::minutes(1);
+
+        let rows = code_table(ADMIN_PWD, &PasswordSpec::Temp, &settings(), from, to).unwrap();
+        assert_eq!(rows.len(), 16);
+        assert_eq!(rows.iter().map(|row| &row.password).collect::<HashSet<_>>().len(), 16);
+        for pair in rows.windows(2) {
+            assert_eq!(pair[1].valid_from - pair[0].valid_from, Duration::seconds(4));
+        }
+
+        // 每行的密码在其生效时间由门锁接受
+        let clock = ManualClock::new(from);
+        let generator = settings().generator(clock.shared());
+        for row in &rows {
+            clock.set(row.valid_from);
+            assert_eq!(generator.verify(&row.password, ADMIN_PWD), Some(PasswordType::Temporary));
+        }
+
+        let rows = code_table(ADMIN_PWD, &PasswordSpec::Limited { hours: 2, minutes: 30 }, &settings(), from, from + Duration::hours(2))
+            .unwrap();
+        assert_eq!(rows.len(), 5);
+    }
+
+    #[test]
+    fn test_period_table_follows_local_days() {
+        let settings = LockSettings {
+            time_offset: 0,
+            timezone: "Europe/Berlin".parse().unwrap(),
+        };
+        let spec = PasswordSpec::Period {
+            date: NaiveDate::from_ymd_opt(2024, 4, 2).unwrap(),
+            hour: 12,
+        };
+
+        // 跨过夏令时开始的短日，每个本地自然日各一个密码，结束时间后停止
+        let from = parse_local_time("2024-03-29 12:00", &settings.timezone).unwrap();
+        let to = parse_local_time("2024-04-05 00:00", &settings.timezone).unwrap();
+        let rows = code_table(ADMIN_PWD, &spec, &settings, from, to).unwrap();
+
+        let days: Vec<String> = rows.iter().map(|row| settings.timezone.format(row.valid_from)).collect();
+        assert_eq!(
+            days,
+            [
+                "2024-03-29 00:00:00",
+                "2024-03-30 00:00:00",
+                "2024-03-31 00:00:00",
+                "2024-04-01 00:00:00",
+                "2024-04-02 00:00:00",
+            ]
+        );
+        assert!(rows.iter().all(|row| settings.timezone.format(row.valid_until) == "2024-04-02 12:00:00"));
+
+        // 起点已在结束时间之后时报告生成错误
+        assert!(code_table(ADMIN_PWD, &spec, &settings, to, to + Duration::days(1)).is_err());
+    }
+
+    #[test]
+    fn test_table_limits() {
+        let from = Utc.with_ymd_and_hms(2024, 6, 1, 12, 0, 0).unwrap();
+        assert!(code_table(ADMIN_PWD, &PasswordSpec::Temp, &settings(), from, from - Duration::seconds(1)).is_err());
+        assert!(code_table(ADMIN_PWD, &PasswordSpec::Temp, &settings(), from, from + Duration::days(1)).is_err());
+    }
+
+    #[test]
+    fn test_human_output() {
+        let clock = ManualClock::new(Utc.with_ymd_and_hms(2024, 6, 1, 12, 0, 0).unwrap());
+        let generator = settings().generator(clock.shared());
+        let timezone = LockTimezone::default();
+
+        let result = generator.generate(ADMIN_PWD, PasswordType::Times(5)).unwrap();
+        let text = format_result(&result, &timezone);
+        assert!(text.contains(&result.password));
+        assert!(text.contains("可用次数：5"));
+
+        let inspection = generator.inspect(&result.password, ADMIN_PWD, clock.now());
+        assert!(format_inspection(&result.password, &inspection, &timezone).ends_with("✅ 当前有效"));
+        assert!(format_inspection("5000000000", &PasswordInspection::NotOurs, &timezone).contains("不是由该管理密码生成"));
+
+        let text = format_table(&PasswordSpec::Times { count: 5 }, std::slice::from_ref(&result), &timezone);
+        assert!(text.contains("共 1 个"));
+        assert!(text.contains(&result.password));
+    }
+}
@@ -1,10 +1,14 @@
 //! NekoHouse Bot主体结构
 
-use crate::auth::{PasswordService, UserService};
-use crate::config::AppConfig;
+use crate::auth::{InviteService, LockService, PasswordService, RecordService, UserService};
+use crate::bot::escalation::spawn_escalation_watcher;
+use crate::bot::expiry::spawn_expiry_sweeper;
+use crate::config::{AppConfig, ConfigManager};
 use crate::database::Database;
 use crate::error::Result;
-use std::sync::Arc;
+use crate::utils::gen_password::{SharedClock, SystemClock};
+use std::sync::{Arc, PoisonError};
+use std::time::Duration;
 use teloxide::{
     prelude::*,
     types::Me,
@@ -12,32 +16,94 @@ use teloxide::{
     dispatching::{UpdateHandler, HandlerExt},
     error_handlers::LoggingErrorHandler
 };
-use tokio::sync::RwLock;
+use tokio::sync::{watch, RwLock};
 
 /// NekoHouse Bot状态
 #[derive(Clone)]
 pub struct BotState {
     pub database: Database,
-    pub config: AppConfig,
+    /// 运行中的配置，修改时整体替换，读取方拿到的始终是完整的一份
+    config: Arc<std::sync::RwLock<Arc<AppConfig>>>,
     pub user_service: Arc<RwLock<UserService>>,
     pub password_service: Arc<RwLock<PasswordService>>,
+    pub record_service: RecordService,
+    pub invite_service: InviteService,
+    pub lock_service: LockService,
+    /// 配置文件管理器，用于持久化运行中修改的配置；测试环境中为空
+    pub config_manager: Option<Arc<RwLock<ConfigManager>>>,
     pub bot_info: Arc<RwLock<Option<Me>>>,
+    /// 当前时间来源，密码生成和授权有效期判断共用
+    pub clock: SharedClock,
 }
 
 impl BotState {
     pub fn new(database: Database, config: AppConfig) -> Self {
+        Self::with_clock(database, config, SystemClock::shared())
+    }
+
+    /// 使用指定的时钟创建Bot状态
+    pub fn with_clock(database: Database, config: AppConfig, clock: SharedClock) -> Self {
         let user_service = Arc::new(RwLock::new(UserService::new(database.clone(), config.clone())));
-        let password_service = Arc::new(RwLock::new(PasswordService::new()));
-        
+        let password_service = Arc::new(RwLock::new(PasswordService::new().with_clock(clock.clone())));
+        let record_service = RecordService::new(database.clone()).with_clock(clock.clone());
+        let invite_service = InviteService::new(database.clone()).with_clock(clock.clone());
+        let lock_service = LockService::new(database.clone());
+
         Self {
             database,
-            config,
+            config: Arc::new(std::sync::RwLock::new(Arc::new(config))),
             user_service,
             password_service,
+            record_service,
+            invite_service,
+            lock_service,
+            config_manager: None,
             bot_info: Arc::new(RwLock::new(None)),
+            clock,
         }
     }
 
+    /// 使用配置文件管理器，运行中修改的配置会写回配置文件
+    pub fn with_config_manager(mut self, config_manager: ConfigManager) -> Self {
+        self.config_manager = Some(Arc::new(RwLock::new(config_manager)));
+        self
+    }
+
+    /// 获取当前配置
+    pub fn config(&self) -> Arc<AppConfig> {
+        self.config.read().unwrap_or_else(PoisonError::into_inner).clone()
+    }
+
+    /// 修改运行中的配置
+    ///
+    /// 有配置文件管理器时先校验并写回配置文件，成功后再替换运行中的配置和用户服务的配置；
+    /// 期间持有用户服务的写锁，权限判断不会看到只更新了一半的配置。
+    pub async fn update_config<F>(&self, updater: F) -> Result<Arc<AppConfig>>
+    where
+        F: FnOnce(&mut AppConfig),
+    {
+        let mut user_service = self.user_service.write().await;
+
+        let updated = match self.config_manager {
+            Some(ref config_manager) => {
+                let mut config_manager = config_manager.write().await;
+                config_manager.update_config(updater)?;
+                config_manager.get_config().clone()
+            }
+            None => {
+                let mut config = self.config().as_ref().clone();
+                updater(&mut config);
+                config.validate()?;
+                config
+            }
+        };
+
+        let updated = Arc::new(updated);
+        *self.config.write().unwrap_or_else(PoisonError::into_inner) = updated.clone();
+        user_service.set_config(updated.as_ref().clone());
+        Ok(updated)
+    }
+
     /// 设置Bot信息
     pub async fn set_bot_info(&self, me: Me) {
         let mut bot_info = self.bot_info.write().await;
@@ -79,6 +145,9 @@ impl NekoHouseBot {
         // 创建状态
         let state = BotState::new(database, config);
 
+        // 升级到多门锁后首次启动时，按配置创建默认门锁
+        state.lock_service.ensure_default(&state.config()).await?;
+
         // 获取Bot信息
         let me = bot.get_me().await?;
         state.set_bot_info(me.clone()).await;
@@ -94,6 +163,21 @@ impl NekoHouseBot {
 
         let handler = self.create_handler();
 
+        // 发送端随run()一同释放，外部取消运行时清理任务也会退出
+        let (shutdown_tx, shutdown_rx) = watch::channel(false);
+        let sweeper = spawn_expiry_sweeper(
+            self.bot.clone(),
+            self.state.clone(),
+            Duration::from_secs(self.state.config().expiry_sweep_interval_secs),
+            shutdown_rx.clone(),
+        );
+        let escalation = spawn_escalation_watcher(
+            self.bot.clone(),
+            self.state.clone(),
+            Duration::from_secs(self.state.config().escalation_check_interval_secs),
+            shutdown_rx,
+        );
+
         Dispatcher::builder(self.bot, handler)
             .dependencies(dptree::deps![self.state])
             .default_handler(|upd| async move {
@@ -106,6 +190,16 @@ impl NekoHouseBot {
             .dispatch()
             .await;
 
+        let _ = shutdown_tx.send(true);
+        if let Err(e) = sweeper.await {
+            log::error!("过期授权清理任务异常退出: {}", e);
+        }
+        if let Some(escalation) = escalation {
+            if let Err(e) = escalation.await {
+                log::error!("审批升级检查任务异常退出: {}", e);
+            }
+        }
+
         log::info!("Bot已停止运行");
         Ok(())
     }
@@ -150,6 +244,12 @@ impl NekoHouseBot {
         &self.state
     }
 
+    /// 使用配置文件管理器，运行中修改的配置会写回配置文件
+    pub fn with_config_manager(mut self, config_manager: ConfigManager) -> Self {
+        self.state = self.state.with_config_manager(config_manager);
+        self
+    }
+
     /// 获取Bot实例
     pub fn bot(&self) -> &Bot {
         &self.bot
@@ -171,13 +271,27 @@ async fn handle_command(
     let bot_clone = bot.clone();
     
     let result = match cmd.clone() {
-        Command::Start => crate::handlers::start_command(bot, msg, cmd, state).await,
+        Command::Start(_) => crate::handlers::start_command(bot, msg, cmd, state).await,
         Command::AddAdmin(_) => crate::handlers::add_admin_command(bot, msg, cmd, state).await,
+        Command::ListAdmins => crate::handlers::list_admins_command(bot, msg, state).await,
+        Command::RemoveAdmin(_) => crate::handlers::remove_admin_command(bot, msg, cmd, state).await,
+        Command::AddSuper(_) => crate::handlers::add_super_command(bot, msg, cmd, state).await,
+        Command::RemoveSuper(_) => crate::handlers::remove_super_command(bot, msg, cmd, state).await,
         Command::EditPassword(_) => crate::handlers::edit_password_command(bot, msg, cmd, state).await,
-        Command::GenInvite => crate::handlers::gen_invite_command(bot, msg, state).await,
+        Command::GenInvite(_) => crate::handlers::gen_invite_command(bot, msg, cmd, state).await,
+        Command::Invites => crate::handlers::invites_command(bot, msg, state).await,
+        Command::Check(_) => crate::handlers::check_command(bot, msg, cmd, state).await,
         Command::Revoke(_) => crate::handlers::revoke_command(bot, msg, cmd, state).await,
         Command::Req(_) => crate::handlers::req_command(bot, msg, cmd, state).await,
         Command::GetPassword => crate::handlers::get_password_command(bot, msg, state).await,
+        Command::Status => crate::handlers::status_command(bot, msg, state).await,
+        Command::History => crate::handlers::history_command(bot, msg, state).await,
+        Command::Audit(_) => crate::handlers::audit_command(bot, msg, cmd, state).await,
+        Command::Locks => crate::handlers::locks_command(bot, msg, state).await,
+        Command::SetLock(_) => crate::handlers::set_lock_command(bot, msg, cmd, state).await,
+        Command::Calibrate(_) => crate::handlers::calibrate_command(bot, msg, cmd, state).await,
+        Command::Pending => crate::handlers::pending_command(bot, msg, state).await,
+        Command::MyVisitors => crate::handlers::my_visitors_command(bot, msg, state).await,
     };
     
     if let Err(e) = &result {
@@ -194,13 +308,29 @@ async fn handle_command(
 #[derive(BotCommands, Clone, Debug)]
 #[command(rename_rule = "lowercase")]
 pub enum Command {
-    /// 开始使用Bot
+    /// 开始使用Bot（邀请深度链接会附带start参数）
     #[command(description = "开始使用")]
-    Start,
+    Start(String),
     
     /// 添加管理员（超级管理员专用）
     #[command(description = "添加管理员 (超级管理员专用)")]
     AddAdmin(String),
+
+    /// 查看管理员列表（超级管理员专用）
+    #[command(description = "查看管理员 (超级管理员专用)")]
+    ListAdmins,
+
+    /// 删除管理员并撤销或转交其访客（超级管理员专用）
+    #[command(description = "删除管理员 (超级管理员专用)")]
+    RemoveAdmin(String),
+
+    /// 添加超级管理员（超级管理员专用）
+    #[command(description = "添加超级管理员 (超级管理员专用)")]
+    AddSuper(String),
+
+    /// 移除超级管理员（超级管理员专用）
+    #[command(description = "移除超级管理员 (超级管理员专用)")]
+    RemoveSuper(String),
     
     /// 修改管理员密码
     #[command(description = "修改密码")]
@@ -209,7 +339,15 @@ pub enum Command {
     
     /// 生成邀请码
     #[command(description = "生成邀请码")]
-    GenInvite,
+    GenInvite(String),
+
+    /// 查看和管理邀请码
+    #[command(description = "查看和管理邀请码")]
+    Invites,
+
+    /// 检查密码的类型和有效期
+    #[command(description = "检查密码")]
+    Check(String),
     
     /// 撤销授权
     #[command(description = "撤销授权")]
@@ -222,6 +360,38 @@ pub enum Command {
     /// 获取密码
     #[command(description = "获取密码")]
     GetPassword,
+
+    /// 查看当前授权状态
+    #[command(description = "查看授权状态")]
+    Status,
+
+    /// 查看历史授权记录
+    #[command(description = "查看历史记录")]
+    History,
+
+    /// 查看审计日志（超级管理员专用）
+    #[command(description = "查看审计日志 (超级管理员专用)")]
+    Audit(String),
+
+    /// 查看门锁列表
+    #[command(description = "查看门锁")]
+    Locks,
+
+    /// 添加或修改门锁（超级管理员专用）
+    #[command(description = "添加或修改门锁 (超级管理员专用)")]
+    SetLock(String),
+
+    /// 查看待审批请求
+    #[command(description = "查看待审批请求")]
+    Pending,
+
+    /// 查看访客的有效授权
+    #[command(description = "查看访客授权")]
+    MyVisitors,
+
+    /// 校准门锁时钟偏差（超级管理员专用）
+    #[command(description = "校准门锁时钟 (超级管理员专用)")]
+    Calibrate(String),
 }
 
 impl Command {
@@ -230,22 +400,42 @@ impl Command {
         use crate::types::UserRole;
         
         match self {
-            Command::AddAdmin(_) => UserRole::SuperAdmin,
-            Command::EditPassword(_) | Command::GenInvite | Command::Revoke(_) => UserRole::Admin,
-            Command::Start | Command::Req(_) | Command::GetPassword => UserRole::Visitor,
+            Command::AddAdmin(_) | Command::ListAdmins | Command::RemoveAdmin(_) | Command::AddSuper(_) | Command::RemoveSuper(_)
+            | Command::Audit(_) | Command::SetLock(_) | Command::Calibrate(_) => {
+                UserRole::SuperAdmin
+            }
+            Command::EditPassword(_) | Command::GenInvite(_) | Command::Invites | Command::Check(_) | Command::Revoke(_)
+            | Command::Locks | Command::Pending | Command::MyVisitors => UserRole::Admin,
+            Command::Start(_) | Command::Req(_) | Command::GetPassword | Command::Status | Command::History => {
+                UserRole::Visitor
+            }
         }
     }
     
     /// 获取命令描述
     pub fn description(&self) -> &'static str {
         match self {
-            Command::Start => "开始使用Bot",
+            Command::Start(_) => "开始使用Bot",
             Command::AddAdmin(_) => "添加管理员",
+            Command::ListAdmins => "查看管理员列表",
+            Command::RemoveAdmin(_) => "删除管理员",
+            Command::AddSuper(_) => "添加超级管理员",
+            Command::RemoveSuper(_) => "移除超级管理员",
             Command::EditPassword(_) => "修改密码", 
-            Command::GenInvite => "生成邀请码",
+            Command::GenInvite(_) => "生成邀请码",
+            Command::Invites => "查看和管理邀请码",
+            Command::Check(_) => "检查密码",
             Command::Revoke(_) => "撤销授权",
             Command::Req(_) => "申请访客授权",
             Command::GetPassword => "获取密码",
+            Command::Status => "查看授权状态",
+            Command::History => "查看历史记录",
+            Command::Audit(_) => "查看审计日志",
+            Command::Locks => "查看门锁",
+            Command::SetLock(_) => "添加或修改门锁",
+            Command::Calibrate(_) => "校准门锁时钟",
+            Command::Pending => "查看待审批请求",
+            Command::MyVisitors => "查看访客授权",
         }
     }
 }
@@ -255,18 +445,19 @@ mod tests {
     use super::*;
     use tempfile::NamedTempFile;
 
-    async fn create_test_config() -> Result<AppConfig> {
+    /// 返回的临时文件需在测试期间保持存活，否则数据库文件会被提前删除
+    async fn create_test_config() -> Result<(AppConfig, NamedTempFile)> {
         let temp_file = NamedTempFile::new()?;
         let mut config = AppConfig::default();
         config.database.path = temp_file.path().to_str().unwrap().to_string();
         config.telegram.bot_token = "123456789:ABCDEFGHIJKLMNOPQRSTUVWXYZ".to_string();
         config.super_admin_ids.push(123456789);
-        Ok(config)
+        Ok((config, temp_file))
     }
 
     #[tokio::test]
     async fn test_bot_state_creation() -> Result<()> {
-        let config = create_test_config().await?;
+        let (config, _temp_file) = create_test_config().await?;
         let database = Database::new(&config.get_database_url()).await?;
         let state = BotState::new(database, config);
 
@@ -280,11 +471,64 @@ mod tests {
         Ok(())
     }
 
+    #[tokio::test]
+    async fn test_update_config() -> Result<()> {
+        let (config, _temp_file) = create_test_config().await?;
+        let config_file = NamedTempFile::new()?;
+        config.save_to_file(config_file.path())?;
+
+        let database = Database::new(&config.get_database_url()).await?;
+        let state = BotState::new(database, config)
+            .with_config_manager(ConfigManager::new(config_file.path().to_str().unwrap())?);
+        let handler_state = state.clone();
+
+        // 其他处理器持有的状态副本和用户服务同时看到新的超级管理员，并写回配置文件
+        state.update_config(|config| config.add_super_admin(987654321)).await?;
+        assert!(handler_state.config().is_super_admin(987654321));
+        assert!(handler_state.user_service.read().await.is_super_admin(987654321));
+        assert!(AppConfig::from_file(config_file.path())?.is_super_admin(987654321));
+
+        state.update_config(|config| config.remove_super_admin(123456789)).await?;
+        assert_eq!(handler_state.config().super_admin_ids, vec![987654321]);
+
+        // 不能移除最后一位超级管理员，失败时运行中的配置保持不变
+        let result = state.update_config(|config| config.remove_super_admin(987654321)).await;
+        assert!(result.is_err());
+        assert!(handler_state.config().is_super_admin(987654321));
+        assert!(handler_state.user_service.read().await.is_super_admin(987654321));
+        assert!(AppConfig::from_file(config_file.path())?.is_super_admin(987654321));
+
+        Ok(())
+    }
+
     #[test]
     fn test_command_permissions() {
-        assert_eq!(Command::Start.required_role(), crate::types::UserRole::Visitor);
-        assert_eq!(Command::AddAdmin(123).required_role(), crate::types::UserRole::SuperAdmin);
+        assert_eq!(Command::Start(String::new()).required_role(), crate::types::UserRole::Visitor);
+        assert_eq!(Command::Status.required_role(), crate::types::UserRole::Visitor);
+        assert_eq!(Command::History.required_role(), crate::types::UserRole::Visitor);
+        assert_eq!(Command::AddAdmin("123".to_string()).required_role(), crate::types::UserRole::SuperAdmin);
+        assert_eq!(Command::Audit(String::new()).required_role(), crate::types::UserRole::SuperAdmin);
+        assert_eq!(Command::ListAdmins.required_role(), crate::types::UserRole::SuperAdmin);
+        assert_eq!(Command::AddSuper("123".to_string()).required_role(), crate::types::UserRole::SuperAdmin);
+        assert_eq!(Command::RemoveSuper("123".to_string()).required_role(), crate::types::UserRole::SuperAdmin);
+        assert_eq!(Command::RemoveAdmin("123 revoke".to_string()).required_role(), crate::types::UserRole::SuperAdmin);
         assert_eq!(Command::EditPassword("1234".to_string()).required_role(), crate::types::UserRole::Admin);
-        assert_eq!(Command::GenInvite.required_role(), crate::types::UserRole::Admin);
+        assert_eq!(Command::GenInvite(String::new()).required_role(), crate::types::UserRole::Admin);
+        assert_eq!(Command::Invites.required_role(), crate::types::UserRole::Admin);
+        assert_eq!(Command::Check("5012345678".to_string()).required_role(), crate::types::UserRole::Admin);
+        assert_eq!(Command::Locks.required_role(), crate::types::UserRole::Admin);
+        assert_eq!(Command::Pending.required_role(), crate::types::UserRole::Admin);
+        assert_eq!(Command::MyVisitors.required_role(), crate::types::UserRole::Admin);
+        assert_eq!(Command::SetLock("大门".to_string()).required_role(), crate::types::UserRole::SuperAdmin);
+        assert_eq!(Command::Calibrate(String::new()).required_role(), crate::types::UserRole::SuperAdmin);
+    }
+
+    #[test]
+    fn test_start_payload_parsing() {
+        let cmd = Command::parse("/start Ag4hX2zLQb6Gd4nEM5xYvA", "nekohouse_bot").unwrap();
+        assert!(matches!(cmd, Command::Start(ref payload) if payload == "Ag4hX2zLQb6Gd4nEM5xYvA"));
+
+        let cmd = Command::parse("/start", "nekohouse_bot").unwrap();
+        assert!(matches!(cmd, Command::Start(ref payload) if payload.is_empty()));
     }
 }
\ No newline at end of file
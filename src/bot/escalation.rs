@@ -0,0 +1,257 @@
+//! 待审批请求超时升级
+//!
+//! 邀请管理员在配置的时间内没有处理访客请求时，把同样的批准/拒绝按钮
+//! 转发给超级管理员（或配置的代理人）。任何一方先作出的决定生效，
+//! 其余审批消息随后被编辑为处理结果。升级状态保存在数据库中，重启后不会重复发送；
+//! 升级消息全部发送失败时不保留升级状态，下次检查时重试。未注册为管理员的代理人
+//! 无法审批，不会收到升级消息；除邀请管理员外没有其他接收人的请求不会被占用升级状态。
+
+use crate::bot::bot::BotState;
+use crate::bot::periodic::spawn_periodic;
+use crate::database::{AdminRepository, ApprovalRepository, InviteRepository, RecordRepository};
+use crate::error::Result;
+use crate::handlers::start::get_user_display_name;
+use crate::handlers::visitor::approval_keyboard;
+use crate::types::{ApprovalMessage, AuthStatus, Record};
+use crate::utils::LockTimezone;
+use chrono::{DateTime, Utc};
+use std::time::Duration;
+use teloxide::prelude::*;
+use teloxide::types::{MessageId, User};
+use tokio::sync::watch;
+use tokio::task::JoinHandle;
+
+/// 启动审批升级检查任务，超时设置为0时不启动
+pub fn spawn_escalation_watcher(
+    bot: Bot,
+    state: BotState,
+    interval: Duration,
+    shutdown: watch::Receiver<bool>,
+) -> Option<JoinHandle<()>> {
+    if state.config().escalation_timeout_secs == 0 {
+        log::info!("审批升级已关闭");
+        return None;
+    }
+
+    Some(spawn_periodic("审批升级检查", interval, shutdown, move || {
+        let bot = bot.clone();
+        let state = state.clone();
+        async move { escalate_pending(&bot, &state).await.map(|_| ()) }
+    }))
+}
+
+/// 升级所有超时未处理的请求，返回本次升级的记录数
+pub async fn escalate_pending(bot: &Bot, state: &BotState) -> Result<usize> {
+    let timeout = chrono::Duration::seconds(state.config().escalation_timeout_secs as i64);
+    let due = RecordRepository::find_pending_for_escalation(state.database.pool(), state.clock.now() - timeout).await?;
+
+    let mut escalated = 0;
+    for record in due {
+        // 单条记录失败不影响其余记录
+        match escalate_one(bot, state, &record).await {
+            Ok(true) => escalated += 1,
+            Ok(false) => {}
+            Err(e) => log::error!("升级授权请求 {} 失败: {}", record.unique_id, e),
+        }
+    }
+
+    Ok(escalated)
+}
+
+/// 占用升级状态并转发单条请求，返回是否有消息送达
+async fn escalate_one(bot: &Bot, state: &BotState, record: &Record) -> Result<bool> {
+    let pool = state.database.pool();
+    let inviter = AdminRepository::find_by_unique_id(pool, record.inviter).await?;
+    let targets = state
+        .user_service
+        .read()
+        .await
+        .escalation_recipients(inviter.as_ref().map(|admin| admin.id))
+        .await?;
+    if targets.is_empty() {
+        // 不占用升级状态，之后新增超级管理员或代理人时仍可升级
+        log::debug!("授权请求 {} 没有可以审批的升级接收人", record.unique_id);
+        return Ok(false);
+    }
+
+    let invite = match record.invite_id {
+        Some(invite_id) => InviteRepository::find_by_id(pool, invite_id).await?,
+        None => None,
+    };
+    let message = format_escalation_notice(
+        record,
+        inviter.as_ref().map(|admin| admin.id),
+        invite.map(|invite| invite.display_name()),
+        &state.config().lock_timezone,
+        state.clock.now(),
+    );
+
+    // 先在数据库中占用升级状态再发送，避免重启或并发时重复升级
+    let mut tx = state.database.begin_transaction().await?;
+    let still_pending = RecordRepository::find_by_id_in_tx(&mut tx, record.unique_id)
+        .await?
+        .is_some_and(|r| r.status == AuthStatus::Pending);
+    if !still_pending || !ApprovalRepository::mark_escalated(&mut tx, record.unique_id).await? {
+        return Ok(false);
+    }
+    tx.commit().await?;
+
+    // 没有任何消息送达时释放升级状态，下次检查时重试
+    let delivered = escalate_record(bot, state, record.unique_id, &message, &targets).await;
+    if delivered == 0 {
+        let mut tx = state.database.begin_transaction().await?;
+        ApprovalRepository::unmark_escalated(&mut tx, record.unique_id).await?;
+        tx.commit().await?;
+    }
+
+    Ok(delivered > 0)
+}
+
+/// 将单条请求转发给升级接收人，返回送达的消息数
+///
+/// 消息送达后即使记录失败也计入送达数，避免释放升级状态后重复发送。
+async fn escalate_record(bot: &Bot, state: &BotState, record_id: i64, message: &str, targets: &[i64]) -> usize {
+    let mut delivered = 0;
+    for &target in targets {
+        match bot
+            .send_message(ChatId(target), message)
+            .reply_markup(approval_keyboard(record_id))
+            .await
+        {
+            Ok(sent) => {
+                delivered += 1;
+                let approval = ApprovalMessage::new(record_id, target, sent.id.0, true);
+                if let Err(e) = save_approval_message(state, &approval).await {
+                    log::error!("记录授权请求 {} 发给 {} 的升级消息失败: {}", record_id, target, e);
+                }
+            }
+            Err(e) => log::warn!("无法将授权请求 {} 升级给 {}: {}", record_id, target, e),
+        }
+    }
+
+    log::info!("授权请求 {} 等待超时，已升级给 {} 位管理员", record_id, delivered);
+    delivered
+}
+
+/// 保存升级后发送的审批消息，请求处理后用于同步更新
+async fn save_approval_message(state: &BotState, approval: &ApprovalMessage) -> Result<()> {
+    let mut tx = state.database.begin_transaction().await?;
+    ApprovalRepository::create_message(&mut tx, approval).await?;
+    tx.commit().await?;
+    Ok(())
+}
+
+/// 请求被处理后，把其他管理员手中的审批消息改为处理结果
+///
+/// `acted_on` 为处理人操作的消息，由调用方自行编辑，这里跳过。
+pub async fn close_approval_messages(
+    bot: &Bot,
+    state: &BotState,
+    record_id: i64,
+    actor: &User,
+    outcome: &str,
+    acted_on: Option<(ChatId, MessageId)>,
+) -> Result<()> {
+    let messages = ApprovalRepository::find_messages_by_record(state.database.pool(), record_id).await?;
+    let text = format_closed_notice(
+        record_id,
+        &get_user_display_name(actor),
+        outcome,
+        &state.config().lock_timezone,
+        state.clock.now(),
+    );
+
+    for message in messages {
+        let target = (ChatId(message.chat_id), MessageId(message.message_id));
+        if acted_on == Some(target) {
+            continue;
+        }
+
+        // 消息可能已被删除或内容相同，编辑失败不影响处理结果
+        if let Err(e) = bot.edit_message_text(target.0, target.1, text.clone()).await {
+            log::debug!("更新审批消息 {:?} 失败: {}", target, e);
+        }
+    }
+
+    Ok(())
+}
+
+/// 生成升级通知
+fn format_escalation_notice(
+    record: &Record,
+    inviter_id: Option<i64>,
+    invite_name: Option<String>,
+    timezone: &LockTimezone,
+    now: DateTime<Utc>,
+) -> String {
+    let waited = (now - record.updated_at).num_minutes();
+
+    format!(
+        "⏫ 访客授权请求超时未处理，已转给您审批\n\n\
+         🆔 访客ID：{}\n\
+         🕐 申请时间：{}\n\
+         🎫 邀请码：{}\n\
+         👤 邀请管理员：{}\n\
+         📝 记录ID：{}\n\n\
+         ⏰ 已等待 {} 分钟，任一管理员处理后其他审批消息会同步更新",
+        record.vis_id,
+        timezone.format(record.updated_at),
+        invite_name.unwrap_or_else(|| "未知".to_string()),
+        inviter_id.map(|id| format!("ID {}", id)).unwrap_or_else(|| "未知".to_string()),
+        record.unique_id,
+        waited
+    )
+}
+
+/// 生成请求已被处理的通知
+fn format_closed_notice(
+    record_id: i64,
+    actor_name: &str,
+    outcome: &str,
+    timezone: &LockTimezone,
+    now: DateTime<Utc>,
+) -> String {
+    format!(
+        "ℹ️ 该授权请求已被处理\n\n\
+         📝 记录ID：{}\n\
+         👤 处理人：{}\n\
+         📋 结果：{}\n\
+         🕐 处理时间：{}",
+        record_id,
+        actor_name,
+        outcome,
+        timezone.format(now)
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    #[test]
+    fn test_escalation_notices() {
+        let mut record = Record::new(987654321, 1);
+        record.unique_id = 42;
+        record.updated_at = Utc.with_ymd_and_hms(2024, 1, 1, 12, 0, 0).unwrap();
+
+        let timezone = LockTimezone::default();
+        let now = record.updated_at + chrono::Duration::minutes(20);
+        let notice = format_escalation_notice(&record, Some(123456789), Some("保洁".to_string()), &timezone, now);
+        assert!(notice.contains("访客ID：987654321"));
+        // 北京时间显示
+        assert!(notice.contains("2024-01-01 20:00:00"));
+        assert!(notice.contains("邀请码：保洁"));
+        assert!(notice.contains("ID 123456789"));
+        assert!(notice.contains("记录ID：42"));
+        assert!(notice.contains("已等待 20 分钟"));
+
+        let notice = format_escalation_notice(&record, None, None, &timezone, now);
+        assert!(notice.contains("邀请管理员：未知"));
+
+        let closed = format_closed_notice(42, "@admin", "已拒绝", &timezone, now);
+        assert!(closed.contains("处理人：@admin"));
+        assert!(closed.contains("结果：已拒绝"));
+        assert!(closed.contains("2024-01-01 20:20:00"));
+    }
+}
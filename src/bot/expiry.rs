@@ -0,0 +1,151 @@
+//! 过期授权后台清理任务
+
+use crate::bot::bot::BotState;
+use crate::bot::periodic::spawn_periodic;
+use crate::database::AdminRepository;
+use crate::error::Result;
+use crate::types::{Admin, Record};
+use crate::utils::LockTimezone;
+use std::future::Future;
+use std::time::Duration;
+use teloxide::prelude::*;
+use tokio::sync::watch;
+use tokio::task::JoinHandle;
+
+/// 启动过期授权清理任务
+///
+/// 任务按固定间隔运行，`shutdown` 收到 `true` 或发送端被丢弃时退出。
+pub fn spawn_expiry_sweeper(
+    bot: Bot,
+    state: BotState,
+    interval: Duration,
+    shutdown: watch::Receiver<bool>,
+) -> JoinHandle<()> {
+    spawn_periodic("过期授权清理", interval, shutdown, move || {
+        let bot = bot.clone();
+        let state = state.clone();
+        async move { sweep_expired(&bot, &state).await.map(|_| ()) }
+    })
+}
+
+/// 执行一次过期清理并发送通知，返回本次过期的记录数
+pub async fn sweep_expired(bot: &Bot, state: &BotState) -> Result<usize> {
+    let expired = state.record_service.expire_due().await?;
+
+    let pool = state.database.pool();
+    let notices = expiry_notices(&expired, &state.config().lock_timezone, |inviter| {
+        AdminRepository::find_by_unique_id(pool, inviter)
+    })
+    .await;
+
+    // 通知失败不影响状态变更
+    for (chat_id, notice) in notices {
+        if let Err(e) = bot.send_message(chat_id, notice).await {
+            log::warn!("无法向 {} 发送授权到期通知: {}", chat_id, e);
+        }
+    }
+
+    Ok(expired.len())
+}
+
+/// 生成过期记录的访客和邀请管理员通知
+///
+/// 记录已经标记为过期，下次清理不会再处理，因此查询邀请管理员失败时
+/// 只跳过该管理员的通知，其余记录照常通知。
+async fn expiry_notices<F, Fut>(records: &[Record], timezone: &LockTimezone, find_admin: F) -> Vec<(ChatId, String)>
+where
+    F: Fn(i64) -> Fut,
+    Fut: Future<Output = Result<Option<Admin>>>,
+{
+    let mut notices = Vec::new();
+    for record in records {
+        log::info!("授权记录 {} 已过期 (访客 {})", record.unique_id, record.vis_id);
+        notices.push((ChatId(record.vis_id), format_visitor_notice(record, timezone)));
+
+        match find_admin(record.inviter).await {
+            Ok(Some(admin)) => notices.push((ChatId(admin.id), format_admin_notice(record, timezone))),
+            Ok(None) => log::warn!("授权记录 {} 的邀请管理员不存在", record.unique_id),
+            Err(e) => log::error!("查询授权记录 {} 的邀请管理员失败: {}", record.unique_id, e),
+        }
+    }
+    notices
+}
+
+/// 生成发给访客的到期通知
+fn format_visitor_notice(record: &Record, timezone: &LockTimezone) -> String {
+    format!(
+        "⌛ 您的访问授权已结束\n\n\
+        🔑 授权类型：{}\n\
+        ⏰ 结束时间：{}\n\n\
+        如需再次访问，请重新申请授权",
+        record.auth_type.description(),
+        record.ended_time.map(|t| timezone.format(t)).unwrap_or_default()
+    )
+}
+
+/// 生成发给邀请管理员的到期通知
+fn format_admin_notice(record: &Record, timezone: &LockTimezone) -> String {
+    format!(
+        "⌛ 访客授权已到期\n\n\
+        📝 记录ID：{}\n\
+        👤 访客ID：{}\n\
+        🔑 授权类型：{}\n\
+        ⏰ 结束时间：{}",
+        record.unique_id,
+        record.vis_id,
+        record.auth_type.description(),
+        record.ended_time.map(|t| timezone.format(t)).unwrap_or_default()
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error::AppError;
+    use crate::types::{AuthStatus, AuthType};
+    use chrono::{TimeZone, Utc};
+
+    #[test]
+    fn test_expiry_notices() {
+        let mut record = Record::new(987654321, 1);
+        record.unique_id = 42;
+        record.status = AuthStatus::Auth;
+        record.auth_type = AuthType::Limited;
+        record.ended_time = Some(Utc.with_ymd_and_hms(2024, 1, 1, 12, 0, 0).unwrap());
+
+        let visitor = format_visitor_notice(&record, &LockTimezone::default());
+        assert!(visitor.contains("时效密码"));
+        // 默认按北京时间显示
+        assert!(visitor.contains("2024-01-01 20:00:00"));
+
+        let admin = format_admin_notice(&record, &"America/New_York".parse().unwrap());
+        assert!(admin.contains("记录ID：42"));
+        assert!(admin.contains("访客ID：987654321"));
+        assert!(admin.contains("2024-01-01 07:00:00"));
+    }
+
+    #[tokio::test]
+    async fn test_expiry_notices_survive_admin_lookup_errors() {
+        let expired: Vec<Record> = (1..=3)
+            .map(|inviter| {
+                let mut record = Record::new(987654320 + inviter, inviter);
+                record.unique_id = inviter;
+                record
+            })
+            .collect();
+
+        // 第一条记录的邀请管理员查询失败，后续记录仍需通知
+        let notices = expiry_notices(&expired, &LockTimezone::default(), |inviter| async move {
+            match inviter {
+                1 => Err(AppError::business("数据库错误")),
+                2 => Ok(Some(Admin::new(111))),
+                _ => Ok(None),
+            }
+        })
+        .await;
+
+        let recipients: Vec<i64> = notices.iter().map(|(chat_id, _)| chat_id.0).collect();
+        assert_eq!(recipients, vec![987654321, 987654322, 111, 987654323]);
+        assert!(notices[2].1.contains("记录ID：2"));
+    }
+}
@@ -1,6 +1,9 @@
 //! Telegram Bot模块
 
 pub mod bot;
+pub mod escalation;
+pub mod expiry;
+pub mod periodic;
 
 // 重新导出主要组件
 pub use bot::NekoHouseBot;
\ No newline at end of file
@@ -0,0 +1,45 @@
+//! 后台定时任务
+
+use crate::error::Result;
+use std::future::Future;
+use std::time::Duration;
+use tokio::sync::watch;
+use tokio::task::JoinHandle;
+
+/// 按固定间隔运行后台任务
+///
+/// 单次执行失败只记录日志，不会终止任务；`shutdown` 收到 `true` 或发送端被丢弃时退出。
+pub fn spawn_periodic<F, Fut>(
+    name: &'static str,
+    interval: Duration,
+    mut shutdown: watch::Receiver<bool>,
+    mut task: F,
+) -> JoinHandle<()>
+where
+    F: FnMut() -> Fut + Send + 'static,
+    Fut: Future<Output = Result<()>> + Send,
+{
+    tokio::spawn(async move {
+        log::info!("{}任务已启动，间隔 {} 秒", name, interval.as_secs());
+
+        let mut ticker = tokio::time::interval(interval);
+        ticker.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+
+        loop {
+            tokio::select! {
+                _ = ticker.tick() => {
+                    if let Err(e) = task().await {
+                        log::error!("{}任务执行失败: {}", name, e);
+                    }
+                }
+                changed = shutdown.changed() => {
+                    if changed.is_err() || *shutdown.borrow() {
+                        break;
+                    }
+                }
+            }
+        }
+
+        log::info!("{}任务已停止", name);
+    })
+}
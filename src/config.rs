@@ -1,6 +1,7 @@
 //! 配置管理模块
 
 use crate::error::{AppError, Result};
+use crate::utils::gen_password::LockTimezone;
 use serde::{Deserialize, Serialize};
 use std::path::Path;
 
@@ -13,8 +14,38 @@ pub struct AppConfig {
     pub telegram: TelegramConfig,
     /// 超级管理员ID列表
     pub super_admin_ids: Vec<i64>,
-    /// 时间偏移（用于密码生成加密）
+    /// 时间偏移（用于密码生成加密），首次启动时作为默认门锁的时间偏移
     pub time_offset: i64,
+    /// 门锁所在时区（IANA时区名或固定偏移），用于所有时间显示，首次启动时作为默认门锁的时区，默认UTC+8
+    #[serde(default)]
+    pub lock_timezone: LockTimezone,
+    /// 过期授权清理间隔（秒）
+    #[serde(default = "default_expiry_sweep_interval_secs")]
+    pub expiry_sweep_interval_secs: u64,
+    /// 待审批请求无人处理多久后升级（秒），0表示不升级
+    #[serde(default = "default_escalation_timeout_secs")]
+    pub escalation_timeout_secs: u64,
+    /// 检查待审批请求是否超时的间隔（秒）
+    #[serde(default = "default_escalation_check_interval_secs")]
+    pub escalation_check_interval_secs: u64,
+    /// 审批升级的接收人（需为管理员），为空时发送给所有超级管理员
+    #[serde(default)]
+    pub escalation_admin_ids: Vec<i64>,
+}
+
+/// 默认每分钟检查一次过期授权
+fn default_expiry_sweep_interval_secs() -> u64 {
+    60
+}
+
+/// 默认待审批请求15分钟无人处理后升级
+fn default_escalation_timeout_secs() -> u64 {
+    900
+}
+
+/// 默认每分钟检查一次待审批请求是否超时
+fn default_escalation_check_interval_secs() -> u64 {
+    60
 }
 
 /// 数据库配置
@@ -61,9 +92,26 @@ impl AppConfig {
             return Err(AppError::validation("至少需要配置一个超级管理员"));
         }
 
+        if self.expiry_sweep_interval_secs == 0 {
+            return Err(AppError::validation("过期授权清理间隔必须大于0"));
+        }
+
+        if self.escalation_check_interval_secs == 0 {
+            return Err(AppError::validation("审批升级检查间隔必须大于0"));
+        }
+
         Ok(())
     }
 
+    /// 获取审批升级的接收人
+    pub fn escalation_targets(&self) -> &[i64] {
+        if self.escalation_admin_ids.is_empty() {
+            &self.super_admin_ids
+        } else {
+            &self.escalation_admin_ids
+        }
+    }
+
     /// 检查用户是否为超级管理员
     pub fn is_super_admin(&self, user_id: i64) -> bool {
         self.super_admin_ids.contains(&user_id)
@@ -103,6 +151,11 @@ impl Default for AppConfig {
             },
             super_admin_ids: Vec::new(),
             time_offset: 0,
+            lock_timezone: LockTimezone::default(),
+            expiry_sweep_interval_secs: default_expiry_sweep_interval_secs(),
+            escalation_timeout_secs: default_escalation_timeout_secs(),
+            escalation_check_interval_secs: default_escalation_check_interval_secs(),
+            escalation_admin_ids: Vec::new(),
         }
     }
 }
@@ -142,14 +195,16 @@ impl ConfigManager {
         &self.config
     }
 
-    /// 更新配置
+    /// 更新配置，校验或保存失败时保持原配置不变
     pub fn update_config<F>(&mut self, updater: F) -> Result<()>
     where
         F: FnOnce(&mut AppConfig),
     {
-        updater(&mut self.config);
-        self.config.validate()?;
-        self.config.save_to_file(&self.config_path)?;
+        let mut config = self.config.clone();
+        updater(&mut config);
+        config.validate()?;
+        config.save_to_file(&self.config_path)?;
+        self.config = config;
         Ok(())
     }
 
@@ -213,6 +268,72 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn test_expiry_sweep_interval_default() -> Result<()> {
+        // 旧配置文件没有该字段时使用默认值
+        let json = r#"{
+            "database": { "path": "./data/nekohouse.db" },
+            "telegram": { "bot_token": "test_token" },
+            "super_admin_ids": [123456789],
+            "time_offset": 0
+        }"#;
+        let mut config: AppConfig = serde_json::from_str(json)?;
+        assert_eq!(config.expiry_sweep_interval_secs, 60);
+        assert!(config.validate().is_ok());
+
+        config.expiry_sweep_interval_secs = 0;
+        assert!(config.validate().is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_escalation_targets() -> Result<()> {
+        let json = r#"{
+            "database": { "path": "./data/nekohouse.db" },
+            "telegram": { "bot_token": "test_token" },
+            "super_admin_ids": [123456789, 987654321],
+            "time_offset": 0
+        }"#;
+        let mut config: AppConfig = serde_json::from_str(json)?;
+        assert_eq!(config.escalation_timeout_secs, 900);
+        assert_eq!(config.escalation_check_interval_secs, 60);
+        assert!(config.validate().is_ok());
+        // 未配置代理人时升级给所有超级管理员
+        assert_eq!(config.escalation_targets(), &[123456789, 987654321]);
+
+        config.escalation_admin_ids = vec![555666777];
+        assert_eq!(config.escalation_targets(), &[555666777]);
+
+        config.escalation_check_interval_secs = 0;
+        assert!(config.validate().is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_lock_timezone() -> Result<()> {
+        let json = r#"{
+            "database": { "path": "./data/nekohouse.db" },
+            "telegram": { "bot_token": "test_token" },
+            "super_admin_ids": [123456789],
+            "time_offset": 0
+        }"#;
+        let config: AppConfig = serde_json::from_str(json)?;
+        assert_eq!(config.lock_timezone, LockTimezone::default());
+
+        let json = json.replace(r#""time_offset": 0"#, r#""time_offset": 0, "lock_timezone": "Europe/Berlin""#);
+        let config: AppConfig = serde_json::from_str(&json)?;
+        assert_eq!(config.lock_timezone.to_string(), "Europe/Berlin");
+        assert!(serde_json::to_string(&config)?.contains(r#""lock_timezone":"Europe/Berlin""#));
+
+        // 无效的时区在加载时报错
+        let json = json.replace("Europe/Berlin", "Europe/Nowhere");
+        assert!(serde_json::from_str::<AppConfig>(&json).is_err());
+
+        Ok(())
+    }
+
     #[test]
     fn test_config_manager() -> Result<()> {
         let temp_file = NamedTempFile::new()?;
@@ -231,6 +352,12 @@ mod tests {
         
         assert_eq!(manager.get_config().telegram.bot_token, "updated_token");
         assert!(manager.get_config().is_super_admin(987654321));
+
+        // 校验失败时内存和文件中的配置都保持不变
+        let result = manager.update_config(|config| config.super_admin_ids.clear());
+        assert!(result.is_err());
+        assert!(manager.get_config().is_super_admin(987654321));
+        assert!(AppConfig::from_file(temp_file.path())?.is_super_admin(987654321));
         
         Ok(())
     }
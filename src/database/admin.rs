@@ -4,27 +4,32 @@ use crate::error::{AppError, Result};
 use crate::types::Admin;
 
 use sqlx::{Row, Sqlite, Transaction};
-use uuid::Uuid;
 
 /// 管理员数据库操作
 pub struct AdminRepository;
 
 impl AdminRepository {
-    /// 创建新管理员
+    /// 创建新管理员，已删除的管理员重新添加时恢复原记录并清空管理密码
     pub async fn create(tx: &mut Transaction<'_, Sqlite>, admin: &Admin) -> Result<i64> {
-        let result = sqlx::query(
+        let row = sqlx::query(
             r#"
-            INSERT INTO admin (id, password, invite_code, updated_at)
-            VALUES (?, ?, ?, CURRENT_TIMESTAMP)
+            INSERT INTO admin (id, password, updated_at)
+            VALUES (?, ?, CURRENT_TIMESTAMP)
+            ON CONFLICT (id) DO UPDATE
+            SET password = excluded.password, removed_at = NULL, updated_at = CURRENT_TIMESTAMP
+            WHERE admin.removed_at IS NOT NULL
+            RETURNING unique_id
             "#,
         )
         .bind(admin.id)
         .bind(&admin.password)
-        .bind(&admin.invite_code)
-        .execute(&mut **tx)
+        .fetch_optional(&mut **tx)
         .await?;
 
-        Ok(result.last_insert_rowid())
+        match row {
+            Some(row) => Ok(row.get("unique_id")),
+            None => Err(AppError::business("该用户已经是管理员")),
+        }
     }
 
     /// 通过Telegram ID查找管理员
@@ -34,9 +39,9 @@ impl AdminRepository {
     ) -> Result<Option<Admin>> {
         let row = sqlx::query(
             r#"
-            SELECT unique_id, id, password, invite_code
+            SELECT unique_id, id, password
             FROM admin
-            WHERE id = ?
+            WHERE id = ? AND removed_at IS NULL
             "#,
         )
         .bind(telegram_id)
@@ -48,21 +53,20 @@ impl AdminRepository {
                 unique_id: row.get("unique_id"),
                 id: row.get("id"),
                 password: row.get("password"),
-                invite_code: row.get("invite_code"),
             }))
         } else {
             Ok(None)
         }
     }
 
-    /// 通过unique_id查找管理员
+    /// 通过unique_id查找管理员（包括已删除的管理员，用于追溯历史记录）
     pub async fn find_by_unique_id(
         pool: &sqlx::Pool<Sqlite>,
         unique_id: i64,
     ) -> Result<Option<Admin>> {
         let row = sqlx::query(
             r#"
-            SELECT unique_id, id, password, invite_code
+            SELECT unique_id, id, password
             FROM admin
             WHERE unique_id = ?
             "#,
@@ -76,35 +80,6 @@ impl AdminRepository {
                 unique_id: row.get("unique_id"),
                 id: row.get("id"),
                 password: row.get("password"),
-                invite_code: row.get("invite_code"),
-            }))
-        } else {
-            Ok(None)
-        }
-    }
-
-    /// 通过邀请码查找管理员
-    pub async fn find_by_invite_code(
-        pool: &sqlx::Pool<Sqlite>,
-        invite_code: &str,
-    ) -> Result<Option<Admin>> {
-        let row = sqlx::query(
-            r#"
-            SELECT unique_id, id, password, invite_code
-            FROM admin
-            WHERE invite_code = ?
-            "#,
-        )
-        .bind(invite_code)
-        .fetch_optional(pool)
-        .await?;
-
-        if let Some(row) = row {
-            Ok(Some(Admin {
-                unique_id: row.get("unique_id"),
-                id: row.get("id"),
-                password: row.get("password"),
-                invite_code: row.get("invite_code"),
             }))
         } else {
             Ok(None)
@@ -137,38 +112,28 @@ impl AdminRepository {
         Ok(result.rows_affected() > 0)
     }
 
-    /// 生成新的邀请码
-    pub async fn generate_invite_code(
-        tx: &mut Transaction<'_, Sqlite>,
-        unique_id: i64,
-    ) -> Result<String> {
-        let new_invite_code = Uuid::new_v4().to_string();
-
+    /// 删除管理员
+    pub async fn delete(tx: &mut Transaction<'_, Sqlite>, unique_id: i64) -> Result<bool> {
         let result = sqlx::query(
             r#"
-            UPDATE admin
-            SET invite_code = ?, updated_at = CURRENT_TIMESTAMP
+            DELETE FROM admin
             WHERE unique_id = ?
             "#,
         )
-        .bind(&new_invite_code)
         .bind(unique_id)
         .execute(&mut **tx)
         .await?;
 
-        if result.rows_affected() > 0 {
-            Ok(new_invite_code)
-        } else {
-            Err(AppError::business("管理员不存在"))
-        }
+        Ok(result.rows_affected() > 0)
     }
 
-    /// 删除管理员
-    pub async fn delete(tx: &mut Transaction<'_, Sqlite>, unique_id: i64) -> Result<bool> {
+    /// 标记管理员已删除并清空管理密码，记录仍保留供历史记录引用
+    pub async fn mark_removed(tx: &mut Transaction<'_, Sqlite>, unique_id: i64) -> Result<bool> {
         let result = sqlx::query(
             r#"
-            DELETE FROM admin
-            WHERE unique_id = ?
+            UPDATE admin
+            SET removed_at = CURRENT_TIMESTAMP, password = NULL, updated_at = CURRENT_TIMESTAMP
+            WHERE unique_id = ? AND removed_at IS NULL
             "#,
         )
         .bind(unique_id)
@@ -182,8 +147,9 @@ impl AdminRepository {
     pub async fn list_all(pool: &sqlx::Pool<Sqlite>) -> Result<Vec<Admin>> {
         let rows = sqlx::query(
             r#"
-            SELECT unique_id, id, password, invite_code
+            SELECT unique_id, id, password
             FROM admin
+            WHERE removed_at IS NULL
             ORDER BY unique_id ASC
             "#,
         )
@@ -196,7 +162,6 @@ impl AdminRepository {
                 unique_id: row.get("unique_id"),
                 id: row.get("id"),
                 password: row.get("password"),
-                invite_code: row.get("invite_code"),
             })
             .collect();
 
@@ -210,7 +175,7 @@ impl AdminRepository {
     ) -> Result<bool> {
         let result = sqlx::query(
             r#"
-            SELECT 1 FROM admin WHERE id = ? LIMIT 1
+            SELECT 1 FROM admin WHERE id = ? AND removed_at IS NULL LIMIT 1
             "#,
         )
         .bind(telegram_id)
@@ -222,7 +187,7 @@ impl AdminRepository {
 
     /// 统计管理员数量
     pub async fn count(pool: &sqlx::Pool<Sqlite>) -> Result<i64> {
-        let row = sqlx::query("SELECT COUNT(*) as count FROM admin")
+        let row = sqlx::query("SELECT COUNT(*) as count FROM admin WHERE removed_at IS NULL")
             .fetch_one(pool)
             .await?;
 
@@ -282,15 +247,16 @@ mod tests {
     use crate::database::Database;
     use tempfile::NamedTempFile;
 
-    async fn setup_test_db() -> Result<Database> {
+    /// 返回的临时文件需在测试期间保持存活，否则数据库文件会被提前删除
+    async fn setup_test_db() -> Result<(Database, NamedTempFile)> {
         let temp_file = NamedTempFile::new()?;
         let db_url = format!("sqlite:{}", temp_file.path().to_str().unwrap());
-        Database::new(&db_url).await
+        Ok((Database::new(&db_url).await?, temp_file))
     }
 
     #[tokio::test]
     async fn test_admin_crud() -> Result<()> {
-        let db = setup_test_db().await?;
+        let (db, _temp_file) = setup_test_db().await?;
         let pool = db.pool();
 
         // 创建管理员
@@ -317,16 +283,6 @@ mod tests {
         let verified = AdminRepository::verify_password(pool, unique_id, "1234").await?;
         assert!(verified);
 
-        // 生成邀请码
-        let mut tx = db.begin_transaction().await?;
-        let invite_code = AdminRepository::generate_invite_code(&mut tx, unique_id).await?;
-        tx.commit().await?;
-        assert!(!invite_code.is_empty());
-
-        // 通过邀请码查找
-        let found_by_code = AdminRepository::find_by_invite_code(pool, &invite_code).await?;
-        assert!(found_by_code.is_some());
-
         db.close().await;
         Ok(())
     }
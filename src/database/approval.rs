@@ -0,0 +1,158 @@
+//! 审批消息表操作模块
+
+use crate::error::Result;
+use crate::types::ApprovalMessage;
+use chrono::Utc;
+use sqlx::{Row, Sqlite, Transaction};
+
+/// 审批消息及升级状态数据库操作
+pub struct ApprovalRepository;
+
+impl ApprovalRepository {
+    /// 保存已发送的审批消息
+    pub async fn create_message(tx: &mut Transaction<'_, Sqlite>, message: &ApprovalMessage) -> Result<i64> {
+        let result = sqlx::query(
+            r#"
+            INSERT INTO approval_message (record_id, chat_id, message_id, escalated, created_at)
+            VALUES (?, ?, ?, ?, ?)
+            "#,
+        )
+        .bind(message.record_id)
+        .bind(message.chat_id)
+        .bind(message.message_id)
+        .bind(message.escalated)
+        .bind(message.created_at)
+        .execute(&mut **tx)
+        .await?;
+
+        Ok(result.last_insert_rowid())
+    }
+
+    /// 获取记录的所有审批消息
+    pub async fn find_messages_by_record(
+        pool: &sqlx::Pool<Sqlite>,
+        record_id: i64,
+    ) -> Result<Vec<ApprovalMessage>> {
+        let rows = sqlx::query(
+            r#"
+            SELECT unique_id, record_id, chat_id, message_id, escalated, created_at
+            FROM approval_message
+            WHERE record_id = ?
+            ORDER BY unique_id ASC
+            "#,
+        )
+        .bind(record_id)
+        .fetch_all(pool)
+        .await?;
+
+        Ok(rows.into_iter().map(Self::row_to_message).collect())
+    }
+
+    /// 标记记录已升级
+    ///
+    /// 每条记录只能升级一次，返回false表示已被升级过（包括重启前）。
+    pub async fn mark_escalated(tx: &mut Transaction<'_, Sqlite>, record_id: i64) -> Result<bool> {
+        let result = sqlx::query(
+            r#"
+            INSERT OR IGNORE INTO approval_escalation (record_id, escalated_at)
+            VALUES (?, ?)
+            "#,
+        )
+        .bind(record_id)
+        .bind(Utc::now())
+        .execute(&mut **tx)
+        .await?;
+
+        Ok(result.rows_affected() > 0)
+    }
+
+    /// 取消记录的升级标记（升级消息全部发送失败时使用），返回是否存在标记
+    pub async fn unmark_escalated(tx: &mut Transaction<'_, Sqlite>, record_id: i64) -> Result<bool> {
+        let result = sqlx::query("DELETE FROM approval_escalation WHERE record_id = ?")
+            .bind(record_id)
+            .execute(&mut **tx)
+            .await?;
+
+        Ok(result.rows_affected() > 0)
+    }
+
+    /// 检查记录是否已升级
+    pub async fn is_escalated(pool: &sqlx::Pool<Sqlite>, record_id: i64) -> Result<bool> {
+        let row = sqlx::query("SELECT COUNT(*) as count FROM approval_escalation WHERE record_id = ?")
+            .bind(record_id)
+            .fetch_one(pool)
+            .await?;
+
+        Ok(row.get::<i64, _>("count") > 0)
+    }
+
+    /// 将数据库行转换为ApprovalMessage对象
+    fn row_to_message(row: sqlx::sqlite::SqliteRow) -> ApprovalMessage {
+        ApprovalMessage {
+            unique_id: row.get("unique_id"),
+            record_id: row.get("record_id"),
+            chat_id: row.get("chat_id"),
+            message_id: row.get("message_id"),
+            escalated: row.get("escalated"),
+            created_at: row.get("created_at"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::database::{AdminRepository, Database, RecordRepository};
+    use crate::types::{Admin, Record};
+    use tempfile::NamedTempFile;
+
+    #[tokio::test]
+    async fn test_escalation_state() -> Result<()> {
+        let temp_file = NamedTempFile::new()?;
+        let db_url = format!("sqlite:{}", temp_file.path().to_str().unwrap());
+        let db = Database::new(&db_url).await?;
+        let pool = db.pool();
+
+        let mut tx = db.begin_transaction().await?;
+        let admin_id = AdminRepository::create(&mut tx, &Admin::new(123456789)).await?;
+        let mut old = Record::new(987654321, admin_id);
+        old.updated_at = Utc::now() - chrono::Duration::minutes(30);
+        let old_id = RecordRepository::create(&mut tx, &old).await?;
+        RecordRepository::create(&mut tx, &Record::new(555666777, admin_id)).await?;
+        ApprovalRepository::create_message(&mut tx, &ApprovalMessage::new(old_id, 123456789, 10, false)).await?;
+        tx.commit().await?;
+
+        // 只有超过等待时间的请求需要升级
+        let cutoff = Utc::now() - chrono::Duration::minutes(15);
+        let due = RecordRepository::find_pending_for_escalation(pool, cutoff).await?;
+        assert_eq!(due.len(), 1);
+        assert_eq!(due[0].unique_id, old_id);
+
+        // 取消标记后可以重新升级
+        let mut tx = db.begin_transaction().await?;
+        assert!(ApprovalRepository::mark_escalated(&mut tx, old_id).await?);
+        assert!(ApprovalRepository::unmark_escalated(&mut tx, old_id).await?);
+        assert!(!ApprovalRepository::unmark_escalated(&mut tx, old_id).await?);
+        tx.commit().await?;
+        assert_eq!(RecordRepository::find_pending_for_escalation(pool, cutoff).await?.len(), 1);
+
+        // 同一记录只能升级一次
+        let mut tx = db.begin_transaction().await?;
+        assert!(ApprovalRepository::mark_escalated(&mut tx, old_id).await?);
+        assert!(!ApprovalRepository::mark_escalated(&mut tx, old_id).await?);
+        ApprovalRepository::create_message(&mut tx, &ApprovalMessage::new(old_id, 111, 20, true)).await?;
+        tx.commit().await?;
+
+        assert!(ApprovalRepository::is_escalated(pool, old_id).await?);
+        assert!(RecordRepository::find_pending_for_escalation(pool, cutoff).await?.is_empty());
+
+        let messages = ApprovalRepository::find_messages_by_record(pool, old_id).await?;
+        assert_eq!(messages.len(), 2);
+        assert!(!messages[0].escalated);
+        assert_eq!((messages[1].chat_id, messages[1].message_id), (111, 20));
+        assert!(messages[1].escalated);
+
+        db.close().await;
+        Ok(())
+    }
+}
@@ -0,0 +1,214 @@
+//! 审计日志表操作模块
+
+use crate::error::{AppError, Result};
+use crate::types::{AuditAction, AuditEvent, AuthStatus};
+use chrono::{DateTime, Utc};
+use sqlx::{Row, Sqlite, Transaction};
+
+/// 审计日志查询条件
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct AuditFilter {
+    /// 操作者Telegram ID
+    pub actor_id: Option<i64>,
+    /// 目标用户（访客）Telegram ID
+    pub target_user: Option<i64>,
+    /// 起始时间（包含）
+    pub from: Option<DateTime<Utc>>,
+    /// 结束时间（不包含）
+    pub to: Option<DateTime<Utc>>,
+}
+
+/// 审计日志数据库操作
+///
+/// 审计日志只允许追加，表上的触发器会拒绝任何UPDATE/DELETE。
+pub struct AuditRepository;
+
+impl AuditRepository {
+    /// 写入审计事件（应与被审计的变更处于同一事务中）
+    pub async fn record(tx: &mut Transaction<'_, Sqlite>, event: &AuditEvent) -> Result<i64> {
+        let result = sqlx::query(
+            r#"
+            INSERT INTO audit_event (actor_id, action, target_record, target_user, before_status, after_status, detail, created_at)
+            VALUES (?, ?, ?, ?, ?, ?, ?, ?)
+            "#,
+        )
+        .bind(event.actor_id)
+        .bind(event.action.as_str())
+        .bind(event.target_record)
+        .bind(event.target_user)
+        .bind(event.before_status.map(|s| s.as_str()))
+        .bind(event.after_status.map(|s| s.as_str()))
+        .bind(&event.detail)
+        .bind(event.created_at)
+        .execute(&mut **tx)
+        .await?;
+
+        Ok(result.last_insert_rowid())
+    }
+
+    /// 按条件分页查询审计事件（按时间倒序）
+    pub async fn query(
+        pool: &sqlx::Pool<Sqlite>,
+        filter: &AuditFilter,
+        limit: i64,
+        offset: i64,
+    ) -> Result<Vec<AuditEvent>> {
+        let rows = sqlx::query(
+            r#"
+            SELECT unique_id, actor_id, action, target_record, target_user, before_status, after_status, detail, created_at
+            FROM audit_event
+            WHERE (? IS NULL OR actor_id = ?)
+              AND (? IS NULL OR target_user = ?)
+              AND (? IS NULL OR julianday(created_at) >= julianday(?))
+              AND (? IS NULL OR julianday(created_at) < julianday(?))
+            ORDER BY unique_id DESC
+            LIMIT ? OFFSET ?
+            "#,
+        )
+        .bind(filter.actor_id)
+        .bind(filter.actor_id)
+        .bind(filter.target_user)
+        .bind(filter.target_user)
+        .bind(filter.from)
+        .bind(filter.from)
+        .bind(filter.to)
+        .bind(filter.to)
+        .bind(limit)
+        .bind(offset)
+        .fetch_all(pool)
+        .await?;
+
+        rows.into_iter().map(Self::row_to_event).collect()
+    }
+
+    /// 统计符合条件的审计事件数量
+    pub async fn count(pool: &sqlx::Pool<Sqlite>, filter: &AuditFilter) -> Result<i64> {
+        let row = sqlx::query(
+            r#"
+            SELECT COUNT(*) as count
+            FROM audit_event
+            WHERE (? IS NULL OR actor_id = ?)
+              AND (? IS NULL OR target_user = ?)
+              AND (? IS NULL OR julianday(created_at) >= julianday(?))
+              AND (? IS NULL OR julianday(created_at) < julianday(?))
+            "#,
+        )
+        .bind(filter.actor_id)
+        .bind(filter.actor_id)
+        .bind(filter.target_user)
+        .bind(filter.target_user)
+        .bind(filter.from)
+        .bind(filter.from)
+        .bind(filter.to)
+        .bind(filter.to)
+        .fetch_one(pool)
+        .await?;
+
+        Ok(row.get("count"))
+    }
+
+    /// 将数据库行转换为AuditEvent对象
+    fn row_to_event(row: sqlx::sqlite::SqliteRow) -> Result<AuditEvent> {
+        let action_str: String = row.get("action");
+        let action = AuditAction::parse(&action_str)
+            .ok_or_else(|| AppError::business("无效的审计动作"))?;
+
+        let before_status: Option<String> = row.get("before_status");
+        let after_status: Option<String> = row.get("after_status");
+
+        Ok(AuditEvent {
+            unique_id: row.get("unique_id"),
+            actor_id: row.get("actor_id"),
+            action,
+            target_record: row.get("target_record"),
+            target_user: row.get("target_user"),
+            before_status: before_status.as_deref().and_then(AuthStatus::from_str),
+            after_status: after_status.as_deref().and_then(AuthStatus::from_str),
+            detail: row.get("detail"),
+            created_at: row.get("created_at"),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::database::Database;
+    use tempfile::NamedTempFile;
+
+    #[tokio::test]
+    async fn test_audit_record_and_query() -> Result<()> {
+        let temp_file = NamedTempFile::new()?;
+        let db_url = format!("sqlite:{}", temp_file.path().to_str().unwrap());
+        let db = Database::new(&db_url).await?;
+        let pool = db.pool();
+
+        let mut tx = db.begin_transaction().await?;
+        AuditRepository::record(
+            &mut tx,
+            &AuditEvent::new(111, AuditAction::AddAdmin).with_target_user(222),
+        )
+        .await?;
+        let mut approve = AuditEvent::new(222, AuditAction::Approve).with_target_user(333);
+        approve.target_record = Some(1);
+        approve.before_status = Some(AuthStatus::Pending);
+        approve.after_status = Some(AuthStatus::Auth);
+        AuditRepository::record(&mut tx, &approve).await?;
+        tx.commit().await?;
+
+        // 无过滤条件时按时间倒序返回全部
+        let all = AuditRepository::query(pool, &AuditFilter::default(), 10, 0).await?;
+        assert_eq!(all.len(), 2);
+        assert_eq!(all[0].action, AuditAction::Approve);
+        assert_eq!(all[0].before_status, Some(AuthStatus::Pending));
+        assert_eq!(all[0].after_status, Some(AuthStatus::Auth));
+
+        // 按操作者过滤
+        let by_actor = AuditFilter { actor_id: Some(111), ..Default::default() };
+        assert_eq!(AuditRepository::count(pool, &by_actor).await?, 1);
+
+        // 按访客过滤
+        let by_visitor = AuditFilter { target_user: Some(333), ..Default::default() };
+        let events = AuditRepository::query(pool, &by_visitor, 10, 0).await?;
+        assert_eq!(events.len(), 1);
+        assert_eq!(events[0].target_record, Some(1));
+
+        // 按时间范围过滤
+        let future = AuditFilter { from: Some(Utc::now() + chrono::Duration::hours(1)), ..Default::default() };
+        assert_eq!(AuditRepository::count(pool, &future).await?, 0);
+        let past = AuditFilter { to: Some(Utc::now() + chrono::Duration::hours(1)), ..Default::default() };
+        assert_eq!(AuditRepository::count(pool, &past).await?, 2);
+
+        // 分页
+        let page2 = AuditRepository::query(pool, &AuditFilter::default(), 1, 1).await?;
+        assert_eq!(page2.len(), 1);
+        assert_eq!(page2[0].action, AuditAction::AddAdmin);
+
+        db.close().await;
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_audit_is_append_only() -> Result<()> {
+        let temp_file = NamedTempFile::new()?;
+        let db_url = format!("sqlite:{}", temp_file.path().to_str().unwrap());
+        let db = Database::new(&db_url).await?;
+
+        let mut tx = db.begin_transaction().await?;
+        AuditRepository::record(&mut tx, &AuditEvent::new(111, AuditAction::EditPassword)).await?;
+        tx.commit().await?;
+
+        let update = sqlx::query("UPDATE audit_event SET actor_id = 999")
+            .execute(db.pool())
+            .await;
+        assert!(update.is_err());
+
+        let delete = sqlx::query("DELETE FROM audit_event").execute(db.pool()).await;
+        assert!(delete.is_err());
+
+        assert_eq!(AuditRepository::count(db.pool(), &AuditFilter::default()).await?, 1);
+
+        db.close().await;
+        Ok(())
+    }
+}
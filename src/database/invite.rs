@@ -0,0 +1,263 @@
+//! 邀请码表操作模块
+
+use crate::error::Result;
+use crate::types::Invite;
+
+use sqlx::{Row, Sqlite, Transaction};
+
+/// 邀请码数据库操作
+pub struct InviteRepository;
+
+impl InviteRepository {
+    /// 创建邀请码
+    pub async fn create(tx: &mut Transaction<'_, Sqlite>, invite: &Invite) -> Result<i64> {
+        let preset = invite.preset.as_ref().map(serde_json::to_string).transpose()?;
+
+        let result = sqlx::query(
+            r#"
+            INSERT INTO invite (code, admin_id, label, expires_at, max_uses, use_count, revoked, preset, created_at)
+            VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?)
+            "#,
+        )
+        .bind(&invite.code)
+        .bind(invite.admin_id)
+        .bind(&invite.label)
+        .bind(invite.expires_at)
+        .bind(invite.max_uses)
+        .bind(invite.use_count)
+        .bind(invite.revoked)
+        .bind(preset)
+        .bind(invite.created_at)
+        .execute(&mut **tx)
+        .await?;
+
+        Ok(result.last_insert_rowid())
+    }
+
+    /// 通过ID查找邀请码
+    pub async fn find_by_id(pool: &sqlx::Pool<Sqlite>, unique_id: i64) -> Result<Option<Invite>> {
+        let row = sqlx::query(
+            r#"
+            SELECT unique_id, code, admin_id, label, expires_at, max_uses, use_count, revoked, preset, created_at
+            FROM invite
+            WHERE unique_id = ?
+            "#,
+        )
+        .bind(unique_id)
+        .fetch_optional(pool)
+        .await?;
+
+        row.map(Self::row_to_invite).transpose()
+    }
+
+    /// 通过邀请码查找
+    pub async fn find_by_code(pool: &sqlx::Pool<Sqlite>, code: &str) -> Result<Option<Invite>> {
+        let row = sqlx::query(
+            r#"
+            SELECT unique_id, code, admin_id, label, expires_at, max_uses, use_count, revoked, preset, created_at
+            FROM invite
+            WHERE code = ?
+            "#,
+        )
+        .bind(code)
+        .fetch_optional(pool)
+        .await?;
+
+        row.map(Self::row_to_invite).transpose()
+    }
+
+    /// 获取管理员的邀请码（按创建时间倒序）
+    pub async fn find_by_admin(pool: &sqlx::Pool<Sqlite>, admin_id: i64) -> Result<Vec<Invite>> {
+        let rows = sqlx::query(
+            r#"
+            SELECT unique_id, code, admin_id, label, expires_at, max_uses, use_count, revoked, preset, created_at
+            FROM invite
+            WHERE admin_id = ?
+            ORDER BY unique_id DESC
+            "#,
+        )
+        .bind(admin_id)
+        .fetch_all(pool)
+        .await?;
+
+        rows.into_iter().map(Self::row_to_invite).collect()
+    }
+
+    /// 使用一次邀请码
+    ///
+    /// 撤销和次数上限在数据库中原子检查，并发使用不会超过上限；
+    /// 过期时间由调用方在应用层检查。
+    pub async fn redeem(tx: &mut Transaction<'_, Sqlite>, unique_id: i64) -> Result<bool> {
+        let result = sqlx::query(
+            r#"
+            UPDATE invite
+            SET use_count = use_count + 1
+            WHERE unique_id = ? AND revoked = 0 AND (max_uses IS NULL OR use_count < max_uses)
+            "#,
+        )
+        .bind(unique_id)
+        .execute(&mut **tx)
+        .await?;
+
+        Ok(result.rows_affected() > 0)
+    }
+
+    /// 撤销邀请码
+    pub async fn revoke(tx: &mut Transaction<'_, Sqlite>, unique_id: i64) -> Result<bool> {
+        let result = sqlx::query(
+            r#"
+            UPDATE invite
+            SET revoked = 1
+            WHERE unique_id = ? AND revoked = 0
+            "#,
+        )
+        .bind(unique_id)
+        .execute(&mut **tx)
+        .await?;
+
+        Ok(result.rows_affected() > 0)
+    }
+
+    /// 撤销管理员的所有邀请码，返回撤销数量
+    pub async fn revoke_by_admin(tx: &mut Transaction<'_, Sqlite>, admin_id: i64) -> Result<u64> {
+        let result = sqlx::query("UPDATE invite SET revoked = 1 WHERE admin_id = ? AND revoked = 0")
+            .bind(admin_id)
+            .execute(&mut **tx)
+            .await?;
+
+        Ok(result.rows_affected())
+    }
+
+    /// 将管理员的所有邀请码转交给另一位管理员，返回转交数量
+    pub async fn transfer_admin(
+        tx: &mut Transaction<'_, Sqlite>,
+        from_admin: i64,
+        to_admin: i64,
+    ) -> Result<u64> {
+        let result = sqlx::query("UPDATE invite SET admin_id = ? WHERE admin_id = ?")
+            .bind(to_admin)
+            .bind(from_admin)
+            .execute(&mut **tx)
+            .await?;
+
+        Ok(result.rows_affected())
+    }
+
+    /// 将数据库行转换为Invite对象
+    fn row_to_invite(row: sqlx::sqlite::SqliteRow) -> Result<Invite> {
+        let preset: Option<String> = row.get("preset");
+        let preset = preset.as_deref().map(serde_json::from_str).transpose()?;
+
+        Ok(Invite {
+            unique_id: row.get("unique_id"),
+            code: row.get("code"),
+            admin_id: row.get("admin_id"),
+            label: row.get("label"),
+            expires_at: row.get("expires_at"),
+            max_uses: row.get("max_uses"),
+            use_count: row.get("use_count"),
+            revoked: row.get("revoked"),
+            preset,
+            created_at: row.get("created_at"),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::database::{AdminRepository, Database};
+    use crate::types::{Admin, AuthPreset, AuthType, InviteState};
+    use chrono::Utc;
+    use tempfile::NamedTempFile;
+
+    #[tokio::test]
+    async fn test_invite_limits() -> Result<()> {
+        let temp_file = NamedTempFile::new()?;
+        let db_url = format!("sqlite:{}", temp_file.path().to_str().unwrap());
+        let db = Database::new(&db_url).await?;
+        let pool = db.pool();
+
+        let mut tx = db.begin_transaction().await?;
+        let admin_id = AdminRepository::create(&mut tx, &Admin::new(123456789)).await?;
+
+        let mut limited = Invite::new(admin_id);
+        limited.label = Some("保洁".to_string());
+        limited.max_uses = Some(2);
+        let limited_id = InviteRepository::create(&mut tx, &limited).await?;
+
+        let mut expired = Invite::new(admin_id);
+        expired.expires_at = Some(Utc::now() - chrono::Duration::minutes(1));
+        InviteRepository::create(&mut tx, &expired).await?;
+        tx.commit().await?;
+
+        // 次数用完后不能再使用
+        let mut tx = db.begin_transaction().await?;
+        assert!(InviteRepository::redeem(&mut tx, limited_id).await?);
+        assert!(InviteRepository::redeem(&mut tx, limited_id).await?);
+        assert!(!InviteRepository::redeem(&mut tx, limited_id).await?);
+        tx.commit().await?;
+
+        let found = InviteRepository::find_by_code(pool, &limited.code).await?.unwrap();
+        assert_eq!(found.label.as_deref(), Some("保洁"));
+        assert_eq!(found.use_count, 2);
+        assert_eq!(found.remaining_uses(), Some(0));
+        assert_eq!(found.state(), InviteState::Exhausted);
+
+        let found = InviteRepository::find_by_code(pool, &expired.code).await?.unwrap();
+        assert_eq!(found.state(), InviteState::Expired);
+
+        let invites = InviteRepository::find_by_admin(pool, admin_id).await?;
+        assert_eq!(invites.len(), 2);
+
+        db.close().await;
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_invite_revoke() -> Result<()> {
+        let temp_file = NamedTempFile::new()?;
+        let db_url = format!("sqlite:{}", temp_file.path().to_str().unwrap());
+        let db = Database::new(&db_url).await?;
+
+        let mut tx = db.begin_transaction().await?;
+        let admin_id = AdminRepository::create(&mut tx, &Admin::new(123456789)).await?;
+        let invite_id = InviteRepository::create(&mut tx, &Invite::new(admin_id)).await?;
+        assert!(InviteRepository::revoke(&mut tx, invite_id).await?);
+        assert!(!InviteRepository::revoke(&mut tx, invite_id).await?);
+        // 已撤销的邀请码不能使用
+        assert!(!InviteRepository::redeem(&mut tx, invite_id).await?);
+        tx.commit().await?;
+
+        let invite = InviteRepository::find_by_id(db.pool(), invite_id).await?.unwrap();
+        assert_eq!(invite.state(), InviteState::Revoked);
+        assert_eq!(invite.use_count, 0);
+
+        db.close().await;
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_invite_preset_roundtrip() -> Result<()> {
+        let temp_file = NamedTempFile::new()?;
+        let db_url = format!("sqlite:{}", temp_file.path().to_str().unwrap());
+        let db = Database::new(&db_url).await?;
+
+        let mut preset = AuthPreset::new(AuthType::Limited);
+        preset.hours = Some(4);
+        preset.minutes = Some(30);
+
+        let mut tx = db.begin_transaction().await?;
+        let admin_id = AdminRepository::create(&mut tx, &Admin::new(123456789)).await?;
+        let mut invite = Invite::new(admin_id);
+        invite.preset = Some(preset.clone());
+        let invite_id = InviteRepository::create(&mut tx, &invite).await?;
+        tx.commit().await?;
+
+        let found = InviteRepository::find_by_id(db.pool(), invite_id).await?.unwrap();
+        assert_eq!(found.preset, Some(preset));
+
+        db.close().await;
+        Ok(())
+    }
+}
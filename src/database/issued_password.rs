@@ -0,0 +1,179 @@
+//! 已发放密码表操作模块
+
+use crate::error::Result;
+use crate::types::IssuedPassword;
+
+use sqlx::{Row, Sqlite, Transaction};
+
+/// 已发放密码数据库操作
+pub struct IssuedPasswordRepository;
+
+impl IssuedPasswordRepository {
+    /// 记录一条已发放的密码
+    pub async fn create(
+        tx: &mut Transaction<'_, Sqlite>,
+        issued: &IssuedPassword,
+    ) -> Result<i64> {
+        let result = sqlx::query(
+            r#"
+            INSERT INTO issued_password (record_id, password, type, generated_at, valid_until, params, time_offset, lock_id)
+            VALUES (?, ?, ?, ?, ?, ?, ?, ?)
+            "#,
+        )
+        .bind(issued.record_id)
+        .bind(&issued.password)
+        .bind(&issued.password_type)
+        .bind(issued.generated_at)
+        .bind(issued.valid_until)
+        .bind(&issued.params)
+        .bind(issued.time_offset)
+        .bind(issued.lock_id)
+        .execute(&mut **tx)
+        .await?;
+
+        Ok(result.last_insert_rowid())
+    }
+
+    /// 按发放顺序获取记录的所有密码
+    pub async fn find_by_record(
+        pool: &sqlx::Pool<Sqlite>,
+        record_id: i64,
+    ) -> Result<Vec<IssuedPassword>> {
+        let rows = sqlx::query(
+            r#"
+            SELECT unique_id, record_id, password, type, generated_at, valid_until, params, time_offset, lock_id
+            FROM issued_password
+            WHERE record_id = ?
+            ORDER BY unique_id ASC
+            "#,
+        )
+        .bind(record_id)
+        .fetch_all(pool)
+        .await?;
+
+        rows.into_iter().map(Self::row_to_issued_password).collect()
+    }
+
+    /// 获取记录最早发放的密码
+    pub async fn find_first_by_record(
+        pool: &sqlx::Pool<Sqlite>,
+        record_id: i64,
+    ) -> Result<Option<IssuedPassword>> {
+        let row = sqlx::query(
+            r#"
+            SELECT unique_id, record_id, password, type, generated_at, valid_until, params, time_offset, lock_id
+            FROM issued_password
+            WHERE record_id = ?
+            ORDER BY unique_id ASC
+            LIMIT 1
+            "#,
+        )
+        .bind(record_id)
+        .fetch_optional(pool)
+        .await?;
+
+        row.map(Self::row_to_issued_password).transpose()
+    }
+
+    /// 查找某个密码的发放记录（用于追溯密码发给了谁）
+    pub async fn find_by_password(
+        pool: &sqlx::Pool<Sqlite>,
+        password: &str,
+    ) -> Result<Vec<IssuedPassword>> {
+        let rows = sqlx::query(
+            r#"
+            SELECT unique_id, record_id, password, type, generated_at, valid_until, params, time_offset, lock_id
+            FROM issued_password
+            WHERE password = ?
+            ORDER BY unique_id DESC
+            "#,
+        )
+        .bind(password)
+        .fetch_all(pool)
+        .await?;
+
+        rows.into_iter().map(Self::row_to_issued_password).collect()
+    }
+
+    /// 统计记录已发放的密码数量
+    pub async fn count_by_record(pool: &sqlx::Pool<Sqlite>, record_id: i64) -> Result<i64> {
+        let row = sqlx::query("SELECT COUNT(*) as count FROM issued_password WHERE record_id = ?")
+            .bind(record_id)
+            .fetch_one(pool)
+            .await?;
+
+        Ok(row.get("count"))
+    }
+
+    /// 将数据库行转换为IssuedPassword对象
+    fn row_to_issued_password(row: sqlx::sqlite::SqliteRow) -> Result<IssuedPassword> {
+        Ok(IssuedPassword {
+            unique_id: row.get("unique_id"),
+            record_id: row.get("record_id"),
+            password: row.get("password"),
+            password_type: row.get("type"),
+            generated_at: row.get("generated_at"),
+            valid_until: row.get("valid_until"),
+            params: row.get("params"),
+            time_offset: row.get("time_offset"),
+            lock_id: row.get("lock_id"),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::database::{AdminRepository, Database, LockRepository, RecordRepository};
+    use crate::types::{Admin, Lock, Record};
+    use crate::utils::{PasswordResult, PasswordType};
+    use chrono::{TimeZone, Utc};
+    use tempfile::NamedTempFile;
+
+    #[tokio::test]
+    async fn test_issued_password_crud() -> Result<()> {
+        let temp_file = NamedTempFile::new()?;
+        let db_url = format!("sqlite:{}", temp_file.path().to_str().unwrap());
+        let db = Database::new(&db_url).await?;
+        let pool = db.pool();
+
+        let mut tx = db.begin_transaction().await?;
+        let admin_id = AdminRepository::create(&mut tx, &Admin::new(123456789)).await?;
+        let record_id = RecordRepository::create(&mut tx, &Record::new(987654321, admin_id)).await?;
+        let mut lock = Lock::new("大门").with_time_offset(30);
+        lock.unique_id = LockRepository::create(&mut tx, &lock).await?;
+
+        let valid_from = Utc.with_ymd_and_hms(2023, 12, 31, 16, 0, 0).unwrap();
+        let result = PasswordResult {
+            password: "5012345678".to_string(),
+            password_type: PasswordType::Times(3),
+            valid_from,
+            valid_until: valid_from + chrono::Duration::hours(20),
+            remaining_uses: Some(3),
+            window: (valid_from.timestamp() / 4) as u32,
+            message: String::new(),
+        };
+        IssuedPasswordRepository::create(&mut tx, &IssuedPassword::from_result(record_id, &result, &lock)).await?;
+        tx.commit().await?;
+
+        let issued = IssuedPasswordRepository::find_by_record(pool, record_id).await?;
+        assert_eq!(issued.len(), 1);
+        assert_eq!(issued[0].password, "5012345678");
+        assert_eq!(issued[0].password_type, "times");
+        assert_eq!(issued[0].params.as_deref(), Some(r#"{"times":3}"#));
+        assert_eq!(issued[0].time_offset, Some(30));
+        assert_eq!(issued[0].lock_id, Some(lock.unique_id));
+        assert_eq!(
+            issued[0].valid_until.map(|t| t.to_rfc3339()),
+            Some("2024-01-01T12:00:00+00:00".to_string())
+        );
+
+        let by_password = IssuedPasswordRepository::find_by_password(pool, "5012345678").await?;
+        assert_eq!(by_password.len(), 1);
+        assert_eq!(by_password[0].record_id, record_id);
+        assert_eq!(IssuedPasswordRepository::count_by_record(pool, record_id).await?, 1);
+
+        db.close().await;
+        Ok(())
+    }
+}
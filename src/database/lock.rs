@@ -0,0 +1,307 @@
+//! 门锁表操作模块
+
+use crate::error::{AppError, Result};
+use crate::types::{AuthType, Lock};
+
+use sqlx::{Row, Sqlite, Transaction};
+
+/// 门锁及访客记录关联的数据库操作
+pub struct LockRepository;
+
+impl LockRepository {
+    /// 创建门锁
+    pub async fn create(tx: &mut Transaction<'_, Sqlite>, lock: &Lock) -> Result<i64> {
+        let result = sqlx::query(
+            r#"
+            INSERT INTO lock (name, time_offset, timezone, auth_types, created_at)
+            VALUES (?, ?, ?, ?, ?)
+            "#,
+        )
+        .bind(&lock.name)
+        .bind(lock.time_offset)
+        .bind(lock.timezone.to_string())
+        .bind(AuthType::join_list(&lock.auth_types))
+        .bind(lock.created_at)
+        .execute(&mut **tx)
+        .await?;
+
+        Ok(result.last_insert_rowid())
+    }
+
+    /// 更新门锁的名称、时间偏移、时区和授权类型
+    pub async fn update(tx: &mut Transaction<'_, Sqlite>, lock: &Lock) -> Result<bool> {
+        let result = sqlx::query(
+            r#"
+            UPDATE lock
+            SET name = ?, time_offset = ?, timezone = ?, auth_types = ?
+            WHERE unique_id = ?
+            "#,
+        )
+        .bind(&lock.name)
+        .bind(lock.time_offset)
+        .bind(lock.timezone.to_string())
+        .bind(AuthType::join_list(&lock.auth_types))
+        .bind(lock.unique_id)
+        .execute(&mut **tx)
+        .await?;
+
+        Ok(result.rows_affected() > 0)
+    }
+
+    /// 获取所有门锁（默认门锁在前）
+    pub async fn find_all(pool: &sqlx::Pool<Sqlite>) -> Result<Vec<Lock>> {
+        let rows = sqlx::query(
+            r#"
+            SELECT unique_id, name, time_offset, timezone, auth_types, created_at
+            FROM lock
+            ORDER BY unique_id ASC
+            "#,
+        )
+        .fetch_all(pool)
+        .await?;
+
+        rows.into_iter().map(Self::row_to_lock).collect()
+    }
+
+    /// 通过ID查找门锁
+    pub async fn find_by_id(pool: &sqlx::Pool<Sqlite>, unique_id: i64) -> Result<Option<Lock>> {
+        let row = sqlx::query(
+            r#"
+            SELECT unique_id, name, time_offset, timezone, auth_types, created_at
+            FROM lock
+            WHERE unique_id = ?
+            "#,
+        )
+        .bind(unique_id)
+        .fetch_optional(pool)
+        .await?;
+
+        row.map(Self::row_to_lock).transpose()
+    }
+
+    /// 通过名称查找门锁
+    pub async fn find_by_name(pool: &sqlx::Pool<Sqlite>, name: &str) -> Result<Option<Lock>> {
+        let row = sqlx::query(
+            r#"
+            SELECT unique_id, name, time_offset, timezone, auth_types, created_at
+            FROM lock
+            WHERE name = ?
+            "#,
+        )
+        .bind(name)
+        .fetch_optional(pool)
+        .await?;
+
+        row.map(Self::row_to_lock).transpose()
+    }
+
+    /// 在事务中获取默认门锁
+    pub async fn find_default_in_tx(tx: &mut Transaction<'_, Sqlite>) -> Result<Option<Lock>> {
+        let row = sqlx::query(
+            r#"
+            SELECT unique_id, name, time_offset, timezone, auth_types, created_at
+            FROM lock
+            ORDER BY unique_id ASC
+            LIMIT 1
+            "#,
+        )
+        .fetch_optional(&mut **tx)
+        .await?;
+
+        row.map(Self::row_to_lock).transpose()
+    }
+
+    /// 获取访客记录关联的门锁
+    pub async fn find_by_record(pool: &sqlx::Pool<Sqlite>, record_id: i64) -> Result<Vec<Lock>> {
+        let rows = sqlx::query(
+            r#"
+            SELECT l.unique_id, l.name, l.time_offset, l.timezone, l.auth_types, l.created_at
+            FROM lock l
+            JOIN record_lock rl ON rl.lock_id = l.unique_id
+            WHERE rl.record_id = ?
+            ORDER BY l.unique_id ASC
+            "#,
+        )
+        .bind(record_id)
+        .fetch_all(pool)
+        .await?;
+
+        rows.into_iter().map(Self::row_to_lock).collect()
+    }
+
+    /// 在事务中获取访客记录关联的门锁
+    pub async fn find_by_record_in_tx(
+        tx: &mut Transaction<'_, Sqlite>,
+        record_id: i64,
+    ) -> Result<Vec<Lock>> {
+        let rows = sqlx::query(
+            r#"
+            SELECT l.unique_id, l.name, l.time_offset, l.timezone, l.auth_types, l.created_at
+            FROM lock l
+            JOIN record_lock rl ON rl.lock_id = l.unique_id
+            WHERE rl.record_id = ?
+            ORDER BY l.unique_id ASC
+            "#,
+        )
+        .bind(record_id)
+        .fetch_all(&mut **tx)
+        .await?;
+
+        rows.into_iter().map(Self::row_to_lock).collect()
+    }
+
+    /// 关联访客记录和门锁，已关联时返回false
+    pub async fn link_record(tx: &mut Transaction<'_, Sqlite>, record_id: i64, lock_id: i64) -> Result<bool> {
+        let result = sqlx::query("INSERT OR IGNORE INTO record_lock (record_id, lock_id) VALUES (?, ?)")
+            .bind(record_id)
+            .bind(lock_id)
+            .execute(&mut **tx)
+            .await?;
+
+        Ok(result.rows_affected() > 0)
+    }
+
+    /// 取消访客记录和门锁的关联，未关联时返回false
+    pub async fn unlink_record(tx: &mut Transaction<'_, Sqlite>, record_id: i64, lock_id: i64) -> Result<bool> {
+        let result = sqlx::query("DELETE FROM record_lock WHERE record_id = ? AND lock_id = ?")
+            .bind(record_id)
+            .bind(lock_id)
+            .execute(&mut **tx)
+            .await?;
+
+        Ok(result.rows_affected() > 0)
+    }
+
+    /// 没有任何门锁时创建默认门锁，返回创建的门锁ID
+    ///
+    /// 升级前只有一个门锁，已有的访客记录和已发放密码都归入默认门锁。
+    pub async fn ensure_default(tx: &mut Transaction<'_, Sqlite>, lock: &Lock) -> Result<Option<i64>> {
+        if Self::find_default_in_tx(tx).await?.is_some() {
+            return Ok(None);
+        }
+
+        let lock_id = Self::create(tx, lock).await?;
+
+        sqlx::query("INSERT INTO record_lock (record_id, lock_id) SELECT unique_id, ? FROM record")
+            .bind(lock_id)
+            .execute(&mut **tx)
+            .await?;
+        sqlx::query("UPDATE issued_password SET lock_id = ? WHERE lock_id IS NULL")
+            .bind(lock_id)
+            .execute(&mut **tx)
+            .await?;
+
+        Ok(Some(lock_id))
+    }
+
+    /// 将数据库行转换为Lock对象
+    fn row_to_lock(row: sqlx::sqlite::SqliteRow) -> Result<Lock> {
+        let timezone: String = row.get("timezone");
+        let auth_types: String = row.get("auth_types");
+
+        Ok(Lock {
+            unique_id: row.get("unique_id"),
+            name: row.get("name"),
+            time_offset: row.get("time_offset"),
+            timezone: timezone.parse().map_err(AppError::Other)?,
+            auth_types: AuthType::parse_list(&auth_types)
+                .ok_or_else(|| AppError::Other(format!("无效的门锁授权类型: {}", auth_types)))?,
+            created_at: row.get("created_at"),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::database::{AdminRepository, Database, RecordRepository};
+    use crate::types::{Admin, Record};
+    use tempfile::NamedTempFile;
+
+    #[tokio::test]
+    async fn test_lock_crud_and_record_links() -> Result<()> {
+        let temp_file = NamedTempFile::new()?;
+        let db_url = format!("sqlite:{}", temp_file.path().to_str().unwrap());
+        let db = Database::new(&db_url).await?;
+        let pool = db.pool();
+
+        let mut tx = db.begin_transaction().await?;
+        let admin_id = AdminRepository::create(&mut tx, &Admin::new(123456789)).await?;
+        let record_id = RecordRepository::create(&mut tx, &Record::new(987654321, admin_id)).await?;
+
+        let gate = Lock::new("大门")
+            .with_time_offset(-45)
+            .with_timezone("Europe/Berlin".parse().unwrap())
+            .with_auth_types(vec![AuthType::Temp, AuthType::Times]);
+        let gate_id = LockRepository::create(&mut tx, &gate).await?;
+        let door_id = LockRepository::create(&mut tx, &Lock::new("公寓门")).await?;
+
+        assert!(LockRepository::link_record(&mut tx, record_id, door_id).await?);
+        assert!(LockRepository::link_record(&mut tx, record_id, gate_id).await?);
+        assert!(!LockRepository::link_record(&mut tx, record_id, gate_id).await?);
+        tx.commit().await?;
+
+        // 第一个门锁为默认门锁
+        let locks = LockRepository::find_all(pool).await?;
+        assert_eq!(locks.iter().map(|l| l.name.as_str()).collect::<Vec<_>>(), vec!["大门", "公寓门"]);
+        assert_eq!(locks[0].time_offset, -45);
+        assert_eq!(locks[0].timezone.to_string(), "Europe/Berlin");
+        assert_eq!(locks[0].auth_types, vec![AuthType::Temp, AuthType::Times]);
+        assert_eq!(locks[1].auth_types, AuthType::ALL.to_vec());
+
+        let linked = LockRepository::find_by_record(pool, record_id).await?;
+        assert_eq!(linked.iter().map(|l| l.unique_id).collect::<Vec<_>>(), vec![gate_id, door_id]);
+
+        let mut tx = db.begin_transaction().await?;
+        assert!(LockRepository::unlink_record(&mut tx, record_id, gate_id).await?);
+        assert!(!LockRepository::unlink_record(&mut tx, record_id, gate_id).await?);
+
+        let mut door = LockRepository::find_by_name(pool, "公寓门").await?.unwrap();
+        door.time_offset = 90;
+        door.auth_types = vec![AuthType::Limited];
+        assert!(LockRepository::update(&mut tx, &door).await?);
+        tx.commit().await?;
+
+        let door = LockRepository::find_by_id(pool, door_id).await?.unwrap();
+        assert_eq!(door.time_offset, 90);
+        assert_eq!(door.auth_types, vec![AuthType::Limited]);
+        assert_eq!(LockRepository::find_by_record(pool, record_id).await?.len(), 1);
+        assert!(LockRepository::find_by_name(pool, "后门").await?.is_none());
+
+        db.close().await;
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_ensure_default_lock() -> Result<()> {
+        let temp_file = NamedTempFile::new()?;
+        let db_url = format!("sqlite:{}", temp_file.path().to_str().unwrap());
+        let db = Database::new(&db_url).await?;
+        let pool = db.pool();
+
+        let mut tx = db.begin_transaction().await?;
+        let admin_id = AdminRepository::create(&mut tx, &Admin::new(123456789)).await?;
+        let record_id = RecordRepository::create(&mut tx, &Record::new(987654321, admin_id)).await?;
+        tx.commit().await?;
+
+        // 首次启动时创建默认门锁，已有记录归入默认门锁
+        let mut tx = db.begin_transaction().await?;
+        let lock_id = LockRepository::ensure_default(&mut tx, &Lock::new("默认门锁").with_time_offset(30)).await?;
+        tx.commit().await?;
+        let lock_id = lock_id.expect("应创建默认门锁");
+
+        let linked = LockRepository::find_by_record(pool, record_id).await?;
+        assert_eq!(linked.len(), 1);
+        assert_eq!(linked[0].unique_id, lock_id);
+        assert_eq!(linked[0].time_offset, 30);
+
+        // 已有门锁时不再创建
+        let mut tx = db.begin_transaction().await?;
+        assert_eq!(LockRepository::ensure_default(&mut tx, &Lock::new("另一个门锁")).await?, None);
+        tx.commit().await?;
+        assert_eq!(LockRepository::find_all(pool).await?.len(), 1);
+
+        db.close().await;
+        Ok(())
+    }
+}
@@ -0,0 +1,592 @@
+//! 数据库版本迁移模块
+//!
+//! 所有表结构变更都以带版本号的迁移步骤嵌入在程序中，启动时按版本号顺序执行。
+//! 每个版本在独立事务中执行，成功后写入 `schema_version` 表；
+//! 若数据库版本高于程序已知的最新版本，则拒绝启动，避免旧程序破坏新结构。
+
+use crate::error::{AppError, Result};
+
+use sqlx::{Pool, Row, Sqlite};
+
+/// 单个迁移版本
+#[derive(Debug)]
+pub struct Migration {
+    /// 版本号，必须严格递增
+    pub version: i64,
+    /// 迁移说明
+    pub description: &'static str,
+    /// 按顺序执行的SQL语句
+    pub statements: &'static [&'static str],
+}
+
+/// 内置的全部迁移，按版本号升序排列
+///
+/// v1 与旧版 `init_tables` 创建的结构完全一致（均为 `IF NOT EXISTS`），
+/// 因此没有 `schema_version` 表的旧数据库会被视为 v0 并从 v1 开始平滑升级。
+pub static MIGRATIONS: &[Migration] = &[
+    Migration {
+        version: 1,
+        description: "初始表结构",
+        statements: &[
+            r#"
+            CREATE TABLE IF NOT EXISTS admin (
+                unique_id INTEGER PRIMARY KEY AUTOINCREMENT,
+                id INTEGER NOT NULL UNIQUE,
+                password TEXT,
+                invite_code TEXT,
+                created_at DATETIME DEFAULT CURRENT_TIMESTAMP,
+                updated_at DATETIME DEFAULT CURRENT_TIMESTAMP
+            )
+            "#,
+            r#"
+            CREATE TABLE IF NOT EXISTS record (
+                unique_id INTEGER PRIMARY KEY AUTOINCREMENT,
+                status TEXT NOT NULL DEFAULT 'pending',
+                vis_id INTEGER NOT NULL,
+                type TEXT NOT NULL DEFAULT 'temp',
+                times INTEGER,
+                start_time DATETIME,
+                ended_time DATETIME,
+                password TEXT,
+                inviter INTEGER NOT NULL,
+                update_at DATETIME DEFAULT CURRENT_TIMESTAMP,
+                created_at DATETIME DEFAULT CURRENT_TIMESTAMP,
+                FOREIGN KEY (inviter) REFERENCES admin (unique_id)
+            )
+            "#,
+            "CREATE INDEX IF NOT EXISTS idx_admin_id ON admin (id)",
+            "CREATE INDEX IF NOT EXISTS idx_record_vis_id ON record (vis_id)",
+            "CREATE INDEX IF NOT EXISTS idx_record_status ON record (status)",
+            "CREATE INDEX IF NOT EXISTS idx_record_inviter ON record (inviter)",
+        ],
+    },
+    Migration {
+        version: 2,
+        description: "统一record表更新时间列名为updated_at",
+        statements: &["ALTER TABLE record RENAME COLUMN update_at TO updated_at"],
+    },
+    Migration {
+        version: 3,
+        description: "新增issued_password表并迁移record.password中的JSON密码列表",
+        statements: &[
+            r#"
+            CREATE TABLE IF NOT EXISTS issued_password (
+                unique_id INTEGER PRIMARY KEY AUTOINCREMENT,
+                record_id INTEGER NOT NULL,
+                password TEXT NOT NULL,
+                type TEXT NOT NULL,
+                generated_at DATETIME NOT NULL,
+                valid_until DATETIME,
+                params TEXT,
+                time_offset INTEGER,
+                FOREIGN KEY (record_id) REFERENCES record (unique_id)
+            )
+            "#,
+            "CREATE INDEX IF NOT EXISTS idx_issued_password_record ON issued_password (record_id)",
+            "CREATE INDEX IF NOT EXISTS idx_issued_password_password ON issued_password (password)",
+            // 旧数据无法得知生成时的时间偏移，time_offset 留空
+            r#"
+            INSERT INTO issued_password (record_id, password, type, generated_at, valid_until, params, time_offset)
+            SELECT r.unique_id,
+                   j.value,
+                   CASE r.type
+                       WHEN 'times' THEN 'times'
+                       WHEN 'limited' THEN 'limited'
+                       WHEN 'period' THEN 'period'
+                       ELSE 'temporary'
+                   END,
+                   COALESCE(r.updated_at, r.created_at, CURRENT_TIMESTAMP),
+                   r.ended_time,
+                   CASE WHEN r.type = 'times' AND r.times IS NOT NULL
+                        THEN json_object('times', r.times)
+                   END,
+                   NULL
+            FROM record r,
+                 json_each(CASE WHEN json_valid(r.password) THEN r.password ELSE '[]' END) j
+            WHERE r.password IS NOT NULL
+            ORDER BY r.unique_id, j.key
+            "#,
+            "ALTER TABLE record DROP COLUMN password",
+        ],
+    },
+    Migration {
+        version: 4,
+        description: "新增只追加的audit_event审计日志表",
+        statements: &[
+            r#"
+            CREATE TABLE IF NOT EXISTS audit_event (
+                unique_id INTEGER PRIMARY KEY AUTOINCREMENT,
+                actor_id INTEGER NOT NULL,
+                action TEXT NOT NULL,
+                target_record INTEGER,
+                target_user INTEGER,
+                before_status TEXT,
+                after_status TEXT,
+                detail TEXT,
+                created_at DATETIME NOT NULL
+            )
+            "#,
+            "CREATE INDEX IF NOT EXISTS idx_audit_event_actor ON audit_event (actor_id)",
+            "CREATE INDEX IF NOT EXISTS idx_audit_event_target_user ON audit_event (target_user)",
+            "CREATE INDEX IF NOT EXISTS idx_audit_event_created_at ON audit_event (created_at)",
+            r#"
+            CREATE TRIGGER IF NOT EXISTS audit_event_no_update
+            BEFORE UPDATE ON audit_event
+            BEGIN
+                SELECT RAISE(ABORT, 'audit_event 为只追加表，禁止修改');
+            END
+            "#,
+            r#"
+            CREATE TRIGGER IF NOT EXISTS audit_event_no_delete
+            BEFORE DELETE ON audit_event
+            BEGIN
+                SELECT RAISE(ABORT, 'audit_event 为只追加表，禁止删除');
+            END
+            "#,
+        ],
+    },
+    Migration {
+        version: 5,
+        description: "细分revoked状态为rejected/cancelled/expired",
+        statements: &[
+            // 审计日志中有明确记录的优先按审计结果归类
+            r#"
+            UPDATE record SET status = 'rejected'
+            WHERE status = 'revoked'
+              AND unique_id IN (
+                  SELECT target_record FROM audit_event
+                  WHERE action = 'reject' AND target_record IS NOT NULL
+              )
+            "#,
+            r#"
+            UPDATE record SET status = 'cancelled'
+            WHERE status = 'revoked'
+              AND unique_id IN (
+                  SELECT target_record FROM audit_event
+                  WHERE action = 'revoke' AND before_status = 'pending' AND target_record IS NOT NULL
+              )
+            "#,
+            // 其余从未获得授权的记录视为被拒绝
+            r#"
+            UPDATE record SET status = 'rejected'
+            WHERE status = 'revoked'
+              AND start_time IS NULL
+              AND unique_id NOT IN (
+                  SELECT target_record FROM audit_event
+                  WHERE action = 'revoke' AND target_record IS NOT NULL
+              )
+            "#,
+            // 在结束时间之后才被标记的记录是旧版惰性过期清理的结果
+            r#"
+            UPDATE record SET status = 'expired'
+            WHERE status = 'revoked'
+              AND ended_time IS NOT NULL
+              AND julianday(ended_time) <= julianday(updated_at)
+              AND unique_id NOT IN (
+                  SELECT target_record FROM audit_event
+                  WHERE action = 'revoke' AND target_record IS NOT NULL
+              )
+            "#,
+        ],
+    },
+    Migration {
+        version: 6,
+        description: "新增invite邀请表，迁移管理员原有邀请码",
+        statements: &[
+            r#"
+            CREATE TABLE IF NOT EXISTS invite (
+                unique_id INTEGER PRIMARY KEY AUTOINCREMENT,
+                code TEXT NOT NULL UNIQUE,
+                admin_id INTEGER NOT NULL,
+                label TEXT,
+                expires_at DATETIME,
+                max_uses INTEGER,
+                use_count INTEGER NOT NULL DEFAULT 0,
+                revoked INTEGER NOT NULL DEFAULT 0,
+                created_at DATETIME NOT NULL,
+                FOREIGN KEY (admin_id) REFERENCES admin (unique_id)
+            )
+            "#,
+            "CREATE INDEX IF NOT EXISTS idx_invite_admin ON invite (admin_id)",
+            // 原邀请码保持可用：不限次数、永不过期
+            r#"
+            INSERT INTO invite (code, admin_id, label, created_at)
+            SELECT invite_code, unique_id, '默认邀请', COALESCE(updated_at, created_at, CURRENT_TIMESTAMP)
+            FROM admin
+            WHERE invite_code IS NOT NULL AND invite_code != ''
+            "#,
+            "ALTER TABLE admin DROP COLUMN invite_code",
+            "ALTER TABLE record ADD COLUMN invite_id INTEGER REFERENCES invite (unique_id)",
+        ],
+    },
+    Migration {
+        version: 7,
+        description: "invite表新增preset列，保存自动批准的预设授权（JSON）",
+        statements: &["ALTER TABLE invite ADD COLUMN preset TEXT"],
+    },
+    Migration {
+        version: 8,
+        description: "新增审批消息表和审批升级表",
+        statements: &[
+            r#"
+            CREATE TABLE IF NOT EXISTS approval_message (
+                unique_id INTEGER PRIMARY KEY AUTOINCREMENT,
+                record_id INTEGER NOT NULL,
+                chat_id INTEGER NOT NULL,
+                message_id INTEGER NOT NULL,
+                escalated INTEGER NOT NULL DEFAULT 0,
+                created_at DATETIME NOT NULL,
+                FOREIGN KEY (record_id) REFERENCES record (unique_id)
+            )
+            "#,
+            "CREATE INDEX IF NOT EXISTS idx_approval_message_record ON approval_message (record_id)",
+            // 每条记录最多升级一次，插入成功即视为取得升级权
+            r#"
+            CREATE TABLE IF NOT EXISTS approval_escalation (
+                record_id INTEGER PRIMARY KEY,
+                escalated_at DATETIME NOT NULL,
+                FOREIGN KEY (record_id) REFERENCES record (unique_id)
+            )
+            "#,
+        ],
+    },
+    Migration {
+        version: 9,
+        description: "新增lock门锁表和record_lock关联表，issued_password记录生成门锁",
+        // 门锁参数来自配置文件，默认门锁在启动时创建并关联已有记录
+        statements: &[
+            r#"
+            CREATE TABLE IF NOT EXISTS lock (
+                unique_id INTEGER PRIMARY KEY AUTOINCREMENT,
+                name TEXT NOT NULL UNIQUE,
+                time_offset INTEGER NOT NULL DEFAULT 0,
+                timezone TEXT NOT NULL,
+                auth_types TEXT NOT NULL,
+                created_at DATETIME NOT NULL
+            )
+            "#,
+            r#"
+            CREATE TABLE IF NOT EXISTS record_lock (
+                record_id INTEGER NOT NULL,
+                lock_id INTEGER NOT NULL,
+                PRIMARY KEY (record_id, lock_id),
+                FOREIGN KEY (record_id) REFERENCES record (unique_id),
+                FOREIGN KEY (lock_id) REFERENCES lock (unique_id)
+            )
+            "#,
+            "CREATE INDEX IF NOT EXISTS idx_record_lock_lock ON record_lock (lock_id)",
+            "ALTER TABLE issued_password ADD COLUMN lock_id INTEGER REFERENCES lock (unique_id)",
+        ],
+    },
+    Migration {
+        version: 10,
+        description: "record新增vis_name访客显示名称",
+        // 旧记录没有保存访客名称，显示时回退为访客ID
+        statements: &["ALTER TABLE record ADD COLUMN vis_name TEXT"],
+    },
+    Migration {
+        version: 11,
+        description: "admin新增removed_at，删除管理员时保留记录",
+        // 访客记录和邀请码引用管理员，删除管理员只做标记，历史记录仍可追溯
+        statements: &["ALTER TABLE admin ADD COLUMN removed_at DATETIME"],
+    },
+];
+
+/// 程序支持的最新数据库版本
+pub fn latest_version() -> i64 {
+    MIGRATIONS.last().map(|m| m.version).unwrap_or(0)
+}
+
+/// 确保版本记录表存在
+async fn ensure_version_table(pool: &Pool<Sqlite>) -> Result<()> {
+    sqlx::query(
+        r#"
+        CREATE TABLE IF NOT EXISTS schema_version (
+            version INTEGER PRIMARY KEY,
+            description TEXT NOT NULL,
+            applied_at DATETIME DEFAULT CURRENT_TIMESTAMP
+        )
+        "#,
+    )
+    .execute(pool)
+    .await?;
+
+    Ok(())
+}
+
+/// 获取数据库当前版本（未执行过任何迁移时为0）
+pub async fn current_version(pool: &Pool<Sqlite>) -> Result<i64> {
+    ensure_version_table(pool).await?;
+
+    let row = sqlx::query("SELECT COALESCE(MAX(version), 0) as version FROM schema_version")
+        .fetch_one(pool)
+        .await?;
+
+    Ok(row.get("version"))
+}
+
+/// 执行所有未应用的迁移，返回迁移后的数据库版本
+pub async fn run_migrations(pool: &Pool<Sqlite>) -> Result<i64> {
+    let current = current_version(pool).await?;
+    let latest = latest_version();
+
+    if current > latest {
+        return Err(AppError::migration(format!(
+            "数据库结构版本 v{} 高于程序支持的 v{}，请升级程序后再启动",
+            current, latest
+        )));
+    }
+
+    for migration in MIGRATIONS.iter().filter(|m| m.version > current) {
+        let mut tx = pool.begin().await?;
+
+        for statement in migration.statements {
+            sqlx::query(statement).execute(&mut *tx).await.map_err(|e| {
+                AppError::migration(format!(
+                    "v{} ({}) 执行失败: {}",
+                    migration.version, migration.description, e
+                ))
+            })?;
+        }
+
+        sqlx::query("INSERT INTO schema_version (version, description) VALUES (?, ?)")
+            .bind(migration.version)
+            .bind(migration.description)
+            .execute(&mut *tx)
+            .await?;
+
+        tx.commit().await?;
+
+        log::info!(
+            "数据库已迁移至 v{}: {}",
+            migration.version,
+            migration.description
+        );
+    }
+
+    Ok(latest.max(current))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::database::{Database, InviteRepository, IssuedPasswordRepository};
+    use sqlx::sqlite::SqlitePool;
+    use tempfile::NamedTempFile;
+
+    /// 旧版 `init_tables` 创建的表结构（v0，无版本记录）
+    const LEGACY_SCHEMA: &[&str] = &[
+        r#"
+        CREATE TABLE IF NOT EXISTS admin (
+            unique_id INTEGER PRIMARY KEY AUTOINCREMENT,
+            id INTEGER NOT NULL UNIQUE,
+            password TEXT,
+            invite_code TEXT,
+            created_at DATETIME DEFAULT CURRENT_TIMESTAMP,
+            updated_at DATETIME DEFAULT CURRENT_TIMESTAMP
+        )
+        "#,
+        r#"
+        CREATE TABLE IF NOT EXISTS record (
+            unique_id INTEGER PRIMARY KEY AUTOINCREMENT,
+            status TEXT NOT NULL DEFAULT 'pending',
+            vis_id INTEGER NOT NULL,
+            type TEXT NOT NULL DEFAULT 'temp',
+            times INTEGER,
+            start_time DATETIME,
+            ended_time DATETIME,
+            password TEXT,
+            inviter INTEGER NOT NULL,
+            update_at DATETIME DEFAULT CURRENT_TIMESTAMP,
+            created_at DATETIME DEFAULT CURRENT_TIMESTAMP,
+            FOREIGN KEY (inviter) REFERENCES admin (unique_id)
+        )
+        "#,
+        "CREATE INDEX IF NOT EXISTS idx_admin_id ON admin (id)",
+        "CREATE INDEX IF NOT EXISTS idx_record_vis_id ON record (vis_id)",
+        "CREATE INDEX IF NOT EXISTS idx_record_status ON record (status)",
+        "CREATE INDEX IF NOT EXISTS idx_record_inviter ON record (inviter)",
+    ];
+
+    async fn create_legacy_db(db_url: &str) -> Result<()> {
+        let pool = SqlitePool::connect(db_url).await?;
+        for statement in LEGACY_SCHEMA {
+            sqlx::query(statement).execute(&pool).await?;
+        }
+
+        sqlx::query("INSERT INTO admin (id, password, invite_code) VALUES (?, ?, ?)")
+            .bind(123456789i64)
+            .bind("1234")
+            .bind("7f1c1f4e-3c1b-4a36-9f0a-1d8a3f0e2b11")
+            .execute(&pool)
+            .await?;
+        sqlx::query(
+            "INSERT INTO record (status, vis_id, type, times, password, inviter) VALUES (?, ?, ?, ?, ?, ?)",
+        )
+        .bind("auth")
+        .bind(987654321i64)
+        .bind("times")
+        .bind(5)
+        .bind(r#"["5012345678","5087654321"]"#)
+        .bind(1i64)
+        .execute(&pool)
+        .await?;
+
+        pool.close().await;
+        Ok(())
+    }
+
+    #[test]
+    fn test_migrations_are_ordered() {
+        let mut previous = 0;
+        for migration in MIGRATIONS {
+            assert!(migration.version > previous, "迁移版本必须严格递增");
+            assert!(!migration.statements.is_empty());
+            previous = migration.version;
+        }
+        assert_eq!(latest_version(), previous);
+    }
+
+    #[tokio::test]
+    async fn test_upgrade_legacy_database() -> Result<()> {
+        let temp_file = NamedTempFile::new()?;
+        let db_url = format!("sqlite:{}", temp_file.path().to_str().unwrap());
+        create_legacy_db(&db_url).await?;
+
+        let database = Database::new(&db_url).await?;
+        assert_eq!(current_version(database.pool()).await?, latest_version());
+
+        // 旧数据完整保留
+        let admin = sqlx::query("SELECT id, password FROM admin")
+            .fetch_one(database.pool())
+            .await?;
+        assert_eq!(admin.get::<i64, _>("id"), 123456789);
+        assert_eq!(admin.get::<String, _>("password"), "1234");
+
+        // 原邀请码迁移为不限次数、永不过期的邀请
+        let invite = InviteRepository::find_by_code(
+            database.pool(),
+            "7f1c1f4e-3c1b-4a36-9f0a-1d8a3f0e2b11",
+        )
+        .await?
+        .expect("原邀请码应迁移到invite表");
+        assert_eq!(invite.admin_id, 1);
+        assert_eq!(invite.max_uses, None);
+        assert_eq!(invite.expires_at, None);
+        assert_eq!(invite.preset, None);
+        assert!(!invite.revoked);
+
+        let record = sqlx::query(
+            "SELECT unique_id, status, vis_id, type, times, updated_at FROM record",
+        )
+        .fetch_one(database.pool())
+        .await?;
+        assert_eq!(record.get::<String, _>("status"), "auth");
+        assert_eq!(record.get::<i64, _>("vis_id"), 987654321);
+        assert_eq!(record.get::<String, _>("type"), "times");
+        assert_eq!(record.get::<i32, _>("times"), 5);
+        assert!(record.get::<Option<String>, _>("updated_at").is_some());
+
+        // JSON密码列表按原顺序拆分为issued_password行
+        let passwords = IssuedPasswordRepository::find_by_record(
+            database.pool(),
+            record.get("unique_id"),
+        )
+        .await?;
+        assert_eq!(passwords.len(), 2);
+        assert_eq!(passwords[0].password, "5012345678");
+        assert_eq!(passwords[1].password, "5087654321");
+        assert_eq!(passwords[0].password_type, "times");
+        assert_eq!(passwords[0].params.as_deref(), Some(r#"{"times":5}"#));
+        assert_eq!(passwords[0].time_offset, None);
+
+        database.close().await;
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_split_legacy_revoked_status() -> Result<()> {
+        let temp_file = NamedTempFile::new()?;
+        let db_url = format!("sqlite:{}", temp_file.path().to_str().unwrap());
+        create_legacy_db(&db_url).await?;
+
+        let pool = SqlitePool::connect(&db_url).await?;
+        let legacy_rows: &[(i64, Option<&str>, Option<&str>, &str)] = &[
+            // 从未授权：拒绝
+            (111, None, None, "2024-01-01 10:00:00"),
+            // 到期后被惰性清理：过期
+            (222, Some("2024-01-01T08:00:00+00:00"), Some("2024-01-01T10:00:00+00:00"), "2024-01-01 11:00:00"),
+            // 到期前被管理员撤销：保持撤销
+            (333, Some("2024-01-01T08:00:00+00:00"), Some("2024-01-01T10:00:00+00:00"), "2024-01-01 09:00:00"),
+        ];
+        for (vis_id, start, end, update_at) in legacy_rows {
+            sqlx::query(
+                "INSERT INTO record (status, vis_id, start_time, ended_time, inviter, update_at) VALUES ('revoked', ?, ?, ?, 1, ?)",
+            )
+            .bind(vis_id)
+            .bind(start)
+            .bind(end)
+            .bind(update_at)
+            .execute(&pool)
+            .await?;
+        }
+        pool.close().await;
+
+        let database = Database::new(&db_url).await?;
+        let status_of = |vis_id: i64| {
+            let pool = database.pool().clone();
+            async move {
+                sqlx::query("SELECT status FROM record WHERE vis_id = ?")
+                    .bind(vis_id)
+                    .fetch_one(&pool)
+                    .await
+                    .map(|row| row.get::<String, _>("status"))
+            }
+        };
+
+        assert_eq!(status_of(111).await?, "rejected");
+        assert_eq!(status_of(222).await?, "expired");
+        assert_eq!(status_of(333).await?, "revoked");
+        assert_eq!(status_of(987654321).await?, "auth");
+
+        database.close().await;
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_migrations_are_idempotent() -> Result<()> {
+        let temp_file = NamedTempFile::new()?;
+        let db_url = format!("sqlite:{}", temp_file.path().to_str().unwrap());
+
+        let database = Database::new(&db_url).await?;
+        database.close().await;
+
+        // 再次打开不会重复执行迁移
+        let database = Database::new(&db_url).await?;
+        let applied = sqlx::query("SELECT COUNT(*) as count FROM schema_version")
+            .fetch_one(database.pool())
+            .await?;
+        assert_eq!(applied.get::<i64, _>("count"), MIGRATIONS.len() as i64);
+
+        database.close().await;
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_refuse_newer_database() -> Result<()> {
+        let temp_file = NamedTempFile::new()?;
+        let db_url = format!("sqlite:{}", temp_file.path().to_str().unwrap());
+
+        let database = Database::new(&db_url).await?;
+        sqlx::query("INSERT INTO schema_version (version, description) VALUES (?, ?)")
+            .bind(latest_version() + 1)
+            .bind("来自更新版本的程序")
+            .execute(database.pool())
+            .await?;
+        database.close().await;
+
+        let result = Database::new(&db_url).await;
+        assert!(matches!(result, Err(AppError::Migration(_))));
+        Ok(())
+    }
+}
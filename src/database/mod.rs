@@ -1,11 +1,22 @@
 //! 数据库模块
 
 pub mod admin;
+pub mod approval;
+pub mod audit;
+pub mod invite;
+pub mod issued_password;
+pub mod lock;
+pub mod migration;
 pub mod record;
 
 // 重新导出数据库操作
 pub use admin::AdminRepository;
-pub use record::RecordRepository;
+pub use approval::ApprovalRepository;
+pub use audit::{AuditFilter, AuditRepository};
+pub use invite::InviteRepository;
+pub use issued_password::IssuedPasswordRepository;
+pub use lock::LockRepository;
+pub use record::{RecordRepository, RecordStatistics};
 
 use crate::error::Result;
 use sqlx::{sqlite::SqlitePool, Pool, Sqlite};
@@ -35,8 +46,8 @@ impl Database {
 
         let database = Self { pool };
 
-        // 初始化数据库表结构
-        database.init_tables().await?;
+        // 执行数据库版本迁移
+        database.migrate().await?;
 
         Ok(database)
     }
@@ -46,67 +57,18 @@ impl Database {
         &self.pool
     }
 
-    /// 初始化数据库表结构
-    async fn init_tables(&self) -> Result<()> {
-        // 创建admin表
-        sqlx::query(
-            r#"
-            CREATE TABLE IF NOT EXISTS admin (
-                unique_id INTEGER PRIMARY KEY AUTOINCREMENT,
-                id INTEGER NOT NULL UNIQUE,
-                password TEXT,
-                invite_code TEXT,
-                created_at DATETIME DEFAULT CURRENT_TIMESTAMP,
-                updated_at DATETIME DEFAULT CURRENT_TIMESTAMP
-            )
-            "#,
-        )
-        .execute(&self.pool)
-        .await?;
-
-        // 创建record表
-        sqlx::query(
-            r#"
-            CREATE TABLE IF NOT EXISTS record (
-                unique_id INTEGER PRIMARY KEY AUTOINCREMENT,
-                status TEXT NOT NULL DEFAULT 'pending',
-                vis_id INTEGER NOT NULL,
-                type TEXT NOT NULL DEFAULT 'temp',
-                times INTEGER,
-                start_time DATETIME,
-                ended_time DATETIME,
-                password TEXT,
-                inviter INTEGER NOT NULL,
-                update_at DATETIME DEFAULT CURRENT_TIMESTAMP,
-                created_at DATETIME DEFAULT CURRENT_TIMESTAMP,
-                FOREIGN KEY (inviter) REFERENCES admin (unique_id)
-            )
-            "#,
-        )
-        .execute(&self.pool)
-        .await?;
-
-        // 创建索引以提高查询性能
-        sqlx::query("CREATE INDEX IF NOT EXISTS idx_admin_id ON admin (id)")
-            .execute(&self.pool)
-            .await?;
-
-        sqlx::query("CREATE INDEX IF NOT EXISTS idx_record_vis_id ON record (vis_id)")
-            .execute(&self.pool)
-            .await?;
-
-        sqlx::query("CREATE INDEX IF NOT EXISTS idx_record_status ON record (status)")
-            .execute(&self.pool)
-            .await?;
-
-        sqlx::query("CREATE INDEX IF NOT EXISTS idx_record_inviter ON record (inviter)")
-            .execute(&self.pool)
-            .await?;
-
-        log::info!("数据库表结构初始化完成");
+    /// 执行数据库版本迁移
+    async fn migrate(&self) -> Result<()> {
+        let version = migration::run_migrations(&self.pool).await?;
+        log::info!("数据库结构已是最新版本 v{}", version);
         Ok(())
     }
 
+    /// 获取数据库当前结构版本
+    pub async fn schema_version(&self) -> Result<i64> {
+        migration::current_version(&self.pool).await
+    }
+
     /// 检查数据库连接
     pub async fn ping(&self) -> Result<()> {
         sqlx::query("SELECT 1").execute(&self.pool).await?;
@@ -1,11 +1,44 @@
 //! 访客记录表操作模块
 
+use super::IssuedPasswordRepository;
 use crate::error::{AppError, Result};
 use crate::types::{AuthStatus, AuthType, Record};
 use chrono::{DateTime, Utc};
-use serde_json;
 use sqlx::{Row, Sqlite, Transaction};
 
+/// 访客记录统计
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct RecordStatistics {
+    /// 记录总数
+    pub total: i64,
+    /// 待审批
+    pub pending: i64,
+    /// 已授权
+    pub authorized: i64,
+    /// 已拒绝
+    pub rejected: i64,
+    /// 已取消
+    pub cancelled: i64,
+    /// 已撤销
+    pub revoked: i64,
+    /// 已过期
+    pub expired: i64,
+}
+
+impl RecordStatistics {
+    /// 获取指定状态的记录数
+    pub fn count(&self, status: AuthStatus) -> i64 {
+        match status {
+            AuthStatus::Pending => self.pending,
+            AuthStatus::Auth => self.authorized,
+            AuthStatus::Rejected => self.rejected,
+            AuthStatus::Cancelled => self.cancelled,
+            AuthStatus::Revoked => self.revoked,
+            AuthStatus::Expired => self.expired,
+        }
+    }
+}
+
 /// 访客记录数据库操作
 pub struct RecordRepository;
 
@@ -14,19 +47,20 @@ impl RecordRepository {
     pub async fn create(tx: &mut Transaction<'_, Sqlite>, record: &Record) -> Result<i64> {
         let result = sqlx::query(
             r#"
-            INSERT INTO record (status, vis_id, type, times, start_time, ended_time, password, inviter, update_at)
-            VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?)
+            INSERT INTO record (status, vis_id, vis_name, type, times, start_time, ended_time, inviter, invite_id, updated_at)
+            VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
             "#,
         )
         .bind(record.status.as_str())
         .bind(record.vis_id)
+        .bind(&record.vis_name)
         .bind(record.auth_type.as_str())
         .bind(record.times)
         .bind(record.start_time)
         .bind(record.ended_time)
-        .bind(&record.password)
         .bind(record.inviter)
-        .bind(record.update_at)
+        .bind(record.invite_id)
+        .bind(record.updated_at)
         .execute(&mut **tx)
         .await?;
 
@@ -40,7 +74,7 @@ impl RecordRepository {
     ) -> Result<Option<Record>> {
         let row = sqlx::query(
             r#"
-            SELECT unique_id, status, vis_id, type, times, start_time, ended_time, password, inviter, update_at
+            SELECT unique_id, status, vis_id, vis_name, type, times, start_time, ended_time, inviter, invite_id, updated_at
             FROM record
             WHERE unique_id = ?
             "#,
@@ -56,6 +90,80 @@ impl RecordRepository {
         }
     }
 
+    /// 在事务中通过unique_id查找记录（用于变更前读取原状态）
+    pub async fn find_by_id_in_tx(
+        tx: &mut Transaction<'_, Sqlite>,
+        unique_id: i64,
+    ) -> Result<Option<Record>> {
+        let row = sqlx::query(
+            r#"
+            SELECT unique_id, status, vis_id, vis_name, type, times, start_time, ended_time, inviter, invite_id, updated_at
+            FROM record
+            WHERE unique_id = ?
+            "#,
+        )
+        .bind(unique_id)
+        .fetch_optional(&mut **tx)
+        .await?;
+
+        row.map(Self::row_to_record).transpose()
+    }
+
+    /// 在事务中查找访客所有状态为已授权的记录
+    pub async fn find_authorized_by_visitor_in_tx(
+        tx: &mut Transaction<'_, Sqlite>,
+        vis_id: i64,
+    ) -> Result<Vec<Record>> {
+        let rows = sqlx::query(
+            r#"
+            SELECT unique_id, status, vis_id, vis_name, type, times, start_time, ended_time, inviter, invite_id, updated_at
+            FROM record
+            WHERE vis_id = ? AND status = 'auth'
+            ORDER BY updated_at DESC
+            "#,
+        )
+        .bind(vis_id)
+        .fetch_all(&mut **tx)
+        .await?;
+
+        rows.into_iter().map(Self::row_to_record).collect()
+    }
+
+    /// 在事务中查找管理员邀请的待审批和已授权记录
+    pub async fn find_open_by_inviter_in_tx(
+        tx: &mut Transaction<'_, Sqlite>,
+        inviter: i64,
+    ) -> Result<Vec<Record>> {
+        let rows = sqlx::query(
+            r#"
+            SELECT unique_id, status, vis_id, vis_name, type, times, start_time, ended_time, inviter, invite_id, updated_at
+            FROM record
+            WHERE inviter = ? AND status IN ('pending', 'auth')
+            ORDER BY unique_id ASC
+            "#,
+        )
+        .bind(inviter)
+        .fetch_all(&mut **tx)
+        .await?;
+
+        rows.into_iter().map(Self::row_to_record).collect()
+    }
+
+    /// 将管理员邀请的所有记录转交给另一位管理员，返回转交数量
+    pub async fn transfer_inviter(
+        tx: &mut Transaction<'_, Sqlite>,
+        from_inviter: i64,
+        to_inviter: i64,
+    ) -> Result<u64> {
+        let result = sqlx::query("UPDATE record SET inviter = ? WHERE inviter = ?")
+            .bind(to_inviter)
+            .bind(from_inviter)
+            .execute(&mut **tx)
+            .await?;
+
+        Ok(result.rows_affected())
+    }
+
     /// 查找访客的待处理请求
     pub async fn find_pending_by_visitor(
         pool: &sqlx::Pool<Sqlite>,
@@ -63,10 +171,10 @@ impl RecordRepository {
     ) -> Result<Option<Record>> {
         let row = sqlx::query(
             r#"
-            SELECT unique_id, status, vis_id, type, times, start_time, ended_time, password, inviter, update_at
+            SELECT unique_id, status, vis_id, vis_name, type, times, start_time, ended_time, inviter, invite_id, updated_at
             FROM record
             WHERE vis_id = ? AND status = 'pending'
-            ORDER BY update_at DESC
+            ORDER BY updated_at DESC
             LIMIT 1
             "#,
         )
@@ -81,59 +189,134 @@ impl RecordRepository {
         }
     }
 
-    /// 查找访客的所有活跃授权
+    /// 分页查找待审批请求（最早提交的在前），`inviter` 为空时查找全部
+    pub async fn find_pending_page(
+        pool: &sqlx::Pool<Sqlite>,
+        inviter: Option<i64>,
+        limit: i64,
+        offset: i64,
+    ) -> Result<Vec<Record>> {
+        let rows = sqlx::query(
+            r#"
+            SELECT unique_id, status, vis_id, vis_name, type, times, start_time, ended_time, inviter, invite_id, updated_at
+            FROM record
+            WHERE status = 'pending' AND (?1 IS NULL OR inviter = ?1)
+            ORDER BY updated_at ASC, unique_id ASC
+            LIMIT ?2 OFFSET ?3
+            "#,
+        )
+        .bind(inviter)
+        .bind(limit)
+        .bind(offset)
+        .fetch_all(pool)
+        .await?;
+
+        rows.into_iter().map(Self::row_to_record).collect()
+    }
+
+    /// 统计待审批请求数量，`inviter` 为空时统计全部
+    pub async fn count_pending(pool: &sqlx::Pool<Sqlite>, inviter: Option<i64>) -> Result<i64> {
+        let count = sqlx::query_scalar(
+            "SELECT COUNT(*) FROM record WHERE status = 'pending' AND (?1 IS NULL OR inviter = ?1)",
+        )
+        .bind(inviter)
+        .fetch_one(pool)
+        .await?;
+
+        Ok(count)
+    }
+
+    /// 查找在指定时间之前提交、尚未升级的待审批请求
+    pub async fn find_pending_for_escalation(
+        pool: &sqlx::Pool<Sqlite>,
+        submitted_before: DateTime<Utc>,
+    ) -> Result<Vec<Record>> {
+        let rows = sqlx::query(
+            r#"
+            SELECT unique_id, status, vis_id, vis_name, type, times, start_time, ended_time, inviter, invite_id, updated_at
+            FROM record
+            WHERE status = 'pending'
+              AND julianday(updated_at) <= julianday(?)
+              AND unique_id NOT IN (SELECT record_id FROM approval_escalation)
+            ORDER BY unique_id ASC
+            "#,
+        )
+        .bind(submitted_before)
+        .fetch_all(pool)
+        .await?;
+
+        rows.into_iter().map(Self::row_to_record).collect()
+    }
+
+    /// 查找访客待审批和已授权的记录（最近更新的在前）
+    pub async fn find_current_by_visitor(pool: &sqlx::Pool<Sqlite>, vis_id: i64) -> Result<Vec<Record>> {
+        let rows = sqlx::query(
+            r#"
+            SELECT unique_id, status, vis_id, vis_name, type, times, start_time, ended_time, inviter, invite_id, updated_at
+            FROM record
+            WHERE vis_id = ? AND status IN ('pending', 'auth')
+            ORDER BY updated_at DESC, unique_id DESC
+            "#,
+        )
+        .bind(vis_id)
+        .fetch_all(pool)
+        .await?;
+
+        rows.into_iter().map(Self::row_to_record).collect()
+    }
+
+    /// 查找访客已结束的记录（最近结束的在前）
+    pub async fn find_history_by_visitor(
+        pool: &sqlx::Pool<Sqlite>,
+        vis_id: i64,
+        limit: i64,
+    ) -> Result<Vec<Record>> {
+        let rows = sqlx::query(
+            r#"
+            SELECT unique_id, status, vis_id, vis_name, type, times, start_time, ended_time, inviter, invite_id, updated_at
+            FROM record
+            WHERE vis_id = ? AND status NOT IN ('pending', 'auth')
+            ORDER BY updated_at DESC, unique_id DESC
+            LIMIT ?
+            "#,
+        )
+        .bind(vis_id)
+        .bind(limit)
+        .fetch_all(pool)
+        .await?;
+
+        rows.into_iter().map(Self::row_to_record).collect()
+    }
+
+    /// 查找访客在 `now` 时仍然有效的授权
+    ///
+    /// 只读取不修改，到期记录的状态变更和通知由定时过期任务负责。
     pub async fn find_active_by_visitor(
         pool: &sqlx::Pool<Sqlite>,
         vis_id: i64,
+        now: DateTime<Utc>,
     ) -> Result<Vec<Record>> {
         // 获取所有状态为'auth'的记录，在应用层判断是否过期
         let rows = sqlx::query(
             r#"
-            SELECT unique_id, status, vis_id, type, times, start_time, ended_time, password, inviter, update_at
+            SELECT unique_id, status, vis_id, vis_name, type, times, start_time, ended_time, inviter, invite_id, updated_at
             FROM record
             WHERE vis_id = ? AND status = 'auth'
-            ORDER BY update_at DESC
+            ORDER BY updated_at DESC
             "#,
         )
         .bind(vis_id)
         .fetch_all(pool)
         .await?;
 
-        let mut expired_ids = Vec::new();
         let mut active_records = Vec::new();
-
-        // 在应用层检查每个记录是否真正活跃
         for row in rows {
             let record = Self::row_to_record(row)?;
-            if record.is_active() {
+            if record.is_active_at(now) {
                 active_records.push(record);
-            } else {
-                // 记录已过期，标记为需要清理
-                expired_ids.push(record.unique_id);
             }
         }
 
-        // 批量清理过期的记录
-        if !expired_ids.is_empty() {
-            let expired_count = expired_ids.len();
-            let mut tx = pool.begin().await?;
-            for expired_id in expired_ids {
-                sqlx::query(
-                    r#"
-                    UPDATE record
-                    SET status = 'revoked', update_at = ?
-                    WHERE unique_id = ?
-                    "#,
-                )
-                .bind(Utc::now())
-                .bind(expired_id)
-                .execute(&mut *tx)
-                .await?;
-            }
-            tx.commit().await?;
-            log::info!("为用户 {} 清理了 {} 个过期授权", vis_id, expired_count);
-        }
-
         Ok(active_records)
     }
 
@@ -144,10 +327,10 @@ impl RecordRepository {
     ) -> Result<Vec<Record>> {
         let rows = sqlx::query(
             r#"
-            SELECT unique_id, status, vis_id, type, times, start_time, ended_time, password, inviter, update_at
+            SELECT unique_id, status, vis_id, vis_name, type, times, start_time, ended_time, inviter, invite_id, updated_at
             FROM record
             WHERE inviter = ?
-            ORDER BY update_at DESC
+            ORDER BY updated_at DESC
             "#,
         )
         .bind(inviter_id)
@@ -162,16 +345,17 @@ impl RecordRepository {
         Ok(records)
     }
 
-    /// 获取所有活跃的授权记录
-    pub async fn find_all_active(pool: &sqlx::Pool<Sqlite>) -> Result<Vec<Record>> {
+    /// 获取在指定时间仍然活跃的全部授权记录
+    pub async fn find_all_active(pool: &sqlx::Pool<Sqlite>, now: DateTime<Utc>) -> Result<Vec<Record>> {
         let rows = sqlx::query(
             r#"
-            SELECT unique_id, status, vis_id, type, times, start_time, ended_time, password, inviter, update_at
+            SELECT unique_id, status, vis_id, vis_name, type, times, start_time, ended_time, inviter, invite_id, updated_at
             FROM record
-            WHERE status = 'auth' AND (ended_time IS NULL OR ended_time > CURRENT_TIMESTAMP)
-            ORDER BY update_at DESC
+            WHERE status = 'auth' AND (ended_time IS NULL OR julianday(ended_time) > julianday(?))
+            ORDER BY updated_at DESC
             "#,
         )
+        .bind(now)
         .fetch_all(pool)
         .await?;
 
@@ -183,21 +367,41 @@ impl RecordRepository {
         Ok(records)
     }
 
-    /// 更新记录状态
+    /// 更新记录状态（按状态机校验，非法变更返回false）
     pub async fn update_status(
         tx: &mut Transaction<'_, Sqlite>,
         unique_id: i64,
         status: AuthStatus,
     ) -> Result<bool> {
+        match Self::find_by_id_in_tx(tx, unique_id).await? {
+            Some(record) => Self::apply_transition(tx, &record, status).await,
+            None => Ok(false),
+        }
+    }
+
+    /// 校验并执行状态变更
+    ///
+    /// 更新条件包含变更前的状态，并发修改过的记录不会被覆盖。
+    async fn apply_transition(
+        tx: &mut Transaction<'_, Sqlite>,
+        record: &Record,
+        to: AuthStatus,
+    ) -> Result<bool> {
+        if let Err(e) = record.clone().transition(to) {
+            log::warn!("拒绝非法状态变更: {}", e);
+            return Ok(false);
+        }
+
         let result = sqlx::query(
             r#"
             UPDATE record
-            SET status = ?, update_at = CURRENT_TIMESTAMP
-            WHERE unique_id = ?
+            SET status = ?, updated_at = CURRENT_TIMESTAMP
+            WHERE unique_id = ? AND status = ?
             "#,
         )
-        .bind(status.as_str())
-        .bind(unique_id)
+        .bind(to.as_str())
+        .bind(record.unique_id)
+        .bind(record.status.as_str())
         .execute(&mut **tx)
         .await?;
 
@@ -213,62 +417,50 @@ impl RecordRepository {
         ended_time: Option<DateTime<Utc>>,
         times: Option<i32>,
     ) -> Result<bool> {
+        let mut record = match Self::find_by_id_in_tx(tx, unique_id).await? {
+            Some(record) => record,
+            None => return Ok(false),
+        };
+        let from = record.status;
+        if let Err(e) = record.approve(auth_type, start_time, ended_time, times) {
+            log::warn!("拒绝非法状态变更: {}", e);
+            return Ok(false);
+        }
+
         let result = sqlx::query(
             r#"
             UPDATE record
-            SET status = 'auth', type = ?, start_time = ?, ended_time = ?, times = ?, update_at = CURRENT_TIMESTAMP
-            WHERE unique_id = ?
+            SET status = ?, type = ?, start_time = ?, ended_time = ?, times = ?, updated_at = CURRENT_TIMESTAMP
+            WHERE unique_id = ? AND status = ?
             "#,
         )
+        .bind(record.status.as_str())
         .bind(auth_type.as_str())
         .bind(start_time)
         .bind(ended_time)
         .bind(times)
         .bind(unique_id)
+        .bind(from.as_str())
         .execute(&mut **tx)
         .await?;
 
         Ok(result.rows_affected() > 0)
     }
 
-    /// 添加密码到记录
-    pub async fn add_password(
+    /// 修改已授权记录的结束时间
+    pub async fn extend_authorization(
         tx: &mut Transaction<'_, Sqlite>,
         unique_id: i64,
-        new_password: &str,
+        ended_time: DateTime<Utc>,
     ) -> Result<bool> {
-        // 先获取现有密码
-        let row = sqlx::query(
-            r#"
-            SELECT password FROM record WHERE unique_id = ?
-            "#,
-        )
-        .bind(unique_id)
-        .fetch_optional(&mut **tx)
-        .await?;
-
-        let mut passwords: Vec<String> = if let Some(row) = row {
-            let password_json: Option<String> = row.get("password");
-            if let Some(json) = password_json {
-                serde_json::from_str(&json).unwrap_or_else(|_| Vec::new())
-            } else {
-                Vec::new()
-            }
-        } else {
-            return Ok(false); // 记录不存在
-        };
-
-        passwords.push(new_password.to_string());
-        let updated_passwords = serde_json::to_string(&passwords)?;
-
         let result = sqlx::query(
             r#"
             UPDATE record
-            SET password = ?, update_at = CURRENT_TIMESTAMP
-            WHERE unique_id = ?
+            SET ended_time = ?, updated_at = CURRENT_TIMESTAMP
+            WHERE unique_id = ? AND status = 'auth'
             "#,
         )
-        .bind(updated_passwords)
+        .bind(ended_time)
         .bind(unique_id)
         .execute(&mut **tx)
         .await?;
@@ -276,116 +468,60 @@ impl RecordRepository {
         Ok(result.rows_affected() > 0)
     }
 
-    /// 撤销所有用户的授权
-    pub async fn revoke_all_by_visitor(
-        tx: &mut Transaction<'_, Sqlite>,
-        vis_id: i64,
-    ) -> Result<usize> {
-        let result = sqlx::query(
-            r#"
-            UPDATE record
-            SET status = 'revoked', update_at = CURRENT_TIMESTAMP
-            WHERE vis_id = ? AND status = 'auth'
-            "#,
-        )
-        .bind(vis_id)
-        .execute(&mut **tx)
-        .await?;
-
-        Ok(result.rows_affected() as usize)
-    }
-
-    /// 撤销指定记录
+    /// 撤销指定记录（待审批的请求记为取消）
     pub async fn revoke_by_id(
         tx: &mut Transaction<'_, Sqlite>,
         unique_id: i64,
     ) -> Result<bool> {
-        let result = sqlx::query(
-            r#"
-            UPDATE record
-            SET status = 'revoked', update_at = CURRENT_TIMESTAMP
-            WHERE unique_id = ? AND status IN ('pending', 'auth')
-            "#,
-        )
-        .bind(unique_id)
-        .execute(&mut **tx)
-        .await?;
-
-        Ok(result.rows_affected() > 0)
-    }
-
-    /// 清理用户的过期授权
-    pub async fn cleanup_expired_authorizations(
-        tx: &mut Transaction<'_, Sqlite>,
-        vis_id: i64,
-    ) -> Result<usize> {
-        let result = sqlx::query(
-            r#"
-            UPDATE record
-            SET status = 'revoked', update_at = CURRENT_TIMESTAMP
-            WHERE vis_id = ? AND status = 'auth' AND ended_time IS NOT NULL AND ended_time <= CURRENT_TIMESTAMP
-            "#,
-        )
-        .bind(vis_id)
-        .execute(&mut **tx)
-        .await?;
-
-        Ok(result.rows_affected() as usize)
+        match Self::find_by_id_in_tx(tx, unique_id).await? {
+            Some(record) => {
+                let to = record.revocation_status();
+                Self::apply_transition(tx, &record, to).await
+            }
+            None => Ok(false),
+        }
     }
 
-    /// 检查用户是否有活跃授权
-    pub async fn has_active_authorization(
-        pool: &sqlx::Pool<Sqlite>,
-        vis_id: i64,
-    ) -> Result<bool> {
-        // 获取所有状态为'auth'的记录，在应用层判断是否过期
+    /// 查找在 `now` 时已到结束时间但仍处于授权状态的记录
+    pub async fn find_expired_authorizations(pool: &sqlx::Pool<Sqlite>, now: DateTime<Utc>) -> Result<Vec<Record>> {
+        // 在应用层判断是否过期，避免数据库中不同时间格式的字符串比较
         let rows = sqlx::query(
             r#"
-            SELECT unique_id, status, vis_id, type, times, start_time, ended_time, password, inviter, update_at
+            SELECT unique_id, status, vis_id, vis_name, type, times, start_time, ended_time, inviter, invite_id, updated_at
             FROM record
-            WHERE vis_id = ? AND status = 'auth'
+            WHERE status = 'auth' AND ended_time IS NOT NULL
+            ORDER BY unique_id ASC
             "#,
         )
-        .bind(vis_id)
         .fetch_all(pool)
         .await?;
 
-        let mut expired_ids = Vec::new();
-        let mut has_active = false;
-
-        // 在应用层检查每个记录是否真正活跃
+        let mut records = Vec::new();
         for row in rows {
             let record = Self::row_to_record(row)?;
-            if record.is_active() {
-                has_active = true;
-            } else {
-                // 记录已过期，标记为需要清理
-                expired_ids.push(record.unique_id);
+            if record.is_expired_at(now) {
+                records.push(record);
             }
         }
 
-        // 批量清理过期的记录
-        if !expired_ids.is_empty() {
-            let expired_count = expired_ids.len();
-            let mut tx = pool.begin().await?;
-            for expired_id in expired_ids {
-                sqlx::query(
-                    r#"
-                    UPDATE record
-                    SET status = 'revoked', update_at = ?
-                    WHERE unique_id = ?
-                    "#,
-                )
-                .bind(Utc::now())
-                .bind(expired_id)
-                .execute(&mut *tx)
-                .await?;
-            }
-            tx.commit().await?;
-            log::info!("为用户 {} 清理了 {} 个过期授权", vis_id, expired_count);
-        }
+        Ok(records)
+    }
+
+    /// 将已授权记录标记为过期
+    pub async fn expire_by_id(
+        tx: &mut Transaction<'_, Sqlite>,
+        unique_id: i64,
+    ) -> Result<bool> {
+        Self::update_status(tx, unique_id, AuthStatus::Expired).await
+    }
 
-        Ok(has_active)
+    /// 检查用户在 `now` 时是否有有效的授权（只读取不修改）
+    pub async fn has_active_authorization(
+        pool: &sqlx::Pool<Sqlite>,
+        vis_id: i64,
+        now: DateTime<Utc>,
+    ) -> Result<bool> {
+        Ok(!Self::find_active_by_visitor(pool, vis_id, now).await?.is_empty())
     }
 
     /// 检查用户是否有待处理请求
@@ -421,29 +557,36 @@ impl RecordRepository {
         Ok(result.rows_affected() > 0)
     }
 
-    /// 获取记录统计信息
-    pub async fn get_statistics(
-        pool: &sqlx::Pool<Sqlite>,
-    ) -> Result<(i64, i64, i64, i64)> {
-        let row = sqlx::query(
+    /// 获取记录统计信息（按状态分类）
+    pub async fn get_statistics(pool: &sqlx::Pool<Sqlite>) -> Result<RecordStatistics> {
+        let rows = sqlx::query(
             r#"
-            SELECT
-                COUNT(*) as total,
-                SUM(CASE WHEN status = 'pending' THEN 1 ELSE 0 END) as pending,
-                SUM(CASE WHEN status = 'auth' THEN 1 ELSE 0 END) as authorized,
-                SUM(CASE WHEN status = 'revoked' THEN 1 ELSE 0 END) as revoked
+            SELECT status, COUNT(*) as count
             FROM record
+            GROUP BY status
             "#,
         )
-        .fetch_one(pool)
+        .fetch_all(pool)
         .await?;
 
-        Ok((
-            row.get("total"),
-            row.get("pending"),
-            row.get("authorized"),
-            row.get("revoked"),
-        ))
+        let mut stats = RecordStatistics::default();
+        for row in rows {
+            let status_str: String = row.get("status");
+            let count: i64 = row.get("count");
+            stats.total += count;
+
+            match AuthStatus::from_str(&status_str) {
+                Some(AuthStatus::Pending) => stats.pending = count,
+                Some(AuthStatus::Auth) => stats.authorized = count,
+                Some(AuthStatus::Rejected) => stats.rejected = count,
+                Some(AuthStatus::Cancelled) => stats.cancelled = count,
+                Some(AuthStatus::Revoked) => stats.revoked = count,
+                Some(AuthStatus::Expired) => stats.expired = count,
+                None => log::warn!("统计时遇到未知状态: {}", status_str),
+            }
+        }
+
+        Ok(stats)
     }
 
     /// 将数据库行转换为Record结构
@@ -460,13 +603,14 @@ impl RecordRepository {
             unique_id: row.get("unique_id"),
             status,
             vis_id: row.get("vis_id"),
+            vis_name: row.get("vis_name"),
             auth_type,
             times: row.get("times"),
             start_time: row.get("start_time"),
             ended_time: row.get("ended_time"),
-            password: row.get("password"),
             inviter: row.get("inviter"),
-            update_at: row.get("update_at"),
+            invite_id: row.get("invite_id"),
+            updated_at: row.get("updated_at"),
         })
     }
 
@@ -475,26 +619,8 @@ impl RecordRepository {
         pool: &sqlx::Pool<Sqlite>,
         unique_id: i64,
     ) -> Result<Option<String>> {
-        let row = sqlx::query(
-            r#"
-            SELECT password FROM record WHERE unique_id = ?
-            "#,
-        )
-        .bind(unique_id)
-        .fetch_optional(pool)
-        .await?;
-
-        if let Some(row) = row {
-            let password_json: Option<String> = row.get("password");
-            if let Some(json) = password_json {
-                let passwords: Vec<String> = serde_json::from_str(&json).unwrap_or_else(|_| Vec::new());
-                Ok(passwords.first().cloned())
-            } else {
-                Ok(None)
-            }
-        } else {
-            Ok(None)
-        }
+        let first = IssuedPasswordRepository::find_first_by_record(pool, unique_id).await?;
+        Ok(first.map(|issued| issued.password))
     }
 
     /// 检查记录是否已有密码（用于限制重复生成）
@@ -502,37 +628,21 @@ impl RecordRepository {
         pool: &sqlx::Pool<Sqlite>,
         unique_id: i64,
     ) -> Result<bool> {
-        let row = sqlx::query(
-            r#"
-            SELECT password FROM record WHERE unique_id = ?
-            "#,
-        )
-        .bind(unique_id)
-        .fetch_optional(pool)
-        .await?;
-
-        if let Some(row) = row {
-            let password_json: Option<String> = row.get("password");
-            if let Some(json) = password_json {
-                let passwords: Vec<String> = serde_json::from_str(&json).unwrap_or_else(|_| Vec::new());
-                Ok(!passwords.is_empty())
-            } else {
-                Ok(false)
-            }
-        } else {
-            Ok(false)
-        }
+        Ok(IssuedPasswordRepository::count_by_record(pool, unique_id).await? > 0)
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::database::{admin::AdminRepository, Database};
-    use crate::types::Admin;
+    use crate::database::{admin::AdminRepository, Database, LockRepository};
+    use crate::types::{Admin, IssuedPassword, Lock};
+    use crate::utils::{PasswordResult, PasswordType};
+    use chrono::TimeZone;
     use tempfile::NamedTempFile;
 
-    async fn setup_test_db() -> Result<(Database, i64)> {
+    /// 返回的临时文件需在测试期间保持存活，否则数据库文件会被提前删除
+    async fn setup_test_db() -> Result<(Database, i64, NamedTempFile)> {
         let temp_file = NamedTempFile::new()?;
         let db_url = format!("sqlite:{}", temp_file.path().to_str().unwrap());
         let db = Database::new(&db_url).await?;
@@ -543,12 +653,31 @@ mod tests {
         let admin_id = AdminRepository::create(&mut tx, &admin).await?;
         tx.commit().await?;
 
-        Ok((db, admin_id))
+        Ok((db, admin_id, temp_file))
+    }
+
+    async fn issue_password(db: &Database, record_id: i64, password: &str) -> Result<()> {
+        let valid_from = Utc.with_ymd_and_hms(2024, 1, 1, 11, 50, 0).unwrap();
+        let result = PasswordResult {
+            password: password.to_string(),
+            password_type: PasswordType::Temporary,
+            valid_from,
+            valid_until: valid_from + chrono::Duration::minutes(10),
+            remaining_uses: None,
+            window: (valid_from.timestamp() / 4) as u32,
+            message: String::new(),
+        };
+        let mut tx = db.begin_transaction().await?;
+        let mut lock = Lock::new(format!("门锁{}", password));
+        lock.unique_id = LockRepository::create(&mut tx, &lock).await?;
+        IssuedPasswordRepository::create(&mut tx, &IssuedPassword::from_result(record_id, &result, &lock)).await?;
+        tx.commit().await?;
+        Ok(())
     }
 
     #[tokio::test]
     async fn test_record_crud() -> Result<()> {
-        let (db, admin_id) = setup_test_db().await?;
+        let (db, admin_id, _temp_file) = setup_test_db().await?;
         let pool = db.pool();
 
         // 创建访客记录
@@ -580,13 +709,11 @@ mod tests {
         assert!(approved);
 
         // 添加密码
-        let mut tx = db.begin_transaction().await?;
-        let password_added = RecordRepository::add_password(&mut tx, record_id, "5001234567").await?;
-        tx.commit().await?;
-        assert!(password_added);
+        issue_password(&db, record_id, "5001234567").await?;
+        assert!(RecordRepository::has_passwords(pool, record_id).await?);
 
         // 检查活跃授权
-        let active_records = RecordRepository::find_active_by_visitor(pool, 987654321).await?;
+        let active_records = RecordRepository::find_active_by_visitor(pool, 987654321, Utc::now()).await?;
         assert!(!active_records.is_empty());
 
         // 撤销授权
@@ -601,7 +728,7 @@ mod tests {
 
     #[tokio::test]
     async fn test_visitor_constraints() -> Result<()> {
-        let (db, admin_id) = setup_test_db().await?;
+        let (db, admin_id, _temp_file) = setup_test_db().await?;
         let pool = db.pool();
         let visitor_id = 555666777;
 
@@ -620,16 +747,107 @@ mod tests {
         assert!(has_pending);
 
         // 检查是否有活跃授权
-        let has_active = RecordRepository::has_active_authorization(pool, visitor_id).await?;
+        let has_active = RecordRepository::has_active_authorization(pool, visitor_id, Utc::now()).await?;
         assert!(!has_active);
 
         db.close().await;
         Ok(())
     }
 
+    #[tokio::test]
+    async fn test_expired_authorizations() -> Result<()> {
+        let (db, admin_id, _temp_file) = setup_test_db().await?;
+        let pool = db.pool();
+
+        let mut tx = db.begin_transaction().await?;
+        let mut expired = Record::new(111111111, admin_id);
+        expired.status = AuthStatus::Auth;
+        expired.ended_time = Some(Utc::now() - chrono::Duration::minutes(1));
+        let expired_id = RecordRepository::create(&mut tx, &expired).await?;
+
+        let mut active = Record::new(222222222, admin_id);
+        active.status = AuthStatus::Auth;
+        active.ended_time = Some(Utc::now() + chrono::Duration::hours(1));
+        RecordRepository::create(&mut tx, &active).await?;
+
+        let mut unlimited = Record::new(333333333, admin_id);
+        unlimited.status = AuthStatus::Auth;
+        RecordRepository::create(&mut tx, &unlimited).await?;
+        tx.commit().await?;
+
+        let now = Utc::now();
+        let due = RecordRepository::find_expired_authorizations(pool, now).await?;
+        assert_eq!(due.len(), 1);
+        assert_eq!(due[0].unique_id, expired_id);
+
+        // 两小时后有效期为1小时的记录也到期了
+        let later = RecordRepository::find_expired_authorizations(pool, now + chrono::Duration::hours(2)).await?;
+        assert_eq!(later.len(), 2);
+
+        // 查询有效授权只读取，不会顺带修改已到期记录的状态
+        assert!(RecordRepository::find_active_by_visitor(pool, 111111111, now).await?.is_empty());
+        assert!(!RecordRepository::has_active_authorization(pool, 111111111, now).await?);
+        assert!(RecordRepository::has_active_authorization(pool, 222222222, now).await?);
+
+        // 全部有效授权按传入的时间判断，不使用数据库的当前时间
+        let active_ids = |records: Vec<Record>| {
+            let mut ids: Vec<i64> = records.iter().map(|r| r.vis_id).collect();
+            ids.sort();
+            ids
+        };
+        assert_eq!(active_ids(RecordRepository::find_all_active(pool, now).await?), vec![222222222, 333333333]);
+        let later = now + chrono::Duration::hours(2);
+        assert_eq!(active_ids(RecordRepository::find_all_active(pool, later).await?), vec![333333333]);
+        let earlier = now - chrono::Duration::hours(1);
+        assert_eq!(
+            active_ids(RecordRepository::find_all_active(pool, earlier).await?),
+            vec![111111111, 222222222, 333333333]
+        );
+        let record = RecordRepository::find_by_id(pool, expired_id).await?.unwrap();
+        assert_eq!(record.status, AuthStatus::Auth);
+
+        let mut tx = db.begin_transaction().await?;
+        assert!(RecordRepository::expire_by_id(&mut tx, expired_id).await?);
+        // 已过期的记录不会被重复处理
+        assert!(!RecordRepository::expire_by_id(&mut tx, expired_id).await?);
+        tx.commit().await?;
+
+        let record = RecordRepository::find_by_id(pool, expired_id).await?.unwrap();
+        assert_eq!(record.status, AuthStatus::Expired);
+        assert!(RecordRepository::find_expired_authorizations(pool, now).await?.is_empty());
+
+        db.close().await;
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_illegal_transitions_are_rejected() -> Result<()> {
+        let (db, admin_id, _temp_file) = setup_test_db().await?;
+        let pool = db.pool();
+
+        let mut tx = db.begin_transaction().await?;
+        let record_id = RecordRepository::create(&mut tx, &Record::new(987654321, admin_id)).await?;
+        assert!(RecordRepository::update_status(&mut tx, record_id, AuthStatus::Rejected).await?);
+
+        // 已拒绝的记录不能再批准、撤销或过期
+        assert!(!RecordRepository::approve_authorization(
+            &mut tx, record_id, AuthType::Temp, Some(Utc::now()), None, None,
+        ).await?);
+        assert!(!RecordRepository::revoke_by_id(&mut tx, record_id).await?);
+        assert!(!RecordRepository::expire_by_id(&mut tx, record_id).await?);
+        tx.commit().await?;
+
+        let record = RecordRepository::find_by_id(pool, record_id).await?.unwrap();
+        assert_eq!(record.status, AuthStatus::Rejected);
+        assert_eq!(record.start_time, None);
+
+        db.close().await;
+        Ok(())
+    }
+
     #[tokio::test]
     async fn test_statistics() -> Result<()> {
-        let (db, admin_id) = setup_test_db().await?;
+        let (db, admin_id, _temp_file) = setup_test_db().await?;
         let pool = db.pool();
 
         // 创建一些测试记录
@@ -647,11 +865,99 @@ mod tests {
         tx.commit().await?;
 
         // 获取统计信息
-        let (total, pending, authorized, revoked) = RecordRepository::get_statistics(pool).await?;
-        assert_eq!(total, 2);
-        assert_eq!(pending, 1);
-        assert_eq!(authorized, 1);
-        assert_eq!(revoked, 0);
+        let stats = RecordRepository::get_statistics(pool).await?;
+        assert_eq!(stats.total, 2);
+        assert_eq!(stats.pending, 1);
+        assert_eq!(stats.authorized, 1);
+        assert_eq!(stats.revoked, 0);
+
+        // 每种终态都单独统计
+        let mut tx = db.begin_transaction().await?;
+        let rejected_id = RecordRepository::create(&mut tx, &Record::new(333333333, admin_id)).await?;
+        assert!(RecordRepository::update_status(&mut tx, rejected_id, AuthStatus::Rejected).await?);
+        let cancelled_id = RecordRepository::create(&mut tx, &Record::new(444444444, admin_id)).await?;
+        assert!(RecordRepository::revoke_by_id(&mut tx, cancelled_id).await?);
+        tx.commit().await?;
+
+        let stats = RecordRepository::get_statistics(pool).await?;
+        assert_eq!(stats.total, 4);
+        assert_eq!(stats.count(AuthStatus::Rejected), 1);
+        assert_eq!(stats.count(AuthStatus::Cancelled), 1);
+        assert_eq!(stats.count(AuthStatus::Revoked), 0);
+        assert_eq!(AuthStatus::ALL.iter().map(|s| stats.count(*s)).sum::<i64>(), stats.total);
+
+        db.close().await;
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_pending_inbox() -> Result<()> {
+        let (db, admin_id, _temp_file) = setup_test_db().await?;
+        let pool = db.pool();
+
+        let mut tx = db.begin_transaction().await?;
+        let other_admin = AdminRepository::create(&mut tx, &Admin::new(555555555)).await?;
+        let base = Utc.with_ymd_and_hms(2024, 1, 1, 12, 0, 0).unwrap();
+        for (i, inviter) in [admin_id, other_admin, admin_id, admin_id].into_iter().enumerate() {
+            let mut record = Record::new(100 + i as i64, inviter).with_visitor_name(format!("访客{}", i));
+            record.updated_at = base + chrono::Duration::minutes(i as i64);
+            RecordRepository::create(&mut tx, &record).await?;
+        }
+        let mut handled = Record::new(200, admin_id);
+        handled.status = AuthStatus::Auth;
+        RecordRepository::create(&mut tx, &handled).await?;
+        tx.commit().await?;
+
+        assert_eq!(RecordRepository::count_pending(pool, Some(admin_id)).await?, 3);
+        assert_eq!(RecordRepository::count_pending(pool, None).await?, 4);
+
+        // 最早提交的请求在前
+        let first_page = RecordRepository::find_pending_page(pool, Some(admin_id), 2, 0).await?;
+        assert_eq!(first_page.iter().map(|r| r.vis_id).collect::<Vec<_>>(), vec![100, 102]);
+        assert_eq!(first_page[0].vis_name.as_deref(), Some("访客0"));
+        let second_page = RecordRepository::find_pending_page(pool, Some(admin_id), 2, 2).await?;
+        assert_eq!(second_page.iter().map(|r| r.vis_id).collect::<Vec<_>>(), vec![103]);
+
+        let all = RecordRepository::find_pending_page(pool, None, 10, 0).await?;
+        assert_eq!(all.iter().map(|r| r.vis_id).collect::<Vec<_>>(), vec![100, 101, 102, 103]);
+
+        db.close().await;
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_visitor_status_and_history() -> Result<()> {
+        let (db, admin_id, _temp_file) = setup_test_db().await?;
+        let pool = db.pool();
+
+        let base = Utc.with_ymd_and_hms(2024, 1, 1, 12, 0, 0).unwrap();
+        let mut tx = db.begin_transaction().await?;
+        let statuses = [
+            AuthStatus::Rejected,
+            AuthStatus::Expired,
+            AuthStatus::Auth,
+            AuthStatus::Revoked,
+            AuthStatus::Pending,
+        ];
+        let mut ids = Vec::new();
+        for (i, status) in statuses.into_iter().enumerate() {
+            let mut record = Record::new(987654321, admin_id);
+            record.status = status;
+            record.updated_at = base + chrono::Duration::hours(i as i64);
+            ids.push(RecordRepository::create(&mut tx, &record).await?);
+        }
+        RecordRepository::create(&mut tx, &Record::new(555666777, admin_id)).await?;
+        tx.commit().await?;
+
+        // 当前记录只包含待审批和已授权，最近更新的在前
+        let current = RecordRepository::find_current_by_visitor(pool, 987654321).await?;
+        assert_eq!(current.iter().map(|r| r.unique_id).collect::<Vec<_>>(), vec![ids[4], ids[2]]);
+
+        let history = RecordRepository::find_history_by_visitor(pool, 987654321, 10).await?;
+        assert_eq!(history.iter().map(|r| r.unique_id).collect::<Vec<_>>(), vec![ids[3], ids[1], ids[0]]);
+        let history = RecordRepository::find_history_by_visitor(pool, 987654321, 2).await?;
+        assert_eq!(history.len(), 2);
+        assert!(RecordRepository::find_history_by_visitor(pool, 555666777, 10).await?.is_empty());
 
         db.close().await;
         Ok(())
@@ -659,7 +965,7 @@ mod tests {
 
     #[tokio::test]
     async fn test_password_persistence() -> Result<()> {
-        let (db, admin_id) = setup_test_db().await?;
+        let (db, admin_id, _temp_file) = setup_test_db().await?;
         let pool = db.pool();
 
         // 创建测试记录
@@ -678,9 +984,7 @@ mod tests {
         assert_eq!(first_password_before, None, "初始状态不应该有第一个密码");
 
         // 添加密码
-        let mut tx = db.begin_transaction().await?;
-        RecordRepository::add_password(&mut tx, record_id, "5001234567").await?;
-        tx.commit().await?;
+        issue_password(&db, record_id, "5001234567").await?;
 
         // 测试添加密码后的状态
         let has_password_after = RecordRepository::has_passwords(pool, record_id).await?;
@@ -690,9 +994,7 @@ mod tests {
         assert_eq!(first_password_after, Some("5001234567".to_string()), "应该返回正确的第一个密码");
 
         // 添加第二个密码
-        let mut tx = db.begin_transaction().await?;
-        RecordRepository::add_password(&mut tx, record_id, "5001234568").await?;
-        tx.commit().await?;
+        issue_password(&db, record_id, "5001234568").await?;
 
         // 测试应该仍然返回第一个密码
         let first_password_still = RecordRepository::get_first_password(pool, record_id).await?;
@@ -704,7 +1006,7 @@ mod tests {
 
     #[tokio::test]
     async fn test_password_service_database_check() -> Result<()> {
-        let (db, admin_id) = setup_test_db().await?;
+        let (db, admin_id, _temp_file) = setup_test_db().await?;
         let pool = db.pool();
 
         // 创建测试记录
@@ -723,9 +1025,7 @@ mod tests {
         assert_eq!(existing_password_before, None, "初始状态PasswordService不应该检测到密码");
 
         // 添加密码到数据库
-        let mut tx = db.begin_transaction().await?;
-        RecordRepository::add_password(&mut tx, record_id, "5009876543").await?;
-        tx.commit().await?;
+        issue_password(&db, record_id, "5009876543").await?;
 
         // 测试PasswordService能检测到数据库中的密码
         let existing_password_after = password_service.has_generated_password(pool, record_id).await?;
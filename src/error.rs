@@ -8,6 +8,9 @@ pub enum AppError {
     #[error("数据库错误: {0}")]
     Database(#[from] sqlx::Error),
 
+    #[error("数据库迁移错误: {0}")]
+    Migration(String),
+
     #[error("配置错误: {0}")]
     Config(#[from] config::ConfigError),
 
@@ -63,6 +66,11 @@ impl AppError {
         Self::Permission(msg.into())
     }
 
+    /// 创建数据库迁移错误
+    pub fn migration<T: Into<String>>(msg: T) -> Self {
+        Self::Migration(msg.into())
+    }
+
     /// 创建密码生成错误
     pub fn password_generation<T: Into<String>>(msg: T) -> Self {
         Self::PasswordGeneration(msg.into())
@@ -8,41 +8,63 @@ pub use crate::utils::gen_password::*;
 #[cfg(test)]
 mod tests {
     use super::*;
+    use chrono::{Duration, Utc};
 
     #[test]
     fn test_password_generation() {
-        let mut generator = UnifiedPasswordGenerator::new();
-        let result = generator.generate_password("admin123", PasswordType::Temp);
-        
-        assert!(!result.password.is_empty());
-        assert_eq!(result.password.len(), 6);
-        assert!(result.expires_at.is_some());
+        let generator = UnifiedPasswordGenerator::new();
+        let result = generator.generate("admin123", PasswordType::Temporary).unwrap();
+
+        // 密码为 5000000000 加上32位加密结果，固定10位
+        assert_eq!(result.password.len(), 10);
+        assert!(result.password.parse::<u64>().unwrap() >= 5_000_000_000);
+        assert!(result.is_valid_at(Utc::now()));
+        assert_eq!(result.valid_until - result.valid_from, Duration::minutes(10));
+        assert_eq!(result.remaining_uses, None);
     }
 
     #[test]
     fn test_times_password() {
-        let mut generator = UnifiedPasswordGenerator::new();
-        let result = generator.generate_password("admin123", PasswordType::Times(3));
-        
-        assert_eq!(result.remaining_times, Some(3));
-        
-        // 验证密码
-        let password = result.password.clone();
-        assert!(generator.verify_password(&password).is_some());
-        assert!(generator.verify_password(&password).is_some());
-        assert!(generator.verify_password(&password).is_some());
-        assert!(generator.verify_password(&password).is_none()); // 第4次应该失败
+        let generator = UnifiedPasswordGenerator::new();
+        let result = generator.generate("admin123", PasswordType::Times(3)).unwrap();
+
+        assert_eq!(result.remaining_uses, Some(3));
+        assert_eq!(result.valid_until - result.valid_from, Duration::hours(20));
+
+        // 使用次数由门锁计数，验证密码本身不消耗次数
+        for _ in 0..4 {
+            assert_eq!(generator.verify(&result.password, "admin123"), Some(PasswordType::Times(3)));
+        }
     }
 
     #[test]
     fn test_limited_password() {
-        let mut generator = UnifiedPasswordGenerator::new();
-        let duration = Duration::hours(1);
-        let result = generator.generate_password("admin123", PasswordType::Limited(duration));
-        
-        assert!(result.expires_at.is_some());
-        let expires_at = result.expires_at.unwrap();
+        let generator = UnifiedPasswordGenerator::new();
+        let result = generator.generate("admin123", PasswordType::Limited(1, 0)).unwrap();
+
+        // 有效期从当前30分钟窗口的起点开始计算
+        assert!(result.valid_from <= Utc::now());
+        assert_eq!(result.valid_until - result.valid_from, Duration::hours(1));
         let expected_time = Utc::now() + Duration::hours(1);
-        assert!((expires_at - expected_time).num_seconds().abs() < 60); // 1分钟误差范围
+        assert!((result.valid_until - expected_time).num_minutes().abs() <= 30);
+    }
+
+    #[test]
+    fn test_result_serde_roundtrip() {
+        let generator = UnifiedPasswordGenerator::new();
+        let result = generator.generate("admin123", PasswordType::Limited(2, 30)).unwrap();
+
+        let json = serde_json::to_string(&result).unwrap();
+        let parsed: PasswordResult = serde_json::from_str(&json).unwrap();
+        assert_eq!(parsed, result);
+
+        assert_eq!(
+            serde_json::to_value(PasswordType::Limited(2, 30)).unwrap(),
+            serde_json::json!({ "limited": [2, 30] })
+        );
+        assert_eq!(
+            serde_json::to_value(PasswordType::Temporary).unwrap(),
+            serde_json::json!("temporary")
+        );
     }
-}
\ No newline at end of file
+}
@@ -1,26 +1,36 @@
 //! 管理员命令处理器
 
+use crate::auth::record_service::SYSTEM_ACTOR_ID;
+use crate::auth::{AdminRemoval, InviteOptions, PasswordService};
 use crate::bot::bot::BotState;
-use crate::database::RecordRepository;
-use crate::error::Result;
+use crate::database::{AuditFilter, AuditRepository, InviteRepository, IssuedPasswordRepository, RecordRepository};
+use crate::error::{AppError, Result};
 use crate::handlers::start::{get_user_display_name, validate_user_input};
-use crate::types::{AuthStatus, CallbackData};
-use chrono::{Utc, FixedOffset};
+use crate::handlers::text::format_duration;
+use crate::handlers::visitor::format_password_entries;
+use crate::types::{Admin, AuditAction, AuditEvent, AuthPreset, AuthStatus, AuthType, CallbackData, Invite, IssuedPassword, Lock, Record};
+use crate::utils::{
+    render_qr_png, search_offsets, CalibrationSample, LockTimezone, OffsetCandidate, PasswordInspection,
+    MAX_CALIBRATION_DRIFT_SECS,
+};
+use chrono::{Utc, NaiveDate, NaiveDateTime};
 use teloxide::{
     prelude::*,
-    types::{InlineKeyboardButton, InlineKeyboardMarkup},
+    types::{InlineKeyboardButton, InlineKeyboardMarkup, InputFile},
+    utils::html,
 };
 
-/// 格式化为UTC+8时间字符串
-fn format_beijing_time(timestamp: chrono::DateTime<Utc>) -> String {
-    let beijing_tz = FixedOffset::east_opt(8 * 3600).unwrap();
-    timestamp.with_timezone(&beijing_tz).format("%Y-%m-%d %H:%M:%S").to_string()
-}
+/// 审计日志每页显示条数
+const AUDIT_PAGE_SIZE: i64 = 10;
 
-/// 获取当前UTC+8时间字符串
-fn current_beijing_time() -> String {
-    format_beijing_time(Utc::now())
-}
+/// 待审批列表每页显示条数
+const PENDING_PAGE_SIZE: i64 = 5;
+
+/// 访客授权列表每页显示条数
+const VISITOR_PAGE_SIZE: i64 = 5;
+
+/// 访客授权每次延长的天数
+const EXTEND_DAYS: i64 = 1;
 
 /// 处理/addadmin命令 - 添加管理员（仅超级管理员）
 pub async fn add_admin_command(
@@ -101,6 +111,342 @@ pub async fn add_admin_command(
     Ok(())
 }
 
+/// 处理/listadmins命令 - 查看管理员列表（仅超级管理员）
+pub async fn list_admins_command(bot: Bot, msg: Message, state: BotState) -> Result<()> {
+    let user = match msg.from() {
+        Some(user) => user,
+        None => {
+            bot.send_message(msg.chat.id, "无法获取用户信息").await?;
+            return Ok(());
+        }
+    };
+
+    let user_id = user.id.0 as i64;
+    let user_service = state.user_service.read().await;
+    if !user_service.is_super_admin(user_id) {
+        bot.send_message(msg.chat.id, "❌ 只有超级管理员可以查看管理员列表")
+            .await?;
+        return Ok(());
+    }
+
+    let admins = user_service.list_all_admins(user_id).await?;
+    if admins.is_empty() {
+        bot.send_message(msg.chat.id, "📭 还没有添加管理员\n\n使用 /addadmin <用户ID> 添加管理员")
+            .await?;
+        return Ok(());
+    }
+
+    let pool = state.database.pool();
+    let now = state.clock.now();
+    let mut entries = Vec::with_capacity(admins.len());
+    for admin in &admins {
+        let records = RecordRepository::find_by_inviter(pool, admin.unique_id).await?;
+        let invites = InviteRepository::find_by_admin(pool, admin.unique_id).await?;
+        let counts = AdminCounts {
+            active: records.iter().filter(|record| record.is_active_at(now)).count(),
+            pending: records.iter().filter(|record| record.status == AuthStatus::Pending).count(),
+            invites: invites.iter().filter(|invite| invite.is_usable()).count(),
+        };
+        entries.push(format_admin_entry(admin, user_service.is_super_admin(admin.id), &counts));
+    }
+    drop(user_service);
+
+    bot.send_message(
+        msg.chat.id,
+        format!("👥 管理员列表（共 {} 位）\n\n{}", admins.len(), entries.join("\n\n")),
+    )
+    .await?;
+
+    Ok(())
+}
+
+/// 管理员名下的授权和邀请码数量
+struct AdminCounts {
+    /// 有效授权
+    active: usize,
+    /// 待审批请求
+    pending: usize,
+    /// 可用邀请码
+    invites: usize,
+}
+
+/// 格式化单个管理员
+fn format_admin_entry(admin: &Admin, is_super_admin: bool, counts: &AdminCounts) -> String {
+    format!(
+        "👤 {}{}（#{}）\n\
+         🔑 管理密码：{}\n\
+         📋 有效授权 {} · 待审批 {} · 可用邀请码 {}",
+        admin.id,
+        if is_super_admin { " ⭐" } else { "" },
+        admin.unique_id,
+        if admin.password.is_some() { "已设置" } else { "未设置" },
+        counts.active,
+        counts.pending,
+        counts.invites
+    )
+}
+
+/// 处理/removeadmin命令 - 删除管理员并撤销或转交其访客（仅超级管理员）
+pub async fn remove_admin_command(
+    bot: Bot,
+    msg: Message,
+    cmd: crate::bot::bot::Command,
+    state: BotState,
+) -> Result<()> {
+    let args = match cmd {
+        crate::bot::bot::Command::RemoveAdmin(args) => args,
+        _ => {
+            bot.send_message(msg.chat.id, "❌ 命令参数错误").await?;
+            return Ok(());
+        }
+    };
+    let operator = match msg.from() {
+        Some(user) => user,
+        None => {
+            bot.send_message(msg.chat.id, "无法获取用户信息").await?;
+            return Ok(());
+        }
+    };
+
+    let operator_id = operator.id.0 as i64;
+    log::info!("用户 {} 尝试删除管理员: {}", operator_id, args);
+
+    let user_service = state.user_service.read().await;
+    if !user_service.is_super_admin(operator_id) {
+        bot.send_message(msg.chat.id, "❌ 只有超级管理员可以删除管理员")
+            .await?;
+        return Ok(());
+    }
+
+    let (target_id, removal) = match parse_remove_admin_args(&args) {
+        Ok(parsed) => parsed,
+        Err(e) => {
+            bot.send_message(msg.chat.id, format!("❌ {}\n\n{}", e, REMOVE_ADMIN_USAGE))
+                .await?;
+            return Ok(());
+        }
+    };
+
+    let summary = match user_service.remove_admin(operator_id, target_id, removal).await {
+        Ok(summary) => summary,
+        Err(e) => {
+            bot.send_message(msg.chat.id, format!("❌ 删除管理员失败: {}", e))
+                .await?;
+            log::warn!("删除管理员失败: {}", e);
+            return Ok(());
+        }
+    };
+    drop(user_service);
+
+    let outcome = match removal {
+        AdminRemoval::Revoke => format!(
+            "🚫 已撤销 {} 条授权或待审批请求、{} 个邀请码",
+            summary.records, summary.invites
+        ),
+        AdminRemoval::Transfer(successor_id) => format!(
+            "🔁 已将 {} 条访客记录、{} 个邀请码转交给 {}",
+            summary.records, summary.invites, successor_id
+        ),
+    };
+    bot.send_message(
+        msg.chat.id,
+        format!(
+            "✅ 已删除管理员 {}\n\n\
+             {}\n\
+             👨‍💼 操作员：{}",
+            target_id,
+            outcome,
+            get_user_display_name(operator)
+        ),
+    )
+    .await?;
+
+    log::info!("超级管理员 {} 删除了管理员 {}", operator_id, target_id);
+    Ok(())
+}
+
+/// /removeadmin 命令的用法说明
+const REMOVE_ADMIN_USAGE: &str = "📋 使用方法：/removeadmin <用户ID> <revoke | transfer 用户ID>\n\
+     • revoke：撤销该管理员的访客授权、待审批请求和邀请码\n\
+     • transfer：将访客记录和邀请码转交给另一位管理员\n\
+     示例：/removeadmin 123456789 transfer 987654321";
+
+/// 解析 /removeadmin 参数，必须明确选择撤销或转交
+fn parse_remove_admin_args(args: &str) -> Result<(i64, AdminRemoval)> {
+    let parts: Vec<&str> = args.split_whitespace().collect();
+    let parse_id = |value: &str| {
+        value
+            .parse::<i64>()
+            .map_err(|_| AppError::validation(format!("无效的用户ID: {}", value)))
+    };
+
+    match parts.as_slice() {
+        [target, "revoke"] => Ok((parse_id(target)?, AdminRemoval::Revoke)),
+        [target, "transfer", successor] => Ok((parse_id(target)?, AdminRemoval::Transfer(parse_id(successor)?))),
+        [_] => Err(AppError::validation("请选择撤销（revoke）或转交（transfer）该管理员的访客")),
+        _ => Err(AppError::validation("参数格式错误")),
+    }
+}
+
+/// 处理/addsuper命令 - 添加超级管理员并写回配置文件（仅超级管理员）
+pub async fn add_super_command(
+    bot: Bot,
+    msg: Message,
+    cmd: crate::bot::bot::Command,
+    state: BotState,
+) -> Result<()> {
+    let args = match cmd {
+        crate::bot::bot::Command::AddSuper(args) => args,
+        _ => {
+            bot.send_message(msg.chat.id, "❌ 命令参数错误").await?;
+            return Ok(());
+        }
+    };
+    let operator = match msg.from() {
+        Some(user) => user,
+        None => {
+            bot.send_message(msg.chat.id, "无法获取用户信息").await?;
+            return Ok(());
+        }
+    };
+
+    let operator_id = operator.id.0 as i64;
+    log::info!("用户 {} 尝试添加超级管理员: {}", operator_id, args);
+
+    if !state.user_service.read().await.is_super_admin(operator_id) {
+        bot.send_message(msg.chat.id, "❌ 只有超级管理员可以添加超级管理员")
+            .await?;
+        return Ok(());
+    }
+
+    let target_id = match args.trim().parse::<i64>() {
+        Ok(id) if id > 0 => id,
+        _ => {
+            bot.send_message(msg.chat.id, "📋 使用方法：/addsuper <用户ID>").await?;
+            return Ok(());
+        }
+    };
+    if state.config().is_super_admin(target_id) {
+        bot.send_message(msg.chat.id, format!("⚠️ {} 已经是超级管理员", target_id))
+            .await?;
+        return Ok(());
+    }
+
+    if let Err(e) = state.update_config(|config| config.add_super_admin(target_id)).await {
+        log::error!("添加超级管理员失败: {}", e);
+        bot.send_message(msg.chat.id, format!("❌ 添加超级管理员失败: {}", e))
+            .await?;
+        return Ok(());
+    }
+    state
+        .user_service
+        .read()
+        .await
+        .record_super_admin_change(operator_id, AuditAction::AddSuperAdmin, target_id)
+        .await?;
+
+    bot.send_message(
+        msg.chat.id,
+        format!(
+            "✅ 已添加超级管理员 {}\n\n{}\n👨‍💼 操作员：{}",
+            target_id,
+            super_admin_persistence_note(&state),
+            get_user_display_name(operator)
+        ),
+    )
+    .await?;
+
+    log::info!("超级管理员 {} 添加了超级管理员 {}", operator_id, target_id);
+    Ok(())
+}
+
+/// 处理/removesuper命令 - 移除超级管理员并写回配置文件（仅超级管理员）
+pub async fn remove_super_command(
+    bot: Bot,
+    msg: Message,
+    cmd: crate::bot::bot::Command,
+    state: BotState,
+) -> Result<()> {
+    let args = match cmd {
+        crate::bot::bot::Command::RemoveSuper(args) => args,
+        _ => {
+            bot.send_message(msg.chat.id, "❌ 命令参数错误").await?;
+            return Ok(());
+        }
+    };
+    let operator = match msg.from() {
+        Some(user) => user,
+        None => {
+            bot.send_message(msg.chat.id, "无法获取用户信息").await?;
+            return Ok(());
+        }
+    };
+
+    let operator_id = operator.id.0 as i64;
+    log::info!("用户 {} 尝试移除超级管理员: {}", operator_id, args);
+
+    if !state.user_service.read().await.is_super_admin(operator_id) {
+        bot.send_message(msg.chat.id, "❌ 只有超级管理员可以移除超级管理员")
+            .await?;
+        return Ok(());
+    }
+
+    let target_id = match args.trim().parse::<i64>() {
+        Ok(id) => id,
+        Err(_) => {
+            bot.send_message(msg.chat.id, "📋 使用方法：/removesuper <用户ID>").await?;
+            return Ok(());
+        }
+    };
+    let config = state.config();
+    if !config.is_super_admin(target_id) {
+        bot.send_message(msg.chat.id, format!("⚠️ {} 不是超级管理员", target_id))
+            .await?;
+        return Ok(());
+    }
+    if config.super_admin_ids.len() == 1 {
+        bot.send_message(msg.chat.id, "❌ 不能移除最后一位超级管理员").await?;
+        return Ok(());
+    }
+
+    // 并发移除时由配置校验兜底，至少保留一位超级管理员
+    if let Err(e) = state.update_config(|config| config.remove_super_admin(target_id)).await {
+        log::error!("移除超级管理员失败: {}", e);
+        bot.send_message(msg.chat.id, format!("❌ 移除超级管理员失败: {}", e))
+            .await?;
+        return Ok(());
+    }
+    state
+        .user_service
+        .read()
+        .await
+        .record_super_admin_change(operator_id, AuditAction::RemoveSuperAdmin, target_id)
+        .await?;
+
+    bot.send_message(
+        msg.chat.id,
+        format!(
+            "✅ 已移除超级管理员 {}\n\n{}\n👨‍💼 操作员：{}",
+            target_id,
+            super_admin_persistence_note(&state),
+            get_user_display_name(operator)
+        ),
+    )
+    .await?;
+
+    log::info!("超级管理员 {} 移除了超级管理员 {}", operator_id, target_id);
+    Ok(())
+}
+
+/// 超级管理员变更是否已写入配置文件的说明
+fn super_admin_persistence_note(state: &BotState) -> &'static str {
+    if state.config_manager.is_some() {
+        "📝 已写入配置文件，立即生效"
+    } else {
+        "⚠️ 未关联配置文件，仅在本次运行中生效"
+    }
+}
+
 /// 处理/editpasswd命令 - 修改管理员密码
 pub async fn edit_password_command(
     bot: Bot,
@@ -176,7 +522,20 @@ pub async fn edit_password_command(
 }
 
 /// 处理/geninvite命令 - 生成邀请码
-pub async fn gen_invite_command(bot: Bot, msg: Message, state: BotState) -> Result<()> {
+pub async fn gen_invite_command(
+    bot: Bot,
+    msg: Message,
+    cmd: crate::bot::bot::Command,
+    state: BotState,
+) -> Result<()> {
+    let args = match cmd {
+        crate::bot::bot::Command::GenInvite(args) => args,
+        _ => {
+            bot.send_message(msg.chat.id, "❌ 命令参数错误").await?;
+            return Ok(());
+        }
+    };
+
     let user = match msg.from() {
         Some(user) => user,
         None => {
@@ -186,7 +545,7 @@ pub async fn gen_invite_command(bot: Bot, msg: Message, state: BotState) -> Resu
     };
 
     let user_id = user.id.0 as i64;
-    log::info!("用户 {} 尝试生成邀请码", user_id);
+    log::info!("用户 {} 尝试生成邀请码: {}", user_id, args);
 
     let user_service = state.user_service.read().await;
 
@@ -216,37 +575,76 @@ pub async fn gen_invite_command(bot: Bot, msg: Message, state: BotState) -> Resu
         return Ok(());
     }
 
-    // 检查是否已有邀请码
-    if let Some(ref existing_code) = admin.invite_code {
-        // 已有邀请码，询问是否更换
-        let keyboard = InlineKeyboardMarkup::new(vec![
-            vec![
-                InlineKeyboardButton::callback("🔄 更换邀请码",
-                    CallbackData::new("regenerate_invite").to_callback_string().unwrap()),
-                InlineKeyboardButton::callback("❌ 取消",
-                    CallbackData::new("cancel").to_callback_string().unwrap()),
-            ]
-        ]);
+    let locks = state.lock_service.list().await?;
+    let options = match parse_invite_args(&args, &state.config().lock_timezone, &locks) {
+        Ok(options) => options,
+        Err(e) => {
+            bot.send_message(
+                msg.chat.id,
+                format!(
+                    "❌ {}\n\n\
+                     {}",
+                    e, GEN_INVITE_USAGE
+                )
+            ).await?;
+            return Ok(());
+        }
+    };
 
-        let message = format!(
-            "🎫 您当前的邀请码：\n<code>{}</code>\n\n\
-             ⚠️ 是否要生成新的邀请码？\n\
-             注意：原邀请码将失效！",
-            existing_code
-        );
+    if let Some(ref preset) = options.preset {
+        if let Err(e) = state.password_service.read().await.validate_preset(preset, &state.config()) {
+            bot.send_message(msg.chat.id, format!("❌ 预设授权无效: {}\n\n{}", e, GEN_INVITE_USAGE))
+                .await?;
+            return Ok(());
+        }
+    }
 
-        bot.send_message(msg.chat.id, message)
-            .reply_markup(keyboard)
-            .parse_mode(teloxide::types::ParseMode::Html)
-            .await?;
-    } else {
-        // 第一次生成邀请码
-        generate_new_invite_code(&bot, msg.chat.id, &user_service, admin.unique_id).await?;
+    match state.invite_service.create(user_id, admin.unique_id, &options).await {
+        Ok(invite) => send_new_invite_message(&bot, msg.chat.id, &invite, &state).await?,
+        Err(e) => {
+            bot.send_message(msg.chat.id, format!("❌ 生成邀请码失败: {}", e))
+                .await?;
+            log::error!("生成邀请码失败: {}", e);
+        }
     }
 
     Ok(())
 }
 
+/// 处理/invites命令 - 查看和管理邀请码
+pub async fn invites_command(bot: Bot, msg: Message, state: BotState) -> Result<()> {
+    let user = match msg.from() {
+        Some(user) => user,
+        None => {
+            bot.send_message(msg.chat.id, "无法获取用户信息").await?;
+            return Ok(());
+        }
+    };
+
+    let user_id = user.id.0 as i64;
+    let user_service = state.user_service.read().await;
+
+    let admin = match user_service.get_admin_info(user_id).await? {
+        Some(admin) => admin,
+        None => {
+            bot.send_message(msg.chat.id, "❌ 只有管理员可以管理邀请码")
+                .await?;
+            return Ok(());
+        }
+    };
+
+    let invites = state.invite_service.list_by_admin(admin.unique_id).await?;
+    let locks = state.lock_service.list().await?;
+    let (text, keyboard) = build_invite_list(&invites, &locks, &state.config().lock_timezone);
+
+    bot.send_message(msg.chat.id, text)
+        .reply_markup(keyboard)
+        .parse_mode(teloxide::types::ParseMode::Html)
+        .await?;
+
+    Ok(())
+}
+
 /// 处理/revoke命令 - 撤销授权
 pub async fn revoke_command(
     bot: Bot,
@@ -288,9 +686,7 @@ pub async fn revoke_command(
         let record_id = target.trim_start_matches("record").trim_start_matches("r");
         match record_id.parse::<i64>() {
             Ok(id) => {
-                let mut tx = state.database.begin_transaction().await?;
-                let success = RecordRepository::revoke_by_id(&mut tx, id).await?;
-                tx.commit().await?;
+                let success = state.record_service.revoke(user_id, id).await?;
                 if success { 1 } else { 0 }
             }
             Err(_) => {
@@ -304,10 +700,7 @@ pub async fn revoke_command(
         let user_id_str = target.trim_start_matches("user").trim_start_matches("u");
         match user_id_str.parse::<i64>() {
             Ok(target_user_id) => {
-                let mut tx = state.database.begin_transaction().await?;
-                let count = RecordRepository::revoke_all_by_visitor(&mut tx, target_user_id).await?;
-                tx.commit().await?;
-                count
+                state.record_service.revoke_all_by_visitor(user_id, target_user_id).await?
             }
             Err(_) => {
                 bot.send_message(msg.chat.id, "❌ 无效的用户ID格式")
@@ -319,10 +712,7 @@ pub async fn revoke_command(
         // 尝试解析为数字 - 默认当作用户ID处理
         match target.parse::<i64>() {
             Ok(target_user_id) => {
-                let mut tx = state.database.begin_transaction().await?;
-                let count = RecordRepository::revoke_all_by_visitor(&mut tx, target_user_id).await?;
-                tx.commit().await?;
-                count
+                state.record_service.revoke_all_by_visitor(user_id, target_user_id).await?
             }
             Err(_) => {
                 bot.send_message(
@@ -350,7 +740,7 @@ pub async fn revoke_command(
             revoked_count,
             target,
             operator_name,
-            current_beijing_time()
+            state.config().lock_timezone.format(state.clock.now())
         )
     } else {
         format!(
@@ -368,46 +758,1430 @@ pub async fn revoke_command(
     Ok(())
 }
 
-/// 生成新邀请码的辅助函数
-async fn generate_new_invite_code(
-    bot: &Bot,
-    chat_id: ChatId,
-    user_service: &crate::auth::UserService,
-    admin_unique_id: i64,
-) -> Result<()> {
-    match user_service.generate_admin_invite_code(admin_unique_id).await {
-        Ok(invite_code) => {
-            let message = format!(
-                "✅ 邀请码生成成功！\n\n\
-                 🎫 您的邀请码：\n<code>{}</code>\n\n\
-                 📋 使用方法：\n\
-                 访客使用命令 /req {} 申请授权\n\n\
-                 💡 提示：请妥善保管邀请码",
-                invite_code, invite_code
-            );
-
-            bot.send_message(chat_id, message)
-                .parse_mode(teloxide::types::ParseMode::Html)
-                .await?;
-        }
-        Err(e) => {
-            bot.send_message(chat_id, format!("❌ 生成邀请码失败: {}", e))
-                .await?;
-            log::error!("生成邀请码失败: {}", e);
-        }
-    }
-    Ok(())
-}
-
-/// 获取管理员状态信息
-pub async fn get_admin_status(
+/// 处理/check命令 - 检查密码是否由自己的管理密码生成及其有效期
+pub async fn check_command(
     bot: Bot,
     msg: Message,
+    cmd: crate::bot::bot::Command,
     state: BotState,
 ) -> Result<()> {
+    let password = match cmd {
+        crate::bot::bot::Command::Check(password) => password.trim().to_string(),
+        _ => {
+            bot.send_message(msg.chat.id, "❌ 命令参数错误").await?;
+            return Ok(());
+        }
+    };
+
     let user = match msg.from() {
         Some(user) => user,
-        None => return Ok(()),
+        None => {
+            bot.send_message(msg.chat.id, "无法获取用户信息").await?;
+            return Ok(());
+        }
+    };
+
+    let user_id = user.id.0 as i64;
+    log::info!("用户 {} 检查密码", user_id);
+
+    let user_service = state.user_service.read().await;
+
+    // 检查权限
+    if !user_service.is_admin(user_id).await? {
+        bot.send_message(msg.chat.id, "❌ 只有管理员可以检查密码")
+            .await?;
+        return Ok(());
+    }
+
+    let admin = match user_service.get_admin_info(user_id).await? {
+        Some(admin) => admin,
+        None => {
+            bot.send_message(msg.chat.id, "❌ 管理员信息不存在")
+                .await?;
+            return Ok(());
+        }
+    };
+    let is_super_admin = user_service.is_super_admin(user_id);
+    drop(user_service);
+
+    let admin_password = match admin.password.clone() {
+        Some(admin_password) => admin_password,
+        None => {
+            bot.send_message(
+                msg.chat.id,
+                "❌ 请先设置管理密码！\n\n使用命令: /editpasswd <密码>\n密码要求: 4-10位数字"
+            ).await?;
+            return Ok(());
+        }
+    };
+
+    // 门锁密码固定为10位数字
+    if password.len() != 10 || !password.chars().all(|c| c.is_ascii_digit()) {
+        bot.send_message(
+            msg.chat.id,
+            "❌ 密码必须是10位数字\n\n\
+             正确格式：/check <密码>\n\
+             例如：/check 5012345678"
+        ).await?;
+        return Ok(());
+    }
+
+    bot.send_message(msg.chat.id, "🔍 正在检查密码，请稍候...").await?;
+
+    // 需要在每个门锁上搜索大量时间窗口，放到阻塞线程中执行
+    let locks = state.lock_service.list().await?;
+    let inspected = password.clone();
+    let now = state.clock.now();
+    let (matched_lock, inspection) = {
+        let locks = locks.clone();
+        tokio::task::spawn_blocking(move || {
+            PasswordService::inspect_on_locks(&inspected, &admin_password, &locks, now)
+        })
+        .await
+        .map_err(|e| AppError::Other(format!("密码检查任务失败: {}", e)))?
+    };
+
+    let timezone = state.config().lock_timezone;
+    let mut message = format_inspection(&password, &inspection, now, &timezone);
+    if let (Some(index), true) = (matched_lock, locks.len() > 1) {
+        message.push_str(&format!("\n🚪 门锁：{}", locks[index].name));
+    }
+
+    // 附上可见的发放记录：自己邀请的访客，超级管理员可看到全部
+    let pool = state.database.pool();
+    for issued in IssuedPasswordRepository::find_by_password(pool, &password).await? {
+        let record = match RecordRepository::find_by_id(pool, issued.record_id).await? {
+            Some(record) if is_super_admin || record.inviter == admin.unique_id => record,
+            _ => continue,
+        };
+        message.push_str(&format!(
+            "\n\n📤 已发放给访客 {}（记录ID：{}，{}）",
+            record.vis_id,
+            record.unique_id,
+            timezone.format(issued.generated_at)
+        ));
+    }
+
+    bot.send_message(msg.chat.id, message).await?;
+    Ok(())
+}
+
+/// 格式化密码检查结果
+fn format_inspection(
+    password: &str,
+    inspection: &PasswordInspection,
+    now: chrono::DateTime<Utc>,
+    timezone: &LockTimezone,
+) -> String {
+    let result = match inspection.result() {
+        Some(result) => result,
+        None => {
+            return format!(
+                "❓ 密码 {} 不是由您的管理密码生成，或生成时间超出检查范围",
+                password
+            );
+        }
+    };
+
+    let verdict = match inspection {
+        PasswordInspection::Valid(_) => {
+            let remaining = (result.valid_until - now).num_minutes().max(0);
+            format!("✅ 当前有效，剩余 {} 小时 {} 分钟", remaining / 60, remaining % 60)
+        }
+        PasswordInspection::Expired(_) => format!("⌛ 已于 {} 过期", timezone.format(result.valid_until)),
+        PasswordInspection::NotYetValid(_) => format!("⏳ 将于 {} 生效", timezone.format(result.valid_from)),
+        PasswordInspection::NotOurs => unreachable!(),
+    };
+
+    let mut message = format!(
+        "🔍 密码检查结果\n\n\
+         🔑 密码：{}\n\
+         📋 类型：{}\n\
+         🪟 时间窗口：{}\n\
+         🕐 生效时间：{}\n\
+         ⏰ 失效时间：{}",
+        result.password,
+        result.password_type,
+        result.window,
+        timezone.format(result.valid_from),
+        timezone.format(result.valid_until)
+    );
+    if let Some(uses) = result.remaining_uses {
+        message.push_str(&format!("\n🔢 可用次数：{}", uses));
+    }
+    message.push_str(&format!("\n\n{}", verdict));
+
+    message
+}
+
+/// 处理/locks命令 - 查看门锁
+pub async fn locks_command(bot: Bot, msg: Message, state: BotState) -> Result<()> {
+    let user = match msg.from() {
+        Some(user) => user,
+        None => {
+            bot.send_message(msg.chat.id, "无法获取用户信息").await?;
+            return Ok(());
+        }
+    };
+
+    let user_id = user.id.0 as i64;
+    let user_service = state.user_service.read().await;
+    if !user_service.is_admin(user_id).await? {
+        bot.send_message(msg.chat.id, "❌ 只有管理员可以查看门锁")
+            .await?;
+        return Ok(());
+    }
+    let is_super_admin = user_service.is_super_admin(user_id);
+    drop(user_service);
+
+    let locks = state.lock_service.list().await?;
+    let mut message = if locks.is_empty() {
+        "🚪 尚未配置门锁".to_string()
+    } else {
+        let entries: Vec<String> = locks
+            .iter()
+            .enumerate()
+            .map(|(index, lock)| format_lock(lock, index == 0))
+            .collect();
+        format!("🚪 门锁列表（共 {} 个）\n\n{}", locks.len(), entries.join("\n\n"))
+    };
+    if is_super_admin {
+        message.push_str(&format!("\n\n💡 {}", SET_LOCK_USAGE));
+    }
+
+    bot.send_message(msg.chat.id, message).await?;
+    Ok(())
+}
+
+/// 处理/setlock命令 - 添加或修改门锁（仅超级管理员）
+pub async fn set_lock_command(
+    bot: Bot,
+    msg: Message,
+    cmd: crate::bot::bot::Command,
+    state: BotState,
+) -> Result<()> {
+    let args = match cmd {
+        crate::bot::bot::Command::SetLock(args) => args,
+        _ => {
+            bot.send_message(msg.chat.id, "❌ 命令参数错误").await?;
+            return Ok(());
+        }
+    };
+    let user = match msg.from() {
+        Some(user) => user,
+        None => {
+            bot.send_message(msg.chat.id, "无法获取用户信息").await?;
+            return Ok(());
+        }
+    };
+
+    let user_id = user.id.0 as i64;
+    log::info!("用户 {} 修改门锁: {}", user_id, args);
+
+    // 检查权限
+    if !state.user_service.read().await.is_super_admin(user_id) {
+        bot.send_message(msg.chat.id, "❌ 只有超级管理员可以修改门锁")
+            .await?;
+        return Ok(());
+    }
+
+    let locks = state.lock_service.list().await?;
+    let lock = match parse_set_lock_args(&args, &locks) {
+        Ok(lock) => lock,
+        Err(e) => {
+            bot.send_message(msg.chat.id, format!("❌ {}\n\n{}", e, SET_LOCK_USAGE))
+                .await?;
+            return Ok(());
+        }
+    };
+
+    let created = lock.unique_id == 0;
+    let saved = state.lock_service.save(user_id, &lock).await?;
+    let is_default = locks.first().is_none_or(|first| first.unique_id == saved.unique_id);
+
+    bot.send_message(
+        msg.chat.id,
+        format!(
+            "✅ 门锁已{}\n\n{}",
+            if created { "添加" } else { "更新" },
+            format_lock(&saved, is_default)
+        ),
+    )
+    .await?;
+    Ok(())
+}
+
+/// 门锁名称最大长度
+const LOCK_NAME_MAX_CHARS: usize = 16;
+
+/// 门锁时间偏移的最大绝对值（秒）
+const LOCK_MAX_TIME_OFFSET: i64 = 86400;
+
+/// /setlock命令使用说明
+const SET_LOCK_USAGE: &str = "📋 使用方法：/setlock <名称> [offset 秒] [tz 时区] [types 类型,...]\n\
+     • 名称不存在时添加门锁，存在时修改指定的项\n\
+     • offset：门锁时钟比真实时间快的秒数，可为负数\n\
+     • tz：门锁时区，如 Asia/Shanghai\n\
+     • types：启用的授权类型，如 limited,period,times,temp,longtime_temp\n\
+     • 示例：/setlock 大门 offset -30 types temp,times";
+
+/// 解析/setlock命令参数，返回新建或修改后的门锁
+fn parse_set_lock_args(args: &str, locks: &[Lock]) -> Result<Lock> {
+    let mut tokens = args.split_whitespace();
+    let name = tokens.next().ok_or_else(|| AppError::validation("请指定门锁名称"))?;
+    if name.chars().count() > LOCK_NAME_MAX_CHARS {
+        return Err(AppError::validation(format!("门锁名称不能超过{}个字符", LOCK_NAME_MAX_CHARS)));
+    }
+    if name.contains(',') {
+        return Err(AppError::validation("门锁名称不能包含逗号"));
+    }
+
+    let mut lock = locks
+        .iter()
+        .find(|lock| lock.name == name)
+        .cloned()
+        .unwrap_or_else(|| Lock::new(name));
+
+    let tokens: Vec<&str> = tokens.collect();
+    for pair in tokens.chunks(2) {
+        let (key, value) = match pair {
+            [key, value] => (*key, *value),
+            [key] => return Err(AppError::validation(format!("参数 {} 缺少取值", key))),
+            _ => unreachable!(),
+        };
+
+        match key {
+            "offset" => {
+                lock.time_offset = value
+                    .parse::<i64>()
+                    .ok()
+                    .filter(|offset| offset.abs() <= LOCK_MAX_TIME_OFFSET)
+                    .ok_or_else(|| AppError::validation(format!("时间偏移必须是 ±{} 以内的整数秒", LOCK_MAX_TIME_OFFSET)))?;
+            }
+            "tz" => lock.timezone = value.parse().map_err(AppError::validation)?,
+            "types" => {
+                lock.auth_types = AuthType::parse_list(value)
+                    .filter(|types| !types.is_empty())
+                    .ok_or_else(|| AppError::validation(format!("未知的授权类型: {}", value)))?;
+            }
+            _ => return Err(AppError::validation(format!("未知参数: {}", key))),
+        }
+    }
+
+    Ok(lock)
+}
+
+/// 格式化单个门锁
+fn format_lock(lock: &Lock, is_default: bool) -> String {
+    let auth_types: Vec<&str> = lock.auth_types.iter().map(|auth_type| auth_type.description()).collect();
+    format!(
+        "🚪 {}{}（ID：{}）\n\
+         ⏱ 时间偏移：{} 秒\n\
+         🌐 时区：{}\n\
+         📋 授权类型：{}",
+        lock.name,
+        if is_default { "（默认）" } else { "" },
+        lock.unique_id,
+        lock.time_offset,
+        lock.timezone,
+        auth_types.join("、")
+    )
+}
+
+/// 处理/calibrate命令 - 根据门锁接受或显示的密码校准时间偏移（仅超级管理员）
+pub async fn calibrate_command(
+    bot: Bot,
+    msg: Message,
+    cmd: crate::bot::bot::Command,
+    state: BotState,
+) -> Result<()> {
+    let args = match cmd {
+        crate::bot::bot::Command::Calibrate(args) => args,
+        _ => {
+            bot.send_message(msg.chat.id, "❌ 命令参数错误").await?;
+            return Ok(());
+        }
+    };
+    let user = match msg.from() {
+        Some(user) => user,
+        None => {
+            bot.send_message(msg.chat.id, "无法获取用户信息").await?;
+            return Ok(());
+        }
+    };
+
+    let user_id = user.id.0 as i64;
+    log::info!("用户 {} 校准门锁时钟: {}", user_id, args);
+
+    // 检查权限
+    let user_service = state.user_service.read().await;
+    if !user_service.is_super_admin(user_id) {
+        bot.send_message(msg.chat.id, "❌ 只有超级管理员可以校准门锁时钟")
+            .await?;
+        return Ok(());
+    }
+    let admin_password = user_service.get_admin_info(user_id).await?.and_then(|admin| admin.password);
+    drop(user_service);
+
+    let admin_password = match admin_password {
+        Some(admin_password) => admin_password,
+        None => {
+            bot.send_message(
+                msg.chat.id,
+                "❌ 请先设置管理密码！\n\n使用命令: /editpasswd <密码>\n密码要求: 4-10位数字"
+            ).await?;
+            return Ok(());
+        }
+    };
+
+    let (sample, password, lock_name) = match parse_calibrate_args(&args) {
+        Ok(parsed) => parsed,
+        Err(e) => {
+            bot.send_message(msg.chat.id, format!("❌ {}\n\n{}", e, CALIBRATE_USAGE))
+                .await?;
+            return Ok(());
+        }
+    };
+
+    // 未指定门锁时校准默认门锁
+    let locks = state.lock_service.list().await?;
+    let lock = match lock_name {
+        Some(ref name) => locks.iter().find(|lock| &lock.name == name),
+        None => locks.first(),
+    };
+    let Some(lock) = lock.cloned() else {
+        bot.send_message(msg.chat.id, format!("❌ 未知的门锁: {}", lock_name.unwrap_or_default()))
+            .await?;
+        return Ok(());
+    };
+    let is_default = locks.first().is_some_and(|first| first.unique_id == lock.unique_id);
+
+    let candidates = search_offsets(&password, &admin_password, sample, state.clock.now());
+    let candidate = match candidates.as_slice() {
+        [] => {
+            bot.send_message(
+                msg.chat.id,
+                format!(
+                    "❓ 没有找到与密码 {} 吻合的时间偏移\n\n\
+                     请确认：\n\
+                     • 密码由您的管理密码生成，且不是周期密码\n\
+                     • 门锁时钟偏差不超过 {} 小时\n\
+                     • 门锁确实{}该密码",
+                    password,
+                    MAX_CALIBRATION_DRIFT_SECS / 3600,
+                    if sample == CalibrationSample::Accepted { "接受" } else { "显示" }
+                ),
+            )
+            .await?;
+            return Ok(());
+        }
+        [candidate] => candidate,
+        _ => {
+            // 密码同时符合多种算法，无法判断门锁使用的是哪一个
+            let lines: Vec<String> = candidates.iter().map(format_offset_candidate).collect();
+            bot.send_message(
+                msg.chat.id,
+                format!(
+                    "⚠️ 密码同时符合多种算法，无法确定时间偏移，未做修改：\n\n{}\n\n\
+                     💡 请换一个密码重新校准",
+                    lines.join("\n")
+                ),
+            )
+            .await?;
+            return Ok(());
+        }
+    };
+
+    let old_offset = lock.time_offset;
+    let new_offset = candidate.suggested(old_offset);
+    if new_offset == old_offset {
+        bot.send_message(
+            msg.chat.id,
+            format!(
+                "✅ 门锁「{}」的时间偏移 {} 秒与密码吻合，无需调整\n\n{}",
+                lock.name,
+                old_offset,
+                format_offset_candidate(candidate)
+            ),
+        )
+        .await?;
+        return Ok(());
+    }
+
+    let saved = state.lock_service.save(user_id, &lock.with_time_offset(new_offset)).await?;
+
+    let mut message = format!(
+        "✅ 已校准门锁「{}」\n\n\
+         {}\n\
+         🔧 时间偏移：{} 秒 → {} 秒",
+        saved.name,
+        format_offset_candidate(candidate),
+        old_offset,
+        saved.time_offset
+    );
+    // 默认门锁的偏移同时写回配置文件，保持命令行工具等读取配置的地方一致
+    if is_default && state.config_manager.is_some() {
+        let persisted = state
+            .update_config(|config| config.time_offset = saved.time_offset)
+            .await;
+        match persisted {
+            Ok(_) => message.push_str("\n📝 已同步写入配置文件"),
+            Err(e) => {
+                log::error!("写入配置文件失败: {}", e);
+                message.push_str(&format!("\n⚠️ 写入配置文件失败: {}", e));
+            }
+        }
+    }
+
+    bot.send_message(msg.chat.id, message).await?;
+    Ok(())
+}
+
+/// /calibrate命令使用说明
+const CALIBRATE_USAGE: &str = "📋 使用方法：/calibrate <accept|show> <密码> [lock 门锁名称]\n\
+     • accept：门锁当前接受该密码（如刚生成的临时密码）\n\
+     • show：门锁当前显示该密码，结果更精确\n\
+     • 未指定门锁时校准默认门锁\n\
+     • 示例：/calibrate accept 5012345678 lock 大门";
+
+/// 解析/calibrate命令参数，返回密码来源、密码和门锁名称
+fn parse_calibrate_args(args: &str) -> Result<(CalibrationSample, String, Option<String>)> {
+    let tokens: Vec<&str> = args.split_whitespace().collect();
+    let (sample, password, rest) = match tokens.as_slice() {
+        [sample, password, rest @ ..] => (*sample, *password, rest),
+        _ => return Err(AppError::validation("请指定密码来源和密码")),
+    };
+
+    let sample = match sample {
+        "accept" | "a" => CalibrationSample::Accepted,
+        "show" | "s" => CalibrationSample::Displayed,
+        _ => return Err(AppError::validation(format!("未知的密码来源: {}", sample))),
+    };
+
+    // 门锁密码固定为10位数字
+    if password.len() != 10 || !password.chars().all(|c| c.is_ascii_digit()) {
+        return Err(AppError::validation("密码必须是10位数字"));
+    }
+
+    let lock_name = match rest {
+        [] => None,
+        ["lock", name] => Some(name.to_string()),
+        _ => return Err(AppError::validation("参数格式错误")),
+    };
+
+    Ok((sample, password.to_string(), lock_name))
+}
+
+/// 格式化吻合的时间偏移范围
+fn format_offset_candidate(candidate: &OffsetCandidate) -> String {
+    format!(
+        "• {}（窗口 {}）：时间偏移 {} ~ {} 秒",
+        candidate.result.password_type,
+        candidate.result.window,
+        candidate.min_offset,
+        candidate.max_offset
+    )
+}
+
+/// 处理/pending命令 - 查看待审批请求，超级管理员可看到全部
+pub async fn pending_command(bot: Bot, msg: Message, state: BotState) -> Result<()> {
+    let user = match msg.from() {
+        Some(user) => user,
+        None => {
+            bot.send_message(msg.chat.id, "无法获取用户信息").await?;
+            return Ok(());
+        }
+    };
+
+    let user_id = user.id.0 as i64;
+    let user_service = state.user_service.read().await;
+    let admin = match user_service.get_admin_info(user_id).await? {
+        Some(admin) => admin,
+        None => {
+            bot.send_message(msg.chat.id, "❌ 只有管理员可以查看待审批请求")
+                .await?;
+            return Ok(());
+        }
+    };
+    let inviter = (!user_service.is_super_admin(user_id)).then_some(admin.unique_id);
+    drop(user_service);
+
+    let (text, keyboard) = build_pending_list(&state, inviter, 1).await?;
+    bot.send_message(msg.chat.id, text)
+        .reply_markup(keyboard)
+        .await?;
+
+    Ok(())
+}
+
+/// 处理待审批列表翻页回调
+pub async fn handle_pending_page_callback(
+    bot: Bot,
+    callback: CallbackQuery,
+    page: i64,
+    state: BotState,
+) -> Result<()> {
+    let user_id = callback.from.id.0 as i64;
+    let user_service = state.user_service.read().await;
+    let admin = match user_service.get_admin_info(user_id).await? {
+        Some(admin) => admin,
+        None => {
+            bot.answer_callback_query(callback.id)
+                .text("❌ 权限不足")
+                .await?;
+            return Ok(());
+        }
+    };
+    let inviter = (!user_service.is_super_admin(user_id)).then_some(admin.unique_id);
+    drop(user_service);
+
+    if let Some(message) = callback.message {
+        let (text, keyboard) = build_pending_list(&state, inviter, page).await?;
+        bot.edit_message_text(message.chat.id, message.id, text)
+            .reply_markup(keyboard)
+            .await?;
+    }
+
+    bot.answer_callback_query(callback.id).await?;
+    Ok(())
+}
+
+/// 构建待审批列表消息，批准/拒绝按钮沿用审批通知的回调；`inviter` 为空时列出全部
+async fn build_pending_list(
+    state: &BotState,
+    inviter: Option<i64>,
+    page: i64,
+) -> Result<(String, InlineKeyboardMarkup)> {
+    let pool = state.database.pool();
+    let total = RecordRepository::count_pending(pool, inviter).await?;
+    if total == 0 {
+        return Ok(("📭 没有待审批的请求".to_string(), InlineKeyboardMarkup::default()));
+    }
+
+    let total_pages = (total + PENDING_PAGE_SIZE - 1) / PENDING_PAGE_SIZE;
+    let page = page.clamp(1, total_pages);
+    let records = RecordRepository::find_pending_page(pool, inviter, PENDING_PAGE_SIZE, (page - 1) * PENDING_PAGE_SIZE).await?;
+
+    let now = state.clock.now();
+    let mut entries = Vec::new();
+    let mut rows = Vec::new();
+    for record in &records {
+        let invite_name = match record.invite_id {
+            Some(invite_id) => InviteRepository::find_by_id(pool, invite_id).await?.map(|invite| invite.display_name()),
+            None => None,
+        };
+        entries.push(format_pending_entry(record, invite_name.as_deref(), now));
+
+        let id = record.unique_id.to_string();
+        rows.push(vec![
+            InlineKeyboardButton::callback(
+                format!("✅ 批准 #{}", record.unique_id),
+                CallbackData::with_data("approve", &id).to_callback_string().unwrap(),
+            ),
+            InlineKeyboardButton::callback(
+                format!("❌ 拒绝 #{}", record.unique_id),
+                CallbackData::with_data("reject", &id).to_callback_string().unwrap(),
+            ),
+        ]);
+    }
+
+    let mut navigation = Vec::new();
+    if page > 1 {
+        navigation.push(InlineKeyboardButton::callback(
+            "⬅️ 上一页",
+            CallbackData::with_data("pending_page", &(page - 1).to_string()).to_callback_string().unwrap(),
+        ));
+    }
+    if page < total_pages {
+        navigation.push(InlineKeyboardButton::callback(
+            "下一页 ➡️",
+            CallbackData::with_data("pending_page", &(page + 1).to_string()).to_callback_string().unwrap(),
+        ));
+    }
+    if !navigation.is_empty() {
+        rows.push(navigation);
+    }
+
+    let text = format!(
+        "📥 待审批请求（共 {} 条，第 {}/{} 页）\n\n{}",
+        total,
+        page,
+        total_pages,
+        entries.join("\n\n")
+    );
+    Ok((text, InlineKeyboardMarkup::new(rows)))
+}
+
+/// 格式化单条待审批请求
+fn format_pending_entry(record: &Record, invite_name: Option<&str>, now: chrono::DateTime<Utc>) -> String {
+    format!(
+        "📝 #{} {}\n\
+         🎫 邀请码：{}\n\
+         ⏰ 已等待 {}",
+        record.unique_id,
+        record.visitor_label(),
+        invite_name.unwrap_or("未知"),
+        format_duration(record.updated_at, now)
+    )
+}
+
+/// 处理/myvisitors命令 - 查看自己邀请的访客当前的授权，超级管理员可看到全部
+pub async fn my_visitors_command(bot: Bot, msg: Message, state: BotState) -> Result<()> {
+    let user = match msg.from() {
+        Some(user) => user,
+        None => {
+            bot.send_message(msg.chat.id, "无法获取用户信息").await?;
+            return Ok(());
+        }
+    };
+
+    let user_id = user.id.0 as i64;
+    let user_service = state.user_service.read().await;
+    let admin = match user_service.get_admin_info(user_id).await? {
+        Some(admin) => admin,
+        None => {
+            bot.send_message(msg.chat.id, "❌ 只有管理员可以查看访客授权")
+                .await?;
+            return Ok(());
+        }
+    };
+    let inviter = (!user_service.is_super_admin(user_id)).then_some(admin.unique_id);
+    drop(user_service);
+
+    let (text, keyboard) = build_visitor_list(&state, inviter, 1).await?;
+    bot.send_message(msg.chat.id, text)
+        .reply_markup(keyboard)
+        .await?;
+
+    Ok(())
+}
+
+/// 处理访客列表翻页回调
+pub async fn handle_visitors_page_callback(
+    bot: Bot,
+    callback: CallbackQuery,
+    page: i64,
+    state: BotState,
+) -> Result<()> {
+    let user_id = callback.from.id.0 as i64;
+    let user_service = state.user_service.read().await;
+    let admin = match user_service.get_admin_info(user_id).await? {
+        Some(admin) => admin,
+        None => {
+            bot.answer_callback_query(callback.id)
+                .text("❌ 权限不足")
+                .await?;
+            return Ok(());
+        }
+    };
+    let inviter = (!user_service.is_super_admin(user_id)).then_some(admin.unique_id);
+    drop(user_service);
+
+    if let Some(message) = callback.message {
+        let (text, keyboard) = build_visitor_list(&state, inviter, page).await?;
+        bot.edit_message_text(message.chat.id, message.id, text)
+            .reply_markup(keyboard)
+            .await?;
+    }
+
+    bot.answer_callback_query(callback.id).await?;
+    Ok(())
+}
+
+/// 处理访客列表中撤销、延长和重发密码的回调
+///
+/// 普通管理员只能操作自己邀请的访客，超级管理员可以操作全部访客。
+/// 撤销和延长后刷新 `page` 页，停留在管理员操作时所在的页面。
+pub async fn handle_visitor_action_callback(
+    bot: Bot,
+    callback: CallbackQuery,
+    action: &str,
+    record_id: i64,
+    page: i64,
+    state: BotState,
+) -> Result<()> {
+    let user_id = callback.from.id.0 as i64;
+    let user_service = state.user_service.read().await;
+    let admin = match user_service.get_admin_info(user_id).await? {
+        Some(admin) => admin,
+        None => {
+            bot.answer_callback_query(callback.id)
+                .text("❌ 权限不足")
+                .await?;
+            return Ok(());
+        }
+    };
+    let inviter = (!user_service.is_super_admin(user_id)).then_some(admin.unique_id);
+    drop(user_service);
+
+    let record = RecordRepository::find_by_id(state.database.pool(), record_id)
+        .await?
+        .filter(|record| inviter.is_none_or(|inviter| record.inviter == inviter));
+    let record = match record {
+        Some(record) if record.is_active_at(state.clock.now()) => record,
+        _ => {
+            bot.answer_callback_query(callback.id)
+                .text("⚠️ 授权不存在或已失效")
+                .await?;
+            return Ok(());
+        }
+    };
+
+    let reply = match action {
+        "visitor_revoke" => {
+            if state.record_service.revoke(user_id, record_id).await? {
+                log::info!("管理员 {} 撤销了记录 #{}", user_id, record_id);
+                "✅ 授权已撤销".to_string()
+            } else {
+                "⚠️ 授权已被处理".to_string()
+            }
+        }
+        "visitor_extend" => {
+            match state.record_service.extend(user_id, record_id, chrono::Duration::days(EXTEND_DAYS)).await? {
+                Some(ended_time) => format!("✅ 已延长至 {}", state.config().lock_timezone.format(ended_time)),
+                None => "⚠️ 该授权不能延长".to_string(),
+            }
+        }
+        "visitor_resend" => resend_current_passwords(&bot, &record, &state).await?,
+        _ => "❌ 未知操作".to_string(),
+    };
+
+    if action != "visitor_resend" {
+        if let Some(message) = callback.message {
+            match build_visitor_list(&state, inviter, page).await {
+                Ok((text, keyboard)) => {
+                    bot.edit_message_text(message.chat.id, message.id, text)
+                        .reply_markup(keyboard)
+                        .await
+                        .ok();
+                }
+                Err(e) => log::error!("刷新访客列表失败: {}", e),
+            }
+        }
+    }
+
+    bot.answer_callback_query(callback.id)
+        .text(reply)
+        .await?;
+    Ok(())
+}
+
+/// 将记录当前有效的密码重新发送给访客，返回回调提示
+async fn resend_current_passwords(bot: &Bot, record: &Record, state: &BotState) -> Result<String> {
+    let now = state.clock.now();
+    let issued = IssuedPasswordRepository::find_by_record(state.database.pool(), record.unique_id).await?;
+    let current = current_passwords(&issued, now);
+    if current.is_empty() {
+        return Ok("⚠️ 没有仍在有效期内的密码".to_string());
+    }
+
+    let locks = state.lock_service.locks_for_record(record.unique_id).await?;
+    let entries: Vec<(&str, &str)> = current
+        .iter()
+        .map(|issued| {
+            let lock_name = locks
+                .iter()
+                .find(|lock| Some(lock.unique_id) == issued.lock_id)
+                .map(|lock| lock.name.as_str())
+                .unwrap_or("未知门锁");
+            (lock_name, issued.password.as_str())
+        })
+        .collect();
+    let expiry = current
+        .iter()
+        .filter_map(|issued| issued.valid_until)
+        .min()
+        .map(|valid_until| state.config().lock_timezone.format(valid_until))
+        .unwrap_or_else(|| "以授权结束时间为准".to_string());
+
+    let message = format!(
+        "🔑 管理员为您重新发送了当前密码\n\n\
+         {}\n\
+         过期时间：{}",
+        format_password_entries(&entries),
+        expiry
+    );
+
+    if let Err(e) = bot
+        .send_message(ChatId(record.vis_id), message)
+        .parse_mode(teloxide::types::ParseMode::Html)
+        .await
+    {
+        log::warn!("向访客 {} 重发密码失败: {}", record.vis_id, e);
+        return Ok("❌ 无法发送给访客，访客可能已停用Bot".to_string());
+    }
+
+    Ok("✅ 密码已重新发送给访客".to_string())
+}
+
+/// 各门锁最近发放且仍在有效期内的密码（按发放顺序）
+fn current_passwords(issued: &[IssuedPassword], now: chrono::DateTime<Utc>) -> Vec<&IssuedPassword> {
+    let mut latest: Vec<&IssuedPassword> = Vec::new();
+    for password in issued.iter().rev() {
+        if !latest.iter().any(|seen| seen.lock_id == password.lock_id) {
+            latest.push(password);
+        }
+    }
+    latest.reverse();
+    latest.retain(|password| password.valid_until.is_none_or(|valid_until| valid_until > now));
+    latest
+}
+
+/// 获取当前有效的访客授权；`inviter` 为空时获取全部
+async fn find_active_records(state: &BotState, inviter: Option<i64>) -> Result<Vec<Record>> {
+    let pool = state.database.pool();
+    let now = state.clock.now();
+    let records = match inviter {
+        Some(inviter) => RecordRepository::find_by_inviter(pool, inviter).await?,
+        None => RecordRepository::find_all_active(pool, now).await?,
+    };
+
+    Ok(records.into_iter().filter(|record| record.is_active_at(now)).collect())
+}
+
+/// 构建访客授权列表消息；`inviter` 为空时列出全部
+async fn build_visitor_list(
+    state: &BotState,
+    inviter: Option<i64>,
+    page: i64,
+) -> Result<(String, InlineKeyboardMarkup)> {
+    let records = find_active_records(state, inviter).await?;
+    if records.is_empty() {
+        return Ok(("📭 当前没有有效的访客授权".to_string(), InlineKeyboardMarkup::default()));
+    }
+
+    let total = records.len() as i64;
+    let total_pages = (total + VISITOR_PAGE_SIZE - 1) / VISITOR_PAGE_SIZE;
+    let page = page.clamp(1, total_pages);
+
+    let now = state.clock.now();
+    let mut entries = Vec::new();
+    let mut rows = Vec::new();
+    for record in records
+        .iter()
+        .skip(((page - 1) * VISITOR_PAGE_SIZE) as usize)
+        .take(VISITOR_PAGE_SIZE as usize)
+    {
+        let password_count = IssuedPasswordRepository::count_by_record(state.database.pool(), record.unique_id).await?;
+        entries.push(format_visitor_entry(record, password_count, now));
+
+        // 回调数据附带当前页码，操作后刷新同一页
+        let id = format!("{}:{}", record.unique_id, page);
+        let mut row = vec![InlineKeyboardButton::callback(
+            format!("🚫 撤销 #{}", record.unique_id),
+            CallbackData::with_data("visitor_revoke", &id).to_callback_string().unwrap(),
+        )];
+        if record.can_extend() {
+            row.push(InlineKeyboardButton::callback(
+                format!("⏩ 延长{}天", EXTEND_DAYS),
+                CallbackData::with_data("visitor_extend", &id).to_callback_string().unwrap(),
+            ));
+        }
+        if password_count > 0 {
+            row.push(InlineKeyboardButton::callback(
+                "📨 重发密码",
+                CallbackData::with_data("visitor_resend", &id).to_callback_string().unwrap(),
+            ));
+        }
+        rows.push(row);
+    }
+
+    let mut navigation = Vec::new();
+    if page > 1 {
+        navigation.push(InlineKeyboardButton::callback(
+            "⬅️ 上一页",
+            CallbackData::with_data("visitors_page", &(page - 1).to_string()).to_callback_string().unwrap(),
+        ));
+    }
+    if page < total_pages {
+        navigation.push(InlineKeyboardButton::callback(
+            "下一页 ➡️",
+            CallbackData::with_data("visitors_page", &(page + 1).to_string()).to_callback_string().unwrap(),
+        ));
+    }
+    if !navigation.is_empty() {
+        rows.push(navigation);
+    }
+
+    let text = format!(
+        "👥 有效的访客授权（共 {} 条，第 {}/{} 页）\n\n{}",
+        total,
+        page,
+        total_pages,
+        entries.join("\n\n")
+    );
+    Ok((text, InlineKeyboardMarkup::new(rows)))
+}
+
+/// 格式化单条访客授权
+fn format_visitor_entry(record: &Record, password_count: i64, now: chrono::DateTime<Utc>) -> String {
+    let remaining = match record.ended_time {
+        Some(ended_time) => format!("剩余 {}", format_duration(now, ended_time)),
+        None => "长期有效".to_string(),
+    };
+
+    format!(
+        "👤 #{} {}\n\
+         🔐 类型：{}\n\
+         ⏳ {}\n\
+         🔑 已发放密码：{} 个",
+        record.unique_id,
+        record.visitor_label(),
+        record.auth_type_label(),
+        remaining,
+        password_count
+    )
+}
+
+/// 处理/audit命令 - 分页查看审计日志（仅超级管理员）
+pub async fn audit_command(
+    bot: Bot,
+    msg: Message,
+    cmd: crate::bot::bot::Command,
+    state: BotState,
+) -> Result<()> {
+    let args = match cmd {
+        crate::bot::bot::Command::Audit(args) => args,
+        _ => {
+            bot.send_message(msg.chat.id, "❌ 命令参数错误").await?;
+            return Ok(());
+        }
+    };
+    let user = match msg.from() {
+        Some(user) => user,
+        None => {
+            bot.send_message(msg.chat.id, "无法获取用户信息").await?;
+            return Ok(());
+        }
+    };
+
+    let user_id = user.id.0 as i64;
+    log::info!("用户 {} 查看审计日志: {}", user_id, args);
+
+    // 检查权限
+    let user_service = state.user_service.read().await;
+    if !user_service.is_super_admin(user_id) {
+        bot.send_message(msg.chat.id, "❌ 只有超级管理员可以查看审计日志")
+            .await?;
+        return Ok(());
+    }
+    drop(user_service);
+
+    let (filter, page) = match parse_audit_args(&args, &state.config().lock_timezone) {
+        Ok(parsed) => parsed,
+        Err(e) => {
+            bot.send_message(
+                msg.chat.id,
+                format!(
+                    "❌ {}\n\n\
+                     正确格式：\n\
+                     /audit [admin <用户ID>] [visitor <用户ID>] [from YYYY-MM-DD] [to YYYY-MM-DD] [page <页码>]\n\n\
+                     例如：\n\
+                     • /audit\n\
+                     • /audit admin 123456789 page 2\n\
+                     • /audit visitor 987654321 from 2024-12-01 to 2024-12-31",
+                    e
+                )
+            ).await?;
+            return Ok(());
+        }
+    };
+
+    let pool = state.database.pool();
+    let total = AuditRepository::count(pool, &filter).await?;
+    let total_pages = ((total + AUDIT_PAGE_SIZE - 1) / AUDIT_PAGE_SIZE).max(1);
+    let events = AuditRepository::query(pool, &filter, AUDIT_PAGE_SIZE, (page - 1) * AUDIT_PAGE_SIZE).await?;
+
+    if events.is_empty() {
+        bot.send_message(msg.chat.id, format!("📭 没有符合条件的审计记录（第 {}/{} 页）", page, total_pages))
+            .await?;
+        return Ok(());
+    }
+
+    let lines: Vec<String> = events
+        .iter()
+        .map(|event| format_audit_event(event, &state.config().lock_timezone))
+        .collect();
+    let mut message = format!(
+        "📜 审计日志（共 {} 条，第 {}/{} 页）\n\n{}",
+        total,
+        page,
+        total_pages,
+        lines.join("\n\n")
+    );
+    if page < total_pages {
+        message.push_str(&format!("\n\n💡 使用 page {} 查看下一页", page + 1));
+    }
+
+    bot.send_message(msg.chat.id, message).await?;
+    Ok(())
+}
+
+/// 解析/audit命令参数，返回过滤条件和页码
+fn parse_audit_args(args: &str, timezone: &LockTimezone) -> Result<(AuditFilter, i64)> {
+    let mut filter = AuditFilter::default();
+    let mut page = 1;
+
+    let tokens: Vec<&str> = args.split_whitespace().collect();
+    for pair in tokens.chunks(2) {
+        let (key, value) = match pair {
+            [key, value] => (*key, *value),
+            [key] => return Err(AppError::validation(format!("参数 {} 缺少取值", key))),
+            _ => unreachable!(),
+        };
+
+        match key {
+            "admin" | "a" => {
+                filter.actor_id = Some(value.parse().map_err(|_| AppError::validation("管理员ID必须是数字"))?);
+            }
+            "visitor" | "v" => {
+                filter.target_user = Some(value.parse().map_err(|_| AppError::validation("访客ID必须是数字"))?);
+            }
+            "from" => {
+                filter.from = Some(parse_local_date(value, timezone)?);
+            }
+            "to" => {
+                // 结束日期包含当天
+                filter.to = Some(parse_local_date(value, timezone)? + chrono::Duration::days(1));
+            }
+            "page" | "p" => {
+                page = value.parse::<i64>().ok().filter(|p| *p >= 1)
+                    .ok_or_else(|| AppError::validation("页码必须是正整数"))?;
+            }
+            _ => return Err(AppError::validation(format!("未知参数: {}", key))),
+        }
+    }
+
+    Ok((filter, page))
+}
+
+/// 将门锁时区的日期解析为当天零点的UTC时间
+fn parse_local_date(value: &str, timezone: &LockTimezone) -> Result<chrono::DateTime<Utc>> {
+    let date = NaiveDate::parse_from_str(value, "%Y-%m-%d")
+        .map_err(|_| AppError::validation(format!("日期格式错误: {}（应为YYYY-MM-DD）", value)))?;
+    Ok(timezone.from_local(&date.and_hms_opt(0, 0, 0).unwrap()))
+}
+
+/// 格式化单条审计事件
+fn format_audit_event(event: &AuditEvent, timezone: &LockTimezone) -> String {
+    let actor = if event.actor_id == SYSTEM_ACTOR_ID {
+        "系统".to_string()
+    } else {
+        event.actor_id.to_string()
+    };
+    let mut line = format!(
+        "#{} {} · {}\n👤 操作员：{}",
+        event.unique_id,
+        timezone.format(event.created_at),
+        event.action.description(),
+        actor
+    );
+
+    match (event.target_record, event.target_user) {
+        (Some(record_id), Some(user_id)) => line.push_str(&format!("\n🎯 记录 #{} · 用户 {}", record_id, user_id)),
+        (Some(record_id), None) => line.push_str(&format!("\n🎯 记录 #{}", record_id)),
+        (None, Some(user_id)) => line.push_str(&format!("\n🎯 用户 {}", user_id)),
+        (None, None) => {}
+    }
+
+    if let (Some(before), Some(after)) = (event.before_status, event.after_status) {
+        line.push_str(&format!("\n🔄 {} → {}", before.as_str(), after.as_str()));
+    }
+
+    if let Some(ref detail) = event.detail {
+        line.push_str(&format!("\n📝 {}", detail));
+    }
+
+    line
+}
+
+/// 邀请码列表最多显示条数
+const INVITE_LIST_LIMIT: usize = 10;
+
+/// 邀请码名称最大长度
+const INVITE_LABEL_MAX_CHARS: usize = 32;
+
+/// /geninvite命令使用说明
+const GEN_INVITE_USAGE: &str = "📋 使用方法：/geninvite [label 名称] [uses 次数] [valid 有效期] [auth 授权类型 ...] [lock 门锁,...]\n\
+     • 有效期单位：m(分钟) / h(小时) / d(天)\n\
+     • 预设授权（访客申请后自动批准）：\n\
+       auth temp\n\
+       auth times times 次数\n\
+       auth limited hours 小时 [minutes 0|30]\n\
+       auth period end YYYY-MM-DDTHH:00\n\
+       auth longtime_temp end YYYY-MM-DDTHH:MM\n\
+     • 预设门锁：lock 大门,公寓门（不指定时使用默认门锁）\n\
+     • 示例：/geninvite label 保洁 uses 10 valid 30d auth limited hours 4";
+
+/// 解析/geninvite命令参数
+fn parse_invite_args(args: &str, timezone: &LockTimezone, locks: &[Lock]) -> Result<InviteOptions> {
+    let mut options = InviteOptions::default();
+    let mut preset = PresetArgs::default();
+
+    let tokens: Vec<&str> = args.split_whitespace().collect();
+    for pair in tokens.chunks(2) {
+        let (key, value) = match pair {
+            [key, value] => (*key, *value),
+            [key] => return Err(AppError::validation(format!("参数 {} 缺少取值", key))),
+            _ => unreachable!(),
+        };
+
+        match key {
+            "label" | "l" => {
+                if value.chars().count() > INVITE_LABEL_MAX_CHARS {
+                    return Err(AppError::validation(format!("名称不能超过{}个字符", INVITE_LABEL_MAX_CHARS)));
+                }
+                options.label = Some(value.to_string());
+            }
+            "uses" | "u" => {
+                options.max_uses = Some(value.parse::<i64>().ok().filter(|n| *n >= 1)
+                    .ok_or_else(|| AppError::validation("使用次数必须是正整数"))?);
+            }
+            "valid" | "v" => {
+                options.validity = Some(parse_validity(value)?);
+            }
+            "auth" => {
+                let auth_type = AuthType::from_str(value)
+                    .ok_or_else(|| AppError::validation(format!("未知的授权类型: {}", value)))?;
+                preset.auth_type = Some(auth_type);
+            }
+            "times" => preset.times = Some(parse_preset_number(key, value)?),
+            "hours" => preset.hours = Some(parse_preset_number(key, value)?),
+            "minutes" => preset.minutes = Some(parse_preset_number(key, value)?),
+            "end" => preset.end_time = Some(parse_local_datetime(value, timezone)?),
+            "lock" => preset.locks = parse_lock_names(value, locks)?,
+            _ => return Err(AppError::validation(format!("未知参数: {}", key))),
+        }
+    }
+
+    options.preset = match preset.auth_type {
+        Some(auth_type) => {
+            if let Some(lock) = locks
+                .iter()
+                .find(|lock| preset.locks.contains(&lock.unique_id) && !lock.supports(auth_type))
+            {
+                return Err(AppError::validation(format!("门锁「{}」未启用{}", lock.name, auth_type.description())));
+            }
+            Some(AuthPreset {
+                auth_type,
+                times: preset.times,
+                hours: preset.hours,
+                minutes: preset.minutes,
+                end_time: preset.end_time,
+                locks: preset.locks,
+            })
+        }
+        None if preset.has_params() => {
+            return Err(AppError::validation("设置预设参数时必须用 auth 指定授权类型"));
+        }
+        None => None,
+    };
+
+    Ok(options)
+}
+
+/// 解析中的预设授权参数，授权类型可能尚未给出
+#[derive(Default)]
+struct PresetArgs {
+    auth_type: Option<AuthType>,
+    times: Option<u32>,
+    hours: Option<u32>,
+    minutes: Option<u32>,
+    end_time: Option<chrono::DateTime<Utc>>,
+    locks: Vec<i64>,
+}
+
+impl PresetArgs {
+    fn has_params(&self) -> bool {
+        self.times.is_some()
+            || self.hours.is_some()
+            || self.minutes.is_some()
+            || self.end_time.is_some()
+            || !self.locks.is_empty()
+    }
+}
+
+/// 将逗号分隔的门锁名称解析为门锁ID
+fn parse_lock_names(value: &str, locks: &[Lock]) -> Result<Vec<i64>> {
+    let mut lock_ids = Vec::new();
+    for name in value.split(',').map(str::trim).filter(|name| !name.is_empty()) {
+        let lock = locks
+            .iter()
+            .find(|lock| lock.name == name)
+            .ok_or_else(|| AppError::validation(format!("未知的门锁: {}", name)))?;
+        if !lock_ids.contains(&lock.unique_id) {
+            lock_ids.push(lock.unique_id);
+        }
+    }
+
+    if lock_ids.is_empty() {
+        return Err(AppError::validation("门锁名称不能为空"));
+    }
+    Ok(lock_ids)
+}
+
+fn parse_preset_number(key: &str, value: &str) -> Result<u32> {
+    value
+        .parse::<u32>()
+        .map_err(|_| AppError::validation(format!("参数 {} 必须是非负整数", key)))
+}
+
+/// 将门锁时区的时间解析为UTC时间，格式为 YYYY-MM-DDTHH:MM
+fn parse_local_datetime(value: &str, timezone: &LockTimezone) -> Result<chrono::DateTime<Utc>> {
+    let datetime = NaiveDateTime::parse_from_str(value, "%Y-%m-%dT%H:%M")
+        .map_err(|_| AppError::validation(format!("时间格式错误: {}（应为YYYY-MM-DDTHH:MM）", value)))?;
+    Ok(timezone.from_local(&datetime))
+}
+
+/// 解析有效期，如 30m、12h、7d
+fn parse_validity(value: &str) -> Result<chrono::Duration> {
+    let invalid = || AppError::validation(format!("有效期格式错误: {}（如 30m、12h、7d）", value));
+
+    let split = value.len().checked_sub(1).filter(|i| value.is_char_boundary(*i)).ok_or_else(invalid)?;
+    let (amount, unit) = value.split_at(split);
+    let amount = amount.parse::<i64>().ok().filter(|n| *n >= 1).ok_or_else(invalid)?;
+
+    let validity = match unit {
+        "m" => chrono::Duration::try_minutes(amount),
+        "h" => chrono::Duration::try_hours(amount),
+        "d" => chrono::Duration::try_days(amount),
+        _ => None,
+    };
+
+    validity
+        .filter(|v| *v <= chrono::Duration::days(3650))
+        .ok_or_else(invalid)
+}
+
+/// 格式化单个邀请码（HTML）
+fn format_invite(invite: &Invite, locks: &[Lock], timezone: &LockTimezone) -> String {
+    let uses = match invite.max_uses {
+        Some(max_uses) => format!("{}/{}", invite.use_count, max_uses),
+        None => format!("{}/不限", invite.use_count),
+    };
+    let expires = invite
+        .expires_at
+        .map(|expires_at| timezone.format(expires_at))
+        .unwrap_or_else(|| "永久有效".to_string());
+
+    let preset = match invite.preset {
+        Some(ref preset) if preset.locks.is_empty() => format!("自动批准 {}", preset.description(timezone)),
+        Some(ref preset) => {
+            let names: Vec<&str> = locks
+                .iter()
+                .filter(|lock| preset.locks.contains(&lock.unique_id))
+                .map(|lock| lock.name.as_str())
+                .collect();
+            format!("自动批准 {}（门锁：{}）", preset.description(timezone), names.join("、"))
+        }
+        None => "需审批".to_string(),
+    };
+
+    format!(
+        "🎫 <b>{}</b>（{}）\n\
+         <code>{}</code>\n\
+         📊 已使用：{}\n\
+         ⏰ 有效期至：{}\n\
+         🔐 授权方式：{}",
+        html::escape(&invite.display_name()),
+        invite.state().description(),
+        invite.code,
+        uses,
+        expires,
+        html::escape(&preset)
+    )
+}
+
+/// 构建邀请码列表消息及管理按钮
+fn build_invite_list(invites: &[Invite], locks: &[Lock], timezone: &LockTimezone) -> (String, InlineKeyboardMarkup) {
+    if invites.is_empty() {
+        return (
+            "🎫 您还没有邀请码\n\n使用 /geninvite 生成新的邀请码".to_string(),
+            InlineKeyboardMarkup::default(),
+        );
+    }
+
+    let active_count = invites.iter().filter(|i| i.is_usable()).count();
+    let mut text = format!("🎫 我的邀请码（有效 {} 个，共 {} 个）", active_count, invites.len());
+    let mut rows = Vec::new();
+
+    for invite in invites.iter().take(INVITE_LIST_LIMIT) {
+        text.push_str("\n\n");
+        text.push_str(&format_invite(invite, locks, timezone));
+
+        if invite.is_usable() {
+            let id = invite.unique_id.to_string();
+            rows.push(vec![
+                InlineKeyboardButton::callback(
+                    format!("🔄 更换 #{}", invite.unique_id),
+                    CallbackData::with_data("regenerate_invite", &id).to_callback_string().unwrap(),
+                ),
+                InlineKeyboardButton::callback(
+                    format!("🗑 撤销 #{}", invite.unique_id),
+                    CallbackData::with_data("revoke_invite", &id).to_callback_string().unwrap(),
+                ),
+            ]);
+        }
+    }
+
+    if invites.len() > INVITE_LIST_LIMIT {
+        text.push_str(&format!("\n\n… 仅显示最近 {} 个", INVITE_LIST_LIMIT));
+    }
+
+    (text, InlineKeyboardMarkup::new(rows))
+}
+
+/// 发送新邀请码
+async fn send_new_invite_message(bot: &Bot, chat_id: ChatId, invite: &Invite, state: &BotState) -> Result<()> {
+    // Bot信息尚未获取时只能提供/req命令
+    let link = match state.get_bot_username().await {
+        Some(username) => invite.deep_link(&username),
+        None => None,
+    };
+    let usage = match link {
+        Some(ref link) => format!("🔗 访客点击链接或扫描二维码即可申请：\n{}\n\n或", html::escape(link)),
+        None => "访客".to_string(),
+    };
+
+    let message = format!(
+        "✅ 邀请码生成成功！\n\n\
+         {}\n\n\
+         📋 使用方法：\n\
+         {}使用命令 /req {} 申请授权\n\n\
+         💡 提示：使用 /invites 查看和撤销邀请码",
+        format_invite(invite, &state.lock_service.list().await?, &state.config().lock_timezone),
+        usage,
+        invite.code
+    );
+
+    bot.send_message(chat_id, message)
+        .parse_mode(teloxide::types::ParseMode::Html)
+        .await?;
+
+    // 附带邀请链接二维码，方便访客在门口直接扫码
+    if let Some(link) = link {
+        match render_qr_png(&link) {
+            Ok(png) => {
+                bot.send_photo(chat_id, InputFile::memory(png).file_name("invite.png"))
+                    .caption(format!("📷 {} 的邀请二维码", invite.display_name()))
+                    .await?;
+            }
+            Err(e) => log::warn!("生成邀请码 #{} 的二维码失败: {}", invite.unique_id, e),
+        }
+    }
+
+    Ok(())
+}
+
+/// 获取管理员状态信息
+pub async fn get_admin_status(
+    bot: Bot,
+    msg: Message,
+    state: BotState,
+) -> Result<()> {
+    let user = match msg.from() {
+        Some(user) => user,
+        None => return Ok(()),
     };
 
     let user_id = user.id.0 as i64;
@@ -428,16 +2202,19 @@ pub async fn get_admin_status(
     // 获取管理的授权统计
     let managed_records = RecordRepository::find_by_inviter(state.database.pool(), admin.unique_id).await?;
     let pending_count = managed_records.iter().filter(|r| r.status == AuthStatus::Pending).count();
-    let active_count = managed_records.iter().filter(|r| r.status == AuthStatus::Auth && r.is_active()).count();
+    let active_count = managed_records.iter().filter(|r| r.status == AuthStatus::Auth && r.is_active_at(state.clock.now())).count();
     let total_count = managed_records.len();
 
+    let invites = state.invite_service.list_by_admin(admin.unique_id).await?;
+    let active_invites = invites.iter().filter(|i| i.is_usable()).count();
+
     let status_message = format!(
         "📊 管理员状态\n\n\
          👤 用户: {}\n\
          🆔 ID: {}\n\
          👑 角色: {}\n\
          🔑 密码状态: {}\n\
-         🎫 邀请码: {}\n\n\
+         🎫 有效邀请码: {} 个\n\n\
          📈 管理统计:\n\
          • 待处理请求: {} 个\n\
          • 活跃授权: {} 个\n\
@@ -446,7 +2223,7 @@ pub async fn get_admin_status(
         user_id,
         if is_super_admin { "超级管理员" } else { "管理员" },
         if has_password { "✅ 已设置" } else { "❌ 未设置" },
-        admin.invite_code.as_deref().unwrap_or("❌ 未生成"),
+        active_invites,
         pending_count,
         active_count,
         total_count
@@ -457,38 +2234,142 @@ pub async fn get_admin_status(
     Ok(())
 }
 
-/// 处理邀请码重新生成的回调
+/// 处理邀请码更换的回调
 pub async fn handle_regenerate_invite_callback(
     bot: Bot,
     callback: CallbackQuery,
+    invite_id: i64,
     state: BotState,
 ) -> Result<()> {
-    let user = match callback.from.clone() {
-        user => user,
+    let user_id = callback.from.id.0 as i64;
+    let user_service = state.user_service.read().await;
+
+    let admin = match user_service.get_admin_info(user_id).await? {
+        Some(admin) => admin,
+        None => {
+            bot.answer_callback_query(callback.id)
+                .text("❌ 只有管理员可以管理邀请码")
+                .await?;
+            return Ok(());
+        }
     };
 
-    let user_id = user.id.0 as i64;
+    let invite = match state.invite_service.regenerate(user_id, admin.unique_id, invite_id).await? {
+        Some(invite) => invite,
+        None => {
+            bot.answer_callback_query(callback.id)
+                .text("⚠️ 邀请码不存在或已撤销")
+                .await?;
+            return Ok(());
+        }
+    };
+
+    if let Some(message) = callback.message {
+        refresh_invite_list(&bot, &message, &state, admin.unique_id).await;
+        send_new_invite_message(&bot, message.chat.id, &invite, &state).await?;
+    }
+
+    bot.answer_callback_query(callback.id)
+        .text("✅ 原邀请码已失效")
+        .await?;
+    Ok(())
+}
+
+/// 处理邀请码撤销的回调
+pub async fn handle_revoke_invite_callback(
+    bot: Bot,
+    callback: CallbackQuery,
+    invite_id: i64,
+    state: BotState,
+) -> Result<()> {
+    let user_id = callback.from.id.0 as i64;
     let user_service = state.user_service.read().await;
 
-    if let Some(admin) = user_service.get_admin_info(user_id).await? {
-        generate_new_invite_code(&bot, 
-            callback.message.as_ref().unwrap().chat.id, 
-            &user_service, 
-            admin.unique_id).await?;
+    let admin = match user_service.get_admin_info(user_id).await? {
+        Some(admin) => admin,
+        None => {
+            bot.answer_callback_query(callback.id)
+                .text("❌ 只有管理员可以管理邀请码")
+                .await?;
+            return Ok(());
+        }
+    };
+
+    if !state.invite_service.revoke(user_id, admin.unique_id, invite_id).await? {
+        bot.answer_callback_query(callback.id)
+            .text("⚠️ 邀请码不存在或已撤销")
+            .await?;
+        return Ok(());
     }
 
-    // 删除原消息
     if let Some(message) = callback.message {
-        bot.delete_message(message.chat.id, message.id).await.ok();
+        refresh_invite_list(&bot, &message, &state, admin.unique_id).await;
     }
 
-    bot.answer_callback_query(callback.id).await?;
+    bot.answer_callback_query(callback.id)
+        .text("✅ 邀请码已撤销")
+        .await?;
     Ok(())
 }
 
+/// 刷新邀请码列表消息
+async fn refresh_invite_list(bot: &Bot, message: &Message, state: &BotState, admin_id: i64) {
+    let (invites, locks) = match tokio::try_join!(
+        state.invite_service.list_by_admin(admin_id),
+        state.lock_service.list()
+    ) {
+        Ok(lists) => lists,
+        Err(e) => {
+            log::error!("获取邀请码列表失败: {}", e);
+            return;
+        }
+    };
+    let (text, keyboard) = build_invite_list(&invites, &locks, &state.config().lock_timezone);
+
+    bot.edit_message_text(message.chat.id, message.id, text)
+        .reply_markup(keyboard)
+        .parse_mode(teloxide::types::ParseMode::Html)
+        .await
+        .ok();
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use chrono::TimeZone;
+
+    #[test]
+    fn test_format_inspection() {
+        use crate::utils::{PasswordResult, PasswordType};
+
+        let result = PasswordResult {
+            password: "5012345678".to_string(),
+            password_type: PasswordType::Times(3),
+            valid_from: Utc.with_ymd_and_hms(2024, 1, 1, 0, 0, 0).unwrap(),
+            valid_until: Utc.with_ymd_and_hms(2024, 1, 1, 20, 0, 0).unwrap(),
+            remaining_uses: Some(3),
+            window: 123,
+            message: String::new(),
+        };
+
+        let now = Utc.with_ymd_and_hms(2024, 1, 1, 18, 30, 0).unwrap();
+        let text = format_inspection("5012345678", &PasswordInspection::Valid(result.clone()), now, &LockTimezone::default());
+        assert!(text.contains("次数密码(3次)"));
+        assert!(text.contains("时间窗口：123"));
+        // 北京时间显示
+        assert!(text.contains("生效时间：2024-01-01 08:00:00"));
+        assert!(text.contains("可用次数：3"));
+        assert!(text.contains("剩余 1 小时 30 分钟"));
+
+        let text = format_inspection("5012345678", &PasswordInspection::Expired(result.clone()), now, &LockTimezone::default());
+        assert!(text.contains("已于 2024-01-02 04:00:00 过期"));
+
+        let text = format_inspection("5012345678", &PasswordInspection::NotYetValid(result), now, &LockTimezone::default());
+        assert!(text.contains("将于 2024-01-01 08:00:00 生效"));
+
+        let text = format_inspection("5012345678", &PasswordInspection::NotOurs, now, &LockTimezone::default());
+        assert!(text.starts_with("❓"));
+    }
 
     #[test]
     fn test_target_parsing() {
@@ -502,4 +2383,233 @@ mod tests {
         assert!("123456789".parse::<i64>().is_ok());
         assert!("invalid".parse::<i64>().is_err());
     }
+
+    #[test]
+    fn test_parse_audit_args() {
+        let (filter, page) = parse_audit_args("", &LockTimezone::default()).unwrap();
+        assert_eq!(filter, AuditFilter::default());
+        assert_eq!(page, 1);
+
+        let (filter, page) = parse_audit_args("admin 123 visitor 456 page 3", &LockTimezone::default()).unwrap();
+        assert_eq!(filter.actor_id, Some(123));
+        assert_eq!(filter.target_user, Some(456));
+        assert_eq!(page, 3);
+
+        // 日期按北京时间解析，结束日期包含当天
+        let (filter, _) = parse_audit_args("from 2024-12-01 to 2024-12-31", &LockTimezone::default()).unwrap();
+        assert_eq!(filter.from.unwrap().to_rfc3339(), "2024-11-30T16:00:00+00:00");
+        assert_eq!(filter.to.unwrap().to_rfc3339(), "2024-12-31T16:00:00+00:00");
+
+        assert!(parse_audit_args("admin", &LockTimezone::default()).is_err());
+        assert!(parse_audit_args("admin abc", &LockTimezone::default()).is_err());
+        assert!(parse_audit_args("page 0", &LockTimezone::default()).is_err());
+        assert!(parse_audit_args("from 2024/12/01", &LockTimezone::default()).is_err());
+        assert!(parse_audit_args("unknown 1", &LockTimezone::default()).is_err());
+    }
+
+    #[test]
+    fn test_parse_invite_args() {
+        assert_eq!(parse_invite_args("", &LockTimezone::default(), &[]).unwrap(), InviteOptions::default());
+
+        let options = parse_invite_args("label 保洁 uses 10 valid 30d", &LockTimezone::default(), &[]).unwrap();
+        assert_eq!(options.label.as_deref(), Some("保洁"));
+        assert_eq!(options.max_uses, Some(10));
+        assert_eq!(options.validity, Some(chrono::Duration::days(30)));
+
+        assert_eq!(parse_invite_args("v 12h", &LockTimezone::default(), &[]).unwrap().validity, Some(chrono::Duration::hours(12)));
+        assert_eq!(parse_invite_args("v 45m", &LockTimezone::default(), &[]).unwrap().validity, Some(chrono::Duration::minutes(45)));
+
+        assert!(parse_invite_args("uses 0", &LockTimezone::default(), &[]).is_err());
+        assert!(parse_invite_args("uses", &LockTimezone::default(), &[]).is_err());
+        assert!(parse_invite_args("valid 7", &LockTimezone::default(), &[]).is_err());
+        assert!(parse_invite_args("valid 7w", &LockTimezone::default(), &[]).is_err());
+        assert!(parse_invite_args("valid 天", &LockTimezone::default(), &[]).is_err());
+        assert!(parse_invite_args("valid 99999d", &LockTimezone::default(), &[]).is_err());
+        assert!(parse_invite_args(&format!("label {}", "长".repeat(33)), &LockTimezone::default(), &[]).is_err());
+        assert!(parse_invite_args("unknown 1", &LockTimezone::default(), &[]).is_err());
+    }
+
+    #[test]
+    fn test_parse_invite_preset_args() {
+        let options = parse_invite_args("label 保洁 auth limited hours 4 minutes 30", &LockTimezone::default(), &[]).unwrap();
+        let preset = options.preset.unwrap();
+        assert_eq!(preset.auth_type, AuthType::Limited);
+        assert_eq!(preset.hours, Some(4));
+        assert_eq!(preset.minutes, Some(30));
+
+        // 结束时间按北京时间解析
+        let preset = parse_invite_args("auth period end 2030-01-02T08:00", &LockTimezone::default(), &[]).unwrap().preset.unwrap();
+        assert_eq!(preset.end_time.unwrap().to_rfc3339(), "2030-01-02T00:00:00+00:00");
+
+        assert_eq!(parse_invite_args("auth temp", &LockTimezone::default(), &[]).unwrap().preset, Some(AuthPreset::new(AuthType::Temp)));
+
+        // 预设参数必须指定授权类型
+        assert!(parse_invite_args("times 3", &LockTimezone::default(), &[]).is_err());
+        assert!(parse_invite_args("auth forever", &LockTimezone::default(), &[]).is_err());
+        assert!(parse_invite_args("auth times times -1", &LockTimezone::default(), &[]).is_err());
+        assert!(parse_invite_args("auth period end 2030-01-02", &LockTimezone::default(), &[]).is_err());
+    }
+
+    #[test]
+    fn test_parse_invite_lock_args() {
+        let mut gate = Lock::new("大门").with_auth_types(vec![AuthType::Temp]);
+        gate.unique_id = 1;
+        let mut door = Lock::new("公寓门");
+        door.unique_id = 2;
+        let locks = [gate, door];
+
+        let preset = parse_invite_args("auth temp lock 公寓门,大门,公寓门", &LockTimezone::default(), &locks)
+            .unwrap()
+            .preset
+            .unwrap();
+        assert_eq!(preset.locks, vec![2, 1]);
+
+        // 门锁必须存在且启用了预设的授权类型
+        assert!(parse_invite_args("auth temp lock 后门", &LockTimezone::default(), &locks).is_err());
+        assert!(parse_invite_args("auth times times 3 lock 大门", &LockTimezone::default(), &locks).is_err());
+        assert!(parse_invite_args("lock 大门", &LockTimezone::default(), &locks).is_err());
+    }
+
+    #[test]
+    fn test_format_pending_entry() {
+        let now = Utc.with_ymd_and_hms(2024, 1, 2, 12, 0, 0).unwrap();
+        let mut record = Record::new(987654321, 1).with_visitor_name("张三");
+        record.unique_id = 42;
+        record.updated_at = now - chrono::Duration::minutes(125);
+
+        let entry = format_pending_entry(&record, Some("保洁"), now);
+        assert!(entry.contains("#42 张三（ID 987654321）"));
+        assert!(entry.contains("邀请码：保洁"));
+        assert!(entry.contains("已等待 2小时5分钟"));
+
+        // 旧记录没有访客名称
+        record.vis_name = None;
+        let entry = format_pending_entry(&record, None, now);
+        assert!(entry.contains("#42 ID 987654321"));
+        assert!(entry.contains("邀请码：未知"));
+    }
+
+    #[test]
+    fn test_format_visitor_entry() {
+        let now = Utc.with_ymd_and_hms(2024, 1, 2, 12, 0, 0).unwrap();
+        let mut record = Record::new(987654321, 1).with_visitor_name("张三");
+        record.unique_id = 7;
+        record.status = AuthStatus::Auth;
+        record.auth_type = AuthType::Times;
+        record.times = Some(3);
+        record.ended_time = Some(now + chrono::Duration::minutes(90));
+
+        let entry = format_visitor_entry(&record, 2, now);
+        assert!(entry.contains("#7 张三（ID 987654321）"));
+        assert!(entry.contains("（3 次）"));
+        assert!(entry.contains("剩余 1小时30分钟"));
+        assert!(entry.contains("已发放密码：2 个"));
+        assert!(!record.can_extend());
+
+        record.auth_type = AuthType::LongtimeTemp;
+        assert!(record.can_extend());
+        record.ended_time = None;
+        assert!(format_visitor_entry(&record, 0, now).contains("长期有效"));
+        assert!(!record.can_extend());
+    }
+
+    #[test]
+    fn test_current_passwords() {
+        let now = Utc.with_ymd_and_hms(2024, 1, 2, 12, 0, 0).unwrap();
+        let issued = |unique_id: i64, lock_id: i64, valid_minutes: i64| IssuedPassword {
+            unique_id,
+            record_id: 1,
+            password: format!("50000000{:02}", unique_id),
+            password_type: "temporary".to_string(),
+            generated_at: now - chrono::Duration::minutes(5),
+            valid_until: Some(now + chrono::Duration::minutes(valid_minutes)),
+            params: None,
+            time_offset: Some(0),
+            lock_id: Some(lock_id),
+        };
+
+        // 每个门锁只保留最近发放的密码，已失效的不再发送
+        let passwords = vec![issued(1, 1, 5), issued(2, 2, 5), issued(3, 1, 10), issued(4, 2, -1)];
+        let current = current_passwords(&passwords, now);
+        assert_eq!(current.iter().map(|p| p.unique_id).collect::<Vec<_>>(), vec![3]);
+        assert!(current_passwords(&passwords[3..], now).is_empty());
+    }
+
+    #[test]
+    fn test_parse_remove_admin_args() {
+        assert_eq!(parse_remove_admin_args("123456789 revoke").unwrap(), (123456789, AdminRemoval::Revoke));
+        assert_eq!(
+            parse_remove_admin_args(" 123456789  transfer 987654321 ").unwrap(),
+            (123456789, AdminRemoval::Transfer(987654321))
+        );
+
+        // 必须明确选择处理方式
+        assert!(parse_remove_admin_args("123456789").is_err());
+        assert!(parse_remove_admin_args("123456789 transfer").is_err());
+        assert!(parse_remove_admin_args("abc revoke").is_err());
+        assert!(parse_remove_admin_args("123456789 transfer abc").is_err());
+        assert!(parse_remove_admin_args("").is_err());
+    }
+
+    #[test]
+    fn test_format_admin_entry() {
+        let mut admin = Admin::new(123456789);
+        admin.unique_id = 3;
+        let counts = AdminCounts { active: 2, pending: 1, invites: 4 };
+
+        let entry = format_admin_entry(&admin, true, &counts);
+        assert!(entry.contains("123456789 ⭐（#3）"));
+        assert!(entry.contains("管理密码：未设置"));
+        assert!(entry.contains("有效授权 2 · 待审批 1 · 可用邀请码 4"));
+
+        admin.password = Some("1234".to_string());
+        assert!(format_admin_entry(&admin, false, &counts).contains("管理密码：已设置"));
+    }
+
+    #[test]
+    fn test_parse_calibrate_args() {
+        let (sample, password, lock) = parse_calibrate_args("show 5012345678").unwrap();
+        assert_eq!(sample, CalibrationSample::Displayed);
+        assert_eq!(password, "5012345678");
+        assert_eq!(lock, None);
+
+        let (sample, _, lock) = parse_calibrate_args("accept 5012345678 lock 大门").unwrap();
+        assert_eq!(sample, CalibrationSample::Accepted);
+        assert_eq!(lock.as_deref(), Some("大门"));
+
+        assert!(parse_calibrate_args("").is_err());
+        assert!(parse_calibrate_args("show").is_err());
+        assert!(parse_calibrate_args("guess 5012345678").is_err());
+        assert!(parse_calibrate_args("show 12345").is_err());
+        assert!(parse_calibrate_args("show 5012345678 lock").is_err());
+        assert!(parse_calibrate_args("show 5012345678 door 大门").is_err());
+    }
+
+    #[test]
+    fn test_parse_set_lock_args() {
+        let mut gate = Lock::new("大门").with_time_offset(30);
+        gate.unique_id = 1;
+        let locks = [gate];
+
+        // 新门锁使用默认设置
+        let lock = parse_set_lock_args("公寓门 offset -45 tz Europe/Berlin", &locks).unwrap();
+        assert_eq!(lock.unique_id, 0);
+        assert_eq!(lock.time_offset, -45);
+        assert_eq!(lock.timezone.to_string(), "Europe/Berlin");
+        assert_eq!(lock.auth_types, AuthType::ALL.to_vec());
+
+        // 已有门锁只修改指定的项
+        let lock = parse_set_lock_args("大门 types temp,times", &locks).unwrap();
+        assert_eq!(lock.unique_id, 1);
+        assert_eq!(lock.time_offset, 30);
+        assert_eq!(lock.auth_types, vec![AuthType::Temp, AuthType::Times]);
+
+        assert!(parse_set_lock_args("", &locks).is_err());
+        assert!(parse_set_lock_args("大,门", &locks).is_err());
+        assert!(parse_set_lock_args("大门 offset", &locks).is_err());
+        assert!(parse_set_lock_args("大门 offset 100000", &locks).is_err());
+        assert!(parse_set_lock_args("大门 tz Mars/Base", &locks).is_err());
+        assert!(parse_set_lock_args("大门 types forever", &locks).is_err());
+        assert!(parse_set_lock_args("大门 color red", &locks).is_err());
+    }
 }
\ No newline at end of file
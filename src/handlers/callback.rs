@@ -1,24 +1,13 @@
 //! 回调查询处理器
 
 use crate::bot::bot::BotState;
+use crate::bot::escalation::close_approval_messages;
 use crate::database::RecordRepository;
 use crate::error::Result;
-use crate::handlers::{admin::handle_regenerate_invite_callback, visitor::{handle_approve_callback, handle_reject_callback}};
+use crate::handlers::{admin::{handle_pending_page_callback, handle_regenerate_invite_callback, handle_revoke_invite_callback, handle_visitor_action_callback, handle_visitors_page_callback}, visitor::{handle_approve_callback, handle_reject_callback, handle_select_auth_callback, handle_toggle_lock_callback}};
 use crate::types::{AuthType, CallbackData};
-use chrono::{Utc, FixedOffset};
 use teloxide::{prelude::*, types::InlineKeyboardButton, types::InlineKeyboardMarkup};
 
-/// 格式化为UTC+8时间字符串
-fn format_beijing_time(timestamp: chrono::DateTime<Utc>) -> String {
-    let beijing_tz = FixedOffset::east_opt(8 * 3600).unwrap();
-    timestamp.with_timezone(&beijing_tz).format("%Y-%m-%d %H:%M:%S").to_string()
-}
-
-/// 获取当前UTC+8时间字符串
-fn current_beijing_time() -> String {
-    format_beijing_time(Utc::now())
-}
-
 /// 处理回调查询
 pub async fn handle_callback_query(
     bot: Bot,
@@ -50,7 +39,32 @@ pub async fn handle_callback_query(
     match parsed_data.action.as_str() {
         // 管理员相关回调
         "regenerate_invite" => {
-            handle_regenerate_invite_callback(bot, callback, state).await?;
+            let invite_id = parse_record_id(&parsed_data)?;
+            handle_regenerate_invite_callback(bot, callback, invite_id, state).await?;
+        }
+
+        "revoke_invite" => {
+            let invite_id = parse_record_id(&parsed_data)?;
+            handle_revoke_invite_callback(bot, callback, invite_id, state).await?;
+        }
+        
+        "pending_page" => {
+            let page = parse_record_id(&parsed_data)?;
+            handle_pending_page_callback(bot, callback, page, state).await?;
+        }
+
+        "visitors_page" => {
+            let page = parse_record_id(&parsed_data)?;
+            handle_visitors_page_callback(bot, callback, page, state).await?;
+        }
+
+        "visitor_revoke" | "visitor_extend" | "visitor_resend" => {
+            if let Some((record_id, page)) = parsed_data.data.as_deref().and_then(|data| data.split_once(':')) {
+                if let (Ok(record_id), Ok(page)) = (record_id.parse::<i64>(), page.parse::<i64>()) {
+                    let action = parsed_data.action.clone();
+                    handle_visitor_action_callback(bot, callback, &action, record_id, page, state).await?;
+                }
+            }
         }
         
         // 访客授权相关回调
@@ -64,6 +78,22 @@ pub async fn handle_callback_query(
             handle_reject_callback(bot, callback, record_id, state).await?;
         }
 
+        // 门锁选择回调
+        "toggle_lock" => {
+            if let Some(data) = &parsed_data.data {
+                if let Some((record_id, lock_id)) = data.split_once(':') {
+                    if let (Ok(record_id), Ok(lock_id)) = (record_id.parse::<i64>(), lock_id.parse::<i64>()) {
+                        handle_toggle_lock_callback(bot, callback, record_id, lock_id, state).await?;
+                    }
+                }
+            }
+        }
+
+        "select_auth" => {
+            let record_id = parse_record_id(&parsed_data)?;
+            handle_select_auth_callback(bot, callback, record_id, state).await?;
+        }
+
         // 授权类型选择回调
         "auth_temp" => {
             let record_id = parse_record_id(&parsed_data)?;
@@ -159,28 +189,30 @@ async fn handle_auth_temp_selection(
     log::info!("管理员选择临时密码授权，记录ID: {}", record_id);
 
     // 直接批准临时密码授权
-    let start_time = Some(Utc::now());
-    let end_time = Some(Utc::now() + chrono::Duration::minutes(10));
+    let now = state.clock.now();
+    let start_time = Some(now);
+    let end_time = Some(now + chrono::Duration::minutes(10));
     
-    let mut tx = state.database.begin_transaction().await?;
-    let success = RecordRepository::approve_authorization(
-        &mut tx,
+    let success = state.record_service.approve(
+        callback.from.id.0 as i64,
         record_id,
         AuthType::Temp,
         start_time,
         end_time,
         None,
     ).await?;
-    tx.commit().await?;
 
     if success {
+        let acted_on = callback.message.as_ref().map(|message| (message.chat.id, message.id));
+        close_approval_messages(&bot, &state, record_id, &callback.from, "已批准（临时密码）", acted_on).await?;
+
         // 立即为临时密码生成密码并推送给访客
         if let Some(record) = RecordRepository::find_by_id(state.database.pool(), record_id).await? {
             let visitor_chat_id = ChatId(record.vis_id);
             
             // 生成并推送密码
             match crate::handlers::visitor::generate_and_send_password(&bot, visitor_chat_id, &record, &state).await {
-                Ok(password) => {
+                Ok(passwords) => {
                     bot.send_message(
                         visitor_chat_id,
                         format!(
@@ -189,13 +221,13 @@ async fn handle_auth_temp_selection(
                              ⏰ 有效期：10分钟\n\
                              🆔 批准ID：{}\n\
                              📅 过期时间：{}\n\
-                             🔑 密码：<code>{}</code>\n\n\
+                             🔑 {}\n\n\
                              💡 密码已自动生成，请妥善保管\n\
                              ⚠️ 此密码10分钟后过期，请及时使用",
                              record_id,
-                             record.ended_time.map(|t| format_beijing_time(t))
+                             record.ended_time.map(|t| state.config().lock_timezone.format(t))
                                  .unwrap_or("未设置".to_string()),
-                             password
+                             crate::handlers::visitor::format_lock_passwords(&passwords)
                         )
                     ).parse_mode(teloxide::types::ParseMode::Html).await.ok();
                 }
@@ -212,7 +244,7 @@ async fn handle_auth_temp_selection(
                              ❗ 密码生成遇到问题，请使用 /getpassword 获取密码\n\
                              💡 如多次获取失败，请联系管理员",
                              record_id,
-                             record.ended_time.map(|t| format_beijing_time(t))
+                             record.ended_time.map(|t| state.config().lock_timezone.format(t))
                                  .unwrap_or("未设置".to_string())
                         )
                     ).await.ok();
@@ -229,7 +261,7 @@ async fn handle_auth_temp_selection(
                  🆔 记录ID：{}\n\
                  🕐 处理时间：{}",
                 record_id,
-                current_beijing_time()
+                state.config().lock_timezone.format(state.clock.now())
             );
 
             bot.edit_message_text(message.chat.id, message.id, updated_message).await?;
@@ -426,7 +458,7 @@ async fn handle_back_to_approve(
          👨‍💼 管理员ID：{}\n\n\
          请您仔细核验后批准",
         record.vis_id,
-        format_beijing_time(record.update_at),
+        state.config().lock_timezone.format(record.updated_at),
         record_id,
         admin.id
     );
@@ -464,28 +496,31 @@ pub async fn handle_confirm_times_callback(
     times: i32,
     state: BotState,
 ) -> Result<()> {
-    let start_time = Some(Utc::now());
-    let end_time = Some(Utc::now() + chrono::Duration::hours(2));
+    let now = state.clock.now();
+    let start_time = Some(now);
+    let end_time = Some(now + chrono::Duration::hours(2));
 
-    let mut tx = state.database.begin_transaction().await?;
-    let success = RecordRepository::approve_authorization(
-        &mut tx,
+    let success = state.record_service.approve(
+        callback.from.id.0 as i64,
         record_id,
         AuthType::Times,
         start_time,
         end_time,
         Some(times),
     ).await?;
-    tx.commit().await?;
 
     if success {
+        let acted_on = callback.message.as_ref().map(|message| (message.chat.id, message.id));
+        let outcome = format!("已批准（{}次密码）", times);
+        close_approval_messages(&bot, &state, record_id, &callback.from, &outcome, acted_on).await?;
+
         // 立即为次数密码生成密码并推送给访客
         if let Some(record) = RecordRepository::find_by_id(state.database.pool(), record_id).await? {
             let visitor_chat_id = ChatId(record.vis_id);
             
             // 生成并推送密码
             match crate::handlers::visitor::generate_and_send_password(&bot, visitor_chat_id, &record, &state).await {
-                Ok(password) => {
+                Ok(passwords) => {
                     bot.send_message(
                         visitor_chat_id,
                         format!(
@@ -495,14 +530,14 @@ pub async fn handle_confirm_times_callback(
                              ⏰ 有效期：2小时\n\
                              🆔 批准ID：{}\n\
                              📅 过期时间：{}\n\
-                             🔑 密码：<code>{}</code>\n\n\
+                             🔑 {}\n\n\
                              💡 密码已自动生成，请妥善保管\n\
                              ⚠️ 每次使用都会消耗一次使用次数",
                              times,
                              record_id,
-                             record.ended_time.map(|t| format_beijing_time(t))
+                             record.ended_time.map(|t| state.config().lock_timezone.format(t))
                                  .unwrap_or("未设置".to_string()),
-                             password
+                             crate::handlers::visitor::format_lock_passwords(&passwords)
                         )
                     ).parse_mode(teloxide::types::ParseMode::Html).await.ok();
                 }
@@ -521,7 +556,7 @@ pub async fn handle_confirm_times_callback(
                              💡 如多次获取失败，请联系管理员",
                              times,
                              record_id,
-                             record.ended_time.map(|t| format_beijing_time(t))
+                             record.ended_time.map(|t| state.config().lock_timezone.format(t))
                                  .unwrap_or("未设置".to_string())
                         )
                     ).await.ok();
@@ -540,7 +575,7 @@ pub async fn handle_confirm_times_callback(
                  🕐 处理时间：{}",
                 times,
                 record_id,
-                current_beijing_time()
+                state.config().lock_timezone.format(state.clock.now())
             );
 
             bot.edit_message_text(message.chat.id, message.id, updated_message).await?;
@@ -567,20 +602,19 @@ pub async fn handle_confirm_limited_callback(
     minutes: u32,
     state: BotState,
 ) -> Result<()> {
-    let start_time = Some(Utc::now());
+    let now = state.clock.now();
+    let start_time = Some(now);
     let duration = chrono::Duration::hours(hours as i64) + chrono::Duration::minutes(minutes as i64);
-    let end_time = Some(Utc::now() + duration);
+    let end_time = Some(now + duration);
 
-    let mut tx = state.database.begin_transaction().await?;
-    let success = RecordRepository::approve_authorization(
-        &mut tx,
+    let success = state.record_service.approve(
+        callback.from.id.0 as i64,
         record_id,
         AuthType::Limited,
         start_time,
         end_time,
         None,
     ).await?;
-    tx.commit().await?;
 
     if success {
         let duration_str = if minutes == 0 {
@@ -589,13 +623,17 @@ pub async fn handle_confirm_limited_callback(
             format!("{}小时{}分钟", hours, minutes)
         };
 
+        let acted_on = callback.message.as_ref().map(|message| (message.chat.id, message.id));
+        let outcome = format!("已批准（{}时效密码）", duration_str);
+        close_approval_messages(&bot, &state, record_id, &callback.from, &outcome, acted_on).await?;
+
         // 立即为时效密码生成密码并推送给访客
         if let Some(record) = RecordRepository::find_by_id(state.database.pool(), record_id).await? {
             let visitor_chat_id = ChatId(record.vis_id);
             
             // 生成并推送密码
             match crate::handlers::visitor::generate_and_send_password(&bot, visitor_chat_id, &record, &state).await {
-                Ok(password) => {
+                Ok(passwords) => {
                     bot.send_message(
                         visitor_chat_id,
                         format!(
@@ -604,13 +642,13 @@ pub async fn handle_confirm_limited_callback(
                              ⏰ 有效时长：{}\n\
                              📅 过期时间：{}\n\
                              🆔 批准ID：{}\n\
-                             🔑 密码：<code>{}</code>\n\n\
+                             🔑 {}\n\n\
                              💡 密码已自动生成，请妥善保管\n\
                              ⚠️ 密码在有效时长内可重复使用",
                              duration_str,
-                             format_beijing_time(end_time.unwrap()),
+                             state.config().lock_timezone.format(end_time.unwrap()),
                              record_id,
-                             password
+                             crate::handlers::visitor::format_lock_passwords(&passwords)
                         )
                     ).parse_mode(teloxide::types::ParseMode::Html).await.ok();
                 }
@@ -627,7 +665,7 @@ pub async fn handle_confirm_limited_callback(
                              ❗ 密码生成遇到问题，请使用 /getpassword 获取密码\n\
                              💡 如多次获取失败，请联系管理员",
                              duration_str,
-                             format_beijing_time(end_time.unwrap()),
+                             state.config().lock_timezone.format(end_time.unwrap()),
                              record_id
                         )
                     ).await.ok();
@@ -645,9 +683,9 @@ pub async fn handle_confirm_limited_callback(
                  🆔 记录ID：{}\n\
                  🕐 处理时间：{}",
                 duration_str,
-                format_beijing_time(end_time.unwrap()),
+                state.config().lock_timezone.format(end_time.unwrap()),
                 record_id,
-                current_beijing_time()
+                state.config().lock_timezone.format(state.clock.now())
             );
 
             bot.edit_message_text(message.chat.id, message.id, updated_message).await?;
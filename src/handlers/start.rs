@@ -6,7 +6,29 @@ use crate::types::UserRole;
 use teloxide::{prelude::*, types::User};
 
 /// 处理/start命令
-pub async fn start_command(bot: Bot, msg: Message, _cmd: crate::bot::bot::Command, state: BotState) -> Result<()> {
+pub async fn start_command(bot: Bot, msg: Message, cmd: crate::bot::bot::Command, state: BotState) -> Result<()> {
+    // 通过邀请深度链接进入时，start参数即编码后的邀请码
+    let payload = match cmd {
+        crate::bot::bot::Command::Start(payload) => payload.trim().to_string(),
+        _ => String::new(),
+    };
+
+    if !payload.is_empty() {
+        return match crate::types::Invite::code_from_start_payload(&payload) {
+            Some(invite_code) => {
+                log::info!("用户通过邀请链接启动Bot，start参数: {}", payload);
+                crate::handlers::visitor::request_with_invite(bot, msg, invite_code, state).await
+            }
+            None => {
+                bot.send_message(
+                    msg.chat.id,
+                    "❌ 邀请链接无效\n请联系管理员获取新的邀请链接或邀请码"
+                ).await?;
+                Ok(())
+            }
+        };
+    }
+
     let user = match msg.from() {
         Some(user) => user,
         None => {
@@ -34,10 +56,22 @@ pub async fn start_command(bot: Bot, msg: Message, _cmd: crate::bot::bot::Comman
                  您的权限：超级管理员\n\n\
                  可用命令：\n\
                  /addadmin <用户ID> - 添加管理员\n\
+                 /listadmins - 查看管理员列表\n\
+                 /removeadmin <用户ID> <revoke | transfer 用户ID> - 删除管理员\n\
+                 /addsuper <用户ID> - 添加超级管理员\n\
+                 /removesuper <用户ID> - 移除超级管理员\n\
                  /editpasswd <密码> - 修改密码\n\
-                 /geninvite - 生成邀请码\n\
+                 /geninvite [参数] - 生成邀请码\n\
+                 /invites - 管理邀请码\n\
+                 /pending - 查看待审批请求\n\
+                 /myvisitors - 管理访客授权\n\
+                 /check <密码> - 检查密码\n\
                  /revoke <目标> - 撤销授权\n\
-                 /getpassword - 获取临时密码\n\n\
+                 /getpassword - 获取临时密码\n\
+                 /audit [条件] - 查看审计日志\n\
+                 /locks - 查看门锁\n\
+                 /setlock <名称> [参数] - 添加或修改门锁\n\
+                 /calibrate <accept|show> <密码> - 校准门锁时钟\n\n\
                  用户ID：{}", 
                 user.id.0
             )
@@ -49,9 +83,14 @@ pub async fn start_command(bot: Bot, msg: Message, _cmd: crate::bot::bot::Comman
                  您的权限：管理员\n\n\
                  可用命令：\n\
                  /editpasswd <密码> - 修改密码\n\
-                 /geninvite - 生成邀请码\n\
+                 /geninvite [参数] - 生成邀请码\n\
+                 /invites - 管理邀请码\n\
+                 /pending - 查看待审批请求\n\
+                 /myvisitors - 管理访客授权\n\
+                 /check <密码> - 检查密码\n\
                  /revoke <目标> - 撤销授权\n\
-                 /getpassword - 获取临时密码\n\n\
+                 /getpassword - 获取临时密码\n\
+                 /locks - 查看门锁\n\n\
                  用户ID：{}", 
                 user.id.0
             )
@@ -62,8 +101,10 @@ pub async fn start_command(bot: Bot, msg: Message, _cmd: crate::bot::bot::Comman
                  您似乎尝试到访NekoHouseV3，现在我们为您操作批准，请填写到访邀请码：\n\n\
                  可用命令：\n\
                  /req <邀请码> - 申请访客授权\n\
-                 /getpassword - 获取密码（需要先获得授权）\n\n\
-                 请联系管理员获取邀请码", 
+                 /getpassword - 获取密码（需要先获得授权）\n\
+                 /status - 查看授权状态\n\
+                 /history - 查看历史记录\n\n\
+                 请联系管理员获取邀请码",
                 user.id.0
             )
         }
@@ -143,24 +184,43 @@ pub fn generate_command_help(role: UserRole) -> String {
         UserRole::SuperAdmin => {
             "🔧 超级管理员命令：\n\
              /addadmin <用户ID> - 添加新管理员\n\
+             /listadmins - 查看管理员及其访客和邀请码数量\n\
+             /removeadmin <用户ID> <revoke | transfer 用户ID> - 删除管理员并撤销或转交其访客\n\
+             /addsuper <用户ID> - 添加超级管理员（立即生效并写回配置文件）\n\
+             /removesuper <用户ID> - 移除超级管理员（不能移除最后一位）\n\
              /editpasswd <密码> - 修改管理密码（4-10位数字）\n\
-             /geninvite - 生成/更新邀请码\n\
+             /geninvite [label 名称] [uses 次数] [valid 有效期] [auth 类型 …] - 生成邀请码（可预设自动批准）\n\
+             /invites - 查看、更换和撤销邀请码\n\
+             /pending - 分页查看待审批请求并直接批准或拒绝（可看到全部请求）\n\
+             /myvisitors - 查看、撤销、延长访客授权及重发密码（可看到全部访客）\n\
+             /check <密码> - 检查密码的类型和有效期\n\
              /revoke <目标> - 撤销授权（record ID或user ID）\n\
-             /getpassword - 获取临时密码\n\n\
+             /getpassword - 获取临时密码\n\
+             /audit [条件] - 查看审计日志（按管理员/访客/日期过滤）\n\
+             /locks - 查看门锁的时间偏移、时区和授权类型\n\
+             /setlock <名称> [offset 秒] [tz 时区] [types 类型] - 添加或修改门锁\n\
+             /calibrate <accept|show> <密码> [lock 门锁名称] - 按门锁接受或显示的密码校准时钟偏差\n\n\
              💡 提示：超级管理员拥有所有权限".to_string()
         }
         UserRole::Admin => {
             "🛠️ 管理员命令：\n\
              /editpasswd <密码> - 修改管理密码（4-10位数字）\n\
-             /geninvite - 生成/更新邀请码\n\
+             /geninvite [label 名称] [uses 次数] [valid 有效期] [auth 类型 …] - 生成邀请码（可预设自动批准）\n\
+             /invites - 查看、更换和撤销邀请码\n\
+             /pending - 分页查看自己邀请的待审批请求并直接批准或拒绝\n\
+             /myvisitors - 查看、撤销、延长自己邀请的访客授权及重发密码\n\
+             /check <密码> - 检查密码的类型和有效期\n\
              /revoke <目标> - 撤销授权（record ID或user ID）\n\
-             /getpassword - 获取临时密码\n\n\
+             /getpassword - 获取临时密码\n\
+             /locks - 查看门锁的时间偏移、时区和授权类型\n\n\
              💡 提示：首次使用前请先设置管理密码".to_string()
         }
         UserRole::Visitor => {
             "📋 访客命令：\n\
              /req <邀请码> - 申请访客授权\n\
-             /getpassword - 获取密码（需要先获得授权）\n\n\
+             /getpassword - 获取密码（需要先获得授权）\n\
+             /status - 查看授权状态\n\
+             /history - 查看历史记录\n\n\
              💡 提示：请向管理员申请邀请码\n\
              ⚠️ 一个用户同时只能有一个待处理请求或活跃授权".to_string()
         }
@@ -261,10 +321,17 @@ mod tests {
         let super_admin_help = generate_command_help(UserRole::SuperAdmin);
         assert!(super_admin_help.contains("/addadmin"));
         assert!(super_admin_help.contains("超级管理员"));
+        assert!(super_admin_help.contains("/setlock"));
+        assert!(super_admin_help.contains("/calibrate"));
+        assert!(super_admin_help.contains("/removeadmin"));
+        assert!(super_admin_help.contains("/addsuper") && super_admin_help.contains("/removesuper"));
 
         let admin_help = generate_command_help(UserRole::Admin);
         assert!(admin_help.contains("/editpasswd"));
         assert!(!admin_help.contains("/addadmin")); // 管理员没有添加管理员权限
+        assert!(admin_help.contains("/locks") && !admin_help.contains("/setlock"));
+        assert!(admin_help.contains("/pending"));
+        assert!(admin_help.contains("/myvisitors"));
 
         let visitor_help = generate_command_help(UserRole::Visitor);
         assert!(visitor_help.contains("/req"));
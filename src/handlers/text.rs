@@ -1,9 +1,11 @@
 //! 文本消息处理器
 
 use crate::bot::bot::BotState;
+use crate::bot::escalation::close_approval_messages;
 use crate::database::RecordRepository;
 use crate::error::Result;
 use crate::types::{AuthStatus, AuthType};
+use crate::utils::LockTimezone;
 use chrono::{DateTime, NaiveDateTime, Utc};
 use teloxide::prelude::*;
 
@@ -86,7 +88,7 @@ async fn handle_period_authorization(
     }
 
     // 解析时间格式 YYYY-MM-DD HH
-    let end_time = match parse_datetime(&datetime_str) {
+    let end_time = match parse_datetime(&datetime_str, &state.config().lock_timezone) {
         Ok(dt) => dt,
         Err(e) => {
             bot.send_message(
@@ -103,31 +105,35 @@ async fn handle_period_authorization(
     };
 
     // 检查时间是否为未来时间
-    if end_time <= Utc::now() {
+    let now = state.clock.now();
+    if end_time <= now {
         bot.send_message(msg.chat.id, "❌ 结束时间必须是未来时间").await?;
         return Ok(());
     }
 
     // 批准授权
-    let mut tx = state.database.begin_transaction().await?;
-    let success = RecordRepository::approve_authorization(
-        &mut tx,
+    let success = state.record_service.approve(
+        user_id,
         record_id,
         AuthType::Period,
-        Some(Utc::now()),
+        Some(now),
         Some(end_time),
         None,
     ).await?;
-    tx.commit().await?;
 
     if success {
+        // 管理员通过文本消息批准，所有审批消息都需要更新
+        if let Some(user) = msg.from() {
+            close_approval_messages(bot, state, record_id, user, "已批准（指定过期时间密码）", None).await?;
+        }
+
         // 立即生成并推送密码给访客
         if let Some(record) = RecordRepository::find_by_id(state.database.pool(), record_id).await? {
             let visitor_chat_id = ChatId(record.vis_id);
             
             // 生成并推送密码
             match crate::handlers::visitor::generate_and_send_password(bot, visitor_chat_id, &record, state).await {
-                Ok(password) => {
+                Ok(passwords) => {
                     bot.send_message(
                         visitor_chat_id,
                         format!(
@@ -135,12 +141,12 @@ async fn handle_period_authorization(
                              📋 授权类型：指定过期时间密码\n\
                              📅 过期时间：{}\n\
                              🆔 批准ID：{}\n\
-                             🔑 密码：<code>{}</code>\n\n\
+                             🔑 {}\n\n\
                              💡 密码已自动生成，请妥善保管\n\
                              ⚠️ 密码在过期时间前可重复使用",
-                            end_time.format("%Y-%m-%d %H:%M:%S"),
+                            state.config().lock_timezone.format(end_time),
                             record_id,
-                            password
+                            crate::handlers::visitor::format_lock_passwords(&passwords)
                         )
                     ).parse_mode(teloxide::types::ParseMode::Html).await.ok();
                 }
@@ -155,7 +161,7 @@ async fn handle_period_authorization(
                              🆔 批准ID：{}\n\n\
                              ❗ 密码生成遇到问题，请使用 /getpassword 获取密码\n\
                              💡 如多次获取失败，请联系管理员",
-                            end_time.format("%Y-%m-%d %H:%M:%S"),
+                            state.config().lock_timezone.format(end_time),
                             record_id
                         )
                     ).await.ok();
@@ -170,8 +176,8 @@ async fn handle_period_authorization(
              📅 过期时间：{}\n\
              🕐 处理时间：{}",
             record_id,
-            end_time.format("%Y-%m-%d %H:%M:%S"),
-            Utc::now().format("%Y-%m-%d %H:%M:%S")
+            state.config().lock_timezone.format(end_time),
+            state.config().lock_timezone.format(state.clock.now())
         );
 
         bot.send_message(msg.chat.id, message).await?;
@@ -201,7 +207,7 @@ async fn handle_longtime_temp_authorization(
     }
 
     // 解析时间格式 YYYY-MM-DD HH:MM
-    let end_time = match parse_datetime_with_minutes(&datetime_str) {
+    let end_time = match parse_datetime_with_minutes(&datetime_str, &state.config().lock_timezone) {
         Ok(dt) => dt,
         Err(e) => {
             bot.send_message(
@@ -218,24 +224,27 @@ async fn handle_longtime_temp_authorization(
     };
 
     // 检查时间是否为未来时间
-    if end_time <= Utc::now() {
+    let now = state.clock.now();
+    if end_time <= now {
         bot.send_message(msg.chat.id, "❌ 结束时间必须是未来时间").await?;
         return Ok(());
     }
 
     // 批准授权
-    let mut tx = state.database.begin_transaction().await?;
-    let success = RecordRepository::approve_authorization(
-        &mut tx,
+    let success = state.record_service.approve(
+        user_id,
         record_id,
         AuthType::LongtimeTemp,
-        Some(Utc::now()),
+        Some(now),
         Some(end_time),
         None,
     ).await?;
-    tx.commit().await?;
 
     if success {
+        if let Some(user) = msg.from() {
+            close_approval_messages(bot, state, record_id, user, "已批准（长期临时密码）", None).await?;
+        }
+
         // 通知访客（长期临时密码不自动推送，需要用户主动获取）
         if let Some(record) = RecordRepository::find_by_id(state.database.pool(), record_id).await? {
             let visitor_chat_id = ChatId(record.vis_id);
@@ -250,7 +259,7 @@ async fn handle_longtime_temp_authorization(
                      💡 使用 /getpassword 获取密码\n\
                      ⚠️ 每次获取的密码有效期为10分钟\n\
                      🔄 如需重新获取，请等待5分钟间隔",
-                    end_time.format("%Y-%m-%d %H:%M:%S"),
+                    state.config().lock_timezone.format(end_time),
                     record_id
                 )
             ).await.ok();
@@ -263,8 +272,8 @@ async fn handle_longtime_temp_authorization(
              📅 有效期至：{}\n\
              🕐 处理时间：{}",
             record_id,
-            end_time.format("%Y-%m-%d %H:%M:%S"),
-            Utc::now().format("%Y-%m-%d %H:%M:%S")
+            state.config().lock_timezone.format(end_time),
+            state.config().lock_timezone.format(state.clock.now())
         );
 
         bot.send_message(msg.chat.id, message).await?;
@@ -276,30 +285,26 @@ async fn handle_longtime_temp_authorization(
     Ok(())
 }
 
-/// 解析日期时间字符串 YYYY-MM-DD HH
-fn parse_datetime(datetime_str: &str) -> Result<DateTime<Utc>> {
+/// 解析门锁时区的日期时间字符串 YYYY-MM-DD HH
+fn parse_datetime(datetime_str: &str, timezone: &LockTimezone) -> Result<DateTime<Utc>> {
     // 添加默认的分钟和秒
     let full_datetime_str = format!("{}:00:00", datetime_str);
     
     let naive_dt = NaiveDateTime::parse_from_str(&full_datetime_str, "%Y-%m-%d %H:%M:%S")
         .map_err(|e| crate::error::AppError::validation(format!("日期时间解析错误: {}", e)))?;
     
-    // 转换为 UTC 时间（假设输入是北京时间 UTC+8）
-    let utc_dt = naive_dt - chrono::Duration::hours(8);
-    Ok(DateTime::from_naive_utc_and_offset(utc_dt, Utc))
+    Ok(timezone.from_local(&naive_dt))
 }
 
-/// 解析日期时间字符串 YYYY-MM-DD HH:MM
-fn parse_datetime_with_minutes(datetime_str: &str) -> Result<DateTime<Utc>> {
+/// 解析门锁时区的日期时间字符串 YYYY-MM-DD HH:MM
+fn parse_datetime_with_minutes(datetime_str: &str, timezone: &LockTimezone) -> Result<DateTime<Utc>> {
     // 添加默认的秒
     let full_datetime_str = format!("{}:00", datetime_str);
     
     let naive_dt = NaiveDateTime::parse_from_str(&full_datetime_str, "%Y-%m-%d %H:%M:%S")
         .map_err(|e| crate::error::AppError::validation(format!("日期时间解析错误: {}", e)))?;
     
-    // 转换为 UTC 时间（假设输入是北京时间 UTC+8）
-    let utc_dt = naive_dt - chrono::Duration::hours(8);
-    Ok(DateTime::from_naive_utc_and_offset(utc_dt, Utc))
+    Ok(timezone.from_local(&naive_dt))
 }
 
 /// 检查记录状态
@@ -365,17 +370,22 @@ mod tests {
 
     #[test]
     fn test_parse_datetime() {
-        let result = parse_datetime("2024-12-25 18");
+        let result = parse_datetime("2024-12-25 18", &LockTimezone::default());
         assert!(result.is_ok());
         
         let dt = result.unwrap();
         // 由于转换了时区，应该是 UTC 时间
         assert_eq!(dt.format("%Y-%m-%d %H:%M:%S").to_string(), "2024-12-25 10:00:00");
+
+        // 按配置的门锁时区解析
+        let berlin: LockTimezone = "Europe/Berlin".parse().unwrap();
+        let dt = parse_datetime("2024-07-01 18", &berlin).unwrap();
+        assert_eq!(dt.format("%Y-%m-%d %H:%M:%S").to_string(), "2024-07-01 16:00:00");
     }
 
     #[test]
     fn test_parse_datetime_with_minutes() {
-        let result = parse_datetime_with_minutes("2024-12-31 23:59");
+        let result = parse_datetime_with_minutes("2024-12-31 23:59", &LockTimezone::default());
         assert!(result.is_ok());
         
         let dt = result.unwrap();
@@ -384,10 +394,10 @@ mod tests {
 
     #[test]
     fn test_invalid_datetime_formats() {
-        assert!(parse_datetime("invalid-date").is_err());
-        assert!(parse_datetime("2024-13-25 18").is_err()); // 无效月份
-        assert!(parse_datetime("2024-12-32 18").is_err()); // 无效日期
-        assert!(parse_datetime("2024-12-25 25").is_err()); // 无效小时
+        assert!(parse_datetime("invalid-date", &LockTimezone::default()).is_err());
+        assert!(parse_datetime("2024-13-25 18", &LockTimezone::default()).is_err()); // 无效月份
+        assert!(parse_datetime("2024-12-32 18", &LockTimezone::default()).is_err()); // 无效日期
+        assert!(parse_datetime("2024-12-25 25", &LockTimezone::default()).is_err()); // 无效小时
     }
 
     #[test]
@@ -1,27 +1,20 @@
 //! 访客命令处理器
 
 use crate::bot::bot::BotState;
-use crate::database::RecordRepository;
-use crate::error::Result;
+use crate::bot::escalation::close_approval_messages;
+use crate::database::{ApprovalRepository, IssuedPasswordRepository, RecordRepository};
+use crate::error::{AppError, Result};
+use crate::handlers::text::format_duration;
 use crate::handlers::start::{get_user_display_name, validate_user_input};
-use crate::types::{AuthStatus, AuthType, CallbackData, PasswordRequest, Record, UserRole};
-use chrono::{Datelike, Timelike, Utc, FixedOffset};
+use crate::types::{ApprovalMessage, AuthStatus, AuthType, CallbackData, IssuedPassword, Lock, PasswordRequest, Record, UserRole};
+use crate::utils::{LockTimezone, PasswordResult};
+use chrono::{Datelike, Timelike, Utc};
 use teloxide::{
     prelude::*,
-    types::{InlineKeyboardButton, InlineKeyboardMarkup},
+    types::{InlineKeyboardButton, InlineKeyboardMarkup, MessageId},
+    utils::html,
 };
 
-/// 格式化为UTC+8时间字符串
-fn format_beijing_time(timestamp: chrono::DateTime<Utc>) -> String {
-    let beijing_tz = FixedOffset::east_opt(8 * 3600).unwrap();
-    timestamp.with_timezone(&beijing_tz).format("%Y-%m-%d %H:%M:%S").to_string()
-}
-
-/// 获取当前UTC+8时间字符串
-fn current_beijing_time() -> String {
-    format_beijing_time(Utc::now())
-}
-
 /// 处理/req命令 - 申请访客授权
 pub async fn req_command(
     bot: Bot,
@@ -37,6 +30,17 @@ pub async fn req_command(
             return Ok(());
         }
     };
+
+    request_with_invite(bot, msg, invite_code, state).await
+}
+
+/// 使用邀请码申请访客授权（/req 和邀请深度链接共用）
+pub async fn request_with_invite(
+    bot: Bot,
+    msg: Message,
+    invite_code: String,
+    state: BotState,
+) -> Result<()> {
     let user = match msg.from() {
         Some(user) => user,
         None => {
@@ -70,7 +74,7 @@ pub async fn req_command(
     }
 
     // 检查用户是否已有活跃授权
-    if RecordRepository::has_active_authorization(state.database.pool(), user_id).await? {
+    if RecordRepository::has_active_authorization(state.database.pool(), user_id, state.clock.now()).await? {
         bot.send_message(
             msg.chat.id,
             "❌ 您当前已有活跃的授权\n\n\
@@ -82,7 +86,29 @@ pub async fn req_command(
     }
 
     // 验证邀请码并查找对应管理员
-    let admin = match user_service.find_admin_by_invite_code(&invite_code).await? {
+    let invite = match state.invite_service.find_by_code(&invite_code).await? {
+        Some(invite) => invite,
+        None => {
+            bot.send_message(
+                msg.chat.id,
+                "❌ 邀请码无效或已过期\n请联系管理员获取正确的邀请码"
+            ).await?;
+            return Ok(());
+        }
+    };
+
+    if !invite.is_usable() {
+        bot.send_message(
+            msg.chat.id,
+            format!(
+                "❌ 邀请码{}\n请联系管理员获取新的邀请码",
+                invite.state().description()
+            )
+        ).await?;
+        return Ok(());
+    }
+
+    let admin = match user_service.get_admin_info_by_unique_id(invite.admin_id).await? {
         Some(admin) => admin,
         None => {
             bot.send_message(
@@ -93,11 +119,27 @@ pub async fn req_command(
         }
     };
 
-    // 创建访客记录
-    let mut tx = state.database.begin_transaction().await?;
-    let record = Record::new(user_id, admin.unique_id);
-    let record_id = RecordRepository::create(&mut tx, &record).await?;
-    tx.commit().await?;
+    // 创建访客记录（同时占用一次邀请码使用次数）
+    let record_id = match state.invite_service.create_request(&invite, user_id, &get_user_display_name(user)).await? {
+        Some(record_id) => record_id,
+        None => {
+            bot.send_message(
+                msg.chat.id,
+                "❌ 邀请码已失效\n请联系管理员获取新的邀请码"
+            ).await?;
+            return Ok(());
+        }
+    };
+
+    drop(user_service);
+
+    // 带预设授权的邀请码直接批准，事后通知管理员
+    if let Some(ref preset) = invite.preset {
+        if auto_approve_with_preset(&bot, &msg, &admin, user, &invite, preset, record_id, &state).await? {
+            log::info!("用户 {} 通过邀请码 #{} 自动获得授权，记录ID: {}", user_id, invite.unique_id, record_id);
+            return Ok(());
+        }
+    }
 
     // 发送确认消息给访客
     let visitor_message = format!(
@@ -109,13 +151,13 @@ pub async fn req_command(
          💡 请耐心等待管理员审核",
         admin.id,
         record_id,
-        current_beijing_time()
+        state.config().lock_timezone.format(state.clock.now())
     );
 
     bot.send_message(msg.chat.id, visitor_message).await?;
 
     // 发送通知给管理员
-    send_approval_request_to_admin(&bot, &admin, user, record_id, &state).await?;
+    send_approval_request_to_admin(&bot, &admin, user, &invite, record_id, &state).await?;
 
     log::info!("用户 {} 成功创建授权请求，记录ID: {}", user_id, record_id);
     Ok(())
@@ -143,7 +185,7 @@ pub async fn get_password_command(bot: Bot, msg: Message, state: BotState) -> Re
     }
 
     // 访客需要检查授权
-    let active_records = RecordRepository::find_active_by_visitor(state.database.pool(), user_id).await?;
+    let active_records = RecordRepository::find_active_by_visitor(state.database.pool(), user_id, state.clock.now()).await?;
 
     if active_records.is_empty() {
         // 检查是否有待处理的请求
@@ -179,7 +221,7 @@ pub async fn get_password_command(bot: Bot, msg: Message, state: BotState) -> Re
     
     for record in active_records {
         // 双重检查记录是否确实活跃
-        if !record.is_active() {
+        if !record.is_active_at(state.clock.now()) {
             expired_count += 1;
             log::warn!("记录 {} 被标记为活跃但实际已过期", record.unique_id);
             continue;
@@ -257,6 +299,181 @@ pub async fn get_password_command(bot: Bot, msg: Message, state: BotState) -> Re
     Ok(())
 }
 
+/// /history 显示的记录条数
+const HISTORY_LIMIT: i64 = 10;
+
+/// 处理/status命令 - 查看当前待审批或有效的授权
+pub async fn status_command(bot: Bot, msg: Message, state: BotState) -> Result<()> {
+    let user = match msg.from() {
+        Some(user) => user,
+        None => {
+            bot.send_message(msg.chat.id, "无法获取用户信息").await?;
+            return Ok(());
+        }
+    };
+
+    let now = state.clock.now();
+    let records: Vec<Record> = RecordRepository::find_current_by_visitor(state.database.pool(), user.id.0 as i64)
+        .await?
+        .into_iter()
+        .filter(|record| record.status == AuthStatus::Pending || record.is_active_at(now))
+        .collect();
+
+    if records.is_empty() {
+        bot.send_message(
+            msg.chat.id,
+            "📭 您当前没有待审批或有效的授权\n\n\
+             请使用 /req <邀请码> 申请授权\n\
+             💡 使用 /history 查看历史记录"
+        ).await?;
+        return Ok(());
+    }
+
+    let config = state.config();
+    let mut entries = Vec::with_capacity(records.len());
+    for record in &records {
+        let remaining = match record.status {
+            AuthStatus::Auth => password_remaining_times(record, &state).await?,
+            _ => Vec::new(),
+        };
+        entries.push(format_status_entry(record, &remaining, &config.lock_timezone, now));
+    }
+
+    bot.send_message(msg.chat.id, format!("📋 授权状态\n\n{}", entries.join("\n\n"))).await?;
+    Ok(())
+}
+
+/// 各门锁最近发放的密码在门锁上的剩余有效时间（门锁名称，剩余时间）
+async fn password_remaining_times(record: &Record, state: &BotState) -> Result<Vec<(String, String)>> {
+    let issued = IssuedPasswordRepository::find_by_record(state.database.pool(), record.unique_id).await?;
+    if issued.is_empty() {
+        return Ok(Vec::new());
+    }
+
+    let admin_password = state.user_service.read().await
+        .get_admin_info_by_unique_id(record.inviter).await?
+        .and_then(|admin| admin.password);
+    let admin_password = match admin_password {
+        Some(password) => password,
+        None => return Ok(Vec::new()),
+    };
+
+    let locks = state.lock_service.locks_for_record(record.unique_id).await?;
+    let password_service = state.password_service.read().await;
+    let mut remaining = Vec::with_capacity(locks.len());
+    for lock in &locks {
+        if let Some(latest) = issued.iter().rev().find(|p| p.lock_id == Some(lock.unique_id)) {
+            let time = password_service
+                .get_remaining_time(&latest.password, &admin_password, lock)?
+                .unwrap_or_else(|| "已失效".to_string());
+            remaining.push((lock.name.clone(), time));
+        }
+    }
+    Ok(remaining)
+}
+
+/// 格式化/status中的一条记录，`remaining` 为各门锁密码的剩余有效时间
+fn format_status_entry(
+    record: &Record,
+    remaining: &[(String, String)],
+    timezone: &LockTimezone,
+    now: chrono::DateTime<Utc>,
+) -> String {
+    if record.status == AuthStatus::Pending {
+        return format!(
+            "⏳ #{} 待审批\n\
+             🕐 提交时间：{}\n\
+             ⌛ 已等待 {}",
+            record.unique_id,
+            timezone.format(record.updated_at),
+            format_duration(record.updated_at, now)
+        );
+    }
+
+    let mut lines = vec![
+        format!("✅ #{} 已授权", record.unique_id),
+        format!("🔐 类型：{}", record.auth_type_label()),
+    ];
+    if let Some(start_time) = record.start_time {
+        lines.push(format!("🕐 开始：{}", timezone.format(start_time)));
+    }
+    match record.ended_time {
+        Some(ended_time) => {
+            lines.push(format!("🏁 结束：{}", timezone.format(ended_time)));
+            lines.push(format!("⌛ 授权剩余 {}", format_duration(now, ended_time)));
+        }
+        None => lines.push("🏁 结束：长期有效".to_string()),
+    }
+
+    if remaining.is_empty() {
+        lines.push("🔑 尚未获取密码，请使用 /getpassword".to_string());
+    } else {
+        for (lock_name, time) in remaining {
+            lines.push(format!("🔑 {} 密码剩余：{}", lock_name, time));
+        }
+    }
+
+    lines.join("\n")
+}
+
+/// 处理/history命令 - 查看已结束的授权记录
+pub async fn history_command(bot: Bot, msg: Message, state: BotState) -> Result<()> {
+    let user = match msg.from() {
+        Some(user) => user,
+        None => {
+            bot.send_message(msg.chat.id, "无法获取用户信息").await?;
+            return Ok(());
+        }
+    };
+
+    let records =
+        RecordRepository::find_history_by_visitor(state.database.pool(), user.id.0 as i64, HISTORY_LIMIT).await?;
+    if records.is_empty() {
+        bot.send_message(msg.chat.id, "📭 暂无历史记录\n\n💡 使用 /status 查看当前授权").await?;
+        return Ok(());
+    }
+
+    let config = state.config();
+    let entries: Vec<String> = records
+        .iter()
+        .map(|record| format_history_entry(record, &config.lock_timezone))
+        .collect();
+
+    bot.send_message(
+        msg.chat.id,
+        format!("📜 最近 {} 条历史记录\n\n{}", records.len(), entries.join("\n")),
+    ).await?;
+    Ok(())
+}
+
+/// 格式化/history中的一条记录，被拒绝和取消的申请未授权过，不显示授权类型
+fn format_history_entry(record: &Record, timezone: &LockTimezone) -> String {
+    let icon = match record.status {
+        AuthStatus::Rejected => "❌",
+        AuthStatus::Cancelled => "↩️",
+        AuthStatus::Revoked => "🚫",
+        _ => "⌛",
+    };
+
+    match record.status {
+        AuthStatus::Revoked | AuthStatus::Expired => format!(
+            "{} #{} {} · {} · {}",
+            icon,
+            record.unique_id,
+            record.status.description(),
+            record.auth_type_label(),
+            timezone.format(record.updated_at)
+        ),
+        _ => format!(
+            "{} #{} {} · {}",
+            icon,
+            record.unique_id,
+            record.status.description(),
+            timezone.format(record.updated_at)
+        ),
+    }
+}
+
 /// 处理管理员获取密码
 async fn handle_admin_get_password(
     bot: &Bot,
@@ -284,7 +501,19 @@ async fn handle_admin_get_password(
         return Ok(());
     }
 
-    // 管理员获取临时密码
+    // 管理员为每个启用临时密码的门锁获取临时密码
+    let locks: Vec<Lock> = state
+        .lock_service
+        .list()
+        .await?
+        .into_iter()
+        .filter(|lock| lock.supports(AuthType::Temp))
+        .collect();
+    if locks.is_empty() {
+        bot.send_message(msg.chat.id, "❌ 没有启用临时密码的门锁").await?;
+        return Ok(());
+    }
+
     let password_request = PasswordRequest {
         admin_password: admin.password.clone().unwrap_or_default(),
         auth_type: AuthType::Temp,
@@ -299,18 +528,29 @@ async fn handle_admin_get_password(
     };
 
     let mut password_service = state.password_service.write().await;
-    match password_service.generate_password(&password_request, &state.config) {
-        Ok(result) => {
+    let generated: Result<Vec<LockPassword>> = locks
+        .into_iter()
+        .map(|lock| {
+            password_service
+                .generate_password(&password_request, &lock)
+                .map(|result| LockPassword { lock, result })
+        })
+        .collect();
+    drop(password_service);
+
+    match generated {
+        Ok(passwords) => {
+            let first = &passwords[0].result;
             let message = format!(
                 "🔑 管理员临时密码\n\n\
-                 密码：<code>{}</code>\n\
+                 {}\n\
                  过期时间：{}\n\
                  类型：{}\n\n\
                  💡 {}",
-                result.password,
-                result.expire_time,
-                result.password_type,
-                result.message
+                format_lock_passwords(&passwords),
+                format_earliest_expiry(&passwords, &state.config().lock_timezone),
+                first.password_type,
+                first.message
             );
 
             bot.send_message(msg.chat.id, message)
@@ -326,6 +566,94 @@ async fn handle_admin_get_password(
     Ok(())
 }
 
+/// 单个门锁的密码
+#[derive(Debug, Clone)]
+pub struct LockPassword {
+    /// 门锁
+    pub lock: Lock,
+    /// 生成结果
+    pub result: PasswordResult,
+}
+
+/// 格式化各门锁的密码（HTML），只有一个门锁时不显示门锁名称
+pub fn format_lock_passwords(passwords: &[LockPassword]) -> String {
+    let entries: Vec<(&str, &str)> = passwords
+        .iter()
+        .map(|p| (p.lock.name.as_str(), p.result.password.as_str()))
+        .collect();
+    format_password_entries(&entries)
+}
+
+/// 格式化（门锁名称，密码）列表（HTML）
+pub fn format_password_entries(entries: &[(&str, &str)]) -> String {
+    if let [(_, password)] = entries {
+        return format!("密码：<code>{}</code>", password);
+    }
+
+    let lines: Vec<String> = entries
+        .iter()
+        .map(|(name, password)| format!("• {}：<code>{}</code>", html::escape(name), password))
+        .collect();
+    format!("密码：\n{}", lines.join("\n"))
+}
+
+/// 各门锁密码中最早的过期时间（不同门锁的时钟偏差不同，过期时间可能略有差异）
+fn format_earliest_expiry(passwords: &[LockPassword], timezone: &LockTimezone) -> String {
+    passwords
+        .iter()
+        .map(|p| p.result.valid_until)
+        .min()
+        .map(|valid_until| timezone.format(valid_until))
+        .unwrap_or_else(|| "未知".to_string())
+}
+
+/// 根据已批准的记录构建密码请求
+fn password_request_for_record(
+    record: &Record,
+    admin_password: String,
+    timezone: &LockTimezone,
+) -> Result<PasswordRequest> {
+    let (hours, minutes, end_year, end_month, end_day, end_hour) = match record.auth_type {
+        AuthType::Limited => {
+            // 从开始时间和结束时间计算小时数
+            if let (Some(start), Some(end)) = (record.start_time, record.ended_time) {
+                let duration = end.signed_duration_since(start);
+                let total_minutes = duration.num_minutes();
+                let hours = (total_minutes / 60) as u32;
+                let minutes = (total_minutes % 60) as u32;
+                (Some(hours), Some(minutes), None, None, None, None)
+            } else {
+                // 默认2小时
+                (Some(2), Some(0), None, None, None, None)
+            }
+        },
+        AuthType::Period => {
+            // 从结束时间提取年月日时 - 需要转换为门锁时区
+            if let Some(end) = record.ended_time {
+                // 将数据库中的UTC时间转换为门锁本地时间，然后提取时间组件
+                let end_local = timezone.to_local(end);
+                (None, None, Some(end_local.year() as u32), Some(end_local.month()), Some(end_local.day()), Some(end_local.hour()))
+            } else {
+                return Err(crate::error::AppError::business("周期密码缺少结束时间"));
+            }
+        },
+        _ => (None, None, None, None, None, None)
+    };
+
+    Ok(PasswordRequest {
+        admin_password,
+        auth_type: record.auth_type,
+        times: record.times.map(|t| t as u32),
+        hours,
+        minutes,
+        end_year,
+        end_month,
+        end_day,
+        end_hour,
+        start_time: record.start_time,
+    })
+}
+
 /// 为记录生成密码
 async fn generate_password_for_record(
     bot: &Bot,
@@ -334,34 +662,23 @@ async fn generate_password_for_record(
     state: &BotState,
 ) -> Result<()> {
     // 首先检查授权是否已过期 - 更详细的检查
-    if !record.is_active() {
+    if !record.is_active_at(state.clock.now()) {
         let expire_info = if let Some(ended_time) = record.ended_time {
-            let beijing_tz = chrono::FixedOffset::east_opt(8 * 3600).unwrap();
-            let ended_time_beijing = ended_time.with_timezone(&beijing_tz);
-            let current_time_beijing = chrono::Utc::now().with_timezone(&beijing_tz);
+            let timezone = &state.config().lock_timezone;
             format!("授权已于 {} 过期（当前时间：{}）",
-                   ended_time_beijing.format("%Y-%m-%d %H:%M:%S"),
-                   current_time_beijing.format("%Y-%m-%d %H:%M:%S"))
+                   timezone.format(ended_time),
+                   timezone.format(state.clock.now()))
         } else {
             "授权已过期".to_string()
         };
         return Err(crate::error::AppError::business(&expire_info));
     }
 
-    let user_service = state.user_service.read().await;
-    let admin = user_service.get_admin_info_by_unique_id(record.inviter).await?
-        .ok_or_else(|| crate::error::AppError::business("管理员信息不存在"))?;
-
-    let admin_password = admin.password
-        .ok_or_else(|| crate::error::AppError::business("管理员未设置密码"))?;
-    drop(user_service); // 释放用户服务锁
-
-    // 统一处理所有授权类型的限制检查和密码生成
-    let mut password_service = state.password_service.write().await;
-    
+    // 统一处理所有授权类型的限制检查
     match record.auth_type {
         AuthType::LongtimeTemp => {
             // 长期临时密码：检查5分钟限制
+            let mut password_service = state.password_service.write().await;
             if !password_service.can_generate_longtime_temp(record.vis_id) {
                 drop(password_service); // 释放锁
                 bot.send_message(
@@ -380,9 +697,21 @@ async fn generate_password_for_record(
         }
         _ => {
             // 其他类型：检查数据库中是否已生成过密码，如果是则阻止重复生成
-            if let Some(existing_password) = password_service.has_generated_password(state.database.pool(), record.unique_id).await? {
-                drop(password_service); // 释放锁
-                
+            let issued = IssuedPasswordRepository::find_by_record(state.database.pool(), record.unique_id).await?;
+            if !issued.is_empty() {
+                let locks = state.lock_service.locks_for_record(record.unique_id).await?;
+                let entries: Vec<(&str, &str)> = issued
+                    .iter()
+                    .map(|issued| {
+                        let lock_name = locks
+                            .iter()
+                            .find(|lock| Some(lock.unique_id) == issued.lock_id)
+                            .map(|lock| lock.name.as_str())
+                            .unwrap_or("未知门锁");
+                        (lock_name, issued.password.as_str())
+                    })
+                    .collect();
+
                 // 发送阻止消息，不再返回密码
                 let type_description = match record.auth_type {
                     AuthType::Limited => "时效密码",
@@ -401,10 +730,10 @@ async fn generate_password_for_record(
                      • 查看之前收到的密码消息\n\
                      • 如果密码丢失，请联系管理员\n\
                      • 如需新密码，请重新申请授权\n\n\
-                     🔐 当前密码：<code>{}</code>\n\
+                     🔐 当前{}\n\
                      ⚠️ 请妥善保管，避免重复获取",
                     type_description,
-                    existing_password
+                    format_password_entries(&entries)
                 );
 
                 bot.send_message(chat_id, message)
@@ -415,51 +744,8 @@ async fn generate_password_for_record(
         }
     }
 
-    // 根据授权类型构建密码请求参数
-    let (hours, minutes, end_year, end_month, end_day, end_hour) = match record.auth_type {
-        AuthType::Limited => {
-            // 从开始时间和结束时间计算小时数
-            if let (Some(start), Some(end)) = (record.start_time, record.ended_time) {
-                let duration = end.signed_duration_since(start);
-                let total_minutes = duration.num_minutes();
-                let hours = (total_minutes / 60) as u32;
-                let minutes = (total_minutes % 60) as u32;
-                (Some(hours), Some(minutes), None, None, None, None)
-            } else {
-                // 默认2小时
-                (Some(2), Some(0), None, None, None, None)
-            }
-        },
-        AuthType::Period => {
-            // 从结束时间提取年月日时 - 需要转换为UTC+8时区
-            if let Some(end) = record.ended_time {
-                // 将数据库中的UTC时间转换为UTC+8时区，然后提取时间组件
-                let beijing_tz = chrono::FixedOffset::east_opt(8 * 3600).unwrap();
-                let end_beijing = end.with_timezone(&beijing_tz);
-                (None, None, Some(end_beijing.year() as u32), Some(end_beijing.month()), Some(end_beijing.day()), Some(end_beijing.hour()))
-            } else {
-                return Err(crate::error::AppError::business("周期密码缺少结束时间"));
-            }
-        },
-        _ => (None, None, None, None, None, None)
-    };
-
-    // 构建密码请求
-    let password_request = PasswordRequest {
-        admin_password,
-        auth_type: record.auth_type,
-        times: record.times.map(|t| t as u32),
-        hours,
-        minutes,
-        end_year,
-        end_month,
-        end_day,
-        end_hour,
-        start_time: record.start_time,
-    };
-
-    // 生成密码 (使用已获取的password_service锁)
-    let result = password_service.generate_password(&password_request, &state.config)?;
+    let passwords = issue_lock_passwords(record, state).await?;
+    let result = &passwords[0].result;
 
     // 格式化消息
     let type_description = match record.auth_type {
@@ -476,7 +762,7 @@ async fn generate_password_for_record(
              • 可用次数：{} 次\n\
              • 每次使用会消耗一次机会\n\
              • 剩余次数请注意合理使用",
-            record.times.unwrap_or(1)
+            result.remaining_uses.unwrap_or(1)
         ),
         AuthType::Limited => format!(
             "⏰ 使用说明：\n\
@@ -506,7 +792,7 @@ async fn generate_password_for_record(
 
     let message = format!(
         "🔑 访问密码生成成功！\n\n\
-         密码：<code>{}</code>\n\
+         {}\n\
          类型：{}\n\
          过期时间：{}\n\n\
          {}\n\n\
@@ -515,9 +801,9 @@ async fn generate_password_for_record(
          • 建议复制保存，避免重复获取\n\
          • 密码过期后请及时重新获取\n\
          • 如遇问题请联系管理员",
-        result.password,
+        format_lock_passwords(&passwords),
         type_description,
-        result.expire_time,
+        format_earliest_expiry(&passwords, &state.config().lock_timezone),
         usage_tips
     );
 
@@ -525,98 +811,197 @@ async fn generate_password_for_record(
         .parse_mode(teloxide::types::ParseMode::Html)
         .await?;
 
-    // 将密码添加到记录中
-    let mut tx = state.database.begin_transaction().await?;
-    RecordRepository::add_password(&mut tx, record.unique_id, &result.password).await?;
-    tx.commit().await?;
-
     Ok(())
 }
 
-/// 生成并发送密码（用于批准后立即推送）
+/// 为记录关联的每个门锁生成密码（用于批准后立即推送）
 pub async fn generate_and_send_password(
     _bot: &Bot,
     _chat_id: ChatId,
     record: &Record,
     state: &BotState,
-) -> Result<String> {
+) -> Result<Vec<LockPassword>> {
     // 首先检查授权是否已过期
-    if !record.is_active() {
+    if !record.is_active_at(state.clock.now()) {
         let expire_info = if let Some(ended_time) = record.ended_time {
-            let beijing_tz = chrono::FixedOffset::east_opt(8 * 3600).unwrap();
-            let ended_time_beijing = ended_time.with_timezone(&beijing_tz);
-            let current_time_beijing = chrono::Utc::now().with_timezone(&beijing_tz);
+            let timezone = &state.config().lock_timezone;
             format!("授权已于 {} 过期（当前时间：{}）",
-                   ended_time_beijing.format("%Y-%m-%d %H:%M:%S"),
-                   current_time_beijing.format("%Y-%m-%d %H:%M:%S"))
+                   timezone.format(ended_time),
+                   timezone.format(state.clock.now()))
         } else {
             "授权已过期".to_string()
         };
         return Err(crate::error::AppError::business(&expire_info));
     }
 
+    issue_lock_passwords(record, state).await
+}
+
+/// 按记录关联的门锁逐个生成密码并保存发放记录
+///
+/// 所有门锁的密码都生成成功后才保存，任一门锁失败时不发放任何密码。
+async fn issue_lock_passwords(record: &Record, state: &BotState) -> Result<Vec<LockPassword>> {
     let user_service = state.user_service.read().await;
     let admin = user_service.get_admin_info_by_unique_id(record.inviter).await?
-        .ok_or_else(|| crate::error::AppError::business("管理员信息不存在"))?;
+        .ok_or_else(|| AppError::business("管理员信息不存在"))?;
 
     let admin_password = admin.password
-        .ok_or_else(|| crate::error::AppError::business("管理员未设置密码"))?;
+        .ok_or_else(|| AppError::business("管理员未设置密码"))?;
     drop(user_service); // 释放用户服务锁
 
-    // 根据授权类型构建密码请求参数
-    let (hours, minutes, end_year, end_month, end_day, end_hour) = match record.auth_type {
-        AuthType::Limited => {
-            // 从开始时间和结束时间计算小时数
-            if let (Some(start), Some(end)) = (record.start_time, record.ended_time) {
-                let duration = end.signed_duration_since(start);
-                let total_minutes = duration.num_minutes();
-                let hours = (total_minutes / 60) as u32;
-                let minutes = (total_minutes % 60) as u32;
-                (Some(hours), Some(minutes), None, None, None, None)
-            } else {
-                // 默认2小时
-                (Some(2), Some(0), None, None, None, None)
-            }
-        },
-        AuthType::Period => {
-            // 从结束时间提取年月日时 - 需要转换为UTC+8时区
-            if let Some(end) = record.ended_time {
-                // 将数据库中的UTC时间转换为UTC+8时区，然后提取时间组件
-                let beijing_tz = chrono::FixedOffset::east_opt(8 * 3600).unwrap();
-                let end_beijing = end.with_timezone(&beijing_tz);
-                (None, None, Some(end_beijing.year() as u32), Some(end_beijing.month()), Some(end_beijing.day()), Some(end_beijing.hour()))
-            } else {
-                return Err(crate::error::AppError::business("周期密码缺少结束时间"));
-            }
-        },
-        _ => (None, None, None, None, None, None)
-    };
-
-    // 构建密码请求
-    let password_request = PasswordRequest {
-        admin_password,
-        auth_type: record.auth_type,
-        times: record.times.map(|t| t as u32),
-        hours,
-        minutes,
-        end_year,
-        end_month,
-        end_day,
-        end_hour,
-        start_time: record.start_time,
-    };
+    let locks = state.lock_service.locks_for_record(record.unique_id).await?;
+    if locks.is_empty() {
+        return Err(AppError::business("授权未关联任何门锁"));
+    }
 
-    // 生成密码
+    // 周期密码按各门锁所在时区拆分结束时间
     let mut password_service = state.password_service.write().await;
-    let result = password_service.generate_password(&password_request, &state.config)?;
+    let mut passwords = Vec::with_capacity(locks.len());
+    for lock in locks {
+        if !lock.supports(record.auth_type) {
+            return Err(AppError::business(format!(
+                "门锁「{}」未启用{}",
+                lock.name,
+                record.auth_type.description()
+            )));
+        }
+        let password_request = password_request_for_record(record, admin_password.clone(), &lock.timezone)?;
+        let result = password_service.generate_password(&password_request, &lock)?;
+        passwords.push(LockPassword { lock, result });
+    }
     drop(password_service); // 释放密码服务锁
 
     // 将密码添加到记录中
     let mut tx = state.database.begin_transaction().await?;
-    RecordRepository::add_password(&mut tx, record.unique_id, &result.password).await?;
+    for password in &passwords {
+        IssuedPasswordRepository::create(
+            &mut tx,
+            &IssuedPassword::from_result(record.unique_id, &password.result, &password.lock),
+        )
+        .await?;
+    }
     tx.commit().await?;
 
-    Ok(result.password)
+    Ok(passwords)
+}
+
+/// 按邀请码的预设授权自动批准请求
+///
+/// 预设在创建邀请码时已校验，这里再次校验（如结束时间可能已过）；
+/// 校验失败或批准未成功时返回false，由调用方转为人工审批。
+#[allow(clippy::too_many_arguments)]
+async fn auto_approve_with_preset(
+    bot: &Bot,
+    msg: &Message,
+    admin: &crate::types::Admin,
+    visitor: &teloxide::types::User,
+    invite: &crate::types::Invite,
+    preset: &crate::types::AuthPreset,
+    record_id: i64,
+    state: &BotState,
+) -> Result<bool> {
+    if let Err(e) = state.password_service.read().await.validate_preset(preset, &state.config()) {
+        log::warn!("邀请码 #{} 的预设授权已失效，转为人工审批: {}", invite.unique_id, e);
+        return Ok(false);
+    }
+
+    // 预设未指定门锁时，批准时关联默认门锁
+    let approved = match state
+        .record_service
+        .approve_with_preset(invite.unique_id, record_id, preset)
+        .await
+    {
+        Ok(approved) => approved,
+        Err(e) => {
+            log::warn!("邀请码 #{} 的预设授权无法批准，转为人工审批: {}", invite.unique_id, e);
+            return Ok(false);
+        }
+    };
+    if !approved {
+        return Ok(false);
+    }
+
+    let record = match RecordRepository::find_by_id(state.database.pool(), record_id).await? {
+        Some(record) => record,
+        None => return Ok(false),
+    };
+
+    let ended = record
+        .ended_time
+        .map(|ended_time| state.config().lock_timezone.format(ended_time))
+        .unwrap_or_else(|| "未设置".to_string());
+
+    // 长期单次密码由访客在需要时自行获取
+    let visitor_message = if preset.auth_type == AuthType::LongtimeTemp {
+        format!(
+            "✅ 您的授权已自动批准！\n\n\
+             📋 授权类型：{}\n\
+             📅 有效期至：{}\n\
+             🆔 批准ID：{}\n\n\
+             💡 需要开门时请使用 /getpassword 获取密码",
+            preset.auth_type.description(),
+            ended,
+            record_id
+        )
+    } else {
+        match generate_and_send_password(bot, msg.chat.id, &record, state).await {
+            Ok(passwords) => format!(
+                "✅ 您的授权已自动批准！\n\n\
+                 📋 授权类型：{}\n\
+                 📅 有效期至：{}\n\
+                 🆔 批准ID：{}\n\
+                 🔑 {}\n\n\
+                 💡 密码已自动生成，请妥善保管",
+                preset.auth_type.description(),
+                ended,
+                record_id,
+                format_lock_passwords(&passwords)
+            ),
+            Err(e) => {
+                log::error!("为访客 {} 生成预设授权密码失败: {}", record.vis_id, e);
+                format!(
+                    "✅ 您的授权已自动批准！\n\n\
+                     📋 授权类型：{}\n\
+                     📅 有效期至：{}\n\
+                     🆔 批准ID：{}\n\n\
+                     ❗ 密码生成遇到问题，请使用 /getpassword 获取密码\n\
+                     💡 如多次获取失败，请联系管理员",
+                    preset.auth_type.description(),
+                    ended,
+                    record_id
+                )
+            }
+        }
+    };
+
+    bot.send_message(msg.chat.id, visitor_message)
+        .parse_mode(teloxide::types::ParseMode::Html)
+        .await?;
+
+    // 事后通知管理员
+    let admin_message = format!(
+        "🤖 访客已通过邀请码自动批准\n\n\
+         👤 访客：{}\n\
+         🆔 用户ID：{}\n\
+         🎫 邀请码：{}\n\
+         📋 授权：{}\n\
+         📅 有效期至：{}\n\
+         📝 记录ID：{}\n\n\
+         💡 如需撤销，请使用 /revoke record {}",
+        get_user_display_name(visitor),
+        visitor.id.0,
+        invite.display_name(),
+        preset.description(&state.config().lock_timezone),
+        ended,
+        record_id,
+        record_id
+    );
+
+    if let Err(e) = bot.send_message(ChatId(admin.id), admin_message).await {
+        log::warn!("发送自动批准通知给管理员 {} 失败: {}", admin.id, e);
+    }
+
+    Ok(true)
 }
 
 /// 发送审批请求给管理员
@@ -624,52 +1009,62 @@ async fn send_approval_request_to_admin(
     bot: &Bot,
     admin: &crate::types::Admin,
     visitor: &teloxide::types::User,
+    invite: &crate::types::Invite,
     record_id: i64,
-    _state: &BotState,
+    state: &BotState,
 ) -> Result<()> {
     let visitor_name = get_user_display_name(visitor);
-    let current_time = Utc::now().format("%Y-%m-%d %H:%M:%S");
-
-    // 创建内联键盘
-    let keyboard = InlineKeyboardMarkup::new(vec![
-        vec![
-            InlineKeyboardButton::callback(
-                "✅ 批准",
-                CallbackData::with_data("approve", &record_id.to_string()).to_callback_string().unwrap()
-            ),
-            InlineKeyboardButton::callback(
-                "❌ 拒绝",
-                CallbackData::with_data("reject", &record_id.to_string()).to_callback_string().unwrap()
-            ),
-        ]
-    ]);
+    let current_time = state.config().lock_timezone.format(state.clock.now());
 
     let message = format!(
         "📋 新的访客授权请求\n\n\
          👤 访客：{}\n\
          🆔 用户ID：{}\n\
          🕐 申请时间：{}\n\
+         🎫 邀请码：{}\n\
          📝 记录ID：{}\n\n\
          ✅ 请您仔细核验访客身份后选择批准或拒绝",
         visitor_name,
         visitor.id.0,
         current_time,
+        invite.display_name(),
         record_id
     );
 
     // 发送给管理员
     let admin_chat_id = ChatId(admin.id);
-    bot.send_message(admin_chat_id, message)
-        .reply_markup(keyboard)
+    let sent = bot.send_message(admin_chat_id, message)
+        .reply_markup(approval_keyboard(record_id))
         .await
         .map_err(|e| {
             log::warn!("发送管理员通知失败: {}", e);
             e
         })?;
 
+    // 记录审批消息，便于他人处理后同步更新
+    let mut tx = state.database.begin_transaction().await?;
+    ApprovalRepository::create_message(&mut tx, &ApprovalMessage::new(record_id, admin.id, sent.id.0, false)).await?;
+    tx.commit().await?;
+
     Ok(())
 }
 
+/// 创建批准/拒绝审批键盘
+pub fn approval_keyboard(record_id: i64) -> InlineKeyboardMarkup {
+    InlineKeyboardMarkup::new(vec![
+        vec![
+            InlineKeyboardButton::callback(
+                "✅ 批准",
+                CallbackData::with_data("approve", &record_id.to_string()).to_callback_string().unwrap()
+            ),
+            InlineKeyboardButton::callback(
+                "❌ 拒绝",
+                CallbackData::with_data("reject", &record_id.to_string()).to_callback_string().unwrap()
+            ),
+        ]
+    ])
+}
+
 /// 处理授权批准回调
 pub async fn handle_approve_callback(
     bot: Bot,
@@ -691,21 +1086,21 @@ pub async fn handle_approve_callback(
         return Ok(());
     }
 
-    // 检查管理员是否设置密码
-    let admin_info = user_service.get_admin_info(admin_id).await?
-        .ok_or_else(|| crate::error::AppError::business("管理员信息不存在"))?;
+    // 获取记录信息
+    let record = RecordRepository::find_by_id(state.database.pool(), record_id).await?
+        .ok_or_else(|| crate::error::AppError::business("授权记录不存在"))?;
 
-    if !user_service.admin_has_password(admin_info.unique_id).await? {
+    // 密码由邀请管理员的管理密码生成；升级后由其他管理员审批时同样需要
+    if !user_service.admin_has_password(record.inviter).await? {
+        let is_inviter = user_service.get_admin_info(admin_id).await?
+            .is_some_and(|admin_info| admin_info.unique_id == record.inviter);
+        let text = if is_inviter { "❌ 请先设置管理密码" } else { "❌ 邀请管理员尚未设置管理密码" };
         bot.answer_callback_query(callback.id)
-            .text("❌ 请先设置管理密码")
+            .text(text)
             .await?;
         return Ok(());
     }
 
-    // 获取记录信息
-    let record = RecordRepository::find_by_id(state.database.pool(), record_id).await?
-        .ok_or_else(|| crate::error::AppError::business("授权记录不存在"))?;
-
     if record.status != AuthStatus::Pending {
         bot.answer_callback_query(callback.id)
             .text("❌ 该请求已被处理")
@@ -713,10 +1108,147 @@ pub async fn handle_approve_callback(
         return Ok(());
     }
 
-    // 创建授权类型选择键盘
-    let keyboard = create_auth_type_keyboard(record_id);
+    drop(user_service);
 
-    let message = format!(
+    let Some(message_to_edit) = callback.message else {
+        bot.answer_callback_query(callback.id).await?;
+        return Ok(());
+    };
+
+    let locks = state.lock_service.list().await?;
+    match locks.as_slice() {
+        [] => {
+            bot.answer_callback_query(callback.id)
+                .text("❌ 尚未配置门锁，请联系超级管理员")
+                .await?;
+            return Ok(());
+        }
+        [lock] => {
+            // 只有一个门锁时无需选择
+            state.lock_service.link_record(record_id, &[lock.unique_id]).await?;
+            show_auth_type_selection(&bot, message_to_edit.chat.id, message_to_edit.id, record_id, &locks).await?;
+        }
+        _ => {
+            let selected: Vec<i64> = state
+                .lock_service
+                .locks_for_record(record_id)
+                .await?
+                .iter()
+                .map(|lock| lock.unique_id)
+                .collect();
+            bot.edit_message_text(message_to_edit.chat.id, message_to_edit.id, LOCK_SELECTION_TEXT)
+                .reply_markup(lock_selection_keyboard(record_id, &locks, &selected))
+                .await?;
+        }
+    }
+
+    bot.answer_callback_query(callback.id).await?;
+    Ok(())
+}
+
+/// 门锁选择提示
+const LOCK_SELECTION_TEXT: &str = "🚪 请选择授权的门锁（可多选）：\n\n\
+     点击门锁名称勾选或取消，选好后点击「下一步」选择授权类型";
+
+/// 处理门锁勾选回调
+pub async fn handle_toggle_lock_callback(
+    bot: Bot,
+    callback: CallbackQuery,
+    record_id: i64,
+    lock_id: i64,
+    state: BotState,
+) -> Result<()> {
+    let admin_id = callback.from.id.0 as i64;
+    if !state.user_service.read().await.is_admin(admin_id).await? {
+        bot.answer_callback_query(callback.id)
+            .text("❌ 权限不足")
+            .await?;
+        return Ok(());
+    }
+
+    if state.lock_service.toggle_record_lock(record_id, lock_id).await?.is_none() {
+        bot.answer_callback_query(callback.id)
+            .text("❌ 该请求已被处理")
+            .await?;
+        return Ok(());
+    }
+
+    let locks = state.lock_service.list().await?;
+    let selected: Vec<i64> = state
+        .lock_service
+        .locks_for_record(record_id)
+        .await?
+        .iter()
+        .map(|lock| lock.unique_id)
+        .collect();
+
+    if let Some(message) = callback.message {
+        bot.edit_message_reply_markup(message.chat.id, message.id)
+            .reply_markup(lock_selection_keyboard(record_id, &locks, &selected))
+            .await?;
+    }
+
+    bot.answer_callback_query(callback.id).await?;
+    Ok(())
+}
+
+/// 处理门锁选择完成回调，进入授权类型选择
+pub async fn handle_select_auth_callback(
+    bot: Bot,
+    callback: CallbackQuery,
+    record_id: i64,
+    state: BotState,
+) -> Result<()> {
+    let admin_id = callback.from.id.0 as i64;
+    if !state.user_service.read().await.is_admin(admin_id).await? {
+        bot.answer_callback_query(callback.id)
+            .text("❌ 权限不足")
+            .await?;
+        return Ok(());
+    }
+
+    let pending = RecordRepository::find_by_id(state.database.pool(), record_id)
+        .await?
+        .is_some_and(|record| record.status == AuthStatus::Pending);
+    if !pending {
+        bot.answer_callback_query(callback.id)
+            .text("❌ 该请求已被处理")
+            .await?;
+        return Ok(());
+    }
+
+    let locks = state.lock_service.locks_for_record(record_id).await?;
+    let warning = if locks.is_empty() {
+        Some("⚠️ 请至少选择一个门锁")
+    } else if Lock::common_auth_types(&locks).is_empty() {
+        Some("⚠️ 所选门锁没有共同启用的授权类型")
+    } else {
+        None
+    };
+    if let Some(warning) = warning {
+        bot.answer_callback_query(callback.id)
+            .text(warning)
+            .await?;
+        return Ok(());
+    }
+
+    if let Some(message) = callback.message {
+        show_auth_type_selection(&bot, message.chat.id, message.id, record_id, &locks).await?;
+    }
+
+    bot.answer_callback_query(callback.id).await?;
+    Ok(())
+}
+
+/// 将审批消息编辑为授权类型选择，只提供所有门锁都启用的类型
+async fn show_auth_type_selection(
+    bot: &Bot,
+    chat_id: ChatId,
+    message_id: MessageId,
+    record_id: i64,
+    locks: &[Lock],
+) -> Result<()> {
+    let mut message = String::from(
         "✅ 请选择授权类型：\n\n\
          📋 授权类型说明：\n\
          • 时效密码：指定有效时长\n\
@@ -725,15 +1257,14 @@ pub async fn handle_approve_callback(
          • 临时密码：10分钟有效期\n\
          • 长期临时密码：管理员指定有效期"
     );
-
-    // 编辑原消息
-    if let Some(message_to_edit) = callback.message {
-        bot.edit_message_text(message_to_edit.chat.id, message_to_edit.id, message)
-            .reply_markup(keyboard)
-            .await?;
+    if locks.len() > 1 {
+        let names: Vec<&str> = locks.iter().map(|lock| lock.name.as_str()).collect();
+        message.push_str(&format!("\n\n🚪 门锁：{}", names.join("、")));
     }
 
-    bot.answer_callback_query(callback.id).await?;
+    bot.edit_message_text(chat_id, message_id, message)
+        .reply_markup(create_auth_type_keyboard(record_id, &Lock::common_auth_types(locks)))
+        .await?;
     Ok(())
 }
 
@@ -749,10 +1280,23 @@ pub async fn handle_reject_callback(
 
     log::info!("管理员 {} 拒绝授权请求 {}", admin_id, record_id);
 
-    // 更新记录状态为撤销
-    let mut tx = state.database.begin_transaction().await?;
-    RecordRepository::update_status(&mut tx, record_id, AuthStatus::Revoked).await?;
-    tx.commit().await?;
+    // 检查权限
+    let user_service = state.user_service.read().await;
+    if !user_service.is_admin(admin_id).await? {
+        bot.answer_callback_query(callback.id)
+            .text("❌ 权限不足")
+            .await?;
+        return Ok(());
+    }
+    drop(user_service);
+
+    // 更新记录状态为撤销（仅待审批的请求可以被拒绝）
+    if !state.record_service.reject(admin_id, record_id).await? {
+        bot.answer_callback_query(callback.id)
+            .text("⚠️ 该请求已被处理")
+            .await?;
+        return Ok(());
+    }
 
     // 获取记录信息通知访客
     if let Some(record) = RecordRepository::find_by_id(state.database.pool(), record_id).await? {
@@ -763,12 +1307,16 @@ pub async fn handle_reject_callback(
         ).await.ok(); // 忽略发送失败
     }
 
+    // 同步更新其他管理员的审批消息
+    let acted_on = callback.message.as_ref().map(|message| (message.chat.id, message.id));
+    close_approval_messages(&bot, &state, record_id, &admin, "已拒绝", acted_on).await?;
+
     // 编辑管理员消息
     if let Some(message) = callback.message {
         let updated_message = format!(
             "❌ 授权请求已拒绝\n\n📝 记录ID：{}\n⏰ 处理时间：{}",
             record_id,
-            Utc::now().format("%Y-%m-%d %H:%M:%S")
+            state.config().lock_timezone.format(state.clock.now())
         );
 
         bot.edit_message_text(message.chat.id, message.id, updated_message)
@@ -783,41 +1331,63 @@ pub async fn handle_reject_callback(
 }
 
 /// 创建授权类型选择键盘
-pub fn create_auth_type_keyboard(record_id: i64) -> InlineKeyboardMarkup {
-    InlineKeyboardMarkup::new(vec![
+pub fn create_auth_type_keyboard(record_id: i64, auth_types: &[AuthType]) -> InlineKeyboardMarkup {
+    let buttons: Vec<InlineKeyboardButton> = AuthType::ALL
+        .into_iter()
+        .filter(|auth_type| auth_types.contains(auth_type))
+        .map(|auth_type| {
+            let (label, action) = match auth_type {
+                AuthType::Limited => ("⏰ 时效密码", "auth_limited"),
+                AuthType::Period => ("📅 指定过期时间", "auth_period"),
+                AuthType::Times => ("🔢 次数密码", "auth_times"),
+                AuthType::Temp => ("⚡ 临时密码", "auth_temp"),
+                AuthType::LongtimeTemp => ("🔄 长期临时密码", "auth_longtime_temp"),
+            };
+            InlineKeyboardButton::callback(
+                label,
+                CallbackData::with_data(action, &record_id.to_string()).to_callback_string().unwrap()
+            )
+        })
+        .collect();
+
+    let mut rows = vec![
         vec![
             InlineKeyboardButton::callback(
                 "返回上一步",
                 CallbackData::with_data("back_to_approve", &record_id.to_string()).to_callback_string().unwrap()
             ),
         ],
-        vec![
-            InlineKeyboardButton::callback(
-                "⏰ 时效密码",
-                CallbackData::with_data("auth_limited", &record_id.to_string()).to_callback_string().unwrap()
-            ),
-            InlineKeyboardButton::callback(
-                "📅 指定过期时间",
-                CallbackData::with_data("auth_period", &record_id.to_string()).to_callback_string().unwrap()
-            ),
-        ],
-        vec![
-            InlineKeyboardButton::callback(
-                "🔢 次数密码",
-                CallbackData::with_data("auth_times", &record_id.to_string()).to_callback_string().unwrap()
-            ),
-            InlineKeyboardButton::callback(
-                "⚡ 临时密码",
-                CallbackData::with_data("auth_temp", &record_id.to_string()).to_callback_string().unwrap()
-            ),
-        ],
-        vec![
-            InlineKeyboardButton::callback(
-                "🔄 长期临时密码",
-                CallbackData::with_data("auth_longtime_temp", &record_id.to_string()).to_callback_string().unwrap()
-            ),
-        ],
-    ])
+    ];
+    rows.extend(buttons.chunks(2).map(|row| row.to_vec()));
+    InlineKeyboardMarkup::new(rows)
+}
+
+/// 创建门锁选择键盘，已勾选的门锁带 ✅ 标记
+pub fn lock_selection_keyboard(record_id: i64, locks: &[Lock], selected: &[i64]) -> InlineKeyboardMarkup {
+    let mut rows: Vec<Vec<InlineKeyboardButton>> = locks
+        .iter()
+        .map(|lock| {
+            let mark = if selected.contains(&lock.unique_id) { "✅" } else { "⬜" };
+            vec![InlineKeyboardButton::callback(
+                format!("{} {}", mark, lock.name),
+                CallbackData::with_data("toggle_lock", &format!("{}:{}", record_id, lock.unique_id))
+                    .to_callback_string()
+                    .unwrap(),
+            )]
+        })
+        .collect();
+
+    rows.push(vec![
+        InlineKeyboardButton::callback(
+            "返回上一步",
+            CallbackData::with_data("back_to_approve", &record_id.to_string()).to_callback_string().unwrap()
+        ),
+        InlineKeyboardButton::callback(
+            "下一步 ➡️",
+            CallbackData::with_data("select_auth", &record_id.to_string()).to_callback_string().unwrap()
+        ),
+    ]);
+    InlineKeyboardMarkup::new(rows)
 }
 
 #[cfg(test)]
@@ -838,4 +1408,93 @@ mod tests {
             assert_eq!(auth_type.description(), *expected);
         }
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn test_auth_type_keyboard_only_offers_enabled_types() {
+        let row_sizes = |keyboard: InlineKeyboardMarkup| {
+            keyboard.inline_keyboard.iter().map(|row| row.len()).collect::<Vec<_>>()
+        };
+
+        assert_eq!(row_sizes(create_auth_type_keyboard(1, &AuthType::ALL)), vec![1, 2, 2, 1]);
+
+        let keyboard = create_auth_type_keyboard(1, &[AuthType::Temp, AuthType::Limited]);
+        assert_eq!(keyboard.inline_keyboard[1][0].text, "⏰ 时效密码");
+        assert_eq!(keyboard.inline_keyboard[1][1].text, "⚡ 临时密码");
+        assert_eq!(row_sizes(create_auth_type_keyboard(1, &[])), vec![1]);
+    }
+
+    #[test]
+    fn test_lock_selection_keyboard() {
+        let mut gate = Lock::new("大门");
+        gate.unique_id = 1;
+        let mut door = Lock::new("公寓门");
+        door.unique_id = 2;
+
+        let keyboard = lock_selection_keyboard(42, &[gate, door], &[2]);
+        assert_eq!(keyboard.inline_keyboard.len(), 3);
+        assert_eq!(keyboard.inline_keyboard[0][0].text, "⬜ 大门");
+        assert_eq!(keyboard.inline_keyboard[1][0].text, "✅ 公寓门");
+        assert_eq!(keyboard.inline_keyboard[2][1].text, "下一步 ➡️");
+    }
+
+    #[test]
+    fn test_format_password_entries() {
+        assert_eq!(format_password_entries(&[("大门", "1234")]), "密码：<code>1234</code>");
+        assert_eq!(
+            format_password_entries(&[("大门", "1234"), ("<公寓>", "5678")]),
+            "密码：\n• 大门：<code>1234</code>\n• &lt;公寓&gt;：<code>5678</code>"
+        );
+    }
+
+    #[test]
+    fn test_format_status_entry() {
+        use chrono::{Duration, TimeZone};
+
+        let timezone = LockTimezone::default();
+        let now = Utc.with_ymd_and_hms(2024, 1, 1, 4, 0, 0).unwrap();
+
+        let mut record = Record::new(987654321, 1);
+        record.unique_id = 7;
+        record.updated_at = now - Duration::minutes(90);
+        let pending = format_status_entry(&record, &[], &timezone, now);
+        assert!(pending.starts_with("⏳ #7 待审批"));
+        assert!(pending.contains("提交时间：2024-01-01 10:30:00"));
+        assert!(pending.contains("已等待 1小时30分钟"));
+
+        record.status = AuthStatus::Auth;
+        record.auth_type = AuthType::Times;
+        record.times = Some(3);
+        record.start_time = Some(now - Duration::hours(1));
+        record.ended_time = Some(now + Duration::hours(2));
+        let active = format_status_entry(&record, &[], &timezone, now);
+        assert!(active.contains("类型：两小时内次数密码（3 次）"));
+        assert!(active.contains("开始：2024-01-01 11:00:00"));
+        assert!(active.contains("结束：2024-01-01 14:00:00"));
+        assert!(active.contains("授权剩余 2小时0分钟"));
+        assert!(active.contains("/getpassword"));
+
+        let remaining = vec![("大门".to_string(), "1小时30分钟".to_string())];
+        let active = format_status_entry(&record, &remaining, &timezone, now);
+        assert!(active.contains("🔑 大门 密码剩余：1小时30分钟"));
+        assert!(!active.contains("/getpassword"));
+
+        record.ended_time = None;
+        assert!(format_status_entry(&record, &remaining, &timezone, now).contains("结束：长期有效"));
+    }
+
+    #[test]
+    fn test_format_history_entry() {
+        use chrono::TimeZone;
+
+        let timezone = LockTimezone::default();
+        let mut record = Record::new(987654321, 1);
+        record.unique_id = 3;
+        record.status = AuthStatus::Rejected;
+        record.updated_at = Utc.with_ymd_and_hms(2024, 1, 1, 4, 0, 0).unwrap();
+        assert_eq!(format_history_entry(&record, &timezone), "❌ #3 已拒绝 · 2024-01-01 12:00:00");
+
+        record.status = AuthStatus::Expired;
+        record.auth_type = AuthType::Limited;
+        assert_eq!(format_history_entry(&record, &timezone), "⌛ #3 已过期 · 时效密码 · 2024-01-01 12:00:00");
+    }
+}
@@ -20,5 +20,5 @@ pub mod error;
 pub use config::{AppConfig, ConfigManager};
 pub use database::Database;
 pub use error::{Result, AppError};
-pub use types::{UserRole, AuthStatus, AuthType, UserInfo, Admin, Record};
-pub use utils::{UnifiedPasswordGenerator, PasswordType, PasswordResult};
\ No newline at end of file
+pub use types::{UserRole, AuthStatus, AuthType, UserInfo, Admin, AuthPreset, Invite, InviteState, ApprovalMessage, Record, IssuedPassword, AuditAction, AuditEvent};
+pub use utils::{UnifiedPasswordGenerator, PasswordType, PasswordResult, PasswordInspection};
\ No newline at end of file
@@ -39,6 +39,9 @@ async fn main() -> Result<()> {
     log::info!("🗄️  数据库路径: {}", config.database.path);
     log::info!("👥 超级管理员数量: {}", config.super_admin_ids.len());
     log::info!("⏰ 时间偏移: {} 秒", config.time_offset);
+    log::info!("🧹 过期授权清理间隔: {} 秒", config.expiry_sweep_interval_secs);
+    log::info!("⏫ 审批升级等待时间: {} 秒", config.escalation_timeout_secs);
+    log::info!("⏫ 审批升级检查间隔: {} 秒", config.escalation_check_interval_secs);
 
     // 测试网络连接
     log::info!("🔍 测试网络连接...");
@@ -46,7 +49,7 @@ async fn main() -> Result<()> {
 
     // 创建并启动Bot
     log::info!("🤖 初始化Telegram Bot...");
-    let bot = NekoHouseBot::new(config).await?;
+    let bot = NekoHouseBot::new(config).await?.with_config_manager(config_manager);
     
     log::info!("🎯 系统准备就绪，开始监听消息...");
     log::info!("📱 Bot信息: {:?}", bot.state().get_bot_info().await);
@@ -96,7 +99,11 @@ fn print_config_example() {
   "super_admin_ids": [
     1234567890
   ],
-  "time_offset": 3600
+  "time_offset": 3600,
+  "expiry_sweep_interval_secs": 60,
+  "escalation_timeout_secs": 900,
+  "escalation_check_interval_secs": 60,
+  "escalation_admin_ids": []
 }}
 
 配置说明：
@@ -104,6 +111,10 @@ fn print_config_example() {
 - telegram.bot_token: 从 @BotFather 获取的Bot Token
 - super_admin_ids: 超级管理员的Telegram用户ID列表
 - time_offset: 密码生成时间偏移（秒），用于增加安全性
+- expiry_sweep_interval_secs: 过期授权清理间隔（秒，可选，默认60）
+- escalation_timeout_secs: 待审批请求升级前的等待时间（秒，可选，默认900，0表示不升级）
+- escalation_check_interval_secs: 检查待审批请求是否超时的间隔（秒，可选，默认60）
+- escalation_admin_ids: 审批升级的接收人（可选，为空时发送给所有超级管理员）
 
 获取用户ID的方法：
 1. 发送消息给 @userinfobot
@@ -1,6 +1,10 @@
 //! 系统类型定义
 
-use chrono::{DateTime, Utc};
+use crate::error::AppError;
+use crate::utils::{LockTimezone, PasswordResult, PasswordType};
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use base64::Engine;
+use chrono::{DateTime, Datelike, Timelike, Utc};
 use serde::{Deserialize, Serialize};
 use uuid::Uuid;
 
@@ -16,6 +20,12 @@ pub enum UserRole {
 }
 
 /// 授权状态枚举
+///
+/// 合法的状态变更：
+/// - 待审批 → 已授权 / 已拒绝 / 已取消
+/// - 已授权 → 已撤销 / 已过期
+///
+/// 已拒绝、已取消、已撤销、已过期均为终态。
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 #[serde(rename_all = "lowercase")]
 pub enum AuthStatus {
@@ -23,16 +33,35 @@ pub enum AuthStatus {
     Pending,
     /// 已授权
     Auth,
-    /// 已撤销
+    /// 已撤销（授权期间被管理员收回）
     Revoked,
+    /// 已过期（授权自然到期）
+    Expired,
+    /// 已拒绝（审批未通过）
+    Rejected,
+    /// 已取消（待审批请求被撤回）
+    Cancelled,
 }
 
 impl AuthStatus {
+    /// 所有状态，用于统计展示
+    pub const ALL: [AuthStatus; 6] = [
+        AuthStatus::Pending,
+        AuthStatus::Auth,
+        AuthStatus::Rejected,
+        AuthStatus::Cancelled,
+        AuthStatus::Revoked,
+        AuthStatus::Expired,
+    ];
+
     pub fn as_str(&self) -> &'static str {
         match self {
             AuthStatus::Pending => "pending",
             AuthStatus::Auth => "auth",
             AuthStatus::Revoked => "revoked",
+            AuthStatus::Expired => "expired",
+            AuthStatus::Rejected => "rejected",
+            AuthStatus::Cancelled => "cancelled",
         }
     }
 
@@ -41,9 +70,41 @@ impl AuthStatus {
             "pending" => Some(AuthStatus::Pending),
             "auth" => Some(AuthStatus::Auth),
             "revoked" => Some(AuthStatus::Revoked),
+            "expired" => Some(AuthStatus::Expired),
+            "rejected" => Some(AuthStatus::Rejected),
+            "cancelled" => Some(AuthStatus::Cancelled),
             _ => None,
         }
     }
+
+    /// 获取状态的中文描述
+    pub fn description(&self) -> &'static str {
+        match self {
+            AuthStatus::Pending => "待审批",
+            AuthStatus::Auth => "已授权",
+            AuthStatus::Revoked => "已撤销",
+            AuthStatus::Expired => "已过期",
+            AuthStatus::Rejected => "已拒绝",
+            AuthStatus::Cancelled => "已取消",
+        }
+    }
+
+    /// 检查是否允许变更到目标状态
+    pub fn can_transition_to(&self, to: AuthStatus) -> bool {
+        matches!(
+            (self, to),
+            (AuthStatus::Pending, AuthStatus::Auth)
+                | (AuthStatus::Pending, AuthStatus::Rejected)
+                | (AuthStatus::Pending, AuthStatus::Cancelled)
+                | (AuthStatus::Auth, AuthStatus::Revoked)
+                | (AuthStatus::Auth, AuthStatus::Expired)
+        )
+    }
+
+    /// 是否为终态
+    pub fn is_final(&self) -> bool {
+        !matches!(self, AuthStatus::Pending | AuthStatus::Auth)
+    }
 }
 
 /// 授权类型枚举
@@ -63,6 +124,15 @@ pub enum AuthType {
 }
 
 impl AuthType {
+    /// 全部授权类型
+    pub const ALL: [AuthType; 5] = [
+        AuthType::Limited,
+        AuthType::Period,
+        AuthType::Times,
+        AuthType::Temp,
+        AuthType::LongtimeTemp,
+    ];
+
     pub fn as_str(&self) -> &'static str {
         match self {
             AuthType::Limited => "limited",
@@ -94,6 +164,91 @@ impl AuthType {
             AuthType::LongtimeTemp => "长期单次密码",
         }
     }
+
+    /// 解析逗号分隔的授权类型列表，如 `temp,times`
+    pub fn parse_list(s: &str) -> Option<Vec<Self>> {
+        let mut auth_types = Vec::new();
+        for value in s.split(',').map(str::trim).filter(|v| !v.is_empty()) {
+            let auth_type = Self::from_str(value)?;
+            if !auth_types.contains(&auth_type) {
+                auth_types.push(auth_type);
+            }
+        }
+        Some(auth_types)
+    }
+
+    /// 转换为逗号分隔的授权类型列表
+    pub fn join_list(auth_types: &[Self]) -> String {
+        auth_types.iter().map(|t| t.as_str()).collect::<Vec<_>>().join(",")
+    }
+}
+
+/// 默认门锁名称
+pub const DEFAULT_LOCK_NAME: &str = "默认门锁";
+
+/// 门锁表实体
+///
+/// 每个门锁有独立的时钟偏差和时区，访客记录可关联多个门锁，密码按门锁分别生成。
+/// `unique_id` 最小的门锁为默认门锁，首次启动时根据配置文件中的时间偏移和时区创建。
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Lock {
+    /// 数据库唯一ID
+    pub unique_id: i64,
+    /// 名称
+    pub name: String,
+    /// 门锁时钟相对真实时间的偏移（秒）
+    pub time_offset: i64,
+    /// 门锁所在时区
+    pub timezone: LockTimezone,
+    /// 启用的授权类型
+    pub auth_types: Vec<AuthType>,
+    /// 创建时间
+    pub created_at: DateTime<Utc>,
+}
+
+impl Lock {
+    /// 创建启用全部授权类型的门锁
+    pub fn new<S: Into<String>>(name: S) -> Self {
+        Self {
+            unique_id: 0, // 由数据库自动分配
+            name: name.into(),
+            time_offset: 0,
+            timezone: LockTimezone::default(),
+            auth_types: AuthType::ALL.to_vec(),
+            created_at: Utc::now(),
+        }
+    }
+
+    /// 设置时间偏移
+    pub fn with_time_offset(mut self, time_offset: i64) -> Self {
+        self.time_offset = time_offset;
+        self
+    }
+
+    /// 设置时区
+    pub fn with_timezone(mut self, timezone: LockTimezone) -> Self {
+        self.timezone = timezone;
+        self
+    }
+
+    /// 设置启用的授权类型
+    pub fn with_auth_types(mut self, auth_types: Vec<AuthType>) -> Self {
+        self.auth_types = auth_types;
+        self
+    }
+
+    /// 是否启用了指定授权类型
+    pub fn supports(&self, auth_type: AuthType) -> bool {
+        self.auth_types.contains(&auth_type)
+    }
+
+    /// 一组门锁共同启用的授权类型，按 `AuthType::ALL` 的顺序排列
+    pub fn common_auth_types(locks: &[Lock]) -> Vec<AuthType> {
+        AuthType::ALL
+            .into_iter()
+            .filter(|auth_type| locks.iter().all(|lock| lock.supports(*auth_type)))
+            .collect()
+    }
 }
 
 /// 管理员表实体
@@ -105,8 +260,6 @@ pub struct Admin {
     pub id: i64,
     /// 密码（明文，4-10位数字）
     pub password: Option<String>,
-    /// 邀请码（UUID）
-    pub invite_code: Option<String>,
 }
 
 impl Admin {
@@ -115,15 +268,9 @@ impl Admin {
             unique_id: 0, // 由数据库自动分配
             id: telegram_id,
             password: None,
-            invite_code: None,
         }
     }
 
-    /// 生成新的邀请码
-    pub fn generate_invite_code(&mut self) {
-        self.invite_code = Some(Uuid::new_v4().to_string());
-    }
-
     /// 验证密码格式（4-10位数字）
     pub fn validate_password(password: &str) -> bool {
         password.len() >= 4 
@@ -132,6 +279,253 @@ impl Admin {
     }
 }
 
+/// 邀请码状态
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum InviteState {
+    /// 可用
+    Active,
+    /// 已被管理员撤销
+    Revoked,
+    /// 已过期
+    Expired,
+    /// 使用次数已用完
+    Exhausted,
+}
+
+impl InviteState {
+    /// 获取邀请码状态的中文描述
+    pub fn description(&self) -> &'static str {
+        match self {
+            InviteState::Active => "有效",
+            InviteState::Revoked => "已撤销",
+            InviteState::Expired => "已过期",
+            InviteState::Exhausted => "次数已用完",
+        }
+    }
+}
+
+/// 邀请码预设授权
+///
+/// 使用带预设的邀请码申请时，系统按预设参数自动批准，无需管理员逐个审批。
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AuthPreset {
+    /// 授权类型
+    pub auth_type: AuthType,
+    /// 使用次数（次数密码）
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub times: Option<u32>,
+    /// 小时数（时效密码）
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub hours: Option<u32>,
+    /// 分钟数（时效密码，0或30）
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub minutes: Option<u32>,
+    /// 结束时间（指定过期时间密码、长期单次密码）
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub end_time: Option<DateTime<Utc>>,
+    /// 授权的门锁（lock表中的unique_id），为空时使用默认门锁
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub locks: Vec<i64>,
+}
+
+impl AuthPreset {
+    pub fn new(auth_type: AuthType) -> Self {
+        Self {
+            auth_type,
+            times: None,
+            hours: None,
+            minutes: None,
+            end_time: None,
+            locks: Vec::new(),
+        }
+    }
+
+    /// 转换为密码请求（用于参数校验，不包含管理员密码），结束时间按门锁时区拆分
+    pub fn to_password_request(&self, timezone: &LockTimezone) -> PasswordRequest {
+        let end = self.end_time.map(|t| timezone.to_local(t));
+
+        PasswordRequest {
+            admin_password: String::new(),
+            auth_type: self.auth_type,
+            times: self.times,
+            hours: self.hours,
+            minutes: self.minutes,
+            end_year: end.map(|t| t.year() as u32),
+            end_month: end.map(|t| t.month()),
+            end_day: end.map(|t| t.day()),
+            end_hour: end.map(|t| t.hour()),
+            start_time: None,
+        }
+    }
+
+    /// 计算从指定时间开始的授权区间，返回（开始时间，结束时间，使用次数）
+    pub fn authorization_window(
+        &self,
+        now: DateTime<Utc>,
+    ) -> (Option<DateTime<Utc>>, Option<DateTime<Utc>>, Option<i32>) {
+        match self.auth_type {
+            AuthType::Temp => (Some(now), Some(now + chrono::Duration::minutes(10)), None),
+            AuthType::Times => (
+                Some(now),
+                Some(now + chrono::Duration::hours(2)),
+                self.times.map(|t| t as i32),
+            ),
+            AuthType::Limited => {
+                let duration = chrono::Duration::hours(self.hours.unwrap_or(0) as i64)
+                    + chrono::Duration::minutes(self.minutes.unwrap_or(0) as i64);
+                (Some(now), Some(now + duration), None)
+            }
+            AuthType::Period | AuthType::LongtimeTemp => (Some(now), self.end_time, None),
+        }
+    }
+
+    /// 获取预设的中文描述，结束时间按门锁时区显示
+    pub fn description(&self, timezone: &LockTimezone) -> String {
+        let detail = match self.auth_type {
+            AuthType::Temp => "10分钟".to_string(),
+            AuthType::Times => format!("{}次，2小时内有效", self.times.unwrap_or(0)),
+            AuthType::Limited => match self.minutes.unwrap_or(0) {
+                0 => format!("{}小时", self.hours.unwrap_or(0)),
+                minutes => format!("{}小时{}分钟", self.hours.unwrap_or(0), minutes),
+            },
+            AuthType::Period | AuthType::LongtimeTemp => match self.end_time {
+                Some(end) => format!(
+                    "至 {}",
+                    timezone.to_local(end).format("%Y-%m-%d %H:%M")
+                ),
+                None => "未设置结束时间".to_string(),
+            },
+        };
+
+        format!("{}（{}）", self.auth_type.description(), detail)
+    }
+}
+
+/// 邀请码表实体
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Invite {
+    /// 数据库唯一ID
+    pub unique_id: i64,
+    /// 邀请码（UUID）
+    pub code: String,
+    /// 所属管理员（admin表中的unique_id）
+    pub admin_id: i64,
+    /// 名称（便于管理员区分用途）
+    pub label: Option<String>,
+    /// 过期时间，为空表示永不过期
+    pub expires_at: Option<DateTime<Utc>>,
+    /// 最大使用次数，为空表示不限次数
+    pub max_uses: Option<i64>,
+    /// 已使用次数
+    pub use_count: i64,
+    /// 是否已撤销
+    pub revoked: bool,
+    /// 预设授权，为空表示访客申请后需管理员审批
+    pub preset: Option<AuthPreset>,
+    /// 创建时间
+    pub created_at: DateTime<Utc>,
+}
+
+impl Invite {
+    pub fn new(admin_id: i64) -> Self {
+        Self {
+            unique_id: 0, // 由数据库自动分配
+            code: Uuid::new_v4().to_string(),
+            admin_id,
+            label: None,
+            expires_at: None,
+            max_uses: None,
+            use_count: 0,
+            revoked: false,
+            preset: None,
+            created_at: Utc::now(),
+        }
+    }
+
+    /// 获取邀请码当前状态
+    pub fn state(&self) -> InviteState {
+        if self.revoked {
+            InviteState::Revoked
+        } else if self.expires_at.is_some_and(|expires_at| Utc::now() > expires_at) {
+            InviteState::Expired
+        } else if self.max_uses.is_some_and(|max_uses| self.use_count >= max_uses) {
+            InviteState::Exhausted
+        } else {
+            InviteState::Active
+        }
+    }
+
+    /// 检查邀请码是否可用
+    pub fn is_usable(&self) -> bool {
+        self.state() == InviteState::Active
+    }
+
+    /// 剩余可用次数，不限次数时为None
+    pub fn remaining_uses(&self) -> Option<i64> {
+        self.max_uses.map(|max_uses| (max_uses - self.use_count).max(0))
+    }
+
+    /// 显示名称（未命名时使用ID）
+    pub fn display_name(&self) -> String {
+        match self.label {
+            Some(ref label) => label.clone(),
+            None => format!("邀请 #{}", self.unique_id),
+        }
+    }
+
+    /// Telegram深度链接的start参数
+    ///
+    /// start参数最长64个字符且只允许 `A-Za-z0-9_-`，因此将UUID的16个字节
+    /// 编码为无填充的URL安全base64（22个字符）。
+    pub fn start_payload(&self) -> Option<String> {
+        let uuid = Uuid::parse_str(&self.code).ok()?;
+        Some(URL_SAFE_NO_PAD.encode(uuid.as_bytes()))
+    }
+
+    /// 从start参数还原邀请码
+    pub fn code_from_start_payload(payload: &str) -> Option<String> {
+        let bytes = URL_SAFE_NO_PAD.decode(payload).ok()?;
+        let uuid = Uuid::from_slice(&bytes).ok()?;
+        Some(uuid.to_string())
+    }
+
+    /// 生成邀请深度链接，如 `https://t.me/<bot_username>?start=<payload>`
+    pub fn deep_link(&self, bot_username: &str) -> Option<String> {
+        self.start_payload()
+            .map(|payload| format!("https://t.me/{}?start={}", bot_username, payload))
+    }
+}
+
+/// 审批请求消息（发送给邀请管理员或升级接收人的带审批按钮的消息）
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ApprovalMessage {
+    /// 数据库唯一ID
+    pub unique_id: i64,
+    /// 关联的访客记录ID
+    pub record_id: i64,
+    /// 接收消息的聊天ID
+    pub chat_id: i64,
+    /// Telegram消息ID
+    pub message_id: i32,
+    /// 是否为超时升级后发送的消息
+    pub escalated: bool,
+    /// 发送时间
+    pub created_at: DateTime<Utc>,
+}
+
+impl ApprovalMessage {
+    pub fn new(record_id: i64, chat_id: i64, message_id: i32, escalated: bool) -> Self {
+        Self {
+            unique_id: 0, // 由数据库自动分配
+            record_id,
+            chat_id,
+            message_id,
+            escalated,
+            created_at: Utc::now(),
+        }
+    }
+}
+
 /// 访客记录表实体
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Record {
@@ -141,6 +535,8 @@ pub struct Record {
     pub status: AuthStatus,
     /// 访客Telegram ID
     pub vis_id: i64,
+    /// 申请时的访客显示名称，旧数据为空
+    pub vis_name: Option<String>,
     /// 授权类型
     pub auth_type: AuthType,
     /// 使用次数（仅用于times类型）
@@ -149,12 +545,12 @@ pub struct Record {
     pub start_time: Option<DateTime<Utc>>,
     /// 授权结束时间
     pub ended_time: Option<DateTime<Utc>>,
-    /// 密码列表（JSON存储）
-    pub password: Option<String>, // 存储JSON格式的密码列表
     /// 邀请者（admin表中的unique_id）
     pub inviter: i64,
+    /// 使用的邀请码（invite表中的unique_id），旧数据为空
+    pub invite_id: Option<i64>,
     /// 最后更新时间
-    pub update_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
 }
 
 impl Record {
@@ -163,47 +559,57 @@ impl Record {
             unique_id: 0, // 由数据库自动分配
             status: AuthStatus::Pending,
             vis_id,
+            vis_name: None,
             auth_type: AuthType::Temp, // 默认值，后续会根据管理员选择更新
             times: None,
             start_time: None,
             ended_time: None,
-            password: None,
             inviter,
-            update_at: Utc::now(),
+            invite_id: None,
+            updated_at: Utc::now(),
         }
     }
 
-    /// 添加密码到密码列表
-    pub fn add_password(&mut self, new_password: &str) -> Result<(), serde_json::Error> {
-        let mut passwords: Vec<String> = if let Some(ref password_json) = self.password {
-            serde_json::from_str(password_json)?
-        } else {
-            Vec::new()
-        };
-        
-        passwords.push(new_password.to_string());
-        self.password = Some(serde_json::to_string(&passwords)?);
-        self.update_at = Utc::now();
-        
-        Ok(())
+    /// 关联使用的邀请码
+    pub fn with_invite(mut self, invite_id: i64) -> Self {
+        self.invite_id = Some(invite_id);
+        self
     }
 
-    /// 获取密码列表
-    pub fn get_passwords(&self) -> Result<Vec<String>, serde_json::Error> {
-        if let Some(ref password_json) = self.password {
-            serde_json::from_str(password_json)
-        } else {
-            Ok(Vec::new())
+    /// 设置访客显示名称
+    pub fn with_visitor_name(mut self, vis_name: impl Into<String>) -> Self {
+        self.vis_name = Some(vis_name.into());
+        self
+    }
+
+    /// 访客名称和ID，旧数据没有名称时只显示ID
+    pub fn visitor_label(&self) -> String {
+        match self.vis_name {
+            Some(ref vis_name) => format!("{}（ID {}）", vis_name, self.vis_id),
+            None => format!("ID {}", self.vis_id),
         }
     }
 
-    /// 检查授权是否仍然有效
+    /// 授权类型描述，次数密码附带可用次数
+    pub fn auth_type_label(&self) -> String {
+        match (self.auth_type, self.times) {
+            (AuthType::Times, Some(times)) => format!("{}（{} 次）", self.auth_type.description(), times),
+            _ => self.auth_type.description().to_string(),
+        }
+    }
+
+    /// 检查授权当前是否仍然有效
     pub fn is_active(&self) -> bool {
+        self.is_active_at(Utc::now())
+    }
+
+    /// 检查授权在指定时间是否有效
+    pub fn is_active_at(&self, now: DateTime<Utc>) -> bool {
         match self.status {
             AuthStatus::Auth => {
                 // 检查是否过期 - 使用UTC时间比较，因为数据库存储的就是UTC时间
                 if let Some(ended_time) = self.ended_time {
-                    Utc::now() <= ended_time
+                    now <= ended_time
                 } else {
                     true // 没有结束时间限制
                 }
@@ -212,20 +618,270 @@ impl Record {
         }
     }
 
-    /// 标记为已撤销
-    pub fn revoke(&mut self) {
-        self.status = AuthStatus::Revoked;
-        self.update_at = Utc::now();
+    /// 检查已授权记录当前是否已到结束时间
+    pub fn is_expired(&self) -> bool {
+        self.is_expired_at(Utc::now())
+    }
+
+    /// 检查已授权记录在指定时间是否已到结束时间
+    pub fn is_expired_at(&self, now: DateTime<Utc>) -> bool {
+        self.status == AuthStatus::Auth
+            && self.ended_time.is_some_and(|ended_time| now > ended_time)
+    }
+
+    /// 变更记录状态，非法的状态变更会被拒绝且不修改记录
+    pub fn transition(&mut self, to: AuthStatus) -> crate::error::Result<()> {
+        if !self.status.can_transition_to(to) {
+            return Err(AppError::business(format!(
+                "记录 #{} 无法从「{}」变更为「{}」",
+                self.unique_id,
+                self.status.description(),
+                to.description()
+            )));
+        }
+
+        self.status = to;
+        self.updated_at = Utc::now();
+        Ok(())
+    }
+
+    /// 授权是否可以延长
+    ///
+    /// 只有长期临时授权在结束时间前持续发放新密码；其他类型的密码有效期由密码本身决定，
+    /// 门锁不会因为记录延长而继续接受，因此不能延长。
+    pub fn can_extend(&self) -> bool {
+        self.status == AuthStatus::Auth
+            && self.auth_type == AuthType::LongtimeTemp
+            && self.ended_time.is_some()
+    }
+
+    /// 撤销时的目标状态：待审批的请求记为取消，已授权的记录记为撤销
+    pub fn revocation_status(&self) -> AuthStatus {
+        match self.status {
+            AuthStatus::Pending => AuthStatus::Cancelled,
+            _ => AuthStatus::Revoked,
+        }
+    }
+
+    /// 撤销记录
+    pub fn revoke(&mut self) -> crate::error::Result<()> {
+        self.transition(self.revocation_status())
     }
 
     /// 批准授权
-    pub fn approve(&mut self, auth_type: AuthType, start_time: Option<DateTime<Utc>>, ended_time: Option<DateTime<Utc>>, times: Option<i32>) {
-        self.status = AuthStatus::Auth;
+    pub fn approve(&mut self, auth_type: AuthType, start_time: Option<DateTime<Utc>>, ended_time: Option<DateTime<Utc>>, times: Option<i32>) -> crate::error::Result<()> {
+        self.transition(AuthStatus::Auth)?;
         self.auth_type = auth_type;
         self.start_time = start_time;
         self.ended_time = ended_time;
         self.times = times;
-        self.update_at = Utc::now();
+        Ok(())
+    }
+}
+
+/// 已发放密码表实体
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct IssuedPassword {
+    /// 数据库唯一ID
+    pub unique_id: i64,
+    /// 所属访客记录（record表中的unique_id）
+    pub record_id: i64,
+    /// 密码
+    pub password: String,
+    /// 密码类型（temporary/times/limited/period）
+    pub password_type: String,
+    /// 生成时间
+    pub generated_at: DateTime<Utc>,
+    /// 失效时间
+    pub valid_until: Option<DateTime<Utc>>,
+    /// 生成参数（JSON）
+    pub params: Option<String>,
+    /// 生成时使用的时间偏移（秒），旧数据迁移而来时未知
+    pub time_offset: Option<i64>,
+    /// 生成密码的门锁（lock表中的unique_id）
+    pub lock_id: Option<i64>,
+}
+
+impl IssuedPassword {
+    /// 根据门锁的密码生成结果创建发放记录
+    pub fn from_result(record_id: i64, result: &PasswordResult, lock: &Lock) -> Self {
+        let params = match result.password_type {
+            PasswordType::Temporary => None,
+            PasswordType::Times(times) => Some(serde_json::json!({ "times": times })),
+            PasswordType::Limited(hours, minutes) => {
+                Some(serde_json::json!({ "hours": hours, "minutes": minutes }))
+            }
+            PasswordType::Period(year, month, day, hour) => Some(serde_json::json!({
+                "year": year,
+                "month": month,
+                "day": day,
+                "hour": hour,
+            })),
+        };
+
+        Self {
+            unique_id: 0, // 由数据库自动分配
+            record_id,
+            password: result.password.clone(),
+            password_type: result.password_type.kind().to_string(),
+            generated_at: Utc::now(),
+            valid_until: Some(result.valid_until),
+            params: params.map(|p| p.to_string()),
+            time_offset: Some(lock.time_offset),
+            lock_id: Some(lock.unique_id),
+        }
+    }
+}
+
+/// 审计动作枚举
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AuditAction {
+    /// 添加管理员
+    AddAdmin,
+    /// 删除管理员
+    RemoveAdmin,
+    /// 修改管理密码
+    EditPassword,
+    /// 批准授权
+    Approve,
+    /// 拒绝授权
+    Reject,
+    /// 撤销授权
+    Revoke,
+    /// 授权到期
+    Expire,
+    /// 创建邀请码
+    CreateInvite,
+    /// 撤销邀请码
+    RevokeInvite,
+    /// 添加或修改门锁
+    EditLock,
+    /// 延长授权
+    Extend,
+    /// 添加超级管理员
+    AddSuperAdmin,
+    /// 移除超级管理员
+    RemoveSuperAdmin,
+}
+
+impl AuditAction {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            AuditAction::AddAdmin => "add_admin",
+            AuditAction::RemoveAdmin => "remove_admin",
+            AuditAction::EditPassword => "edit_password",
+            AuditAction::Approve => "approve",
+            AuditAction::Reject => "reject",
+            AuditAction::Revoke => "revoke",
+            AuditAction::Expire => "expire",
+            AuditAction::CreateInvite => "create_invite",
+            AuditAction::RevokeInvite => "revoke_invite",
+            AuditAction::EditLock => "edit_lock",
+            AuditAction::Extend => "extend",
+            AuditAction::AddSuperAdmin => "add_super_admin",
+            AuditAction::RemoveSuperAdmin => "remove_super_admin",
+        }
+    }
+
+    pub fn parse(s: &str) -> Option<Self> {
+        match s {
+            "add_admin" => Some(AuditAction::AddAdmin),
+            "remove_admin" => Some(AuditAction::RemoveAdmin),
+            "edit_password" => Some(AuditAction::EditPassword),
+            "approve" => Some(AuditAction::Approve),
+            "reject" => Some(AuditAction::Reject),
+            "revoke" => Some(AuditAction::Revoke),
+            "expire" => Some(AuditAction::Expire),
+            "create_invite" => Some(AuditAction::CreateInvite),
+            "revoke_invite" => Some(AuditAction::RevokeInvite),
+            "edit_lock" => Some(AuditAction::EditLock),
+            "extend" => Some(AuditAction::Extend),
+            "add_super_admin" => Some(AuditAction::AddSuperAdmin),
+            "remove_super_admin" => Some(AuditAction::RemoveSuperAdmin),
+            _ => None,
+        }
+    }
+
+    /// 获取审计动作的中文描述
+    pub fn description(&self) -> &'static str {
+        match self {
+            AuditAction::AddAdmin => "添加管理员",
+            AuditAction::RemoveAdmin => "删除管理员",
+            AuditAction::EditPassword => "修改管理密码",
+            AuditAction::Approve => "批准授权",
+            AuditAction::Reject => "拒绝授权",
+            AuditAction::Revoke => "撤销授权",
+            AuditAction::Expire => "授权到期",
+            AuditAction::CreateInvite => "创建邀请码",
+            AuditAction::RevokeInvite => "撤销邀请码",
+            AuditAction::EditLock => "修改门锁",
+            AuditAction::Extend => "延长授权",
+            AuditAction::AddSuperAdmin => "添加超级管理员",
+            AuditAction::RemoveSuperAdmin => "移除超级管理员",
+        }
+    }
+}
+
+/// 审计日志表实体（只追加，不可修改）
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AuditEvent {
+    /// 数据库唯一ID
+    pub unique_id: i64,
+    /// 操作者Telegram ID
+    pub actor_id: i64,
+    /// 审计动作
+    pub action: AuditAction,
+    /// 目标记录（record表中的unique_id）
+    pub target_record: Option<i64>,
+    /// 目标用户Telegram ID
+    pub target_user: Option<i64>,
+    /// 变更前状态
+    pub before_status: Option<AuthStatus>,
+    /// 变更后状态
+    pub after_status: Option<AuthStatus>,
+    /// 附加说明
+    pub detail: Option<String>,
+    /// 发生时间
+    pub created_at: DateTime<Utc>,
+}
+
+impl AuditEvent {
+    pub fn new(actor_id: i64, action: AuditAction) -> Self {
+        Self {
+            unique_id: 0, // 由数据库自动分配
+            actor_id,
+            action,
+            target_record: None,
+            target_user: None,
+            before_status: None,
+            after_status: None,
+            detail: None,
+            created_at: Utc::now(),
+        }
+    }
+
+    /// 针对访客记录的状态变更事件
+    pub fn for_record(actor_id: i64, action: AuditAction, record: &Record, after: AuthStatus) -> Self {
+        Self {
+            target_record: Some(record.unique_id),
+            target_user: Some(record.vis_id),
+            before_status: Some(record.status),
+            after_status: Some(after),
+            ..Self::new(actor_id, action)
+        }
+    }
+
+    /// 设置目标用户
+    pub fn with_target_user(mut self, user_id: i64) -> Self {
+        self.target_user = Some(user_id);
+        self
+    }
+
+    /// 设置附加说明
+    pub fn with_detail<S: Into<String>>(mut self, detail: S) -> Self {
+        self.detail = Some(detail.into());
+        self
     }
 }
 
@@ -277,12 +933,17 @@ pub struct CallbackData {
 #[cfg(test)]
 mod timezone_tests {
     use super::*;
-    use chrono::{Utc, FixedOffset, Timelike};
+    use chrono::{Utc, FixedOffset, TimeZone, Timelike};
+
+    /// Fixed reference time, late enough in the UTC day that Beijing time is already on the next day
+    fn reference_time() -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(2024, 1, 1, 20, 30, 0).unwrap()
+    }
 
     /// Test timezone conversion functions
     #[test]
     fn test_beijing_timezone_conversion() {
-        let utc_time = Utc::now();
+        let utc_time = reference_time();
         let beijing_tz = FixedOffset::east_opt(8 * 3600).unwrap();
         let beijing_time = utc_time.with_timezone(&beijing_tz);
         
@@ -298,28 +959,35 @@ mod timezone_tests {
         };
         
         assert_eq!(hour_diff, 8, "Beijing time should be 8 hours ahead of UTC");
+        assert_eq!(beijing_time.format("%Y-%m-%d %H:%M").to_string(), "2024-01-02 04:30");
     }
 
     /// Test the is_active method with UTC+8 timezone handling
     #[test]
     fn test_record_is_active_with_timezone() {
-        let beijing_tz = FixedOffset::east_opt(8 * 3600).unwrap();
+        let now = reference_time();
         
         // Create a record with future expiry time
         let mut record = Record::new(123456789, 1);
         record.status = AuthStatus::Auth;
-        record.ended_time = Some(Utc::now() + chrono::Duration::hours(1));
+        record.ended_time = Some(now + chrono::Duration::hours(1));
         
         // Record should be active
-        assert!(record.is_active(), "Record with future expiry should be active");
+        assert!(record.is_active_at(now), "Record with future expiry should be active");
+        assert!(!record.is_expired_at(now));
+        
+        // The same record is inactive once its end time has passed
+        let later = now + chrono::Duration::hours(1) + chrono::Duration::seconds(1);
+        assert!(!record.is_active_at(later), "Record should be inactive after its expiry");
+        assert!(record.is_expired_at(later));
         
         // Create a record with past expiry time
         let mut expired_record = Record::new(987654321, 1);
         expired_record.status = AuthStatus::Auth;
-        expired_record.ended_time = Some(Utc::now() - chrono::Duration::hours(1));
+        expired_record.ended_time = Some(now - chrono::Duration::hours(1));
         
         // Record should be inactive
-        assert!(!expired_record.is_active(), "Record with past expiry should be inactive");
+        assert!(!expired_record.is_active_at(now), "Record with past expiry should be inactive");
         
         // Create a record without expiry time
         let mut no_expiry_record = Record::new(555666777, 1);
@@ -327,22 +995,23 @@ mod timezone_tests {
         no_expiry_record.ended_time = None;
         
         // Record should be active
-        assert!(no_expiry_record.is_active(), "Record without expiry should be active");
+        assert!(no_expiry_record.is_active_at(now), "Record without expiry should be active");
+        assert!(!no_expiry_record.is_expired_at(now));
         
         // Create a record with pending status
         let mut pending_record = Record::new(111222333, 1);
         pending_record.status = AuthStatus::Pending;
-        pending_record.ended_time = Some(Utc::now() + chrono::Duration::hours(1));
+        pending_record.ended_time = Some(now + chrono::Duration::hours(1));
         
         // Record should be inactive due to pending status
-        assert!(!pending_record.is_active(), "Pending record should be inactive regardless of expiry");
+        assert!(!pending_record.is_active_at(now), "Pending record should be inactive regardless of expiry");
     }
 
-    /// Test timezone consistency between database queries and Record::is_active
+    /// Test timezone consistency between database queries and Record::is_active_at
     #[test]
     fn test_timezone_consistency() {
         // This test simulates the database query logic using UTC+8
-        let current_utc = Utc::now();
+        let current_utc = reference_time();
         let beijing_tz = FixedOffset::east_opt(8 * 3600).unwrap();
         let current_beijing = current_utc.with_timezone(&beijing_tz);
         
@@ -353,15 +1022,15 @@ mod timezone_tests {
         // Database query logic (simulated)
         let db_considers_active = future_beijing > current_beijing;
         
-        // Record::is_active logic
+        // Record::is_active_at logic
         let mut record = Record::new(123456789, 1);
         record.status = AuthStatus::Auth;
         record.ended_time = Some(future_utc);
-        let record_considers_active = record.is_active();
+        let record_considers_active = record.is_active_at(current_utc);
         
         // Both should agree
         assert_eq!(db_considers_active, record_considers_active,
-                  "Database query and Record::is_active should agree on timezone handling");
+                  "Database query and Record::is_active_at should agree on timezone handling");
         
         // Test with past time
         let past_utc = current_utc - chrono::Duration::minutes(30);
@@ -370,14 +1039,95 @@ mod timezone_tests {
         let db_considers_past_active = past_beijing > current_beijing;
         
         record.ended_time = Some(past_utc);
-        let record_considers_past_active = record.is_active();
+        let record_considers_past_active = record.is_active_at(current_utc);
         
         assert_eq!(db_considers_past_active, record_considers_past_active,
-                  "Database query and Record::is_active should agree on past expiry");
+                  "Database query and Record::is_active_at should agree on past expiry");
         assert!(!db_considers_past_active, "Past expiry should be considered inactive");
     }
 }
 
+#[cfg(test)]
+mod invite_tests {
+    use super::*;
+
+    #[test]
+    fn test_start_payload_roundtrip() {
+        let invite = Invite::new(1);
+        let payload = invite.start_payload().unwrap();
+        assert_eq!(payload.len(), 22);
+        assert!(payload.chars().all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-'));
+        assert_eq!(Invite::code_from_start_payload(&payload), Some(invite.code.clone()));
+
+        assert_eq!(
+            invite.deep_link("nekohouse_bot"),
+            Some(format!("https://t.me/nekohouse_bot?start={}", payload))
+        );
+
+        assert_eq!(Invite::code_from_start_payload(""), None);
+        assert_eq!(Invite::code_from_start_payload("not-a-payload"), None);
+        assert_eq!(Invite::code_from_start_payload(&invite.code), None);
+    }
+}
+
+#[cfg(test)]
+mod record_state_tests {
+    use super::*;
+
+    #[test]
+    fn test_legal_transitions() {
+        let mut approved = Record::new(123456789, 1);
+        assert!(approved.approve(AuthType::Temp, Some(Utc::now()), None, None).is_ok());
+        assert_eq!(approved.status, AuthStatus::Auth);
+        assert!(approved.transition(AuthStatus::Expired).is_ok());
+        assert!(approved.status.is_final());
+
+        let mut rejected = Record::new(123456789, 1);
+        assert!(rejected.transition(AuthStatus::Rejected).is_ok());
+
+        // 撤销待审批请求记为取消，撤销已授权记录记为撤销
+        let mut cancelled = Record::new(123456789, 1);
+        assert!(cancelled.revoke().is_ok());
+        assert_eq!(cancelled.status, AuthStatus::Cancelled);
+
+        let mut revoked = Record::new(123456789, 1);
+        revoked.status = AuthStatus::Auth;
+        assert!(revoked.revoke().is_ok());
+        assert_eq!(revoked.status, AuthStatus::Revoked);
+    }
+
+    #[test]
+    fn test_illegal_transitions() {
+        // 终态不能再变更
+        for status in AuthStatus::ALL.iter().filter(|s| s.is_final()) {
+            for to in AuthStatus::ALL {
+                assert!(!status.can_transition_to(to), "{:?} -> {:?}", status, to);
+            }
+        }
+
+        let mut revoked = Record::new(123456789, 1);
+        revoked.status = AuthStatus::Revoked;
+        assert!(revoked.approve(AuthType::Temp, None, None, None).is_err());
+        assert_eq!(revoked.status, AuthStatus::Revoked);
+
+        let mut pending = Record::new(123456789, 1);
+        assert!(pending.transition(AuthStatus::Expired).is_err());
+        assert!(pending.transition(AuthStatus::Revoked).is_err());
+
+        let mut auth = Record::new(123456789, 1);
+        auth.status = AuthStatus::Auth;
+        assert!(auth.transition(AuthStatus::Rejected).is_err());
+        assert!(auth.transition(AuthStatus::Auth).is_err());
+    }
+
+    #[test]
+    fn test_status_round_trip() {
+        for status in AuthStatus::ALL {
+            assert_eq!(AuthStatus::from_str(status.as_str()), Some(status));
+        }
+    }
+}
+
 impl CallbackData {
     pub fn new<S: Into<String>>(action: S) -> Self {
         Self {
@@ -0,0 +1,145 @@
+//! 门锁时钟偏差校准
+//!
+//! 门锁时钟会逐渐偏离真实时间，时间偏移不准时临时密码和限时密码会被门锁拒绝。
+//! 校准时提供一个门锁当前接受或当前显示的密码，按各算法解密出密码所用的时间窗口，
+//! 由此反推门锁时间相对真实时间的偏移范围。周期密码以天为单位，不能用于校准。
+
+use super::{LimitedPasswordGenerator, PasswordResult, PasswordType, TempPasswordGenerator, TimesPasswordGenerator};
+use chrono::{DateTime, Duration, Utc};
+
+/// 校准时搜索的最大时钟偏差（秒）
+pub const MAX_CALIBRATION_DRIFT_SECS: i64 = 6 * 3600;
+
+/// 校准所用密码的来源
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CalibrationSample {
+    /// 门锁当前接受该密码，门锁时间落在密码的有效期内
+    Accepted,
+    /// 门锁当前显示该密码，门锁时间落在密码所用的时间窗口内
+    Displayed,
+}
+
+/// 与密码吻合的时间偏移范围
+#[derive(Debug, Clone, PartialEq)]
+pub struct OffsetCandidate {
+    /// 按门锁时间（偏移为0）解出的生成结果
+    pub result: PasswordResult,
+    /// 最小偏移（秒）
+    pub min_offset: i64,
+    /// 最大偏移（秒）
+    pub max_offset: i64,
+}
+
+impl OffsetCandidate {
+    /// 指定偏移是否在范围内
+    pub fn contains(&self, offset: i64) -> bool {
+        (self.min_offset..=self.max_offset).contains(&offset)
+    }
+
+    /// 范围内最接近当前偏移的值，当前偏移已在范围内时保持不变
+    pub fn suggested(&self, current_offset: i64) -> i64 {
+        current_offset.clamp(self.min_offset, self.max_offset)
+    }
+}
+
+/// 密码类型的时间窗口长度（秒），周期密码不参与校准
+fn window_secs(password_type: &PasswordType) -> Option<i64> {
+    match password_type {
+        PasswordType::Temporary => Some(4),
+        PasswordType::Times(_) => Some(32 * 4),
+        PasswordType::Limited(_, _) => Some(1800),
+        PasswordType::Period(..) => None,
+    }
+}
+
+/// 搜索与密码吻合的时间偏移
+///
+/// `at` 为门锁接受或显示该密码时的真实时间。每种算法最多给出一个范围，
+/// 返回多个范围时说明密码同时符合多种算法，无法确定偏移。
+pub fn search_offsets(
+    password: &str,
+    admin_pwd: &str,
+    sample: CalibrationSample,
+    at: DateTime<Utc>,
+) -> Vec<OffsetCandidate> {
+    // 检查范围以检查时间为终点，推后到最大偏差处以覆盖门锁时钟偏快的情况
+    let search_at = at + Duration::seconds(MAX_CALIBRATION_DRIFT_SECS);
+    let matches = [
+        TempPasswordGenerator::new().inspect(password, admin_pwd, search_at),
+        TimesPasswordGenerator::new().inspect(password, admin_pwd, search_at),
+        LimitedPasswordGenerator::new().inspect(password, admin_pwd, search_at),
+    ];
+
+    matches
+        .into_iter()
+        .flatten()
+        .filter_map(|result| {
+            let window = window_secs(&result.password_type)?;
+            let from = (result.valid_from - at).num_seconds();
+            let until = match sample {
+                CalibrationSample::Accepted => (result.valid_until - at).num_seconds(),
+                CalibrationSample::Displayed => from + window - 1,
+            };
+
+            let min_offset = from.max(-MAX_CALIBRATION_DRIFT_SECS);
+            let max_offset = until.min(MAX_CALIBRATION_DRIFT_SECS);
+            (min_offset <= max_offset).then_some(OffsetCandidate { result, min_offset, max_offset })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::gen_password::{Clock, ManualClock};
+    use chrono::TimeZone;
+
+    #[test]
+    fn test_displayed_temp_password() {
+        let clock = ManualClock::new(Utc.with_ymd_and_hms(2024, 6, 1, 8, 0, 1).unwrap());
+        // 门锁时钟快95秒
+        let displayed = TempPasswordGenerator::new()
+            .with_clock(clock.shared())
+            .with_time_offset(95)
+            .generate("123456")
+            .unwrap();
+
+        let candidates = search_offsets(&displayed.password, "123456", CalibrationSample::Displayed, clock.now());
+        assert_eq!(candidates.len(), 1);
+        let candidate = &candidates[0];
+        assert_eq!(candidate.result.password_type, PasswordType::Temporary);
+        assert_eq!(candidate.max_offset - candidate.min_offset, 3);
+        assert!(candidate.contains(95));
+        assert_eq!(candidate.suggested(95), 95);
+        assert_eq!(candidate.suggested(0), candidate.min_offset);
+    }
+
+    #[test]
+    fn test_accepted_passwords() {
+        let clock = ManualClock::new(Utc.with_ymd_and_hms(2024, 6, 1, 8, 0, 1).unwrap());
+        // 门锁时钟慢2小时，密码在1小时前按门锁时间生成
+        let generated_at = clock.now() - Duration::hours(1);
+        let generation_clock = ManualClock::new(generated_at);
+        let accepted = LimitedPasswordGenerator::new()
+            .with_clock(generation_clock.shared())
+            .with_time_offset(-7200)
+            .generate("123456", 2, 0)
+            .unwrap();
+
+        let candidates = search_offsets(&accepted.password, "123456", CalibrationSample::Accepted, clock.now());
+        assert_eq!(candidates.len(), 1);
+        let candidate = &candidates[0];
+        assert_eq!(candidate.result.password_type, PasswordType::Limited(2, 0));
+        assert!(candidate.contains(-7200));
+        assert!(!candidate.contains(0));
+        assert_eq!(candidate.suggested(0), candidate.max_offset);
+
+        // 门锁时间在有效期之外时没有吻合的偏移
+        let stale = TempPasswordGenerator::new()
+            .with_clock(ManualClock::new(clock.now() - Duration::hours(8)).shared())
+            .generate("123456")
+            .unwrap();
+        assert!(search_offsets(&stale.password, "123456", CalibrationSample::Accepted, clock.now()).is_empty());
+        assert!(search_offsets("0000000000", "123456", CalibrationSample::Accepted, clock.now()).is_empty());
+    }
+}
@@ -0,0 +1,105 @@
+//! 时钟抽象
+//!
+//! 密码生成器、密码服务和记录有效期判断都通过时钟获取当前时间，
+//! 测试时可以换成手动时钟，也可以用来计算未来某一时刻门锁接受的密码。
+
+use chrono::{DateTime, Duration, Utc};
+use std::sync::{Arc, Mutex};
+
+/// 时钟
+pub trait Clock: Send + Sync {
+    /// 当前时间
+    fn now(&self) -> DateTime<Utc>;
+
+    /// 当前时间戳（毫秒）
+    fn timestamp_millis(&self) -> i64 {
+        self.now().timestamp_millis()
+    }
+}
+
+/// 可在多个组件间共享的时钟
+pub type SharedClock = Arc<dyn Clock>;
+
+/// 系统时钟
+#[derive(Debug, Clone, Copy, Default)]
+pub struct SystemClock;
+
+impl SystemClock {
+    /// 创建可共享的系统时钟
+    pub fn shared() -> SharedClock {
+        Arc::new(SystemClock)
+    }
+}
+
+impl Clock for SystemClock {
+    fn now(&self) -> DateTime<Utc> {
+        Utc::now()
+    }
+}
+
+/// 手动时钟，时间固定不变，只能通过 `set` 和 `advance` 调整
+///
+/// 克隆出的实例共享同一时间，便于测试中交给被测组件后继续拨动。
+#[derive(Debug, Clone)]
+pub struct ManualClock {
+    now: Arc<Mutex<DateTime<Utc>>>,
+}
+
+impl ManualClock {
+    /// 创建停在指定时间的时钟
+    pub fn new(now: DateTime<Utc>) -> Self {
+        Self {
+            now: Arc::new(Mutex::new(now)),
+        }
+    }
+
+    /// 转换为可共享的时钟，与当前实例共享同一时间
+    pub fn shared(&self) -> SharedClock {
+        Arc::new(self.clone())
+    }
+
+    /// 设置当前时间
+    pub fn set(&self, now: DateTime<Utc>) {
+        *self.now.lock().unwrap() = now;
+    }
+
+    /// 拨快（负数为拨慢）指定时长
+    pub fn advance(&self, duration: Duration) {
+        *self.now.lock().unwrap() += duration;
+    }
+}
+
+impl Clock for ManualClock {
+    fn now(&self) -> DateTime<Utc> {
+        *self.now.lock().unwrap()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    #[test]
+    fn test_manual_clock() {
+        let start = Utc.with_ymd_and_hms(2024, 1, 1, 10, 0, 0).unwrap();
+        let clock = ManualClock::new(start);
+        let shared = clock.shared();
+        assert_eq!(shared.now(), start);
+
+        // 共享的实例同步变化
+        clock.advance(Duration::minutes(30));
+        assert_eq!(shared.now(), start + Duration::minutes(30));
+        assert_eq!(shared.timestamp_millis(), (start + Duration::minutes(30)).timestamp_millis());
+
+        clock.set(start - Duration::days(1));
+        assert_eq!(shared.now(), start - Duration::days(1));
+    }
+
+    #[test]
+    fn test_system_clock() {
+        let before = Utc::now();
+        let now = SystemClock::shared().now();
+        assert!(before <= now && now <= Utc::now());
+    }
+}
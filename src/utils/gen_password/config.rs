@@ -40,32 +40,6 @@ impl AppConfig {
     pub const PERIOD_PWD_DEFAULT_TOLERANCE: u32 = 1;
 }
 
-/// 菜单选项枚举
-#[derive(Debug, Clone, PartialEq)]
-pub enum MenuOption {
-    TempPassword,
-    TimesPassword,
-    LimitedPassword,
-    PeriodPassword,
-    VerifyPassword,
-    Exit,
-    Invalid,
-}
-
-impl From<&str> for MenuOption {
-    fn from(choice: &str) -> Self {
-        match choice.trim() {
-            "1" => MenuOption::TempPassword,
-            "2" => MenuOption::TimesPassword,
-            "3" => MenuOption::LimitedPassword,
-            "4" => MenuOption::PeriodPassword,
-            "5" => MenuOption::VerifyPassword,
-            "6" => MenuOption::Exit,
-            _ => MenuOption::Invalid,
-        }
-    }
-}
-
 /// 验证结果枚举
 #[derive(Debug, Clone)]
 pub enum VerificationResult {
@@ -208,4 +182,32 @@ impl InputValidator {
             Ok(())
         }
     }
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_input_validation() {
+        assert!(InputValidator::validate_admin_password("1234").is_ok());
+        assert!(InputValidator::validate_admin_password("123").is_err());
+
+        assert!(InputValidator::validate_use_times(5).is_ok());
+        assert!(InputValidator::validate_use_times(0).is_err());
+        assert!(InputValidator::validate_use_times(32).is_err());
+
+        assert!(InputValidator::validate_minutes(0).is_ok());
+        assert!(InputValidator::validate_minutes(30).is_ok());
+        assert!(InputValidator::validate_minutes(15).is_err());
+    }
+
+    #[test]
+    fn test_time_formatter() {
+        let formatted = TimeFormatter::format_duration_ms(3661000); // 1小时1分1秒
+        assert!(formatted.contains("1小时1分钟"));
+
+        assert_eq!(TimeFormatter::format_hours_minutes(2, 30), "2小时30分钟");
+        assert_eq!(TimeFormatter::format_hours_minutes(0, 30), "30分钟");
+    }
+}
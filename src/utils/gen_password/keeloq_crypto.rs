@@ -1,211 +1,147 @@
 //! KeeLoq 加密算法 Rust 实现
 //! 用于生成基于时间和管理密码的安全密码
+//!
+//! 非线性函数使用标准的32位查找常量，轮函数只有移位和位运算，不含分支；
+//! 管理密码对应的密钥只需计算一次，可在多次加解密间复用。
+
+use super::clock::{Clock, SystemClock};
+use chrono::{DateTime, Utc};
+
+/// 非线性函数查找表，第 i 位为输入 (b31, b26, b20, b9, b1) 组成的5位数 i 对应的输出
+const NLF: u32 = 0x3A5C742E;
+
+/// 加密轮数
+const ROUNDS: u32 = 528;
+
+/// 密钥生成常量 N
+const KEY_N: [u8; 4] = [133, 103, 37, 67];
+
+/// 密钥生成常量 T
+const KEY_T: [u8; 4] = [68, 84, 25, 55];
+
+/// 由管理密码生成的64位密钥
+///
+/// 第 r 轮使用第 `r % 64` 位，低32位由常量 N 生成，高32位由常量 T 生成。
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct KeeLoqKey(u64);
+
+impl KeeLoqKey {
+    /// 由管理密码生成密钥
+    ///
+    /// 管理密码取前8位，不足8位时补0，非数字字符按0处理。
+    pub fn new(admin_pwd: &str) -> Self {
+        let mut digits = [0u8; 8];
+        for (digit, byte) in digits.iter_mut().zip(admin_pwd.bytes()) {
+            *digit = (byte as char).to_digit(10).unwrap_or(0) as u8;
+        }
 
-use chrono::{DateTime, Utc, FixedOffset};
+        let low = u32::from_le_bytes(std::array::from_fn(|i| KEY_N[i] ^ digits[i]));
+        let high = u32::from_le_bytes(std::array::from_fn(|i| KEY_T[i] ^ digits[i + 4]));
+        KeeLoqKey(((high as u64) << 32) | low as u64)
+    }
+}
 
-/// KeeLoq 加密算法结构体
-pub struct KeeLoqCrypto {
-    /// S-Box 查找表，用于非线性函数计算
-    sbox: [[[[[u8; 2]; 2]; 2]; 2]; 2],
-    /// 密钥生成常量 N
-    key_n: [u8; 4],
-    /// 密钥生成常量 T  
-    key_t: [u8; 4],
+/// 非线性函数，取状态的第31、26、20、9、1位查表
+#[inline(always)]
+fn nlf(state: u32) -> u32 {
+    let index = (((state >> 31) & 1) << 4)
+        | (((state >> 26) & 1) << 3)
+        | (((state >> 20) & 1) << 2)
+        | (((state >> 9) & 1) << 1)
+        | ((state >> 1) & 1);
+    (NLF >> index) & 1
 }
 
+/// KeeLoq 加密算法结构体
+#[derive(Debug, Clone, Copy, Default)]
+pub struct KeeLoqCrypto;
+
 impl KeeLoqCrypto {
     /// 创建新的 KeeLoq 加密实例
     pub fn new() -> Self {
-        let mut crypto = KeeLoqCrypto {
-            sbox: [[[[[0; 2]; 2]; 2]; 2]; 2],
-            key_n: [133, 103, 37, 67],
-            key_t: [68, 84, 25, 55],
-        };
-        
-        // 初始化 S-Box
-        crypto.init_sbox();
-        crypto
-    }
-
-    /// 初始化 S-Box 查找表
-    fn init_sbox(&mut self) {
-        // S-Box 值基于原始 JavaScript 实现
-        self.sbox[0][0][0][0][0] = 0; self.sbox[0][0][0][0][1] = 1;
-        self.sbox[0][0][0][1][0] = 1; self.sbox[0][0][0][1][1] = 1;
-        self.sbox[0][0][1][0][0] = 0; self.sbox[0][0][1][0][1] = 1;
-        self.sbox[0][0][1][1][0] = 0; self.sbox[0][0][1][1][1] = 0;
-        self.sbox[0][1][0][0][0] = 0; self.sbox[0][1][0][0][1] = 0;
-        self.sbox[0][1][0][1][0] = 1; self.sbox[0][1][0][1][1] = 0;
-        self.sbox[0][1][1][0][0] = 1; self.sbox[0][1][1][0][1] = 1;
-        self.sbox[0][1][1][1][0] = 1; self.sbox[0][1][1][1][1] = 0;
-        self.sbox[1][0][0][0][0] = 0; self.sbox[1][0][0][0][1] = 0;
-        self.sbox[1][0][0][1][0] = 1; self.sbox[1][0][0][1][1] = 1;
-        self.sbox[1][0][1][0][0] = 1; self.sbox[1][0][1][0][1] = 0;
-        self.sbox[1][0][1][1][0] = 1; self.sbox[1][0][1][1][1] = 0;
-        self.sbox[1][1][0][0][0] = 0; self.sbox[1][1][0][0][1] = 1;
-        self.sbox[1][1][0][1][0] = 0; self.sbox[1][1][0][1][1] = 1;
-        self.sbox[1][1][1][0][0] = 1; self.sbox[1][1][1][0][1] = 1;
-        self.sbox[1][1][1][1][0] = 0; self.sbox[1][1][1][1][1] = 0;
-    }
-
-    /// 获取指定位的值
-    fn get_bit(&self, value: u32, bit: u8) -> u8 {
-        if (value & (1 << bit)) != 0 { 1 } else { 0 }
-    }
-
-    /// 右移位操作，支持循环位
-    fn shift_right(&self, value: u32, carry: u8) -> u32 {
-        if carry != 0 {
-            (value >> 1) | 0x80000000
-        } else {
-            (value >> 1) & 0x7FFFFFFF
+        KeeLoqCrypto
+    }
+
+    /// KeeLoq 核心加密函数
+    ///
+    /// 每轮由非线性函数、第16位、第0位和密钥位异或得到反馈位，状态右移一位后从最高位移入。
+    fn keeloq_encrypt(data: u32, key: KeeLoqKey) -> u32 {
+        let mut state = data;
+        let mut key_bits = key.0;
+
+        for _ in 0..ROUNDS {
+            let feedback = (nlf(state) ^ (state >> 16) ^ state ^ key_bits as u32) & 1;
+            state = (state >> 1) | (feedback << 31);
+            key_bits = key_bits.rotate_right(1);
         }
+
+        state
     }
 
-    /// 字节数组转换为32位整数
-    fn bytes_to_u32(&self, bytes: &[u8]) -> u32 {
-        let mut result = 0u32;
-        for (i, &byte) in bytes.iter().enumerate() {
-            result += ((byte as u32) & 0xFF) << (8 * i);
+    /// KeeLoq 核心解密函数
+    ///
+    /// 按相反顺序执行各轮：加密时被右移丢弃的最低位，可以由最高位的反馈位和其余已知位还原。
+    fn keeloq_decrypt(data: u32, key: KeeLoqKey) -> u32 {
+        let mut state = data;
+        // 从最后一轮使用的密钥位开始倒序取
+        let mut key_bits = key.0.rotate_right((ROUNDS - 1) % 64);
+
+        for _ in 0..ROUNDS {
+            // 左移还原加密前除最低位外的各位
+            let shifted = state << 1;
+            let lowest = ((state >> 31) ^ nlf(shifted) ^ (shifted >> 16) ^ key_bits as u32) & 1;
+            state = shifted | lowest;
+            key_bits = key_bits.rotate_left(1);
         }
-        result
+
+        state
     }
 
-    /// 32位整数转换为字节数组并返回整数
-    fn u32_to_bytes_and_back(&self, value: u32) -> u32 {
-        let bytes = [
-            ((value >> 24) & 0xFF) as u8,
-            ((value >> 16) & 0xFF) as u8,
-            ((value >> 8) & 0xFF) as u8,
-            (value & 0xFF) as u8,
-        ];
-        self.bytes_to_u32(&bytes)
+    /// 使用已生成的密钥加密，返回加密代码
+    ///
+    /// 输入和输出都按字节倒序参与运算，与原 JavaScript 实现一致。
+    pub fn encrypt(&self, timestamp: u32, key: &KeeLoqKey) -> u32 {
+        Self::keeloq_encrypt(timestamp.swap_bytes(), *key).swap_bytes()
     }
 
-    /// KeeLoq 核心加密函数
-    fn keeloq_encrypt(&self, data: u32, key1: u32, key2: u32) -> u32 {
-        let mut n = data;
-        
-        // 528 轮加密
-        for round in 0..528 {
-            let bit_pos = round % 64;
-            
-            // 非线性函数计算
-            let b31 = self.get_bit(n, 31);
-            let b26 = self.get_bit(n, 26);
-            let b20 = self.get_bit(n, 20);
-            let b9 = self.get_bit(n, 9);
-            let b1 = self.get_bit(n, 1);
-            
-            let sbox_output = self.sbox[b31 as usize][b26 as usize][b20 as usize][b9 as usize][b1 as usize];
-            
-            let b16 = self.get_bit(n, 16);
-            let b0 = self.get_bit(n, 0);
-            
-            let key_bit = if bit_pos < 32 {
-                self.get_bit(key1, bit_pos as u8)
-            } else {
-                self.get_bit(key2, (bit_pos - 32) as u8)
-            };
-            
-            let feedback = sbox_output ^ b16 ^ b0 ^ key_bit;
-            
-            n = self.shift_right(n, feedback);
-        }
-        
-        n
+    /// 使用已生成的密钥解密，还原加密时的输入
+    pub fn decrypt(&self, code: u32, key: &KeeLoqKey) -> u32 {
+        Self::keeloq_decrypt(code.swap_bytes(), *key).swap_bytes()
     }
 
-    /// 主要加密用户代码函数
+    /// 主要加密用户代码函数，返回10位加密代码
     pub fn crypt_usercode(&self, timestamp: u32, admin_pwd: &str) -> String {
-        // 处理管理密码，确保长度为8位
-        let mut pwd = admin_pwd.to_string();
-        if pwd.len() >= 8 {
-            pwd = pwd[..8].to_string();
-        } else {
-            while pwd.len() < 8 {
-                pwd.push('0');
-            }
-        }
+        format!("{:010}", self.encrypt(timestamp, &KeeLoqKey::new(admin_pwd)))
+    }
 
-        // 将密码转换为数字数组
-        let mut pwd_digits = Vec::new();
-        for ch in pwd.chars() {
-            pwd_digits.push(ch.to_digit(10).unwrap_or(0) as u8);
-        }
+    /// 解密用户代码，还原加密时的输入（时间窗口及各类型编码的参数）
+    ///
+    /// 与 `crypt_usercode` 互逆，`code` 为去掉 5000000000 前缀后的加密代码。
+    pub fn decrypt_usercode(&self, code: u32, admin_pwd: &str) -> u32 {
+        self.decrypt(code, &KeeLoqKey::new(admin_pwd))
+    }
 
-        // 生成密钥
-        let mut key_s = Vec::new();
-        let mut key_g = Vec::new();
-        
-        for i in 0..self.key_n.len() {
-            key_s.push(self.key_n[i] ^ pwd_digits[i]);
-        }
-        
-        for i in 0..self.key_t.len() {
-            key_g.push(self.key_t[i] ^ pwd_digits[i + 4]);
-        }
+    /// 获取当前时间戳（毫秒）
+    ///
+    /// 时间戳与时区无关，时区只影响周期密码的天数计算和时间显示。
+    pub fn current_timestamp() -> i64 {
+        Self::lock_timestamp(&SystemClock, 0)
+    }
 
-        let key1 = self.bytes_to_u32(&key_s);
-        let key2 = self.bytes_to_u32(&key_g);
-        
-        // 转换时间戳
-        let converted_timestamp = self.u32_to_bytes_and_back(timestamp);
-        
-        // 执行加密
-        let encrypted = self.keeloq_encrypt(converted_timestamp, key1, key2);
-        let result = self.u32_to_bytes_and_back(encrypted);
-        
-        // 处理负数（JavaScript 兼容性）
-        let final_result = if (result as i32) < 0 {
-            (result as u32).wrapping_add(0)
-        } else {
-            result
-        };
-
-        // 格式化为10位字符串
-        format!("{:010}", final_result)
-    }
-
-    /// 获取UTC+8时间戳（毫秒）
-    pub fn get_utc8_timestamp() -> i64 {
-        let beijing_tz = FixedOffset::east_opt(8 * 3600).unwrap();
-        let beijing_time: DateTime<FixedOffset> = Utc::now().with_timezone(&beijing_tz);
-        beijing_time.timestamp_millis()
-    }
-
-    /// 获取带时间偏移的UTC+8时间戳（毫秒）
-    /// time_offset_seconds: 时间偏移秒数，用于密码锁防重放攻击
-    pub fn get_utc8_timestamp_with_offset(time_offset_seconds: i32) -> i64 {
-        let beijing_tz = FixedOffset::east_opt(8 * 3600).unwrap();
-        let beijing_time: DateTime<FixedOffset> = Utc::now().with_timezone(&beijing_tz);
-        beijing_time.timestamp_millis() + (time_offset_seconds as i64 * 1000)
-    }
-
-    /// 获取UTC+8时间戳（秒）
-    pub fn get_utc8_timestamp_sec() -> i64 {
-        Self::get_utc8_timestamp() / 1000
-    }
-
-    /// 获取带时间偏移的UTC+8时间戳（秒）
-    pub fn get_utc8_timestamp_sec_with_offset(time_offset_seconds: i32) -> i64 {
-        Self::get_utc8_timestamp_with_offset(time_offset_seconds) / 1000
-    }
-
-    /// 格式化UTC+8时间为字符串
-    pub fn format_utc8_time(timestamp_ms: i64) -> String {
-        let beijing_tz = FixedOffset::east_opt(8 * 3600).unwrap();
-        let datetime = DateTime::from_timestamp_millis(timestamp_ms)
-            .unwrap()
-            .with_timezone(&beijing_tz);
-        
-        datetime.format("%Y-%m-%d %H:%M:%S").to_string()
+    /// 获取门锁时间戳（毫秒）
+    /// time_offset_seconds: 门锁时间相对时钟的偏移秒数，用于密码锁防重放攻击
+    pub fn lock_timestamp(clock: &dyn Clock, time_offset_seconds: i64) -> i64 {
+        clock.timestamp_millis() + time_offset_seconds * 1000
+    }
+
+    /// 获取当前时间戳（秒）
+    pub fn current_timestamp_sec() -> i64 {
+        Self::current_timestamp() / 1000
     }
-}
 
-impl Default for KeeLoqCrypto {
-    fn default() -> Self {
-        Self::new()
+    /// 将毫秒时间戳转换为UTC时间
+    pub fn datetime_from_millis(timestamp_ms: i64) -> DateTime<Utc> {
+        DateTime::from_timestamp_millis(timestamp_ms).unwrap()
     }
 }
 
@@ -222,9 +158,78 @@ mod tests {
     }
 
     #[test]
-    fn test_utc8_time() {
-        let timestamp = KeeLoqCrypto::get_utc8_timestamp();
-        let formatted = KeeLoqCrypto::format_utc8_time(timestamp);
-        println!("UTC+8 时间: {}", formatted);
+    fn test_golden_corpus() {
+        let crypto = KeeLoqCrypto::new();
+        let mut count = 0;
+
+        for line in include_str!("testdata/keeloq_golden.txt").lines() {
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let fields: Vec<&str> = line.split_whitespace().collect();
+            let (admin_pwd, input, code) = (fields[0], fields[1].parse::<u32>().unwrap(), fields[2]);
+
+            assert_eq!(crypto.crypt_usercode(input, admin_pwd), code, "{}", line);
+            assert_eq!(crypto.decrypt_usercode(code.parse().unwrap(), admin_pwd), input, "{}", line);
+            count += 1;
+        }
+
+        assert_eq!(count, 594);
+    }
+
+    #[test]
+    fn test_key_reuse() {
+        let crypto = KeeLoqCrypto::new();
+        let key = KeeLoqKey::new("123456");
+
+        // 短密码补0后与显式补0的密码相同，超过8位的部分被忽略
+        assert_eq!(key, KeeLoqKey::new("12345600"));
+        assert_eq!(KeeLoqKey::new("12345678"), KeeLoqKey::new("1234567890"));
+
+        for input in [0, 106_000_000, u32::MAX] {
+            let code = crypto.encrypt(input, &key);
+            assert_eq!(format!("{:010}", code), crypto.crypt_usercode(input, "123456"));
+            assert_eq!(crypto.decrypt(code, &key), input);
+        }
+    }
+
+    #[test]
+    fn test_decrypt_usercode() {
+        let crypto = KeeLoqCrypto::new();
+
+        // 管理密码覆盖补零、截断和非数字字符的情况
+        for admin_pwd in ["1234", "123456", "87654321", "1234567890", "12ab56cd"] {
+            for timestamp in (0..=u32::MAX).step_by(9_999_991).chain([1, u32::MAX - 1, u32::MAX]) {
+                let code: u32 = crypto.crypt_usercode(timestamp, admin_pwd).parse().unwrap();
+                assert_eq!(crypto.decrypt_usercode(code, admin_pwd), timestamp);
+            }
+
+            // 任意代码解密后再加密得到原代码
+            for code in (7..=u32::MAX).step_by(10_000_019) {
+                let timestamp = crypto.decrypt_usercode(code, admin_pwd);
+                assert_eq!(crypto.crypt_usercode(timestamp, admin_pwd), format!("{:010}", code));
+            }
+        }
+
+        // 不同管理密码解密结果不同
+        let code: u32 = crypto.crypt_usercode(106_000_000, "123456").parse().unwrap();
+        assert_ne!(crypto.decrypt_usercode(code, "654321"), 106_000_000);
+    }
+
+    #[test]
+    fn test_lock_timestamp() {
+        use super::super::clock::ManualClock;
+        use chrono::TimeZone;
+
+        let clock = ManualClock::new(Utc.with_ymd_and_hms(2024, 1, 1, 0, 0, 0).unwrap());
+        assert_eq!(KeeLoqCrypto::lock_timestamp(&clock, 0), 1704067200000);
+        assert_eq!(KeeLoqCrypto::lock_timestamp(&clock, -120), 1704067200000 - 120000);
+    }
+
+    #[test]
+    fn test_current_timestamp() {
+        let timestamp = KeeLoqCrypto::current_timestamp();
+        assert_eq!(KeeLoqCrypto::datetime_from_millis(timestamp).timestamp_millis(), timestamp);
+        println!("当前时间戳: {}", timestamp);
     }
 }
\ No newline at end of file
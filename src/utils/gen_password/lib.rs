@@ -6,28 +6,33 @@
 //! 3. 限时密码 - 指定时长有效
 //! 4. 周期密码 - 指定时间段有效
 //!
-//! 所有时间处理都使用UTC+8时区（北京时间）
+//! 周期密码的天数和所有时间显示都按门锁时区计算，默认为UTC+8（北京时间）
 
 // 核心模块
+pub mod clock;
 pub mod keeloq_crypto;
 pub mod temp_password;
 pub mod times_password;
 pub mod limited_password;
 pub mod period_password;
+pub mod timezone;
+pub mod vectors;
+pub mod calibration;
 
 // 工具模块
-pub mod ui_utils;
-pub mod demo_framework;
 pub mod config;
 
 // 重新导出核心功能
-pub use keeloq_crypto::KeeLoqCrypto;
+pub use clock::{Clock, ManualClock, SharedClock, SystemClock};
+pub use keeloq_crypto::{KeeLoqCrypto, KeeLoqKey};
+pub use timezone::LockTimezone;
+pub use calibration::{search_offsets, CalibrationSample, OffsetCandidate, MAX_CALIBRATION_DRIFT_SECS};
 
 // 密码生成器
-pub use temp_password::{TempPasswordGenerator, TempPasswordGeneratorWithOffset};
-pub use times_password::{TimesPasswordGenerator, TimesPasswordGeneratorWithOffset};
-pub use limited_password::{LimitedPasswordGenerator, LimitedPasswordGeneratorWithOffset};
-pub use period_password::{PeriodPasswordGenerator, PeriodPasswordGeneratorWithOffset};
+pub use temp_password::TempPasswordGenerator;
+pub use times_password::TimesPasswordGenerator;
+pub use limited_password::LimitedPasswordGenerator;
+pub use period_password::PeriodPasswordGenerator;
 
 // 便捷函数
 pub use temp_password::{generate_temp_password, verify_temp_password};
@@ -36,12 +41,52 @@ pub use limited_password::{generate_limited_password, verify_limited_password, c
 pub use period_password::{generate_period_password, generate_period_password_from_string, verify_period_password, check_period_password_remaining_time};
 
 // 工具功能
-pub use ui_utils::{InputHandler, MenuDisplay, Formatter, ErrorHandler};
-pub use demo_framework::DemoFramework;
-pub use config::{AppConfig, MenuOption, VerificationResult, TimeFormatter, ValidatorConfig, InputValidator};
+pub use config::{AppConfig, VerificationResult, TimeFormatter, ValidatorConfig, InputValidator};
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+/// 检查密码时，在密码最长有效期之外再向前搜索的时长（毫秒）
+const INSPECT_LOOKBACK_MS: i64 = 24 * 3600 * 1000;
+
+/// 检查密码时向后搜索的时长（毫秒），用于发现因时钟偏差尚未生效的密码
+const INSPECT_LOOKAHEAD_MS: i64 = 10 * 60 * 1000;
+
+/// 解析密码，去掉 5000000000 前缀得到加密代码
+///
+/// 加密代码为32位，超出范围的不可能由任何管理密码生成。
+fn parse_code(password: &str) -> Option<u32> {
+    match password.parse::<u64>() {
+        Ok(num) if num >= 5000000000 => u32::try_from(num - 5000000000).ok(),
+        _ => None,
+    }
+}
+
+/// 格式化剩余时间，精确到分钟
+fn format_remaining_hours(remaining_ms: i64) -> String {
+    let remaining_hours = remaining_ms / (1000 * 60 * 60);
+    let remaining_minutes = (remaining_ms % (1000 * 60 * 60)) / (1000 * 60);
+    format!("{}小时{}分钟", remaining_hours, remaining_minutes)
+}
+
+/// 格式化剩余时间，超过一天时显示天数，省略为0的高位
+fn format_remaining_days(remaining_ms: i64) -> String {
+    let remaining_days = remaining_ms / (1000 * 60 * 60 * 24);
+    let remaining_hours = (remaining_ms % (1000 * 60 * 60 * 24)) / (1000 * 60 * 60);
+    let remaining_minutes = (remaining_ms % (1000 * 60 * 60)) / (1000 * 60);
+
+    if remaining_days > 0 {
+        format!("{}天{}小时{}分钟", remaining_days, remaining_hours, remaining_minutes)
+    } else if remaining_hours > 0 {
+        format!("{}小时{}分钟", remaining_hours, remaining_minutes)
+    } else {
+        format!("{}分钟", remaining_minutes)
+    }
+}
 
 /// 密码类型枚举
-#[derive(Debug, Clone, PartialEq)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
 pub enum PasswordType {
     /// 临时密码
     Temporary,
@@ -53,6 +98,18 @@ pub enum PasswordType {
     Period(u32, u32, u32, u32), // year, month, day, hour
 }
 
+impl PasswordType {
+    /// 类型标识（用于持久化）
+    pub fn kind(&self) -> &'static str {
+        match self {
+            PasswordType::Temporary => "temporary",
+            PasswordType::Times(_) => "times",
+            PasswordType::Limited(_, _) => "limited",
+            PasswordType::Period(_, _, _, _) => "period",
+        }
+    }
+}
+
 impl std::fmt::Display for PasswordType {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
@@ -68,16 +125,92 @@ impl std::fmt::Display for PasswordType {
 }
 
 /// 密码生成结果
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct PasswordResult {
     /// 生成的密码
     pub password: String,
-    /// 过期时间
-    pub expire_time: String,
-    /// 描述消息
-    pub message: String,
     /// 密码类型
     pub password_type: PasswordType,
+    /// 生效时间（所用时间窗口的起点）
+    pub valid_from: DateTime<Utc>,
+    /// 失效时间
+    pub valid_until: DateTime<Utc>,
+    /// 可用次数，仅次数密码有值
+    pub remaining_uses: Option<u32>,
+    /// 加密时使用的时间窗口序号
+    ///
+    /// 临时密码为4秒窗口，次数密码为按32对齐的4秒窗口，限时密码为30分钟窗口，周期密码为门锁时区的天数。
+    pub window: u32,
+    /// 描述消息
+    pub message: String,
+}
+
+impl PasswordResult {
+    /// 过期时间（按指定时区显示，格式为 YYYY-MM-DD HH:MM:SS）
+    pub fn expire_time(&self, timezone: &LockTimezone) -> String {
+        timezone.format(self.valid_until)
+    }
+
+    /// 指定时间是否在有效期内
+    pub fn is_valid_at(&self, at: DateTime<Utc>) -> bool {
+        self.valid_from <= at && at <= self.valid_until
+    }
+
+    /// 将有效期整体平移，消息中按 `timezone` 显示的过期时间同步更新
+    pub fn shifted(mut self, offset: chrono::Duration, timezone: &LockTimezone) -> Self {
+        let old_expire_time = self.expire_time(timezone);
+        self.valid_from += offset;
+        self.valid_until += offset;
+        self.message = self.message.replace(&old_expire_time, &self.expire_time(timezone));
+        self
+    }
+}
+
+/// 密码检查结论
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "verdict", content = "result", rename_all = "snake_case")]
+pub enum PasswordInspection {
+    /// 在检查时间有效
+    Valid(PasswordResult),
+    /// 已于 `valid_until` 过期
+    Expired(PasswordResult),
+    /// 将于 `valid_from` 生效
+    NotYetValid(PasswordResult),
+    /// 不是由该管理密码生成，或生成时间超出检查范围
+    NotOurs,
+}
+
+impl PasswordInspection {
+    /// 根据检查时间判断匹配结果的状态
+    pub fn classify(result: PasswordResult, at: DateTime<Utc>) -> Self {
+        if at < result.valid_from {
+            PasswordInspection::NotYetValid(result)
+        } else if at > result.valid_until {
+            PasswordInspection::Expired(result)
+        } else {
+            PasswordInspection::Valid(result)
+        }
+    }
+
+    /// 匹配到的生成结果
+    pub fn result(&self) -> Option<&PasswordResult> {
+        match self {
+            PasswordInspection::Valid(result)
+            | PasswordInspection::Expired(result)
+            | PasswordInspection::NotYetValid(result) => Some(result),
+            PasswordInspection::NotOurs => None,
+        }
+    }
+
+    /// 取出匹配到的生成结果
+    pub fn into_result(self) -> Option<PasswordResult> {
+        match self {
+            PasswordInspection::Valid(result)
+            | PasswordInspection::Expired(result)
+            | PasswordInspection::NotYetValid(result) => Some(result),
+            PasswordInspection::NotOurs => None,
+        }
+    }
 }
 
 /// 统一的密码生成器
@@ -99,44 +232,44 @@ impl UnifiedPasswordGenerator {
         }
     }
 
+    /// 使用指定的时钟获取当前时间
+    pub fn with_clock(self, clock: SharedClock) -> Self {
+        UnifiedPasswordGenerator {
+            temp_gen: self.temp_gen.with_clock(clock.clone()),
+            times_gen: self.times_gen.with_clock(clock.clone()),
+            limited_gen: self.limited_gen.with_clock(clock.clone()),
+            period_gen: self.period_gen.with_clock(clock),
+        }
+    }
+
+    /// 设置门锁时间偏移（秒），生成、验证和检查都使用门锁时间，有效期换算回真实时间
+    pub fn with_time_offset(self, time_offset: i64) -> Self {
+        UnifiedPasswordGenerator {
+            temp_gen: self.temp_gen.with_time_offset(time_offset),
+            times_gen: self.times_gen.with_time_offset(time_offset),
+            limited_gen: self.limited_gen.with_time_offset(time_offset),
+            period_gen: self.period_gen.with_time_offset(time_offset),
+        }
+    }
+
+    /// 设置门锁时区，用于周期密码的天数计算和消息中的时间显示
+    pub fn with_timezone(self, timezone: LockTimezone) -> Self {
+        UnifiedPasswordGenerator {
+            temp_gen: self.temp_gen.with_timezone(timezone),
+            times_gen: self.times_gen.with_timezone(timezone),
+            limited_gen: self.limited_gen.with_timezone(timezone),
+            period_gen: self.period_gen.with_timezone(timezone),
+        }
+    }
+
     /// 根据密码类型生成密码
     pub fn generate(&self, admin_pwd: &str, password_type: PasswordType) -> Result<PasswordResult, String> {
         match password_type {
-            PasswordType::Temporary => {
-                let (password, expire_time, message) = self.temp_gen.generate(admin_pwd)?;
-                Ok(PasswordResult {
-                    password,
-                    expire_time,
-                    message,
-                    password_type: PasswordType::Temporary,
-                })
-            }
-            PasswordType::Times(count) => {
-                let (password, expire_time, message) = self.times_gen.generate(admin_pwd, count)?;
-                Ok(PasswordResult {
-                    password,
-                    expire_time,
-                    message,
-                    password_type: PasswordType::Times(count),
-                })
-            }
-            PasswordType::Limited(hours, minutes) => {
-                let (password, expire_time, message) = self.limited_gen.generate(admin_pwd, hours, minutes)?;
-                Ok(PasswordResult {
-                    password,
-                    expire_time,
-                    message,
-                    password_type: PasswordType::Limited(hours, minutes),
-                })
-            }
+            PasswordType::Temporary => self.temp_gen.generate(admin_pwd),
+            PasswordType::Times(count) => self.times_gen.generate(admin_pwd, count),
+            PasswordType::Limited(hours, minutes) => self.limited_gen.generate(admin_pwd, hours, minutes),
             PasswordType::Period(year, month, day, hour) => {
-                let (password, expire_time, message) = self.period_gen.generate(admin_pwd, year, month, day, hour)?;
-                Ok(PasswordResult {
-                    password,
-                    expire_time,
-                    message,
-                    password_type: PasswordType::Period(year, month, day, hour),
-                })
+                self.period_gen.generate(admin_pwd, year, month, day, hour)
             }
         }
     }
@@ -158,15 +291,32 @@ impl UnifiedPasswordGenerator {
             return Some(PasswordType::Limited(hours, minutes));
         }
 
-        // 尝试验证周期密码
-        if let Some(_expire_time) = self.period_gen.verify(password, admin_pwd, 1) {
-            // 周期密码无法从密码本身推断原始参数，返回通用标识
-            return Some(PasswordType::Period(0, 0, 0, 0));
+        // 尝试验证周期密码，结束日期和小时由解密恢复
+        if let Some(result) = self.period_gen.verify_result(password, admin_pwd, 1) {
+            return Some(result.password_type);
         }
 
         None
     }
 
+    /// 检查密码（自动识别类型）
+    ///
+    /// 依次按四种算法解密，从时间窗口落在检查范围内的结果恢复密码类型、参数和准确的有效期，
+    /// 并给出相对于 `at` 的结论。过期较久的密码不在检查范围内，会被判定为 `NotOurs`。
+    pub fn inspect(&self, password: &str, admin_pwd: &str, at: DateTime<Utc>) -> PasswordInspection {
+        let matched = self
+            .temp_gen
+            .inspect(password, admin_pwd, at)
+            .or_else(|| self.times_gen.inspect(password, admin_pwd, at))
+            .or_else(|| self.limited_gen.inspect(password, admin_pwd, at))
+            .or_else(|| self.period_gen.inspect(password, admin_pwd, at));
+
+        match matched {
+            Some(result) => PasswordInspection::classify(result, at),
+            None => PasswordInspection::NotOurs,
+        }
+    }
+
     /// 获取密码剩余有效时间
     pub fn get_remaining_time(&self, password: &str, admin_pwd: &str) -> Option<String> {
         // 尝试不同类型的剩余时间检查
@@ -175,16 +325,16 @@ impl UnifiedPasswordGenerator {
             return Some(format!("{}分钟", remaining_minutes));
         }
 
-        if let Some((remaining_str, _times)) = check_password_remaining_time(password, admin_pwd) {
-            return Some(remaining_str);
+        if let Some((remaining_ms, _times)) = self.times_gen.check_remaining_time(password, admin_pwd) {
+            return Some(format_remaining_hours(remaining_ms));
         }
 
-        if let Some((remaining_str, _hours, _minutes)) = check_limited_password_remaining_time(password, admin_pwd) {
-            return Some(remaining_str);
+        if let Some((remaining_ms, _hours, _minutes)) = self.limited_gen.check_remaining_time(password, admin_pwd) {
+            return Some(format_remaining_hours(remaining_ms));
         }
 
-        if let Some((remaining_str, _expire_time)) = check_period_password_remaining_time(password, admin_pwd) {
-            return Some(remaining_str);
+        if let Some((remaining_ms, _expire_time)) = self.period_gen.check_remaining_time(password, admin_pwd) {
+            return Some(format_remaining_days(remaining_ms));
         }
 
         None
@@ -209,6 +359,12 @@ pub fn verify_password(password: &str, admin_pwd: &str) -> Option<PasswordType>
     generator.verify(password, admin_pwd)
 }
 
+/// 便捷函数：检查任意密码
+pub fn inspect_password(password: &str, admin_pwd: &str, at: DateTime<Utc>) -> PasswordInspection {
+    let generator = UnifiedPasswordGenerator::new();
+    generator.inspect(password, admin_pwd, at)
+}
+
 /// 便捷函数：获取任意密码的剩余时间
 pub fn get_password_remaining_time(password: &str, admin_pwd: &str) -> Option<String> {
     let generator = UnifiedPasswordGenerator::new();
@@ -218,6 +374,7 @@ pub fn get_password_remaining_time(password: &str, admin_pwd: &str) -> Option<St
 #[cfg(test)]
 mod tests {
     use super::*;
+    use chrono::Datelike;
 
     #[test]
     fn test_unified_generator() {
@@ -250,6 +407,70 @@ mod tests {
         let verified_type = generator.verify(&temp_result.password, admin_pwd);
         assert!(verified_type.is_some());
         println!("验证临时密码成功: {:?}", verified_type);
+
+        // 周期密码返回解密恢复的结束日期和小时
+        let end = LockTimezone::default().to_local(Utc::now() + chrono::Duration::days(3));
+        let period = PasswordType::Period(end.year() as u32, end.month(), end.day(), 18);
+        let period_result = generator.generate(admin_pwd, period.clone()).unwrap();
+        assert_eq!(generator.verify(&period_result.password, admin_pwd), Some(period));
+    }
+
+    #[test]
+    fn test_inspect_recovers_parameters() {
+        let generator = UnifiedPasswordGenerator::new();
+        let admin_pwd = "123456";
+        let now = Utc::now();
+
+        let result = generator.generate(admin_pwd, PasswordType::Temporary).unwrap();
+        assert_eq!(generator.inspect(&result.password, admin_pwd, now), PasswordInspection::Valid(result));
+
+        // 逐个算法检查，避免在调试构建中重复搜索其他算法
+        let result = generator.generate(admin_pwd, PasswordType::Times(7)).unwrap();
+        assert_eq!(generator.times_gen.inspect(&result.password, admin_pwd, now), Some(result));
+
+        let result = generator.generate(admin_pwd, PasswordType::Limited(2, 30)).unwrap();
+        assert_eq!(generator.limited_gen.inspect(&result.password, admin_pwd, now), Some(result));
+
+        let end = LockTimezone::default().to_local(now + chrono::Duration::days(3));
+        let period = PasswordType::Period(end.year() as u32, end.month(), end.day(), 18);
+        let result = generator.generate(admin_pwd, period).unwrap();
+        assert_eq!(generator.period_gen.inspect(&result.password, admin_pwd, now), Some(result));
+    }
+
+    #[test]
+    fn test_inspect_verdicts() {
+        let generator = UnifiedPasswordGenerator::new();
+        let admin_pwd = "123456";
+
+        let result = generator.generate(admin_pwd, PasswordType::Temporary).unwrap();
+        let after = result.valid_until + chrono::Duration::minutes(1);
+        assert_eq!(
+            generator.inspect(&result.password, admin_pwd, after),
+            PasswordInspection::Expired(result.clone())
+        );
+
+        let before = result.valid_from - chrono::Duration::minutes(5);
+        assert_eq!(
+            generator.inspect(&result.password, admin_pwd, before),
+            PasswordInspection::NotYetValid(result)
+        );
+
+        // 格式不符的密码直接判定为不是本系统生成
+        assert_eq!(generator.inspect("1234", admin_pwd, Utc::now()), PasswordInspection::NotOurs);
+        assert_eq!(generator.inspect("abc", admin_pwd, Utc::now()), PasswordInspection::NotOurs);
+        assert_eq!(generator.inspect("5012345678", "123", Utc::now()), PasswordInspection::NotOurs);
+    }
+
+    #[test]
+    fn test_shifted_result() {
+        let generator = UnifiedPasswordGenerator::new();
+        let result = generator.generate("123456", PasswordType::Temporary).unwrap();
+        let timezone = LockTimezone::default();
+        let shifted = result.clone().shifted(chrono::Duration::seconds(-30), &timezone);
+
+        assert_eq!(result.valid_until - shifted.valid_until, chrono::Duration::seconds(30));
+        assert!(shifted.message.contains(&shifted.expire_time(&timezone)));
+        assert_eq!(shifted.window, result.window);
     }
 
     #[test]
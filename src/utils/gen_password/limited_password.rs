@@ -1,11 +1,31 @@
 //! 限时密码生成算法 Rust 实现
 //! 生成基于指定时长有效的密码
 
+use super::clock::{SharedClock, SystemClock};
 use super::keeloq_crypto::KeeLoqCrypto;
+use super::timezone::LockTimezone;
+use super::{format_remaining_hours, parse_code, PasswordResult, PasswordType, INSPECT_LOOKAHEAD_MS, INSPECT_LOOKBACK_MS};
+use chrono::{DateTime, Utc};
+
+/// 生成持续时间描述
+fn duration_description(hours: u32, minutes: u32) -> String {
+    if hours == 0 {
+        format!("{}分钟", minutes)
+    } else if minutes == 0 {
+        format!("{}小时", hours)
+    } else {
+        format!("{}小时{}分钟", hours, minutes)
+    }
+}
 
 /// 限时密码生成器
 pub struct LimitedPasswordGenerator {
     crypto: KeeLoqCrypto,
+    clock: SharedClock,
+    /// 门锁时间相对真实时间的偏移（秒）
+    time_offset: i64,
+    /// 门锁时区，用于消息中的时间显示
+    timezone: LockTimezone,
 }
 
 impl LimitedPasswordGenerator {
@@ -13,9 +33,42 @@ impl LimitedPasswordGenerator {
     pub fn new() -> Self {
         LimitedPasswordGenerator {
             crypto: KeeLoqCrypto::new(),
+            clock: SystemClock::shared(),
+            time_offset: 0,
+            timezone: LockTimezone::default(),
         }
     }
 
+    /// 使用指定的时钟获取当前时间
+    pub fn with_clock(mut self, clock: SharedClock) -> Self {
+        self.clock = clock;
+        self
+    }
+
+    /// 设置门锁时间偏移（秒）
+    ///
+    /// 加密使用门锁时间（真实时间加偏移），返回的有效期换算回真实时间。
+    pub fn with_time_offset(mut self, time_offset: i64) -> Self {
+        self.time_offset = time_offset;
+        self
+    }
+
+    /// 设置门锁时区
+    pub fn with_timezone(mut self, timezone: LockTimezone) -> Self {
+        self.timezone = timezone;
+        self
+    }
+
+    /// 当前门锁时间戳（毫秒）
+    fn lock_time_ms(&self) -> i64 {
+        KeeLoqCrypto::lock_timestamp(self.clock.as_ref(), self.time_offset)
+    }
+
+    /// 将按门锁时间计算的生成结果换算为真实时间
+    fn to_real_time(&self, result: PasswordResult) -> PasswordResult {
+        result.shifted(chrono::Duration::seconds(-self.time_offset), &self.timezone)
+    }
+
     /// 生成限时密码
     /// 
     /// # 参数
@@ -24,9 +77,9 @@ impl LimitedPasswordGenerator {
     /// * `minutes` - 有效分钟数（0或30）
     /// 
     /// # 返回值
-    /// * `Ok((password, expire_time, message))` - 成功时返回密码、过期时间和消息
+    /// * `Ok(PasswordResult)` - 成功时返回密码、有效期和消息
     /// * `Err(String)` - 失败时返回错误信息
-    pub fn generate(&self, admin_pwd: &str, hours: u32, minutes: u32) -> Result<(String, String, String), String> {
+    pub fn generate(&self, admin_pwd: &str, hours: u32, minutes: u32) -> Result<PasswordResult, String> {
         // 检查管理员密码长度
         if admin_pwd.len() < 4 {
             return Err("管理员密码至少需要4位".to_string());
@@ -45,15 +98,12 @@ impl LimitedPasswordGenerator {
         // 计算总的半小时数
         let total_half_hours = hours * 2 + (minutes / 30);
 
-        // 获取UTC+8当前时间戳（毫秒）
-        let current_time_ms = KeeLoqCrypto::get_utc8_timestamp();
+        // 获取门锁当前时间戳（毫秒）
+        let current_time_ms = self.lock_time_ms();
         
         // 转换为30分钟时间窗口 (对应 t = Math.floor(t / 18e5))
         let time_window = (current_time_ms / 1800000) as u32;
         
-        // 计算过期时间 (对应 i = 18e5 * (t + r))
-        let expire_time_ms = ((time_window + total_half_hours) as i64) * 1800000;
-        
         // 构造加密输入 (对应 d = 256 * t + 2147483648 + r)
         let mut crypto_input = time_window * 256;
         crypto_input += 2147483648; // 0x80000000
@@ -66,30 +116,60 @@ impl LimitedPasswordGenerator {
         let password_num = 5000000000u64 + encrypted_code.parse::<u64>().unwrap_or(0);
         let password = password_num.to_string();
         
-        // 格式化过期时间
-        let expire_time_str = KeeLoqCrypto::format_utc8_time(expire_time_ms);
+        Ok(self.to_real_time(self.result_for_window(password, time_window, total_half_hours)))
+    }
+
+    /// 根据30分钟时间窗口和半小时数构造生成结果
+    fn result_for_window(&self, password: String, time_window: u32, total_half_hours: u32) -> PasswordResult {
+        let (hours, minutes) = Self::from_half_hours(total_half_hours);
         
-        // 生成持续时间描述
-        let duration_desc = if hours == 0 {
-            format!("{}分钟", minutes)
-        } else if minutes == 0 {
-            if hours == 1 {
-                "1小时".to_string()
-            } else {
-                format!("{}小时", hours)
-            }
-        } else {
-            if hours == 1 {
-                format!("1小时{}分钟", minutes)
-            } else {
-                format!("{}小时{}分钟", hours, minutes)
-            }
-        };
+        // 计算过期时间 (对应 i = 18e5 * (t + r))
+        let valid_from_ms = (time_window as i64) * 1800000;
+        let expire_time_ms = ((time_window + total_half_hours) as i64) * 1800000;
         
-        // 生成消息
-        let message = format!("限时密码，有效时长{}，过期时间 {}", duration_desc, expire_time_str);
+        let message = format!(
+            "限时密码，有效时长{}，过期时间 {}",
+            duration_description(hours, minutes),
+            self.timezone.format_millis(expire_time_ms)
+        );
         
-        Ok((password, expire_time_str, message))
+        PasswordResult {
+            password,
+            password_type: PasswordType::Limited(hours, minutes),
+            valid_from: KeeLoqCrypto::datetime_from_millis(valid_from_ms),
+            valid_until: KeeLoqCrypto::datetime_from_millis(expire_time_ms),
+            remaining_uses: None,
+            window: time_window,
+            message,
+        }
+    }
+
+    /// 解密密码，得到30分钟时间窗口和半小时数
+    fn decrypt_params(&self, password: &str, admin_pwd: &str) -> Option<(u32, u32)> {
+        if admin_pwd.len() < 4 {
+            return None;
+        }
+        let code = parse_code(password)?;
+
+        // 加密输入为 256 * 时间窗口 + 0x80000000 + 半小时数 (对应 d = 256 * t + 2147483648 + r)
+        let crypto_input = self.crypto.decrypt_usercode(code, admin_pwd).wrapping_sub(2147483648);
+        Some((crypto_input >> 8, crypto_input & 0xFF))
+    }
+
+    /// 检查限时密码
+    ///
+    /// 解密得到生成该密码的时间窗口和有效时长，无论是否仍在有效期内都返回对应的生成结果。
+    /// 只接受最长有效期（127.5小时）之外再向前1天、向后10分钟内的窗口，超出范围时返回None。
+    pub fn inspect(&self, password: &str, admin_pwd: &str, at: DateTime<Utc>) -> Option<PasswordResult> {
+        let (window, total_half_hours) = self.decrypt_params(password, admin_pwd)?;
+
+        let at_ms = at.timestamp_millis() + self.time_offset * 1000;
+        let newest = ((at_ms + INSPECT_LOOKAHEAD_MS) / 1800000) as u32;
+        let oldest = ((at_ms - 255 * 1800000 - INSPECT_LOOKBACK_MS) / 1800000) as u32;
+
+        (oldest..=newest)
+            .contains(&window)
+            .then(|| self.to_real_time(self.result_for_window(password.to_string(), window, total_half_hours)))
     }
 
     /// 验证限时密码是否有效
@@ -103,54 +183,19 @@ impl LimitedPasswordGenerator {
     /// * `Some((hours, minutes))` - 密码有效，返回原始的小时和分钟设置
     /// * `None` - 密码无效
     pub fn verify(&self, password: &str, admin_pwd: &str, tolerance_windows: u32) -> Option<(u32, u32)> {
-        if admin_pwd.len() < 4 {
-            return None;
-        }
+        let (window, total_half_hours) = self.decrypt_params(password, admin_pwd)?;
 
-        // 移除前缀，获取实际的加密代码 (对应 5e9 前缀)
-        let password_num = match password.parse::<u64>() {
-            Ok(num) if num >= 5000000000 => num - 5000000000,
-            _ => return None,
-        };
-
-        let current_time_ms = KeeLoqCrypto::get_utc8_timestamp();
+        let current_time_ms = self.lock_time_ms();
         let current_window = (current_time_ms / 1800000) as u32;
 
-        // 在容忍范围内检查时间窗口
-        for window_offset in 0..=tolerance_windows {
-            for &direction in &[0i32, 1i32, -1i32] {
-                let check_window = match direction {
-                    0 => current_window,
-                    1 => current_window + window_offset,
-                    -1 => current_window.wrapping_sub(window_offset),
-                    _ => continue,
-                };
-
-                // 检查不同的时长组合
-                for hours in 0..=127 {
-                    for &minutes in &[0, 30] {
-                        let total_half_hours = hours * 2 + (minutes / 30);
-                        
-                        // 使用检查窗口作为基准计算
-                        let mut crypto_input = check_window * 256;
-                        crypto_input += 2147483648;
-                        crypto_input += total_half_hours;
-                        
-                        let expected_code = self.crypto.crypt_usercode(crypto_input, admin_pwd);
-                        
-                        if password_num == expected_code.parse::<u64>().unwrap_or(0) {
-                            // 检查密码是否还在有效期内
-                            let password_expire_time = ((check_window + total_half_hours) as i64) * 1800000;
-                            if current_time_ms <= password_expire_time {
-                                return Some((hours, minutes));
-                            }
-                        }
-                    }
-                }
-            }
+        // 密码的时间窗口与当前窗口前后相差不超过 tolerance_windows
+        if window.abs_diff(current_window) > tolerance_windows {
+            return None;
         }
 
-        None
+        // 检查密码是否还在有效期内
+        let password_expire_time = ((window + total_half_hours) as i64) * 1800000;
+        (current_time_ms <= password_expire_time).then(|| Self::from_half_hours(total_half_hours))
     }
 
     /// 检查密码剩余有效时间
@@ -164,7 +209,7 @@ impl LimitedPasswordGenerator {
     /// * `None` - 密码无效或已过期
     pub fn check_remaining_time(&self, password: &str, admin_pwd: &str) -> Option<(i64, u32, u32)> {
         if let Some((hours, minutes)) = self.verify(password, admin_pwd, 5) {
-            let current_time_ms = KeeLoqCrypto::get_utc8_timestamp();
+            let current_time_ms = self.lock_time_ms();
             let current_window = (current_time_ms / 1800000) as u32;
             
             // 根据验证结果计算过期时间
@@ -182,15 +227,15 @@ impl LimitedPasswordGenerator {
     }
 
     /// 获取当前时间窗口信息（30分钟为一个窗口）
-    pub fn get_current_window_info() -> (u32, String, String) {
-        let current_time_ms = KeeLoqCrypto::get_utc8_timestamp();
+    pub fn get_current_window_info(timezone: &LockTimezone) -> (u32, String, String) {
+        let current_time_ms = KeeLoqCrypto::current_timestamp();
         let current_window = (current_time_ms / 1800000) as u32;
         
         let window_start_ms = (current_window as i64) * 1800000;
         let window_end_ms = window_start_ms + 1800000;
         
-        let start_time = KeeLoqCrypto::format_utc8_time(window_start_ms);
-        let end_time = KeeLoqCrypto::format_utc8_time(window_end_ms);
+        let start_time = timezone.format_millis(window_start_ms);
+        let end_time = timezone.format_millis(window_end_ms);
         
         (current_window, start_time, end_time)
     }
@@ -222,7 +267,7 @@ impl Default for LimitedPasswordGenerator {
 }
 
 /// 便捷函数：生成限时密码
-pub fn generate_limited_password(admin_pwd: &str, hours: u32, minutes: u32) -> Result<(String, String, String), String> {
+pub fn generate_limited_password(admin_pwd: &str, hours: u32, minutes: u32) -> Result<PasswordResult, String> {
     let generator = LimitedPasswordGenerator::new();
     generator.generate(admin_pwd, hours, minutes)
 }
@@ -237,10 +282,7 @@ pub fn verify_limited_password(password: &str, admin_pwd: &str) -> Option<(u32,
 pub fn check_limited_password_remaining_time(password: &str, admin_pwd: &str) -> Option<(String, u32, u32)> {
     let generator = LimitedPasswordGenerator::new();
     if let Some((remaining_ms, hours, minutes)) = generator.check_remaining_time(password, admin_pwd) {
-        let remaining_hours = remaining_ms / (1000 * 60 * 60);
-        let remaining_minutes = (remaining_ms % (1000 * 60 * 60)) / (1000 * 60);
-        let remaining_str = format!("{}小时{}分钟", remaining_hours, remaining_minutes);
-        Some((remaining_str, hours, minutes))
+        Some((format_remaining_hours(remaining_ms), hours, minutes))
     } else {
         None
     }
@@ -249,6 +291,9 @@ pub fn check_limited_password_remaining_time(password: &str, admin_pwd: &str) ->
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::utils::gen_password::{Clock, ManualClock};
+    use chrono::{Duration, TimeZone};
+    use std::collections::HashMap;
 
     #[test]
     fn test_generate_limited_password() {
@@ -256,12 +301,18 @@ mod tests {
         
         // 测试生成2小时30分钟的限时密码
         match generator.generate("123456", 2, 30) {
-            Ok((password, expire_time, message)) => {
-                println!("限时密码: {}", password);
-                println!("过期时间: {}", expire_time);
-                println!("消息: {}", message);
-                assert!(password.len() >= 10);
-                assert!(message.contains("2小时30分钟"));
+            Ok(result) => {
+                println!("限时密码: {}", result.password);
+                println!("过期时间: {}", result.expire_time(&LockTimezone::default()));
+                println!("消息: {}", result.message);
+                assert!(result.password.len() >= 10);
+                assert!(result.message.contains("2小时30分钟"));
+                assert_eq!(result.password_type, PasswordType::Limited(2, 30));
+                assert_eq!(result.valid_from.timestamp_millis(), result.window as i64 * 1800000);
+                assert_eq!(
+                    result.valid_until - result.valid_from,
+                    chrono::Duration::hours(2) + chrono::Duration::minutes(30)
+                );
             }
             Err(e) => panic!("生成限时密码失败: {}", e),
         }
@@ -290,7 +341,7 @@ mod tests {
         let minutes = 30;
         
         // 生成密码
-        if let Ok((password, _, _)) = generator.generate(admin_pwd, hours, minutes) {
+        if let Ok(PasswordResult { password, .. }) = generator.generate(admin_pwd, hours, minutes) {
             // 验证密码应该成功并返回正确的时长
             if let Some((verified_hours, verified_minutes)) = generator.verify(&password, admin_pwd, 2) {
                 println!("验证成功，时长: {}小时{}分钟", verified_hours, verified_minutes);
@@ -319,7 +370,7 @@ mod tests {
 
     #[test]
     fn test_window_info() {
-        let (window, start, end) = LimitedPasswordGenerator::get_current_window_info();
+        let (window, start, end) = LimitedPasswordGenerator::get_current_window_info(&LockTimezone::default());
         println!("当前30分钟窗口: {}", window);
         println!("窗口开始时间: {}", start);
         println!("窗口结束时间: {}", end);
@@ -341,7 +392,7 @@ mod tests {
         
         for (hours, minutes) in test_cases {
             match generator.generate(admin_pwd, hours, minutes) {
-                Ok((password, _, message)) => {
+                Ok(PasswordResult { password, message, .. }) => {
                     println!("时长 {}h{}m 的密码: {}", hours, minutes, password);
                     println!("消息: {}", message);
                     
@@ -360,7 +411,7 @@ mod tests {
     fn test_convenience_functions() {
         // 测试便捷函数
         match generate_limited_password("123456", 3, 0) {
-            Ok((password, _, _)) => {
+            Ok(PasswordResult { password, .. }) => {
                 println!("便捷函数生成的限时密码: {}", password);
                 if let Some((hours, minutes)) = verify_limited_password(&password, "123456") {
                     assert_eq!(hours, 3);
@@ -375,166 +426,81 @@ mod tests {
             Err(e) => panic!("便捷函数失败: {}", e),
         }
     }
-}
-
-/// 支持时间偏移的限时密码生成器
-pub struct LimitedPasswordGeneratorWithOffset {
-    crypto: KeeLoqCrypto,
-    time_offset: i32,
-}
-
-impl LimitedPasswordGeneratorWithOffset {
-    /// 创建新的带时间偏移的限时密码生成器实例
-    pub fn new(time_offset: i32) -> Self {
-        LimitedPasswordGeneratorWithOffset {
-            crypto: KeeLoqCrypto::new(),
-            time_offset,
-        }
-    }
-
-    /// 生成限时密码（考虑时间偏移）
-    pub fn generate(&self, admin_pwd: &str, hours: u32, minutes: u32) -> Result<(String, String, String), String> {
-        // 检查管理员密码长度
-        if admin_pwd.len() < 4 {
-            return Err("管理员密码至少需要4位".to_string());
-        }
-
-        // 检查小时数范围
-        if hours > 127 {
-            return Err("小时数不能超过127".to_string());
-        }
 
-        // 检查分钟数（只允许0或30）
-        if minutes != 0 && minutes != 30 {
-            return Err("分钟数只能是0或30".to_string());
-        }
-
-        // 计算总的半小时数
-        let total_half_hours = hours * 2 + (minutes / 30);
-
-        // 获取带偏移的UTC+8当前时间戳（毫秒）
-        let current_time_ms = KeeLoqCrypto::get_utc8_timestamp_with_offset(self.time_offset);
-        
-        // 转换为30分钟时间窗口
-        let time_window = (current_time_ms / 1800000) as u32;
-        
-        // 计算过期时间（显示给用户的是真实时间）
-        let real_current_time_ms = KeeLoqCrypto::get_utc8_timestamp();
-        let real_time_window = (real_current_time_ms / 1800000) as u32;
-        let expire_time_ms = ((real_time_window + total_half_hours) as i64) * 1800000;
-        
-        // 构造加密输入
-        let mut crypto_input = time_window * 256;
-        crypto_input += 2147483648; // 0x80000000
-        crypto_input += total_half_hours;
-        
-        // 使用KeeLoq加密算法生成密码
-        let encrypted_code = self.crypto.crypt_usercode(crypto_input, admin_pwd);
-        
-        // 添加前缀
-        let password_num = 5000000000u64 + encrypted_code.parse::<u64>().unwrap_or(0);
-        let password = password_num.to_string();
-        
-        // 格式化过期时间（显示真实时间）
-        let expire_time_str = KeeLoqCrypto::format_utc8_time(expire_time_ms);
-        
-        // 生成持续时间描述
-        let duration_desc = if hours == 0 {
-            format!("{}分钟", minutes)
-        } else if minutes == 0 {
-            if hours == 1 {
-                "1小时".to_string()
-            } else {
-                format!("{}小时", hours)
-            }
-        } else {
-            if hours == 1 {
-                format!("1小时{}分钟", minutes)
-            } else {
-                format!("{}小时{}分钟", hours, minutes)
-            }
-        };
-        
-        // 生成消息
-        let message = format!("限时密码，有效时长{}，过期时间 {}", duration_desc, expire_time_str);
-        
-        Ok((password, expire_time_str, message))
+    #[test]
+    fn test_clock_and_time_offset() {
+        let now = Utc.with_ymd_and_hms(2024, 1, 1, 10, 29, 0).unwrap();
+        let clock = ManualClock::new(now);
+        let generator = LimitedPasswordGenerator::new().with_clock(clock.shared());
+        let offset_generator = LimitedPasswordGenerator::new()
+            .with_clock(clock.shared())
+            .with_time_offset(120);
+
+        // 门锁已进入下一个30分钟窗口，有效期按真实时间计算
+        let offset_result = offset_generator.generate("123456", 1, 0).unwrap();
+        assert_eq!(offset_result.valid_from, Utc.with_ymd_and_hms(2024, 1, 1, 10, 28, 0).unwrap());
+        assert_eq!(offset_result.valid_until, Utc.with_ymd_and_hms(2024, 1, 1, 11, 28, 0).unwrap());
+        assert_eq!(offset_generator.verify(&offset_result.password, "123456", 0), Some((1, 0)));
+
+        clock.advance(Duration::minutes(2));
+        let lock_result = generator.generate("123456", 1, 0).unwrap();
+        assert_eq!(offset_result.password, lock_result.password);
+        assert_eq!(lock_result.valid_from, Utc.with_ymd_and_hms(2024, 1, 1, 10, 30, 0).unwrap());
     }
 
-    /// 验证限时密码是否有效（考虑时间偏移）
-    pub fn verify(&self, password: &str, admin_pwd: &str, tolerance_windows: u32) -> Option<(u32, u32)> {
-        if admin_pwd.len() < 4 {
-            return None;
-        }
-
-        // 移除前缀，获取实际的加密代码
-        let password_num = match password.parse::<u64>() {
-            Ok(num) if num >= 5000000000 => num - 5000000000,
-            _ => return None,
-        };
-
-        // 使用带偏移的时间戳
-        let current_time_ms = KeeLoqCrypto::get_utc8_timestamp_with_offset(self.time_offset);
+    /// 原有的逐个时间窗口和时长加密比对的验证方式，列出其接受的全部加密代码及对应的时长
+    fn scanned_codes(
+        crypto: &KeeLoqCrypto,
+        admin_pwd: &str,
+        current_time_ms: i64,
+        tolerance_windows: u32,
+    ) -> HashMap<u32, (u32, u32)> {
         let current_window = (current_time_ms / 1800000) as u32;
+        let mut codes = HashMap::new();
 
-        // 在容忍范围内检查时间窗口
         for window_offset in 0..=tolerance_windows {
-            for &direction in &[0i32, 1i32, -1i32] {
-                let check_window = match direction {
-                    0 => current_window,
-                    1 => current_window + window_offset,
-                    -1 => current_window.wrapping_sub(window_offset),
-                    _ => continue,
-                };
-
-                // 检查不同的时长组合
+            for check_window in [current_window, current_window + window_offset, current_window - window_offset] {
                 for hours in 0..=127 {
-                    for &minutes in &[0, 30] {
+                    for minutes in [0, 30] {
                         let total_half_hours = hours * 2 + (minutes / 30);
-                        
-                        let mut crypto_input = check_window * 256;
-                        crypto_input += 2147483648;
-                        crypto_input += total_half_hours;
-                        
-                        let expected_code = self.crypto.crypt_usercode(crypto_input, admin_pwd);
-                        
-                        if password_num == expected_code.parse::<u64>().unwrap_or(0) {
-                            // 检查密码是否还在有效期内（基于真实时间）
-                            let real_current_time_ms = KeeLoqCrypto::get_utc8_timestamp();
-                            let password_expire_time = ((check_window + total_half_hours) as i64) * 1800000;
-                            if real_current_time_ms <= password_expire_time {
-                                return Some((hours, minutes));
-                            }
+                        let crypto_input = check_window * 256 + 2147483648 + total_half_hours;
+                        let code = crypto.crypt_usercode(crypto_input, admin_pwd).parse().unwrap();
+                        if current_time_ms <= ((check_window + total_half_hours) as i64) * 1800000 {
+                            codes.entry(code).or_insert((hours, minutes));
                         }
                     }
                 }
             }
         }
 
-        None
+        codes
     }
 
-    /// 检查密码剩余有效时间（考虑时间偏移）
-    pub fn check_remaining_time(&self, password: &str, admin_pwd: &str) -> Option<(String, u32, u32)> {
-        if let Some((hours, minutes)) = self.verify(password, admin_pwd, 5) {
-            // 显示的剩余时间基于真实时间
-            let current_time_ms = KeeLoqCrypto::get_utc8_timestamp();
+    #[test]
+    fn test_decrypt_matches_window_scan() {
+        let crypto = KeeLoqCrypto::new();
+        let clock = ManualClock::new(Utc.with_ymd_and_hms(2024, 6, 1, 12, 0, 1).unwrap());
+        let generator = LimitedPasswordGenerator::new().with_clock(clock.shared());
+
+        for (admin_pwd, elapsed) in [("123456", Duration::zero()), ("1234567890", Duration::minutes(29))] {
+            clock.advance(elapsed);
+            let current_time_ms = clock.now().timestamp_millis();
             let current_window = (current_time_ms / 1800000) as u32;
-            
-            // 根据验证结果计算过期时间
-            let _total_half_hours = hours * 2 + (minutes / 30);
-            let expire_window = current_window + 1; // 至少到下个窗口
-            let expire_time_ms = (expire_window as i64) * 1800000;
-            
-            let remaining_ms = expire_time_ms - current_time_ms;
-            
-            if remaining_ms > 0 {
-                let remaining_hours = remaining_ms / (1000 * 60 * 60);
-                let remaining_minutes = (remaining_ms % (1000 * 60 * 60)) / (1000 * 60);
-                let remaining_str = format!("{}小时{}分钟", remaining_hours, remaining_minutes);
-                return Some((remaining_str, hours, minutes));
+
+            for tolerance in [0, 2] {
+                let accepted = scanned_codes(&crypto, admin_pwd, current_time_ms, tolerance);
+
+                // 范围内外各窗口的短时长输入（包括已过期的），以及任意代码
+                let nearby = (current_window - tolerance - 1..=current_window + tolerance + 1)
+                    .flat_map(|window| [0, 1, 2, 255].map(|half_hours| window * 256 + 2147483648 + half_hours))
+                    .map(|crypto_input| crypto.crypt_usercode(crypto_input, admin_pwd).parse::<u32>().unwrap());
+                let candidates: Vec<u32> = accepted.keys().copied().chain(nearby).chain([0, u32::MAX]).collect();
+
+                for code in candidates {
+                    let password = (5000000000u64 + code as u64).to_string();
+                    assert_eq!(generator.verify(&password, admin_pwd, tolerance), accepted.get(&code).copied());
+                }
             }
         }
-        None
     }
-}
\ No newline at end of file
+}
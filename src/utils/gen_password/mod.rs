@@ -6,7 +6,7 @@
 //! 3. 限时密码 - 指定时长有效
 //! 4. 周期密码 - 指定时间段有效
 //!
-//! 所有时间处理都使用UTC+8时区（北京时间）
+//! 周期密码按门锁时区的自然日计算，默认为UTC+8（北京时间）
 
 // 重新导出lib.rs中的所有内容
 pub use self::lib::*;
@@ -1,12 +1,25 @@
 //! 周期密码生成算法 Rust 实现
 //! 生成在指定时间段内有效的密码
 
+use super::clock::{SharedClock, SystemClock};
 use super::keeloq_crypto::KeeLoqCrypto;
-use chrono::{DateTime, FixedOffset, NaiveDate, NaiveDateTime, NaiveTime, TimeZone};
+use super::timezone::LockTimezone;
+use super::{format_remaining_days, parse_code, PasswordResult, PasswordType, INSPECT_LOOKAHEAD_MS};
+use chrono::{DateTime, Datelike, NaiveDate, NaiveDateTime, NaiveTime, Timelike, Utc};
+
+/// 检查周期密码时向前搜索的天数
+///
+/// 周期密码的有效期最长可达数年，无法覆盖所有生成日期，只识别最近一周内生成的密码。
+const INSPECT_LOOKBACK_DAYS: i64 = 7;
 
 /// 周期密码生成器
 pub struct PeriodPasswordGenerator {
     crypto: KeeLoqCrypto,
+    clock: SharedClock,
+    /// 门锁时间相对真实时间的偏移（秒）
+    time_offset: i64,
+    /// 门锁时区，用于周期天数计算和消息中的时间显示
+    timezone: LockTimezone,
 }
 
 impl PeriodPasswordGenerator {
@@ -14,9 +27,42 @@ impl PeriodPasswordGenerator {
     pub fn new() -> Self {
         PeriodPasswordGenerator {
             crypto: KeeLoqCrypto::new(),
+            clock: SystemClock::shared(),
+            time_offset: 0,
+            timezone: LockTimezone::default(),
         }
     }
 
+    /// 使用指定的时钟获取当前时间
+    pub fn with_clock(mut self, clock: SharedClock) -> Self {
+        self.clock = clock;
+        self
+    }
+
+    /// 设置门锁时间偏移（秒）
+    ///
+    /// 加密使用门锁时间（真实时间加偏移），返回的有效期换算回真实时间。
+    pub fn with_time_offset(mut self, time_offset: i64) -> Self {
+        self.time_offset = time_offset;
+        self
+    }
+
+    /// 设置门锁时区
+    pub fn with_timezone(mut self, timezone: LockTimezone) -> Self {
+        self.timezone = timezone;
+        self
+    }
+
+    /// 当前门锁时间戳（毫秒）
+    fn lock_time_ms(&self) -> i64 {
+        KeeLoqCrypto::lock_timestamp(self.clock.as_ref(), self.time_offset)
+    }
+
+    /// 将按门锁时间计算的生成结果换算为真实时间
+    fn to_real_time(&self, result: PasswordResult) -> PasswordResult {
+        result.shifted(chrono::Duration::seconds(-self.time_offset), &self.timezone)
+    }
+
     /// 生成周期密码
     /// 
     /// # 参数
@@ -27,9 +73,9 @@ impl PeriodPasswordGenerator {
     /// * `end_hour` - 结束小时（0-23）
     /// 
     /// # 返回值
-    /// * `Ok((password, expire_time, message))` - 成功时返回密码、过期时间和消息
+    /// * `Ok(PasswordResult)` - 成功时返回密码、有效期和消息
     /// * `Err(String)` - 失败时返回错误信息
-    pub fn generate(&self, admin_pwd: &str, end_year: u32, end_month: u32, end_day: u32, end_hour: u32) -> Result<(String, String, String), String> {
+    pub fn generate(&self, admin_pwd: &str, end_year: u32, end_month: u32, end_day: u32, end_hour: u32) -> Result<PasswordResult, String> {
         // 检查管理员密码长度
         if admin_pwd.len() < 4 {
             return Err("管理员密码至少需要4位".to_string());
@@ -55,25 +101,21 @@ impl PeriodPasswordGenerator {
         let end_time = NaiveTime::from_hms_opt(end_hour, 0, 0).unwrap();
         let end_datetime = NaiveDateTime::new(end_date, end_time);
         
-        // 转换为UTC+8时区
-        let beijing_tz = FixedOffset::east_opt(8 * 3600).unwrap();
-        let end_datetime_tz = beijing_tz.from_local_datetime(&end_datetime).single()
-            .ok_or("无法转换到UTC+8时区")?;
+        // 按门锁时区换算结束时间
+        let end_utc = self.timezone.from_local(&end_datetime);
 
-        // 获取当前UTC+8时间
-        let current_time_ms = KeeLoqCrypto::get_utc8_timestamp();
-        let current_datetime = DateTime::from_timestamp_millis(current_time_ms)
-            .unwrap()
-            .with_timezone(&beijing_tz);
+        // 获取门锁当前时间
+        let current_time_ms = self.lock_time_ms();
+        let current_datetime = KeeLoqCrypto::datetime_from_millis(current_time_ms);
 
         // 检查结束时间是否晚于当前时间
-        if end_datetime_tz <= current_datetime {
+        if end_utc <= current_datetime {
             return Err("结束时间必须晚于当前时间".to_string());
         }
 
-        // 计算时间戳（秒） (对应 l = Math.floor(r.getTime() / 1e3) + 28800)
-        let current_timestamp_sec = current_time_ms / 1000 + 28800; // 加上UTC+8偏移
-        let end_timestamp_sec = end_datetime_tz.timestamp();
+        // 计算门锁本地时间戳（秒） (对应 l = Math.floor(r.getTime() / 1e3) + 28800，28800为UTC+8的偏移)
+        let current_timestamp_sec = self.timezone.local_timestamp(current_datetime);
+        let end_timestamp_sec = self.timezone.local_timestamp(end_utc);
 
         // 计算天数（从1970-01-01开始的天数） (对应 u = Math.floor(l / 86400))
         let current_days = (current_timestamp_sec / 86400) as u32;
@@ -81,9 +123,9 @@ impl PeriodPasswordGenerator {
         // 构造加密输入 (对应 m = 32768 * u + 3221225472)
         let mut crypto_input = current_days * 32768 + 3221225472; // 0xC0000000
         
-        // 计算结束时间的小时数 (对应 p = Math.floor((h - 86400 * u) / 3600) + 8)
+        // 计算结束时间距当天本地0点的小时数 (对应 p = Math.floor((h - 86400 * u) / 3600) + 8)
         let end_day_start_sec = current_days as i64 * 86400;
-        let hours_from_day_start = ((end_timestamp_sec - end_day_start_sec) / 3600) + 8;
+        let hours_from_day_start = (end_timestamp_sec - end_day_start_sec) / 3600;
         
         // 检查小时数是否超出支持范围
         if hours_from_day_start > 32768 {
@@ -99,13 +141,73 @@ impl PeriodPasswordGenerator {
         let password_num = 5000000000u64 + encrypted_code.parse::<u64>().unwrap_or(0);
         let password = password_num.to_string();
         
-        // 格式化过期时间
-        let expire_time_str = end_datetime_tz.format("%Y-%m-%d %H:%M:%S").to_string();
-        
-        // 生成消息
-        let message = format!("周期密码，有效期至 {}", expire_time_str);
-        
-        Ok((password, expire_time_str, message))
+        Ok(self.to_real_time(self.result_for_day(password, current_days, hours_from_day_start as u32)))
+    }
+
+    /// 门锁本地时间第 `days` 天的0点
+    fn day_start(days: u32) -> NaiveDateTime {
+        DateTime::from_timestamp(days as i64 * 86400, 0).unwrap().naive_utc()
+    }
+
+    /// 根据天数和结束小时数构造生成结果
+    fn result_for_day(&self, password: String, days: u32, end_hours: u32) -> PasswordResult {
+        // 逆向计算结束时间：门锁本地时间为当天0点后 p 小时
+        let day_start = Self::day_start(days);
+        let end_local = day_start + chrono::Duration::hours(end_hours as i64);
+        let valid_until = self.timezone.from_local(&end_local);
+
+        let message = format!("周期密码，有效期至 {}", self.timezone.format(valid_until));
+        
+        // 生效时间为密码编码的当天门锁本地时间0点
+        PasswordResult {
+            password,
+            password_type: PasswordType::Period(
+                end_local.year() as u32,
+                end_local.month(),
+                end_local.day(),
+                end_local.hour(),
+            ),
+            valid_from: self.timezone.from_local(&day_start),
+            valid_until,
+            remaining_uses: None,
+            window: days,
+            message,
+        }
+    }
+
+    /// 解密密码，得到编码的天数和结束小时数（1-32768）
+    fn decrypt_params(&self, password: &str, admin_pwd: &str) -> Option<(u32, u32)> {
+        if admin_pwd.len() < 4 {
+            return None;
+        }
+        let code = parse_code(password)?;
+
+        // 加密输入为 32768 * 天数 + 0xC0000000 + 小时数 (对应 m = 32768 * u + 3221225472 + p)
+        let crypto_input = self.crypto.decrypt_usercode(code, admin_pwd).wrapping_sub(3221225472);
+
+        // 小时数为32768时进位到天数部分，小时数不能为0
+        match (crypto_input >> 15, crypto_input & 0x7FFF) {
+            (0, 0) => None,
+            (days, 0) => Some((days - 1, 32768)),
+            (days, end_hours) => Some((days, end_hours)),
+        }
+    }
+
+    /// 检查周期密码
+    ///
+    /// 解密得到生成该密码的日期和结束时间，无论是否仍在有效期内都返回对应的生成结果。
+    /// 只接受 `at` 之前7天至之后10分钟内生成的密码，超出范围时返回None。
+    pub fn inspect(&self, password: &str, admin_pwd: &str, at: DateTime<Utc>) -> Option<PasswordResult> {
+        let (days, end_hours) = self.decrypt_params(password, admin_pwd)?;
+
+        let at_lock = at + chrono::Duration::seconds(self.time_offset);
+        let at_days = self.timezone.local_timestamp(at_lock) / 86400;
+        let newest = self.timezone.local_timestamp(at_lock + chrono::Duration::milliseconds(INSPECT_LOOKAHEAD_MS)) / 86400;
+        let oldest = at_days - INSPECT_LOOKBACK_DAYS;
+
+        (oldest as u32..=newest as u32)
+            .contains(&days)
+            .then(|| self.to_real_time(self.result_for_day(password.to_string(), days, end_hours)))
     }
 
     /// 使用便捷的日期时间字符串生成周期密码
@@ -113,7 +215,7 @@ impl PeriodPasswordGenerator {
     /// # 参数
     /// * `admin_pwd` - 管理员密码
     /// * `end_datetime_str` - 结束时间字符串，格式："YYYY-MM-DD HH:MM:SS"
-    pub fn generate_from_string(&self, admin_pwd: &str, end_datetime_str: &str) -> Result<(String, String, String), String> {
+    pub fn generate_from_string(&self, admin_pwd: &str, end_datetime_str: &str) -> Result<PasswordResult, String> {
         // 解析日期时间字符串
         let parts: Vec<&str> = end_datetime_str.split_whitespace().collect();
         if parts.len() != 2 {
@@ -143,61 +245,28 @@ impl PeriodPasswordGenerator {
     /// * `tolerance_days` - 容忍的天数误差
     /// 
     /// # 返回值
-    /// * `Some(expire_datetime)` - 密码有效，返回过期时间
+    /// * `Some(expire_datetime)` - 密码有效，返回过期时间（真实时间，按门锁时区显示）
     /// * `None` - 密码无效
     pub fn verify(&self, password: &str, admin_pwd: &str, tolerance_days: u32) -> Option<String> {
-        if admin_pwd.len() < 4 {
-            return None;
-        }
+        self.verify_result(password, admin_pwd, tolerance_days)
+            .map(|result| self.timezone.format(result.valid_until))
+    }
 
-        // 移除前缀，获取实际的加密代码 (对应 5e9 前缀)
-        let password_num = match password.parse::<u64>() {
-            Ok(num) if num >= 5000000000 => num - 5000000000,
-            _ => return None,
-        };
+    /// 验证周期密码是否有效，返回解密恢复的结束时间和换算为真实时间的有效期
+    pub fn verify_result(&self, password: &str, admin_pwd: &str, tolerance_days: u32) -> Option<PasswordResult> {
+        let (days, end_hours) = self.decrypt_params(password, admin_pwd)?;
 
-        let current_time_ms = KeeLoqCrypto::get_utc8_timestamp();
-        let current_timestamp_sec = current_time_ms / 1000 + 28800;
-        let current_days = (current_timestamp_sec / 86400) as u32;
+        let current_datetime = KeeLoqCrypto::datetime_from_millis(self.lock_time_ms());
+        let current_days = (self.timezone.local_timestamp(current_datetime) / 86400) as u32;
 
-        // 在容忍范围内检查天数
-        for day_offset in 0..=tolerance_days {
-            for &direction in &[0i32, -1i32] {
-                let check_days = if direction < 0 && day_offset > 0 {
-                    current_days.wrapping_sub(day_offset)
-                } else if direction == 0 {
-                    current_days
-                } else {
-                    continue;
-                };
-
-                // 检查不同的小时数 (对应原JS中的 p 值)
-                for hours in 8..=32776 { // 从UTC+8开始检查
-                    let mut crypto_input = check_days * 32768 + 3221225472;
-                    crypto_input += hours;
-                    
-                    let expected_code = self.crypto.crypt_usercode(crypto_input, admin_pwd);
-                    
-                    if password_num == expected_code.parse::<u64>().unwrap_or(0) {
-                        // 使用生成时的逆向计算来获得实际的过期时间
-                        // hours 是 p 值，即 Math.floor((h - 86400 * u) / 3600) + 8 的结果
-                        // 所以实际的结束时间秒数 h = (hours - 8) * 3600 + 86400 * check_days
-                        let actual_end_timestamp = ((hours - 8) * 3600) as i64 + (check_days as i64) * 86400;
-                        
-                        // 检查是否还在有效期内
-                        if (current_time_ms / 1000 + 28800) <= actual_end_timestamp {
-                            let beijing_tz = FixedOffset::east_opt(8 * 3600).unwrap();
-                            if let Some(expire_datetime) = DateTime::from_timestamp(actual_end_timestamp, 0) {
-                                let expire_datetime_tz = expire_datetime.with_timezone(&beijing_tz);
-                                return Some(expire_datetime_tz.format("%Y-%m-%d %H:%M:%S").to_string());
-                            }
-                        }
-                    }
-                }
-            }
+        // 编码的天数不晚于当天，且最多早 tolerance_days 天
+        if current_days.wrapping_sub(days) > tolerance_days {
+            return None;
         }
 
-        None
+        // 结束时间为门锁本地时间当天0点后 end_hours 小时，检查是否还在有效期内
+        let result = self.result_for_day(password.to_string(), days, end_hours);
+        (current_datetime <= result.valid_until).then(|| self.to_real_time(result))
     }
 
     /// 检查密码剩余有效时间
@@ -210,20 +279,14 @@ impl PeriodPasswordGenerator {
     /// * `Some((remaining_ms, expire_time))` - 密码有效，返回剩余毫秒数和过期时间
     /// * `None` - 密码无效或已过期
     pub fn check_remaining_time(&self, password: &str, admin_pwd: &str) -> Option<(i64, String)> {
-        if let Some(expire_time_str) = self.verify(password, admin_pwd, 3) {
-            // 解析过期时间
-            let _beijing_tz = FixedOffset::east_opt(8 * 3600).unwrap();
-            if let Ok(expire_datetime) = DateTime::parse_from_str(&format!("{} +08:00", expire_time_str), "%Y-%m-%d %H:%M:%S %z") {
-                let current_time_ms = KeeLoqCrypto::get_utc8_timestamp();
-                let expire_time_ms = expire_datetime.timestamp_millis();
-                let remaining_ms = expire_time_ms - current_time_ms;
-                
-                if remaining_ms > 0 {
-                    return Some((remaining_ms, expire_time_str));
-                }
-            }
+        let valid_until = self.verify_result(password, admin_pwd, 3)?.valid_until;
+        let remaining_ms = valid_until.timestamp_millis() - self.clock.timestamp_millis();
+
+        if remaining_ms > 0 {
+            Some((remaining_ms, self.timezone.format(valid_until)))
+        } else {
+            None
         }
-        None
     }
 
     /// 获取给定日期的月份天数
@@ -257,13 +320,13 @@ impl Default for PeriodPasswordGenerator {
 }
 
 /// 便捷函数：生成周期密码
-pub fn generate_period_password(admin_pwd: &str, end_year: u32, end_month: u32, end_day: u32, end_hour: u32) -> Result<(String, String, String), String> {
+pub fn generate_period_password(admin_pwd: &str, end_year: u32, end_month: u32, end_day: u32, end_hour: u32) -> Result<PasswordResult, String> {
     let generator = PeriodPasswordGenerator::new();
     generator.generate(admin_pwd, end_year, end_month, end_day, end_hour)
 }
 
 /// 便捷函数：从字符串生成周期密码
-pub fn generate_period_password_from_string(admin_pwd: &str, end_datetime_str: &str) -> Result<(String, String, String), String> {
+pub fn generate_period_password_from_string(admin_pwd: &str, end_datetime_str: &str) -> Result<PasswordResult, String> {
     let generator = PeriodPasswordGenerator::new();
     generator.generate_from_string(admin_pwd, end_datetime_str)
 }
@@ -278,19 +341,7 @@ pub fn verify_period_password(password: &str, admin_pwd: &str) -> Option<String>
 pub fn check_period_password_remaining_time(password: &str, admin_pwd: &str) -> Option<(String, String)> {
     let generator = PeriodPasswordGenerator::new();
     if let Some((remaining_ms, expire_time)) = generator.check_remaining_time(password, admin_pwd) {
-        let remaining_days = remaining_ms / (1000 * 60 * 60 * 24);
-        let remaining_hours = (remaining_ms % (1000 * 60 * 60 * 24)) / (1000 * 60 * 60);
-        let remaining_minutes = (remaining_ms % (1000 * 60 * 60)) / (1000 * 60);
-        
-        let remaining_str = if remaining_days > 0 {
-            format!("{}天{}小时{}分钟", remaining_days, remaining_hours, remaining_minutes)
-        } else if remaining_hours > 0 {
-            format!("{}小时{}分钟", remaining_hours, remaining_minutes)
-        } else {
-            format!("{}分钟", remaining_minutes)
-        };
-        
-        Some((remaining_str, expire_time))
+        Some((format_remaining_days(remaining_ms), expire_time))
     } else {
         None
     }
@@ -299,22 +350,29 @@ pub fn check_period_password_remaining_time(password: &str, admin_pwd: &str) ->
 #[cfg(test)]
 mod tests {
     use super::*;
-    use chrono::{Utc, Datelike, Timelike};
+    use crate::utils::gen_password::{Clock, ManualClock};
+    use chrono::{Datelike, Duration, TimeZone, Timelike, Utc};
+    use std::collections::HashMap;
 
     #[test]
     fn test_generate_period_password() {
         let generator = PeriodPasswordGenerator::new();
         
         // 测试生成明天同一时间的周期密码
-        let beijing_tz = FixedOffset::east_opt(8 * 3600).unwrap();
-        let tomorrow = Utc::now().with_timezone(&beijing_tz).date_naive().succ_opt().unwrap();
+        let timezone = LockTimezone::default();
+        let tomorrow = timezone.to_local(Utc::now()).date().succ_opt().unwrap();
         
         match generator.generate("123456", tomorrow.year() as u32, tomorrow.month(), tomorrow.day(), 18) {
-            Ok((password, expire_time, message)) => {
-                println!("周期密码: {}", password);
-                println!("过期时间: {}", expire_time);
-                println!("消息: {}", message);
-                assert!(password.len() >= 10);
+            Ok(result) => {
+                println!("周期密码: {}", result.password);
+                println!("过期时间: {}", result.expire_time(&LockTimezone::default()));
+                println!("消息: {}", result.message);
+                assert!(result.password.len() >= 10);
+                assert!(result.expire_time(&LockTimezone::default()).ends_with("18:00:00"));
+                // 生效时间为编码当天的北京时间0点
+                let valid_from = timezone.to_local(result.valid_from);
+                assert_eq!((valid_from.hour(), valid_from.minute()), (0, 0));
+                assert_eq!(valid_from.date().succ_opt(), Some(tomorrow));
             }
             Err(e) => println!("生成周期密码失败（这可能是预期的）: {}", e),
         }
@@ -326,10 +384,10 @@ mod tests {
         
         // 测试从字符串生成
         match generator.generate_from_string("123456", "2024-12-31 23:59:59") {
-            Ok((password, expire_time, message)) => {
-                println!("从字符串生成的周期密码: {}", password);
-                println!("过期时间: {}", expire_time);
-                println!("消息: {}", message);
+            Ok(result) => {
+                println!("从字符串生成的周期密码: {}", result.password);
+                println!("过期时间: {}", result.expire_time(&LockTimezone::default()));
+                println!("消息: {}", result.message);
             }
             Err(e) => println!("从字符串生成失败: {}", e),
         }
@@ -367,15 +425,14 @@ mod tests {
         let admin_pwd = "123456";
         
         // 尝试生成一个未来的密码进行验证测试
-        let beijing_tz = FixedOffset::east_opt(8 * 3600).unwrap();
-        let future_time = Utc::now().with_timezone(&beijing_tz) + chrono::Duration::hours(2);
+        let future_time = LockTimezone::default().to_local(Utc::now() + chrono::Duration::hours(2));
         
         match generator.generate(admin_pwd, 
                                 future_time.year() as u32, 
                                 future_time.month(), 
                                 future_time.day(), 
                                 future_time.hour()) {
-            Ok((password, _, _)) => {
+            Ok(PasswordResult { password, .. }) => {
                 // 验证密码
                 if let Some(expire_time) = generator.verify(&password, admin_pwd, 2) {
                     println!("验证成功，过期时间: {}", expire_time);
@@ -394,15 +451,15 @@ mod tests {
     fn test_convenience_functions() {
         // 测试便捷函数
         match generate_period_password_from_string("123456", "2024-12-25 18:00:00") {
-            Ok((password, expire_time, _message)) => {
-                println!("便捷函数生成的周期密码: {}", password);
-                println!("过期时间: {}", expire_time);
+            Ok(result) => {
+                println!("便捷函数生成的周期密码: {}", result.password);
+                println!("过期时间: {}", result.expire_time(&LockTimezone::default()));
                 
-                if let Some(verified_expire_time) = verify_period_password(&password, "123456") {
+                if let Some(verified_expire_time) = verify_period_password(&result.password, "123456") {
                     println!("便捷函数验证成功，过期时间: {}", verified_expire_time);
                 }
                 
-                if let Some((remaining, expire)) = check_period_password_remaining_time(&password, "123456") {
+                if let Some((remaining, expire)) = check_period_password_remaining_time(&result.password, "123456") {
                     println!("剩余时间: {}, 过期时间: {}", remaining, expire);
                 }
             }
@@ -423,200 +480,123 @@ mod tests {
         
         for datetime_str in test_cases {
             match generator.generate_from_string("123456", datetime_str) {
-                Ok((password, expire_time, _)) => {
-                    println!("时间 {} 的密码: {}", datetime_str, password);
-                    println!("过期时间: {}", expire_time);
+                Ok(result) => {
+                    println!("时间 {} 的密码: {}", datetime_str, result.password);
+                    println!("过期时间: {}", result.expire_time(&LockTimezone::default()));
                 }
                 Err(e) => println!("解析时间 {} 失败: {}", datetime_str, e),
             }
         }
     }
-}
-
-/// 支持时间偏移的周期密码生成器
-pub struct PeriodPasswordGeneratorWithOffset {
-    crypto: KeeLoqCrypto,
-    time_offset: i32,
-}
 
-impl PeriodPasswordGeneratorWithOffset {
-    /// 创建新的带时间偏移的周期密码生成器实例
-    pub fn new(time_offset: i32) -> Self {
-        PeriodPasswordGeneratorWithOffset {
-            crypto: KeeLoqCrypto::new(),
-            time_offset,
-        }
+    #[test]
+    fn test_clock_and_time_offset() {
+        let now = Utc.with_ymd_and_hms(2024, 1, 1, 10, 0, 0).unwrap();
+        let clock = ManualClock::new(now);
+        let generator = PeriodPasswordGenerator::new().with_clock(clock.shared());
+        let offset_generator = PeriodPasswordGenerator::new()
+            .with_clock(clock.shared())
+            .with_time_offset(120);
+
+        // 结束时间为北京时间2024-01-03 18:00，门锁快2分钟时按真实时间提前2分钟失效
+        let result = generator.generate("123456", 2024, 1, 3, 18).unwrap();
+        let offset_result = offset_generator.generate("123456", 2024, 1, 3, 18).unwrap();
+        assert_eq!(result.password, offset_result.password);
+        assert_eq!(result.valid_until, Utc.with_ymd_and_hms(2024, 1, 3, 10, 0, 0).unwrap());
+        assert_eq!(offset_result.valid_until, result.valid_until - Duration::minutes(2));
+        assert_eq!(offset_result.password_type, PasswordType::Period(2024, 1, 3, 18));
+
+        // 结束时间不能早于门锁当前时间
+        clock.set(Utc.with_ymd_and_hms(2024, 1, 3, 9, 59, 0).unwrap());
+        assert!(generator.generate("123456", 2024, 1, 3, 18).is_ok());
+        assert!(offset_generator.generate("123456", 2024, 1, 3, 18).is_err());
     }
 
-    /// 生成周期密码（考虑时间偏移）
-    pub fn generate(&self, admin_pwd: &str, end_year: u32, end_month: u32, end_day: u32, end_hour: u32) -> Result<(String, String, String), String> {
-        // 检查管理员密码长度
-        if admin_pwd.len() < 4 {
-            return Err("管理员密码至少需要4位".to_string());
-        }
-
-        // 验证日期参数
-        if end_month < 1 || end_month > 12 {
-            return Err("月份必须在1-12之间".to_string());
-        }
-        if end_day < 1 || end_day > 31 {
-            return Err("日期必须在1-31之间".to_string());
-        }
-        if end_hour > 23 {
-            return Err("小时必须在0-23之间".to_string());
-        }
-
-        // 创建结束时间
-        let end_date = match NaiveDate::from_ymd_opt(end_year as i32, end_month, end_day) {
-            Some(date) => date,
-            None => return Err("无效的日期".to_string()),
-        };
-        
-        let end_time = NaiveTime::from_hms_opt(end_hour, 0, 0).unwrap();
-        let end_datetime = NaiveDateTime::new(end_date, end_time);
-        
-        // 转换为UTC+8时区
-        let beijing_tz = FixedOffset::east_opt(8 * 3600).unwrap();
-        let end_datetime_tz = beijing_tz.from_local_datetime(&end_datetime).single()
-            .ok_or("无法转换到UTC+8时区")?;
-
-        // 获取带偏移的当前UTC+8时间
-        let current_time_ms = KeeLoqCrypto::get_utc8_timestamp_with_offset(self.time_offset);
-        let _current_datetime = DateTime::from_timestamp_millis(current_time_ms)
-            .unwrap()
-            .with_timezone(&beijing_tz);
-
-        // 获取真实的当前时间（用于显示）
-        let real_current_time_ms = KeeLoqCrypto::get_utc8_timestamp();
-        let real_current_datetime = DateTime::from_timestamp_millis(real_current_time_ms)
-            .unwrap()
-            .with_timezone(&beijing_tz);
-
-        // 检查结束时间是否晚于真实当前时间
-        if end_datetime_tz <= real_current_datetime {
-            return Err("结束时间必须晚于当前时间".to_string());
-        }
-
-        // 计算时间戳（秒）- 使用带偏移的时间
-        let current_timestamp_sec = current_time_ms / 1000 + 28800;
-        let end_timestamp_sec = end_datetime_tz.timestamp();
-
-        // 计算天数（从1970-01-01开始的天数）
-        let current_days = (current_timestamp_sec / 86400) as u32;
-        
-        // 构造加密输入
-        let mut crypto_input = current_days * 32768 + 3221225472; // 0xC0000000
-        
-        // 计算结束时间的小时数
-        let end_day_start_sec = current_days as i64 * 86400;
-        let hours_from_day_start = ((end_timestamp_sec - end_day_start_sec) / 3600) + 8;
-        
-        // 检查小时数是否超出支持范围
-        if hours_from_day_start > 32768 {
-            return Err("结束时间超出支持范围".to_string());
-        }
-        
-        crypto_input += hours_from_day_start as u32;
-        
-        // 使用KeeLoq加密算法生成密码
-        let encrypted_code = self.crypto.crypt_usercode(crypto_input, admin_pwd);
-        
-        // 添加前缀
-        let password_num = 5000000000u64 + encrypted_code.parse::<u64>().unwrap_or(0);
-        let password = password_num.to_string();
-        
-        // 格式化过期时间（显示真实时间）
-        let expire_time_str = end_datetime_tz.format("%Y-%m-%d %H:%M:%S").to_string();
-        
-        // 生成消息
-        let message = format!("周期密码，有效期至 {}", expire_time_str);
-        
-        Ok((password, expire_time_str, message))
+    #[test]
+    fn test_lock_timezone() {
+        let clock = ManualClock::new(Utc.with_ymd_and_hms(2024, 3, 30, 23, 30, 0).unwrap());
+        let timezone: LockTimezone = "Europe/Berlin".parse().unwrap();
+        let generator = PeriodPasswordGenerator::new()
+            .with_clock(clock.shared())
+            .with_timezone(timezone);
+
+        // 柏林已是3月31日，当天切换到夏令时：结束时间按UTC+2换算，生效时间为当天本地0点（UTC+1）
+        let result = generator.generate("123456", 2024, 4, 1, 12).unwrap();
+        assert_eq!(result.valid_until, Utc.with_ymd_and_hms(2024, 4, 1, 10, 0, 0).unwrap());
+        assert_eq!(result.valid_from, Utc.with_ymd_and_hms(2024, 3, 30, 23, 0, 0).unwrap());
+        assert_eq!(result.password_type, PasswordType::Period(2024, 4, 1, 12));
+        assert!(result.message.contains("2024-04-01 12:00:00"));
+        assert_eq!(generator.verify(&result.password, "123456", 1), Some("2024-04-01 12:00:00".to_string()));
+        assert_eq!(generator.inspect(&result.password, "123456", clock.now()), Some(result.clone()));
+
+        // 同一时刻纽约仍是3月30日，天数不同，生成的密码也不同
+        let new_york = PeriodPasswordGenerator::new()
+            .with_clock(clock.shared())
+            .with_timezone("America/New_York".parse().unwrap());
+        let other = new_york.generate("123456", 2024, 4, 1, 12).unwrap();
+        assert_eq!(other.valid_until, Utc.with_ymd_and_hms(2024, 4, 1, 16, 0, 0).unwrap());
+        assert_ne!(other.password, result.password);
+        assert!(new_york.verify(&result.password, "123456", 1).is_none());
     }
 
-    /// 验证周期密码是否有效（考虑时间偏移）
-    pub fn verify(&self, password: &str, admin_pwd: &str, tolerance_days: u32) -> Option<String> {
-        if admin_pwd.len() < 4 {
-            return None;
-        }
-
-        // 移除前缀，获取实际的加密代码
-        let password_num = match password.parse::<u64>() {
-            Ok(num) if num >= 5000000000 => num - 5000000000,
-            _ => return None,
-        };
-
-        // 使用带偏移的时间戳
-        let current_time_ms = KeeLoqCrypto::get_utc8_timestamp_with_offset(self.time_offset);
-        let current_timestamp_sec = current_time_ms / 1000 + 28800;
-        let current_days = (current_timestamp_sec / 86400) as u32;
+    /// 原有的逐个天数和小时数加密比对的验证方式，列出其接受的全部加密代码及对应的过期时间
+    fn scanned_codes(
+        crypto: &KeeLoqCrypto,
+        admin_pwd: &str,
+        timezone: &LockTimezone,
+        current_datetime: DateTime<Utc>,
+        tolerance_days: u32,
+    ) -> HashMap<u32, DateTime<Utc>> {
+        let current_days = (timezone.local_timestamp(current_datetime) / 86400) as u32;
+        let mut codes = HashMap::new();
 
-        // 在容忍范围内检查天数
         for day_offset in 0..=tolerance_days {
-            for &direction in &[0i32, -1i32] {
-                let check_days = if direction < 0 && day_offset > 0 {
-                    current_days.wrapping_sub(day_offset)
-                } else if direction == 0 {
-                    current_days
-                } else {
-                    continue;
-                };
-
-                // 检查不同的小时数
-                for hours in 8..=32776 {
-                    let mut crypto_input = check_days * 32768 + 3221225472;
-                    crypto_input += hours;
-                    
-                    let expected_code = self.crypto.crypt_usercode(crypto_input, admin_pwd);
-                    
-                    if password_num == expected_code.parse::<u64>().unwrap_or(0) {
-                        // 使用生成时的逆向计算来获得实际的过期时间
-                        let actual_end_timestamp = ((hours - 8) * 3600) as i64 + (check_days as i64) * 86400;
-                        
-                        // 检查是否还在有效期内（基于真实时间）
-                        let real_current_time_ms = KeeLoqCrypto::get_utc8_timestamp();
-                        if (real_current_time_ms / 1000 + 28800) <= actual_end_timestamp {
-                            let beijing_tz = FixedOffset::east_opt(8 * 3600).unwrap();
-                            if let Some(expire_datetime) = DateTime::from_timestamp(actual_end_timestamp, 0) {
-                                let expire_datetime_tz = expire_datetime.with_timezone(&beijing_tz);
-                                return Some(expire_datetime_tz.format("%Y-%m-%d %H:%M:%S").to_string());
-                            }
-                        }
-                    }
+            let check_days = current_days - day_offset;
+            for hours in 1..=32768 {
+                let code = crypto.crypt_usercode(check_days * 32768 + 3221225472 + hours, admin_pwd).parse().unwrap();
+                let end_local = PeriodPasswordGenerator::day_start(check_days) + Duration::hours(hours as i64);
+                let end_datetime = timezone.from_local(&end_local);
+                if current_datetime <= end_datetime {
+                    codes.entry(code).or_insert(end_datetime);
                 }
             }
         }
 
-        None
+        codes
     }
 
-    /// 检查密码剩余有效时间（考虑时间偏移）
-    pub fn check_remaining_time(&self, password: &str, admin_pwd: &str) -> Option<(String, String)> {
-        if let Some(expire_time_str) = self.verify(password, admin_pwd, 3) {
-            // 解析过期时间
-            if let Ok(expire_datetime) = DateTime::parse_from_str(&format!("{} +08:00", expire_time_str), "%Y-%m-%d %H:%M:%S %z") {
-                // 显示的剩余时间基于真实时间
-                let current_time_ms = KeeLoqCrypto::get_utc8_timestamp();
-                let expire_time_ms = expire_datetime.timestamp_millis();
-                let remaining_ms = expire_time_ms - current_time_ms;
-                
-                if remaining_ms > 0 {
-                    let remaining_days = remaining_ms / (1000 * 60 * 60 * 24);
-                    let remaining_hours = (remaining_ms % (1000 * 60 * 60 * 24)) / (1000 * 60 * 60);
-                    let remaining_minutes = (remaining_ms % (1000 * 60 * 60)) / (1000 * 60);
-                    
-                    let remaining_str = if remaining_days > 0 {
-                        format!("{}天{}小时{}分钟", remaining_days, remaining_hours, remaining_minutes)
-                    } else if remaining_hours > 0 {
-                        format!("{}小时{}分钟", remaining_hours, remaining_minutes)
-                    } else {
-                        format!("{}分钟", remaining_minutes)
-                    };
-                    
-                    return Some((remaining_str, expire_time_str));
-                }
-            }
+    #[test]
+    fn test_decrypt_matches_day_scan() {
+        let crypto = KeeLoqCrypto::new();
+        let now = Utc.with_ymd_and_hms(2024, 6, 1, 20, 30, 0).unwrap();
+        let timezone = LockTimezone::default();
+        let generator = PeriodPasswordGenerator::new().with_clock(ManualClock::new(now).shared());
+        let admin_pwd = "123456";
+        let current_days = (timezone.local_timestamp(now) / 86400) as u32;
+
+        let accepted = scanned_codes(&crypto, admin_pwd, &timezone, now, 1);
+        assert!(accepted.len() > 60000);
+
+        // 相邻日期的首尾小时数（32768小时进位到下一天）、天数部分为0的输入，以及任意代码
+        let boundary = [current_days - 2, current_days - 1, current_days, current_days + 1]
+            .into_iter()
+            .flat_map(|days| [0, 1, 20, 32767].map(|hours| days * 32768 + 3221225472 + hours))
+            .chain([3221225472, 3221225472 + 32768])
+            .map(|crypto_input| crypto.crypt_usercode(crypto_input, admin_pwd).parse::<u32>().unwrap());
+        // 扫描两天共65536种输入，逐一解密开销较大，接受的代码按固定间隔抽取
+        let candidates: Vec<u32> = accepted.keys().copied().step_by(61).chain(boundary).chain([0, u32::MAX]).collect();
+
+        for code in candidates {
+            let password = (5000000000u64 + code as u64).to_string();
+            assert_eq!(generator.verify_result(&password, admin_pwd, 1).map(|r| r.valid_until), accepted.get(&code).copied());
         }
-        None
+
+        // 不容忍误差时只接受当天编码的密码
+        let today: u32 = crypto.crypt_usercode(current_days * 32768 + 3221225472 + 24, admin_pwd).parse().unwrap();
+        let yesterday: u32 = crypto.crypt_usercode((current_days - 1) * 32768 + 3221225472 + 48, admin_pwd).parse().unwrap();
+        assert_eq!(accepted.get(&today), Some(&Utc.with_ymd_and_hms(2024, 6, 2, 16, 0, 0).unwrap()));
+        assert!(generator.verify_result(&(5000000000u64 + today as u64).to_string(), admin_pwd, 0).is_some());
+        assert!(generator.verify_result(&(5000000000u64 + yesterday as u64).to_string(), admin_pwd, 0).is_none());
+        assert!(generator.verify_result(&(5000000000u64 + yesterday as u64).to_string(), admin_pwd, 1).is_some());
     }
-}
\ No newline at end of file
+}
@@ -1,11 +1,20 @@
 //! 临时密码生成算法 Rust 实现
 //! 基于4秒时间窗口生成临时密码，有效期10分钟
 
+use super::clock::{SharedClock, SystemClock};
 use super::keeloq_crypto::KeeLoqCrypto;
+use super::timezone::LockTimezone;
+use super::{parse_code, PasswordResult, PasswordType, INSPECT_LOOKAHEAD_MS, INSPECT_LOOKBACK_MS};
+use chrono::{DateTime, Utc};
 
 /// 临时密码生成器
 pub struct TempPasswordGenerator {
     crypto: KeeLoqCrypto,
+    clock: SharedClock,
+    /// 门锁时间相对真实时间的偏移（秒）
+    time_offset: i64,
+    /// 门锁时区，用于消息中的时间显示
+    timezone: LockTimezone,
 }
 
 impl TempPasswordGenerator {
@@ -13,32 +22,62 @@ impl TempPasswordGenerator {
     pub fn new() -> Self {
         TempPasswordGenerator {
             crypto: KeeLoqCrypto::new(),
+            clock: SystemClock::shared(),
+            time_offset: 0,
+            timezone: LockTimezone::default(),
         }
     }
 
+    /// 使用指定的时钟获取当前时间
+    pub fn with_clock(mut self, clock: SharedClock) -> Self {
+        self.clock = clock;
+        self
+    }
+
+    /// 设置门锁时间偏移（秒）
+    ///
+    /// 加密使用门锁时间（真实时间加偏移），返回的有效期换算回真实时间。
+    pub fn with_time_offset(mut self, time_offset: i64) -> Self {
+        self.time_offset = time_offset;
+        self
+    }
+
+    /// 设置门锁时区
+    pub fn with_timezone(mut self, timezone: LockTimezone) -> Self {
+        self.timezone = timezone;
+        self
+    }
+
+    /// 当前门锁时间戳（毫秒）
+    fn lock_time_ms(&self) -> i64 {
+        KeeLoqCrypto::lock_timestamp(self.clock.as_ref(), self.time_offset)
+    }
+
+    /// 将按门锁时间计算的生成结果换算为真实时间
+    fn to_real_time(&self, result: PasswordResult) -> PasswordResult {
+        result.shifted(chrono::Duration::seconds(-self.time_offset), &self.timezone)
+    }
+
     /// 生成临时密码
     /// 
     /// # 参数
     /// * `admin_pwd` - 管理员密码（至少4位）
     /// 
     /// # 返回值
-    /// * `Ok((password, expire_time, message))` - 成功时返回密码、过期时间和消息
+    /// * `Ok(PasswordResult)` - 成功时返回密码、有效期和消息
     /// * `Err(String)` - 失败时返回错误信息
-    pub fn generate(&self, admin_pwd: &str) -> Result<(String, String, String), String> {
+    pub fn generate(&self, admin_pwd: &str) -> Result<PasswordResult, String> {
         // 检查管理员密码长度
         if admin_pwd.len() < 4 {
             return Err("管理员密码至少需要4位".to_string());
         }
 
-        // 获取UTC+8当前时间戳（毫秒）
-        let current_time_ms = KeeLoqCrypto::get_utc8_timestamp();
+        // 获取门锁当前时间戳（毫秒）
+        let current_time_ms = self.lock_time_ms();
         
         // 转换为4秒时间窗口 (对应 t = Math.floor(t / 4e3))
         let time_window = (current_time_ms / 4000) as u32;
         
-        // 计算过期时间 (对应 l = 4e3 * t + 6e5)
-        let expire_time_ms = (time_window as i64) * 4000 + 600000;
-        
         // 使用KeeLoq加密算法生成密码 (对应 o = a.CRYPT_USERCODE(t, s))
         let encrypted_code = self.crypto.crypt_usercode(time_window, admin_pwd);
         
@@ -46,13 +85,53 @@ impl TempPasswordGenerator {
         let password_num = 5000000000u64 + encrypted_code.parse::<u64>().unwrap_or(0);
         let password = password_num.to_string();
         
-        // 格式化过期时间
-        let expire_time_str = KeeLoqCrypto::format_utc8_time(expire_time_ms);
+        Ok(self.to_real_time(self.result_for_window(password, time_window)))
+    }
+
+    /// 根据时间窗口构造生成结果
+    fn result_for_window(&self, password: String, time_window: u32) -> PasswordResult {
+        // 计算过期时间 (对应 l = 4e3 * t + 6e5)
+        let valid_from_ms = (time_window as i64) * 4000;
+        let expire_time_ms = valid_from_ms + 600000;
         
-        // 生成消息
-        let message = format!("临时密码有效期至 {}", expire_time_str);
+        let message = format!("临时密码有效期至 {}", self.timezone.format_millis(expire_time_ms));
         
-        Ok((password, expire_time_str, message))
+        PasswordResult {
+            password,
+            password_type: PasswordType::Temporary,
+            valid_from: KeeLoqCrypto::datetime_from_millis(valid_from_ms),
+            valid_until: KeeLoqCrypto::datetime_from_millis(expire_time_ms),
+            remaining_uses: None,
+            window: time_window,
+            message,
+        }
+    }
+
+    /// 解密密码，得到生成时使用的时间窗口
+    fn decrypt_window(&self, password: &str, admin_pwd: &str) -> Option<u32> {
+        if admin_pwd.len() < 4 {
+            return None;
+        }
+        let code = parse_code(password)?;
+
+        // 临时密码的加密输入就是时间窗口 (对应 o = a.CRYPT_USERCODE(t, s))
+        Some(self.crypto.decrypt_usercode(code, admin_pwd))
+    }
+
+    /// 检查临时密码
+    ///
+    /// 解密得到生成该密码的时间窗口，无论是否仍在有效期内都返回对应的生成结果。
+    /// 只接受有效期之外再向前1天、向后10分钟内的窗口，超出范围时返回None。
+    pub fn inspect(&self, password: &str, admin_pwd: &str, at: DateTime<Utc>) -> Option<PasswordResult> {
+        let window = self.decrypt_window(password, admin_pwd)?;
+
+        let at_ms = at.timestamp_millis() + self.time_offset * 1000;
+        let newest = ((at_ms + INSPECT_LOOKAHEAD_MS) / 4000) as u32;
+        let oldest = ((at_ms - 600000 - INSPECT_LOOKBACK_MS) / 4000) as u32;
+
+        (oldest..=newest)
+            .contains(&window)
+            .then(|| self.to_real_time(self.result_for_window(password.to_string(), window)))
     }
 
     /// 验证临时密码是否有效
@@ -66,41 +145,11 @@ impl TempPasswordGenerator {
     /// * `true` - 密码有效
     /// * `false` - 密码无效
     pub fn verify(&self, password: &str, admin_pwd: &str, tolerance_windows: u32) -> bool {
-        if admin_pwd.len() < 4 {
-            return false;
-        }
-
-        // 移除前缀，获取实际的加密代码 (对应 5e9 前缀)
-        let password_num = match password.parse::<u64>() {
-            Ok(num) if num >= 5000000000 => num - 5000000000,
-            _ => return false,
-        };
-
-        let current_time_ms = KeeLoqCrypto::get_utc8_timestamp();
-        let current_window = (current_time_ms / 4000) as u32;
+        let current_window = (self.lock_time_ms() / 4000) as u32;
 
-        // 在容忍范围内检查时间窗口
-        for offset in 0..=tolerance_windows {
-            // 检查当前和之前的时间窗口
-            for &direction in &[0i32, -1i32] {
-                let check_window = if direction < 0 && offset > 0 {
-                    current_window.wrapping_sub(offset)
-                } else if direction == 0 {
-                    current_window
-                } else {
-                    continue;
-                };
-
-                // 直接使用时间窗口数值，不乘以4000 (对应原JS中的 t)
-                let expected_code = self.crypto.crypt_usercode(check_window, admin_pwd);
-                
-                if password_num == expected_code.parse::<u64>().unwrap_or(0) {
-                    return true;
-                }
-            }
-        }
-
-        false
+        // 密码的时间窗口不晚于当前窗口，且最多早 tolerance_windows 个窗口
+        self.decrypt_window(password, admin_pwd)
+            .is_some_and(|window| current_window.wrapping_sub(window) <= tolerance_windows)
     }
 
     /// 检查密码剩余有效时间
@@ -117,7 +166,7 @@ impl TempPasswordGenerator {
             return None;
         }
 
-        let current_time_ms = KeeLoqCrypto::get_utc8_timestamp();
+        let current_time_ms = self.lock_time_ms();
         let time_window = (current_time_ms / 4000) as u32;
         let expire_time_ms = (time_window as i64) * 4000 + 600000; // 10分钟有效期
         
@@ -130,14 +179,14 @@ impl TempPasswordGenerator {
     }
 
     /// 获取当前时间窗口信息
-    pub fn get_current_window_info() -> (u32, String, String) {
-        let current_time_ms = KeeLoqCrypto::get_utc8_timestamp();
+    pub fn get_current_window_info(timezone: &LockTimezone) -> (u32, String, String) {
+        let current_time_ms = KeeLoqCrypto::current_timestamp();
         let current_window = (current_time_ms / 4000) as u32;
         let window_start_ms = (current_window as i64) * 4000;
         let window_end_ms = window_start_ms + 4000;
         
-        let start_time = KeeLoqCrypto::format_utc8_time(window_start_ms);
-        let end_time = KeeLoqCrypto::format_utc8_time(window_end_ms);
+        let start_time = timezone.format_millis(window_start_ms);
+        let end_time = timezone.format_millis(window_end_ms);
         
         (current_window, start_time, end_time)
     }
@@ -150,7 +199,7 @@ impl Default for TempPasswordGenerator {
 }
 
 /// 便捷函数：生成临时密码
-pub fn generate_temp_password(admin_pwd: &str) -> Result<(String, String, String), String> {
+pub fn generate_temp_password(admin_pwd: &str) -> Result<PasswordResult, String> {
     let generator = TempPasswordGenerator::new();
     generator.generate(admin_pwd)
 }
@@ -164,6 +213,9 @@ pub fn verify_temp_password(password: &str, admin_pwd: &str) -> bool {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::utils::gen_password::{Clock, ManualClock};
+    use chrono::{Duration, TimeZone};
+    use std::collections::HashSet;
 
     #[test]
     fn test_generate_temp_password() {
@@ -171,11 +223,15 @@ mod tests {
         
         // 测试正常生成
         match generator.generate("123456") {
-            Ok((password, expire_time, message)) => {
-                println!("临时密码: {}", password);
-                println!("过期时间: {}", expire_time);
-                println!("消息: {}", message);
-                assert!(password.len() >= 10);
+            Ok(result) => {
+                println!("临时密码: {}", result.password);
+                println!("过期时间: {}", result.expire_time(&LockTimezone::default()));
+                println!("消息: {}", result.message);
+                assert!(result.password.len() >= 10);
+                assert_eq!(result.password_type, PasswordType::Temporary);
+                assert_eq!(result.valid_until - result.valid_from, chrono::Duration::minutes(10));
+                assert_eq!(result.valid_from.timestamp_millis(), result.window as i64 * 4000);
+                assert_eq!(result.remaining_uses, None);
             }
             Err(e) => panic!("生成临时密码失败: {}", e),
         }
@@ -201,7 +257,7 @@ mod tests {
         let admin_pwd = "123456";
         
         // 生成密码
-        if let Ok((password, _, _)) = generator.generate(admin_pwd) {
+        if let Ok(PasswordResult { password, .. }) = generator.generate(admin_pwd) {
             // 验证密码应该成功
             assert!(generator.verify(&password, admin_pwd, 1));
             
@@ -215,7 +271,7 @@ mod tests {
 
     #[test]
     fn test_window_info() {
-        let (window, start, end) = TempPasswordGenerator::get_current_window_info();
+        let (window, start, end) = TempPasswordGenerator::get_current_window_info(&LockTimezone::default());
         println!("当前时间窗口: {}", window);
         println!("窗口开始时间: {}", start);
         println!("窗口结束时间: {}", end);
@@ -225,118 +281,80 @@ mod tests {
     fn test_convenience_functions() {
         // 测试便捷函数
         match generate_temp_password("123456") {
-            Ok((password, _, _)) => {
+            Ok(PasswordResult { password, .. }) => {
                 println!("便捷函数生成的临时密码: {}", password);
                 assert!(verify_temp_password(&password, "123456"));
             }
             Err(e) => panic!("便捷函数失败: {}", e),
         }
     }
-}
 
-/// 支持时间偏移的临时密码生成器
-pub struct TempPasswordGeneratorWithOffset {
-    crypto: KeeLoqCrypto,
-    time_offset: i32,
-}
-
-impl TempPasswordGeneratorWithOffset {
-    /// 创建新的带时间偏移的临时密码生成器实例
-    pub fn new(time_offset: i32) -> Self {
-        TempPasswordGeneratorWithOffset {
-            crypto: KeeLoqCrypto::new(),
-            time_offset,
-        }
+    #[test]
+    fn test_clock_and_time_offset() {
+        let now = Utc.with_ymd_and_hms(2024, 1, 1, 10, 0, 1).unwrap();
+        let clock = ManualClock::new(now);
+        let generator = TempPasswordGenerator::new().with_clock(clock.shared());
+
+        let result = generator.generate("123456").unwrap();
+        assert_eq!(result.window, (now.timestamp_millis() / 4000) as u32);
+        assert_eq!(result.valid_from, Utc.with_ymd_and_hms(2024, 1, 1, 10, 0, 0).unwrap());
+
+        // 门锁快2分钟：密码与2分钟后生成的相同，有效期按真实时间提前2分钟
+        let offset_generator = TempPasswordGenerator::new()
+            .with_clock(clock.shared())
+            .with_time_offset(120);
+        let offset_result = offset_generator.generate("123456").unwrap();
+        assert!(offset_generator.verify(&offset_result.password, "123456", 1));
+        assert!(!generator.verify(&offset_result.password, "123456", 1));
+        assert_eq!(
+            offset_generator.inspect(&offset_result.password, "123456", now),
+            Some(offset_result.clone())
+        );
+
+        clock.advance(Duration::minutes(2));
+        let lock_result = generator.generate("123456").unwrap();
+        assert_eq!(offset_result.password, lock_result.password);
+        assert_eq!(offset_result.window, lock_result.window);
+        assert_eq!(offset_result.valid_until, lock_result.valid_until - Duration::minutes(2));
+        assert!(offset_result.message.contains("2024-01-01 18:10:00"));
     }
 
-    /// 生成临时密码（考虑时间偏移）
-    pub fn generate(&self, admin_pwd: &str) -> Result<(String, String, String), String> {
-        // 检查管理员密码长度
-        if admin_pwd.len() < 4 {
-            return Err("管理员密码至少需要4位".to_string());
-        }
-
-        // 获取带偏移的UTC+8当前时间戳（毫秒）
-        let current_time_ms = KeeLoqCrypto::get_utc8_timestamp_with_offset(self.time_offset);
-        
-        // 转换为4秒时间窗口
-        let time_window = (current_time_ms / 4000) as u32;
-        
-        // 计算过期时间（显示给用户的是真实时间，不包含偏移）
-        let real_current_time_ms = KeeLoqCrypto::get_utc8_timestamp();
-        let real_time_window = (real_current_time_ms / 4000) as u32;
-        let expire_time_ms = (real_time_window as i64) * 4000 + 600000;
-        
-        // 使用KeeLoq加密算法生成密码
-        let encrypted_code = self.crypto.crypt_usercode(time_window, admin_pwd);
-        
-        // 添加前缀
-        let password_num = 5000000000u64 + encrypted_code.parse::<u64>().unwrap_or(0);
-        let password = password_num.to_string();
-        
-        // 格式化过期时间（显示真实时间）
-        let expire_time_str = KeeLoqCrypto::format_utc8_time(expire_time_ms);
-        
-        // 生成消息
-        let message = format!("临时密码有效期至 {}", expire_time_str);
-        
-        Ok((password, expire_time_str, message))
+    /// 原有的逐个时间窗口加密比对的验证方式，列出其接受的全部加密代码
+    fn scanned_codes(crypto: &KeeLoqCrypto, admin_pwd: &str, current_window: u32, tolerance_windows: u32) -> HashSet<u32> {
+        (0..=tolerance_windows)
+            .map(|offset| current_window.wrapping_sub(offset))
+            .map(|window| crypto.crypt_usercode(window, admin_pwd).parse().unwrap())
+            .collect()
     }
 
-    /// 验证临时密码是否有效（考虑时间偏移）
-    pub fn verify(&self, password: &str, admin_pwd: &str, tolerance_windows: u32) -> bool {
-        if admin_pwd.len() < 4 {
-            return false;
-        }
-
-        // 移除前缀，获取实际的加密代码
-        let password_num = match password.parse::<u64>() {
-            Ok(num) if num >= 5000000000 => num - 5000000000,
-            _ => return false,
-        };
-
-        // 使用带偏移的时间戳
-        let current_time_ms = KeeLoqCrypto::get_utc8_timestamp_with_offset(self.time_offset);
-        let current_window = (current_time_ms / 4000) as u32;
-
-        // 在容忍范围内检查时间窗口
-        for offset in 0..=tolerance_windows {
-            for &direction in &[0i32, -1i32] {
-                let check_window = if direction < 0 && offset > 0 {
-                    current_window.wrapping_sub(offset)
-                } else if direction == 0 {
-                    current_window
-                } else {
-                    continue;
-                };
-
-                let expected_code = self.crypto.crypt_usercode(check_window, admin_pwd);
-                
-                if password_num == expected_code.parse::<u64>().unwrap_or(0) {
-                    return true;
+    #[test]
+    fn test_decrypt_matches_window_scan() {
+        let crypto = KeeLoqCrypto::new();
+        let clock = ManualClock::new(Utc.with_ymd_and_hms(2024, 6, 1, 12, 0, 0).unwrap());
+        let generator = TempPasswordGenerator::new().with_clock(clock.shared());
+
+        for admin_pwd in ["1234", "123456", "1234567890"] {
+            for _ in 0..4 {
+                clock.advance(Duration::milliseconds(2999));
+                let current_window = (clock.now().timestamp_millis() / 4000) as u32;
+
+                for tolerance in [0, 1, 150] {
+                    let accepted = scanned_codes(&crypto, admin_pwd, current_window, tolerance);
+
+                    // 容忍范围内外各个窗口生成的代码，以及任意代码
+                    let candidates = (current_window - tolerance - 3..=current_window + 3)
+                        .map(|window| crypto.crypt_usercode(window, admin_pwd).parse::<u32>().unwrap())
+                        .chain([0, 1, u32::MAX]);
+                    for code in candidates {
+                        let password = (5000000000u64 + code as u64).to_string();
+                        assert_eq!(generator.verify(&password, admin_pwd, tolerance), accepted.contains(&code));
+                    }
                 }
             }
         }
 
-        false
-    }
-
-    /// 检查密码剩余有效时间（考虑时间偏移）
-    pub fn check_remaining_time(&self, password: &str, admin_pwd: &str) -> Option<i64> {
-        if !self.verify(password, admin_pwd, 1) {
-            return None;
-        }
-
-        // 显示的剩余时间基于真实时间
-        let current_time_ms = KeeLoqCrypto::get_utc8_timestamp();
-        let time_window = (current_time_ms / 4000) as u32;
-        let expire_time_ms = (time_window as i64) * 4000 + 600000;
-        
-        let remaining_ms = expire_time_ms - current_time_ms;
-        if remaining_ms > 0 {
-            Some(remaining_ms)
-        } else {
-            None
-        }
+        // 超出32位的代码不可能由任何管理密码生成
+        assert!(!generator.verify("9999999999", "123456", 150));
+        assert!(!generator.verify("4999999999", "123456", 150));
     }
-}
\ No newline at end of file
+}
@@ -1,11 +1,20 @@
 //! 次数限制密码生成算法 Rust 实现
 //! 生成可使用指定次数的密码，有效期20小时
 
+use super::clock::{SharedClock, SystemClock};
 use super::keeloq_crypto::KeeLoqCrypto;
+use super::timezone::LockTimezone;
+use super::{format_remaining_hours, parse_code, PasswordResult, PasswordType, INSPECT_LOOKAHEAD_MS, INSPECT_LOOKBACK_MS};
+use chrono::{DateTime, Utc};
 
 /// 次数限制密码生成器
 pub struct TimesPasswordGenerator {
     crypto: KeeLoqCrypto,
+    clock: SharedClock,
+    /// 门锁时间相对真实时间的偏移（秒）
+    time_offset: i64,
+    /// 门锁时区，用于消息中的时间显示
+    timezone: LockTimezone,
 }
 
 impl TimesPasswordGenerator {
@@ -13,9 +22,42 @@ impl TimesPasswordGenerator {
     pub fn new() -> Self {
         TimesPasswordGenerator {
             crypto: KeeLoqCrypto::new(),
+            clock: SystemClock::shared(),
+            time_offset: 0,
+            timezone: LockTimezone::default(),
         }
     }
 
+    /// 使用指定的时钟获取当前时间
+    pub fn with_clock(mut self, clock: SharedClock) -> Self {
+        self.clock = clock;
+        self
+    }
+
+    /// 设置门锁时间偏移（秒）
+    ///
+    /// 加密使用门锁时间（真实时间加偏移），返回的有效期换算回真实时间。
+    pub fn with_time_offset(mut self, time_offset: i64) -> Self {
+        self.time_offset = time_offset;
+        self
+    }
+
+    /// 设置门锁时区
+    pub fn with_timezone(mut self, timezone: LockTimezone) -> Self {
+        self.timezone = timezone;
+        self
+    }
+
+    /// 当前门锁时间戳（毫秒）
+    fn lock_time_ms(&self) -> i64 {
+        KeeLoqCrypto::lock_timestamp(self.clock.as_ref(), self.time_offset)
+    }
+
+    /// 将按门锁时间计算的生成结果换算为真实时间
+    fn to_real_time(&self, result: PasswordResult) -> PasswordResult {
+        result.shifted(chrono::Duration::seconds(-self.time_offset), &self.timezone)
+    }
+
     /// 生成次数限制密码
     /// 
     /// # 参数
@@ -23,9 +65,9 @@ impl TimesPasswordGenerator {
     /// * `use_times` - 可使用次数（1-31次）
     /// 
     /// # 返回值
-    /// * `Ok((password, expire_time, message))` - 成功时返回密码、过期时间和消息
+    /// * `Ok(PasswordResult)` - 成功时返回密码、有效期和消息
     /// * `Err(String)` - 失败时返回错误信息
-    pub fn generate(&self, admin_pwd: &str, use_times: u32) -> Result<(String, String, String), String> {
+    pub fn generate(&self, admin_pwd: &str, use_times: u32) -> Result<PasswordResult, String> {
         // 检查管理员密码长度
         if admin_pwd.len() < 4 {
             return Err("管理员密码至少需要4位".to_string());
@@ -36,19 +78,13 @@ impl TimesPasswordGenerator {
             return Err("使用次数必须在1-31之间".to_string());
         }
 
-        // 获取UTC+8当前时间戳（毫秒）
-        let current_time_ms = KeeLoqCrypto::get_utc8_timestamp();
+        // 获取门锁当前时间戳（毫秒）
+        let current_time_ms = self.lock_time_ms();
         
         // 转换为4秒时间窗口，并进行位运算处理
         let mut time_window = (current_time_ms / 4000) as u32;
         time_window &= 0xFFFFFFE0; // 相当于 JavaScript 中的 &= 4294967264
         
-        // 计算时间戳
-        let timestamp = (time_window as u64) * 4000;
-        
-        // 计算过期时间（当前时间窗口 + 20小时 = 72000秒 = 72000000毫秒）
-        let expire_time_ms = timestamp as i64 + 72000000;
-        
         // 构造加密输入：时间窗口 + 使用次数 + 特殊常量
         let mut crypto_input = time_window;
         crypto_input += use_times;
@@ -61,13 +97,65 @@ impl TimesPasswordGenerator {
         let password_num = 5000000000u64 + encrypted_code.parse::<u64>().unwrap_or(0);
         let password = password_num.to_string();
         
-        // 格式化过期时间
-        let expire_time_str = KeeLoqCrypto::format_utc8_time(expire_time_ms);
+        Ok(self.to_real_time(self.result_for_window(password, time_window, use_times)))
+    }
+
+    /// 根据对齐后的时间窗口和使用次数构造生成结果
+    fn result_for_window(&self, password: String, aligned_window: u32, use_times: u32) -> PasswordResult {
+        // 计算过期时间（时间窗口起点 + 20小时 = 72000秒 = 72000000毫秒）
+        let valid_from_ms = (aligned_window as i64) * 4000;
+        let expire_time_ms = valid_from_ms + 72000000;
         
-        // 生成消息
-        let message = format!("次数限制密码，可使用{}次，有效期至 {}", use_times, expire_time_str);
+        let message = format!(
+            "次数限制密码，可使用{}次，有效期至 {}",
+            use_times,
+            self.timezone.format_millis(expire_time_ms)
+        );
         
-        Ok((password, expire_time_str, message))
+        PasswordResult {
+            password,
+            password_type: PasswordType::Times(use_times),
+            valid_from: KeeLoqCrypto::datetime_from_millis(valid_from_ms),
+            valid_until: KeeLoqCrypto::datetime_from_millis(expire_time_ms),
+            remaining_uses: Some(use_times),
+            window: aligned_window,
+            message,
+        }
+    }
+
+    /// 解密密码，得到对齐后的时间窗口和使用次数
+    ///
+    /// 使用次数不在1-31之间的不是次数密码。
+    fn decrypt_params(&self, password: &str, admin_pwd: &str) -> Option<(u32, u32)> {
+        if admin_pwd.len() < 4 {
+            return None;
+        }
+        let code = parse_code(password)?;
+
+        // 加密输入为 对齐时间窗口 + 使用次数 + 0x40000000，对齐窗口的低5位为0
+        let crypto_input = self.crypto.decrypt_usercode(code, admin_pwd).wrapping_sub(1073741824);
+        let use_times = crypto_input & 0x1F;
+        if use_times < 1 {
+            return None;
+        }
+
+        Some((crypto_input & 0xFFFFFFE0, use_times))
+    }
+
+    /// 检查次数限制密码
+    ///
+    /// 解密得到生成该密码的对齐时间窗口和使用次数，无论是否仍在有效期内都返回对应的生成结果。
+    /// 只接受有效期之外再向前1天、向后10分钟内的窗口，超出范围时返回None。
+    pub fn inspect(&self, password: &str, admin_pwd: &str, at: DateTime<Utc>) -> Option<PasswordResult> {
+        let (window, times) = self.decrypt_params(password, admin_pwd)?;
+
+        let at_ms = at.timestamp_millis() + self.time_offset * 1000;
+        let newest = ((at_ms + INSPECT_LOOKAHEAD_MS) / 4000) as u32 & 0xFFFFFFE0;
+        let oldest = ((at_ms - 72000000 - INSPECT_LOOKBACK_MS) / 4000) as u32 & 0xFFFFFFE0;
+
+        (oldest..=newest)
+            .contains(&window)
+            .then(|| self.to_real_time(self.result_for_window(password.to_string(), window, times)))
     }
 
     /// 验证次数限制密码是否有效
@@ -82,47 +170,20 @@ impl TimesPasswordGenerator {
     /// * `Some(times)` - 密码有效，返回实际的使用次数
     /// * `None` - 密码无效
     pub fn verify(&self, password: &str, admin_pwd: &str, _expected_times: u32, tolerance_windows: u32) -> Option<u32> {
-        if admin_pwd.len() < 4 {
-            return None;
-        }
-
-        // 移除前缀，获取实际的加密代码 (对应 5e9 前缀)
-        let password_num = match password.parse::<u64>() {
-            Ok(num) if num >= 5000000000 => num - 5000000000,
-            _ => return None,
-        };
+        let (window, times) = self.decrypt_params(password, admin_pwd)?;
 
-        let current_time_ms = KeeLoqCrypto::get_utc8_timestamp();
+        let current_time_ms = self.lock_time_ms();
         let current_window = (current_time_ms / 4000) as u32;
         let aligned_window = current_window & 0xFFFFFFE0;
 
-        // 在容忍范围内检查时间窗口
-        for window_offset in 0..=tolerance_windows {
-            let check_window = if window_offset == 0 {
-                aligned_window
-            } else {
-                aligned_window.wrapping_sub(window_offset * 32) // 每个对齐窗口是32个基本窗口
-            };
-
-            // 检查不同的使用次数
-            for times in 1..=31 {
-                let mut crypto_input = check_window;
-                crypto_input += times;
-                crypto_input += 1073741824;
-                
-                let expected_code = self.crypto.crypt_usercode(crypto_input, admin_pwd);
-                
-                if password_num == expected_code.parse::<u64>().unwrap_or(0) {
-                    // 检查是否在有效期内（20小时）
-                    let password_expire_time = (check_window as i64) * 4000 + 72000000;
-                    if current_time_ms <= password_expire_time {
-                        return Some(times);
-                    }
-                }
-            }
+        // 密码的对齐窗口不晚于当前对齐窗口，且最多早 tolerance_windows 个对齐窗口（每个对齐窗口是32个基本窗口）
+        if aligned_window.wrapping_sub(window) / 32 > tolerance_windows {
+            return None;
         }
 
-        None
+        // 检查是否在有效期内（20小时）
+        let password_expire_time = (window as i64) * 4000 + 72000000;
+        (current_time_ms <= password_expire_time).then_some(times)
     }
 
     /// 检查密码剩余有效时间
@@ -136,7 +197,7 @@ impl TimesPasswordGenerator {
     /// * `None` - 密码无效或已过期
     pub fn check_remaining_time(&self, password: &str, admin_pwd: &str) -> Option<(i64, u32)> {
         if let Some(times) = self.verify(password, admin_pwd, 0, 5) {
-            let current_time_ms = KeeLoqCrypto::get_utc8_timestamp();
+            let current_time_ms = self.lock_time_ms();
             let current_window = (current_time_ms / 4000) as u32;
             let aligned_window = current_window & 0xFFFFFFE0;
             
@@ -152,16 +213,16 @@ impl TimesPasswordGenerator {
     }
 
     /// 获取当前时间窗口信息
-    pub fn get_current_window_info() -> (u32, u32, String, String) {
-        let current_time_ms = KeeLoqCrypto::get_utc8_timestamp();
+    pub fn get_current_window_info(timezone: &LockTimezone) -> (u32, u32, String, String) {
+        let current_time_ms = KeeLoqCrypto::current_timestamp();
         let current_window = (current_time_ms / 4000) as u32;
         let aligned_window = current_window & 0xFFFFFFE0;
         
         let window_start_ms = (aligned_window as i64) * 4000;
         let window_expire_ms = window_start_ms + 72000000;
         
-        let start_time = KeeLoqCrypto::format_utc8_time(window_start_ms);
-        let expire_time = KeeLoqCrypto::format_utc8_time(window_expire_ms);
+        let start_time = timezone.format_millis(window_start_ms);
+        let expire_time = timezone.format_millis(window_expire_ms);
         
         (current_window, aligned_window, start_time, expire_time)
     }
@@ -174,7 +235,7 @@ impl Default for TimesPasswordGenerator {
 }
 
 /// 便捷函数：生成次数限制密码
-pub fn generate_times_password(admin_pwd: &str, use_times: u32) -> Result<(String, String, String), String> {
+pub fn generate_times_password(admin_pwd: &str, use_times: u32) -> Result<PasswordResult, String> {
     let generator = TimesPasswordGenerator::new();
     generator.generate(admin_pwd, use_times)
 }
@@ -189,10 +250,7 @@ pub fn verify_times_password(password: &str, admin_pwd: &str) -> Option<u32> {
 pub fn check_password_remaining_time(password: &str, admin_pwd: &str) -> Option<(String, u32)> {
     let generator = TimesPasswordGenerator::new();
     if let Some((remaining_ms, times)) = generator.check_remaining_time(password, admin_pwd) {
-        let remaining_hours = remaining_ms / (1000 * 60 * 60);
-        let remaining_minutes = (remaining_ms % (1000 * 60 * 60)) / (1000 * 60);
-        let remaining_str = format!("{}小时{}分钟", remaining_hours, remaining_minutes);
-        Some((remaining_str, times))
+        Some((format_remaining_hours(remaining_ms), times))
     } else {
         None
     }
@@ -201,6 +259,9 @@ pub fn check_password_remaining_time(password: &str, admin_pwd: &str) -> Option<
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::utils::gen_password::{Clock, ManualClock};
+    use chrono::{Duration, TimeZone};
+    use std::collections::HashMap;
 
     #[test]
     fn test_generate_times_password() {
@@ -208,12 +269,15 @@ mod tests {
         
         // 测试正常生成
         match generator.generate("123456", 5) {
-            Ok((password, expire_time, message)) => {
-                println!("次数限制密码: {}", password);
-                println!("过期时间: {}", expire_time);
-                println!("消息: {}", message);
-                assert!(password.len() >= 10);
-                assert!(message.contains("5次"));
+            Ok(result) => {
+                println!("次数限制密码: {}", result.password);
+                println!("过期时间: {}", result.expire_time(&LockTimezone::default()));
+                println!("消息: {}", result.message);
+                assert!(result.password.len() >= 10);
+                assert!(result.message.contains("5次"));
+                assert_eq!(result.remaining_uses, Some(5));
+                assert_eq!(result.window % 32, 0);
+                assert_eq!(result.valid_until - result.valid_from, chrono::Duration::hours(20));
             }
             Err(e) => panic!("生成次数限制密码失败: {}", e),
         }
@@ -238,7 +302,7 @@ mod tests {
         let use_times = 3;
         
         // 生成密码
-        if let Ok((password, _, _)) = generator.generate(admin_pwd, use_times) {
+        if let Ok(PasswordResult { password, .. }) = generator.generate(admin_pwd, use_times) {
             // 验证密码应该成功并返回正确的次数
             if let Some(verified_times) = generator.verify(&password, admin_pwd, use_times, 2) {
                 println!("验证成功，使用次数: {}", verified_times);
@@ -257,7 +321,7 @@ mod tests {
         let generator = TimesPasswordGenerator::new();
         let admin_pwd = "123456";
         
-        if let Ok((password, _, _)) = generator.generate(admin_pwd, 10) {
+        if let Ok(PasswordResult { password, .. }) = generator.generate(admin_pwd, 10) {
             if let Some((remaining_ms, times)) = generator.check_remaining_time(&password, admin_pwd) {
                 println!("剩余时间: {}毫秒, 使用次数: {}", remaining_ms, times);
                 assert!(remaining_ms > 0);
@@ -268,7 +332,7 @@ mod tests {
 
     #[test]
     fn test_window_info() {
-        let (current, aligned, start, expire) = TimesPasswordGenerator::get_current_window_info();
+        let (current, aligned, start, expire) = TimesPasswordGenerator::get_current_window_info(&LockTimezone::default());
         println!("当前窗口: {}, 对齐窗口: {}", current, aligned);
         println!("开始时间: {}", start);
         println!("过期时间: {}", expire);
@@ -278,7 +342,7 @@ mod tests {
     fn test_convenience_functions() {
         // 测试便捷函数
         match generate_times_password("123456", 7) {
-            Ok((password, _, _)) => {
+            Ok(PasswordResult { password, .. }) => {
                 println!("便捷函数生成的次数限制密码: {}", password);
                 if let Some(times) = verify_times_password(&password, "123456") {
                     assert_eq!(times, 7);
@@ -292,138 +356,80 @@ mod tests {
             Err(e) => panic!("便捷函数失败: {}", e),
         }
     }
-}
 
-/// 支持时间偏移的次数密码生成器
-pub struct TimesPasswordGeneratorWithOffset {
-    crypto: KeeLoqCrypto,
-    time_offset: i32,
-}
-
-impl TimesPasswordGeneratorWithOffset {
-    /// 创建新的带时间偏移的次数密码生成器实例
-    pub fn new(time_offset: i32) -> Self {
-        TimesPasswordGeneratorWithOffset {
-            crypto: KeeLoqCrypto::new(),
-            time_offset,
-        }
-    }
-
-    /// 生成次数限制密码（考虑时间偏移）
-    pub fn generate(&self, admin_pwd: &str, use_times: u32) -> Result<(String, String, String), String> {
-        // 检查管理员密码长度
-        if admin_pwd.len() < 4 {
-            return Err("管理员密码至少需要4位".to_string());
-        }
-
-        // 检查使用次数范围
-        if use_times < 1 || use_times > 31 {
-            return Err("使用次数必须在1-31之间".to_string());
-        }
-
-        // 获取带偏移的UTC+8当前时间戳（毫秒）
-        let current_time_ms = KeeLoqCrypto::get_utc8_timestamp_with_offset(self.time_offset);
-        
-        // 转换为4秒时间窗口，并进行位运算处理
-        let mut time_window = (current_time_ms / 4000) as u32;
-        time_window &= 0xFFFFFFE0;
-        
-        // 计算时间戳
-        let _timestamp = (time_window as u64) * 4000;
-        
-        // 计算过期时间（显示给用户的是真实时间）
-        let real_current_time_ms = KeeLoqCrypto::get_utc8_timestamp();
-        let real_time_window = (real_current_time_ms / 4000) as u32;
-        let real_aligned_window = real_time_window & 0xFFFFFFE0;
-        let expire_time_ms = (real_aligned_window as i64) * 4000 + 72000000;
-        
-        // 构造加密输入：时间窗口 + 使用次数 + 特殊常量
-        let mut crypto_input = time_window;
-        crypto_input += use_times;
-        crypto_input += 1073741824; // 0x40000000
-        
-        // 使用KeeLoq加密算法生成密码
-        let encrypted_code = self.crypto.crypt_usercode(crypto_input, admin_pwd);
-        
-        // 添加前缀
-        let password_num = 5000000000u64 + encrypted_code.parse::<u64>().unwrap_or(0);
-        let password = password_num.to_string();
-        
-        // 格式化过期时间（显示真实时间）
-        let expire_time_str = KeeLoqCrypto::format_utc8_time(expire_time_ms);
-        
-        // 生成消息
-        let message = format!("次数限制密码，可使用{}次，有效期至 {}", use_times, expire_time_str);
-        
-        Ok((password, expire_time_str, message))
+    #[test]
+    fn test_clock_and_time_offset() {
+        let now = Utc.with_ymd_and_hms(2024, 1, 1, 10, 0, 0).unwrap();
+        let clock = ManualClock::new(now);
+        let generator = TimesPasswordGenerator::new().with_clock(clock.shared());
+        let offset_generator = TimesPasswordGenerator::new()
+            .with_clock(clock.shared())
+            .with_time_offset(120);
+
+        // 门锁快2分钟：密码与2分钟后生成的相同，有效期按真实时间提前2分钟
+        let offset_result = offset_generator.generate("123456", 3).unwrap();
+        assert_eq!(offset_generator.verify(&offset_result.password, "123456", 3, 2), Some(3));
+        assert_eq!(
+            offset_generator.inspect(&offset_result.password, "123456", now),
+            Some(offset_result.clone())
+        );
+
+        clock.advance(Duration::minutes(2));
+        let lock_result = generator.generate("123456", 3).unwrap();
+        assert_eq!(offset_result.password, lock_result.password);
+        assert_eq!(offset_result.window, lock_result.window);
+        assert_eq!(offset_result.valid_until, lock_result.valid_until - Duration::minutes(2));
     }
 
-    /// 验证次数限制密码是否有效（考虑时间偏移）
-    pub fn verify(&self, password: &str, admin_pwd: &str, _expected_times: u32, tolerance_windows: u32) -> Option<u32> {
-        if admin_pwd.len() < 4 {
-            return None;
-        }
-
-        // 移除前缀，获取实际的加密代码
-        let password_num = match password.parse::<u64>() {
-            Ok(num) if num >= 5000000000 => num - 5000000000,
-            _ => return None,
-        };
-
-        // 使用带偏移的时间戳
-        let current_time_ms = KeeLoqCrypto::get_utc8_timestamp_with_offset(self.time_offset);
-        let current_window = (current_time_ms / 4000) as u32;
-        let aligned_window = current_window & 0xFFFFFFE0;
+    /// 原有的逐个对齐窗口和使用次数加密比对的验证方式，列出其接受的全部加密代码及对应的使用次数
+    fn scanned_codes(crypto: &KeeLoqCrypto, admin_pwd: &str, current_time_ms: i64, tolerance_windows: u32) -> HashMap<u32, u32> {
+        let aligned_window = ((current_time_ms / 4000) as u32) & 0xFFFFFFE0;
+        let mut codes = HashMap::new();
 
-        // 在容忍范围内检查时间窗口
         for window_offset in 0..=tolerance_windows {
-            let check_window = if window_offset == 0 {
-                aligned_window
-            } else {
-                aligned_window.wrapping_sub(window_offset * 32)
-            };
-
-            // 检查不同的使用次数
+            let check_window = aligned_window.wrapping_sub(window_offset * 32);
             for times in 1..=31 {
-                let mut crypto_input = check_window;
-                crypto_input += times;
-                crypto_input += 1073741824;
-                
-                let expected_code = self.crypto.crypt_usercode(crypto_input, admin_pwd);
-                
-                if password_num == expected_code.parse::<u64>().unwrap_or(0) {
-                    // 检查是否在有效期内（基于真实时间）
-                    let real_current_time_ms = KeeLoqCrypto::get_utc8_timestamp();
-                    let password_expire_time = (check_window as i64) * 4000 + 72000000;
-                    if real_current_time_ms <= password_expire_time {
-                        return Some(times);
-                    }
+                let code = crypto.crypt_usercode(check_window + times + 1073741824, admin_pwd).parse().unwrap();
+                if current_time_ms <= (check_window as i64) * 4000 + 72000000 {
+                    codes.entry(code).or_insert(times);
                 }
             }
         }
 
-        None
+        codes
     }
 
-    /// 检查密码剩余有效时间（考虑时间偏移）
-    pub fn check_remaining_time(&self, password: &str, admin_pwd: &str) -> Option<(String, u32)> {
-        if let Some(times) = self.verify(password, admin_pwd, 0, 5) {
-            // 显示的剩余时间基于真实时间
-            let current_time_ms = KeeLoqCrypto::get_utc8_timestamp();
-            let current_window = (current_time_ms / 4000) as u32;
-            let aligned_window = current_window & 0xFFFFFFE0;
-            
-            // 计算密码的过期时间
-            let expire_time_ms = (aligned_window as i64) * 4000 + 72000000;
-            let remaining_ms = expire_time_ms - current_time_ms;
-            
-            if remaining_ms > 0 {
-                let remaining_hours = remaining_ms / (1000 * 60 * 60);
-                let remaining_minutes = (remaining_ms % (1000 * 60 * 60)) / (1000 * 60);
-                let remaining_str = format!("{}小时{}分钟", remaining_hours, remaining_minutes);
-                return Some((remaining_str, times));
+    #[test]
+    fn test_decrypt_matches_window_scan() {
+        let crypto = KeeLoqCrypto::new();
+        let clock = ManualClock::new(Utc.with_ymd_and_hms(2024, 6, 1, 12, 0, 0).unwrap());
+        let generator = TimesPasswordGenerator::new().with_clock(clock.shared());
+
+        for admin_pwd in ["1234", "87654321"] {
+            for _ in 0..3 {
+                clock.advance(Duration::seconds(41));
+                let current_time_ms = clock.now().timestamp_millis();
+                let aligned_window = ((current_time_ms / 4000) as u32) & 0xFFFFFFE0;
+
+                for tolerance in [0, 2, 5] {
+                    let accepted = scanned_codes(&crypto, admin_pwd, current_time_ms, tolerance);
+
+                    // 容忍范围之外的对齐窗口、使用次数为0的输入，以及任意代码
+                    let outside = [aligned_window - (tolerance + 1) * 32, aligned_window, aligned_window + 32]
+                        .into_iter()
+                        .flat_map(|window| [0, 1, 31].map(|times| window + times + 1073741824))
+                        .map(|crypto_input| crypto.crypt_usercode(crypto_input, admin_pwd).parse::<u32>().unwrap());
+                    let candidates: Vec<u32> = accepted.keys().copied().chain(outside).chain([0, u32::MAX]).collect();
+
+                    for code in candidates {
+                        let password = (5000000000u64 + code as u64).to_string();
+                        assert_eq!(
+                            generator.verify(&password, admin_pwd, 0, tolerance),
+                            accepted.get(&code).copied()
+                        );
+                    }
+                }
             }
         }
-        None
     }
-}
\ No newline at end of file
+}
@@ -0,0 +1,218 @@
+//! 门锁时区
+//!
+//! 周期密码按门锁所在时区的自然日编码，面向用户显示的时间也按该时区格式化。
+//! 支持IANA时区名（如 `Asia/Shanghai`，自动处理夏令时）和固定偏移（如 `+08:00`、`UTC-5`），
+//! 默认为UTC+8（北京时间）。
+
+use chrono::{DateTime, Duration, FixedOffset, LocalResult, NaiveDateTime, Offset, TimeZone, Utc};
+use chrono_tz::Tz;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::str::FromStr;
+
+/// 门锁时区
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub enum LockTimezone {
+    /// 固定偏移
+    Fixed(FixedOffset),
+    /// IANA时区，偏移随夏令时变化
+    Named(Tz),
+}
+
+impl LockTimezone {
+    /// UTC+8（北京时间）
+    pub fn beijing() -> Self {
+        LockTimezone::Fixed(FixedOffset::east_opt(8 * 3600).unwrap())
+    }
+
+    /// 指定时刻相对UTC的偏移
+    pub fn offset_at(&self, utc: DateTime<Utc>) -> FixedOffset {
+        match self {
+            LockTimezone::Fixed(offset) => *offset,
+            LockTimezone::Named(tz) => tz.offset_from_utc_datetime(&utc.naive_utc()).fix(),
+        }
+    }
+
+    /// 将UTC时间转换为本地时间
+    pub fn to_local(&self, utc: DateTime<Utc>) -> NaiveDateTime {
+        utc.naive_utc() + Duration::seconds(self.offset_at(utc).local_minus_utc() as i64)
+    }
+
+    /// 将本地时间转换为UTC时间
+    ///
+    /// 夏令时结束时重复出现的本地时间取较早的一次；夏令时开始时跳过的本地时间
+    /// 按跳变前的偏移换算，即落在跳变后对应的时刻。
+    pub fn from_local(&self, local: &NaiveDateTime) -> DateTime<Utc> {
+        match self {
+            LockTimezone::Fixed(offset) => Utc.from_utc_datetime(&(*local - Duration::seconds(offset.local_minus_utc() as i64))),
+            LockTimezone::Named(tz) => match tz.from_local_datetime(local) {
+                LocalResult::Single(time) | LocalResult::Ambiguous(time, _) => time.with_timezone(&Utc),
+                LocalResult::None => {
+                    let before = Utc.from_utc_datetime(&(*local - Duration::days(1)));
+                    Utc.from_utc_datetime(&(*local - Duration::seconds(self.offset_at(before).local_minus_utc() as i64)))
+                }
+            },
+        }
+    }
+
+    /// 本地时间戳（秒），即本地时间按UTC计算的秒数，用于按本地自然日计算天数
+    pub fn local_timestamp(&self, utc: DateTime<Utc>) -> i64 {
+        self.to_local(utc).and_utc().timestamp()
+    }
+
+    /// 格式化为本地时间字符串（YYYY-MM-DD HH:MM:SS）
+    pub fn format(&self, utc: DateTime<Utc>) -> String {
+        self.to_local(utc).format("%Y-%m-%d %H:%M:%S").to_string()
+    }
+
+    /// 格式化毫秒时间戳为本地时间字符串（YYYY-MM-DD HH:MM:SS）
+    pub fn format_millis(&self, timestamp_ms: i64) -> String {
+        self.format(DateTime::from_timestamp_millis(timestamp_ms).unwrap())
+    }
+}
+
+impl Default for LockTimezone {
+    fn default() -> Self {
+        Self::beijing()
+    }
+}
+
+impl fmt::Display for LockTimezone {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            LockTimezone::Fixed(offset) => write!(f, "{}", offset),
+            LockTimezone::Named(tz) => write!(f, "{}", tz.name()),
+        }
+    }
+}
+
+impl FromStr for LockTimezone {
+    type Err = String;
+
+    /// 解析IANA时区名或固定偏移
+    ///
+    /// 固定偏移可带 `UTC`/`GMT` 前缀，分钟可省略，如 `+08:00`、`+0530`、`UTC-5`、`UTC`。
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let value = s.trim();
+        let invalid = || format!("无效的时区: {}，请使用IANA时区名（如 Asia/Shanghai）或固定偏移（如 +08:00）", s);
+
+        if let Ok(tz) = value.parse::<Tz>() {
+            return Ok(LockTimezone::Named(tz));
+        }
+
+        let offset = value
+            .strip_prefix("UTC")
+            .or_else(|| value.strip_prefix("GMT"))
+            .unwrap_or(value);
+        if offset.is_empty() {
+            return Ok(LockTimezone::Fixed(FixedOffset::east_opt(0).unwrap()));
+        }
+
+        let (sign, rest) = match offset.split_at(1) {
+            ("+", rest) => (1, rest),
+            ("-", rest) => (-1, rest),
+            _ => return Err(invalid()),
+        };
+        let (hours, minutes) = match rest.split_once(':') {
+            Some((hours, minutes)) => (hours, minutes),
+            None if rest.len() == 4 => rest.split_at(2),
+            None => (rest, "0"),
+        };
+        let hours: i32 = hours.parse().map_err(|_| invalid())?;
+        let minutes: i32 = minutes.parse().map_err(|_| invalid())?;
+        if !(0..=14).contains(&hours) || !(0..60).contains(&minutes) {
+            return Err(invalid());
+        }
+
+        FixedOffset::east_opt(sign * (hours * 3600 + minutes * 60))
+            .map(LockTimezone::Fixed)
+            .ok_or_else(invalid)
+    }
+}
+
+impl TryFrom<String> for LockTimezone {
+    type Error = String;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        value.parse()
+    }
+}
+
+impl From<LockTimezone> for String {
+    fn from(timezone: LockTimezone) -> Self {
+        timezone.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_timezone() {
+        assert_eq!("+08:00".parse::<LockTimezone>(), Ok(LockTimezone::beijing()));
+        assert_eq!("UTC+8".parse::<LockTimezone>(), Ok(LockTimezone::beijing()));
+        assert_eq!(
+            "+0530".parse::<LockTimezone>(),
+            Ok(LockTimezone::Fixed(FixedOffset::east_opt(5 * 3600 + 1800).unwrap()))
+        );
+        assert_eq!(
+            "GMT-5".parse::<LockTimezone>(),
+            Ok(LockTimezone::Fixed(FixedOffset::west_opt(5 * 3600).unwrap()))
+        );
+        assert_eq!("UTC".parse::<LockTimezone>(), Ok(LockTimezone::Named(Tz::UTC)));
+        assert_eq!(
+            "Europe/Berlin".parse::<LockTimezone>(),
+            Ok(LockTimezone::Named(Tz::Europe__Berlin))
+        );
+
+        assert!("Mars/Olympus".parse::<LockTimezone>().is_err());
+        assert!("+25:00".parse::<LockTimezone>().is_err());
+        assert!("8".parse::<LockTimezone>().is_err());
+
+        // 显示形式可以重新解析
+        for value in ["+08:00", "-03:30", "America/New_York"] {
+            let timezone: LockTimezone = value.parse().unwrap();
+            assert_eq!(timezone.to_string(), value);
+            assert_eq!(timezone.to_string().parse::<LockTimezone>(), Ok(timezone));
+        }
+    }
+
+    #[test]
+    fn test_fixed_offset_conversion() {
+        let timezone = LockTimezone::beijing();
+        let utc = Utc.with_ymd_and_hms(2024, 1, 1, 20, 30, 0).unwrap();
+
+        assert_eq!(timezone.format(utc), "2024-01-02 04:30:00");
+        assert_eq!(timezone.from_local(&timezone.to_local(utc)), utc);
+        assert_eq!(timezone.local_timestamp(utc), utc.timestamp() + 28800);
+    }
+
+    #[test]
+    fn test_daylight_saving_conversion() {
+        let timezone: LockTimezone = "Europe/Berlin".parse().unwrap();
+
+        // 冬令时UTC+1，夏令时UTC+2
+        let winter = Utc.with_ymd_and_hms(2024, 1, 15, 12, 0, 0).unwrap();
+        let summer = Utc.with_ymd_and_hms(2024, 7, 15, 12, 0, 0).unwrap();
+        assert_eq!(timezone.format(winter), "2024-01-15 13:00:00");
+        assert_eq!(timezone.format(summer), "2024-07-15 14:00:00");
+
+        // 2024-03-31 02:00 跳到 03:00，跳过的本地时间落在跳变后
+        let skipped = NaiveDateTime::parse_from_str("2024-03-31 02:30:00", "%Y-%m-%d %H:%M:%S").unwrap();
+        assert_eq!(timezone.from_local(&skipped), Utc.with_ymd_and_hms(2024, 3, 31, 1, 30, 0).unwrap());
+
+        // 2024-10-27 03:00 回到 02:00，重复的本地时间取较早的一次
+        let repeated = NaiveDateTime::parse_from_str("2024-10-27 02:30:00", "%Y-%m-%d %H:%M:%S").unwrap();
+        assert_eq!(timezone.from_local(&repeated), Utc.with_ymd_and_hms(2024, 10, 27, 0, 30, 0).unwrap());
+    }
+
+    #[test]
+    fn test_serde_roundtrip() {
+        let timezone: LockTimezone = serde_json::from_str("\"Asia/Tokyo\"").unwrap();
+        assert_eq!(timezone, LockTimezone::Named(Tz::Asia__Tokyo));
+        assert_eq!(serde_json::to_string(&LockTimezone::beijing()).unwrap(), "\"+08:00\"");
+        assert!(serde_json::from_str::<LockTimezone>("\"Nowhere\"").is_err());
+    }
+}
@@ -0,0 +1,276 @@
+//! 密码算法测试向量
+//!
+//! 四种密码算法移植自 JavaScript 门锁工具，门锁只认这些算法的原始输出。测试向量记录固定时钟下
+//! 各算法生成的密码和有效期，保存在 `testdata/password_vectors.json`，由测试套件逐条核对。
+//!
+//! 修改用例后运行 `cargo run --bin gen_password_vectors` 重新生成文件；
+//! 只有在有意改变算法输出时才允许已有向量的结果变化，此时需要同时递增 `VECTOR_FILE_VERSION`。
+
+use super::period_password::PeriodPasswordGenerator;
+use super::{LockTimezone, ManualClock, PasswordType, UnifiedPasswordGenerator};
+use chrono::{DateTime, TimeZone, Utc};
+use serde::{Deserialize, Serialize};
+
+/// 测试向量文件版本
+pub const VECTOR_FILE_VERSION: u32 = 1;
+
+/// 测试向量文件相对仓库根目录的路径
+pub const VECTOR_FILE_PATH: &str = "src/utils/gen_password/testdata/password_vectors.json";
+
+/// 测试用例：生成密码所需的全部输入
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct VectorCase {
+    /// 用例名称，说明覆盖的边界情况
+    pub name: String,
+    /// 管理密码
+    pub admin_pwd: String,
+    /// 生成时的真实时间
+    pub now: DateTime<Utc>,
+    /// 门锁时间偏移（秒）
+    pub time_offset: i64,
+    /// 门锁时区
+    pub timezone: LockTimezone,
+    /// 密码类型及参数
+    pub password_type: PasswordType,
+}
+
+/// 测试向量：用例及其期望输出
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TestVector {
+    #[serde(flatten)]
+    pub case: VectorCase,
+    /// 期望的密码（含 5000000000 前缀）
+    pub password: String,
+    /// 期望的时间窗口序号
+    pub window: u32,
+    /// 期望的生效时间（真实时间）
+    pub valid_from: DateTime<Utc>,
+    /// 期望的失效时间（真实时间）
+    pub valid_until: DateTime<Utc>,
+}
+
+/// 月份天数，固定周期密码按月末计算结束日期的结果
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct MonthDays {
+    pub year: u32,
+    pub month: u32,
+    pub days: u32,
+}
+
+/// 测试向量文件
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct VectorFile {
+    pub version: u32,
+    pub month_days: Vec<MonthDays>,
+    pub vectors: Vec<TestVector>,
+}
+
+impl VectorCase {
+    fn new(name: &str, admin_pwd: &str, now: DateTime<Utc>, password_type: PasswordType) -> Self {
+        VectorCase {
+            name: name.to_string(),
+            admin_pwd: admin_pwd.to_string(),
+            now,
+            time_offset: 0,
+            timezone: LockTimezone::default(),
+            password_type,
+        }
+    }
+
+    fn with_time_offset(mut self, time_offset: i64) -> Self {
+        self.time_offset = time_offset;
+        self
+    }
+
+    fn with_timezone(mut self, timezone: &str) -> Self {
+        self.timezone = timezone.parse().unwrap();
+        self
+    }
+
+    /// 按用例配置的生成器，时钟固定在 `now`
+    pub fn generator(&self) -> UnifiedPasswordGenerator {
+        UnifiedPasswordGenerator::new()
+            .with_clock(ManualClock::new(self.now).shared())
+            .with_time_offset(self.time_offset)
+            .with_timezone(self.timezone)
+    }
+
+    /// 生成测试向量
+    pub fn generate(&self) -> Result<TestVector, String> {
+        let result = self
+            .generator()
+            .generate(&self.admin_pwd, self.password_type.clone())
+            .map_err(|e| format!("{}: {}", self.name, e))?;
+
+        Ok(TestVector {
+            case: self.clone(),
+            password: result.password,
+            window: result.window,
+            valid_from: result.valid_from,
+            valid_until: result.valid_until,
+        })
+    }
+}
+
+/// 解析UTC时间，支持毫秒
+fn utc(value: &str) -> DateTime<Utc> {
+    DateTime::parse_from_rfc3339(value).unwrap().with_timezone(&Utc)
+}
+
+/// 周期密码用例，结束日期为指定月份的最后一天
+fn month_end_case(name: &str, year: u32, month: u32, hour: u32) -> VectorCase {
+    let day = PeriodPasswordGenerator::get_month_days(year, month).unwrap();
+    let now = Utc.with_ymd_and_hms(year as i32, month, 1, 0, 0, 0).unwrap();
+    VectorCase::new(name, "123456", now, PasswordType::Period(year, month, day, hour))
+}
+
+/// 全部测试用例
+pub fn vector_cases() -> Vec<VectorCase> {
+    vec![
+        // 临时密码：4秒窗口边界、加密代码带前导零或接近上限、管理密码补零和截断、时间偏移
+        VectorCase::new("temp_window_start", "123456", utc("2024-01-01T00:00:00Z"), PasswordType::Temporary),
+        VectorCase::new("temp_window_last_ms", "123456", utc("2024-01-01T00:00:03.999Z"), PasswordType::Temporary),
+        VectorCase::new("temp_next_window", "123456", utc("2024-01-01T00:00:04Z"), PasswordType::Temporary),
+        VectorCase::new("temp_prefix_leading_zeros", "123456", utc("2024-03-01T00:05:08Z"), PasswordType::Temporary),
+        VectorCase::new("temp_prefix_near_max", "123456", utc("2024-03-01T00:02:32Z"), PasswordType::Temporary),
+        VectorCase::new("temp_short_admin_pwd", "1234", utc("2024-06-15T08:30:00Z"), PasswordType::Temporary),
+        VectorCase::new("temp_long_admin_pwd", "1234567890", utc("2024-06-15T08:30:00Z"), PasswordType::Temporary),
+        VectorCase::new("temp_lock_ahead", "123456", utc("2024-06-15T08:30:00Z"), PasswordType::Temporary)
+            .with_time_offset(120),
+        VectorCase::new("temp_lock_behind", "123456", utc("2024-06-15T08:30:00Z"), PasswordType::Temporary)
+            .with_time_offset(-300),
+        // 次数密码：按32个窗口（128秒）对齐的边界、次数上下限
+        VectorCase::new("times_aligned_start", "123456", utc("2024-01-01T00:00:00Z"), PasswordType::Times(1)),
+        VectorCase::new("times_aligned_last_ms", "123456", utc("2024-01-01T00:02:07.999Z"), PasswordType::Times(31)),
+        VectorCase::new("times_next_aligned", "123456", utc("2024-01-01T00:02:08Z"), PasswordType::Times(5)),
+        VectorCase::new("times_lock_ahead", "87654321", utc("2024-06-15T08:30:00Z"), PasswordType::Times(10))
+            .with_time_offset(120),
+        // 限时密码：30分钟窗口边界、最短和最长时长、偏移跨越窗口
+        VectorCase::new("limited_window_start", "123456", utc("2024-01-01T00:00:00Z"), PasswordType::Limited(0, 30)),
+        VectorCase::new("limited_window_last_ms", "123456", utc("2024-01-01T00:29:59.999Z"), PasswordType::Limited(2, 0)),
+        VectorCase::new("limited_max_duration", "123456", utc("2024-01-01T00:30:00Z"), PasswordType::Limited(127, 30)),
+        VectorCase::new("limited_lock_behind_previous_window", "1234", utc("2024-06-15T08:30:00Z"), PasswordType::Limited(4, 0))
+            .with_time_offset(-120),
+        // 周期密码：本地自然日边界、月末、闰年、最大小时数、偏移跨越日期、其他时区
+        VectorCase::new("period_local_day_start", "123456", utc("2023-12-31T16:00:00Z"), PasswordType::Period(2024, 1, 1, 18)),
+        VectorCase::new("period_local_day_last_second", "123456", utc("2023-12-31T15:59:59Z"), PasswordType::Period(2024, 1, 1, 18)),
+        month_end_case("period_month_end_january", 2024, 1, 23),
+        month_end_case("period_month_end_april", 2024, 4, 23),
+        month_end_case("period_leap_february", 2024, 2, 12),
+        month_end_case("period_common_february", 2023, 2, 12),
+        month_end_case("period_leap_century_february", 2000, 2, 12),
+        month_end_case("period_year_end", 2024, 12, 23),
+        VectorCase::new("period_max_hours", "123456", utc("2023-12-31T16:00:00Z"), PasswordType::Period(2027, 9, 27, 8)),
+        VectorCase::new("period_lock_ahead_next_day", "123456", utc("2024-06-15T15:59:00Z"), PasswordType::Period(2024, 6, 20, 18))
+            .with_time_offset(120),
+        VectorCase::new("period_berlin_dst", "123456", utc("2024-03-30T23:30:00Z"), PasswordType::Period(2024, 4, 1, 12))
+            .with_timezone("Europe/Berlin"),
+        VectorCase::new("period_new_york", "123456", utc("2024-03-30T23:30:00Z"), PasswordType::Period(2024, 4, 1, 12))
+            .with_timezone("America/New_York"),
+    ]
+}
+
+/// 需要固定的月份天数：各类闰年规则下的二月，以及一整年的各月
+fn month_days() -> Vec<MonthDays> {
+    let february = [1900, 2000, 2023, 2024, 2100].map(|year| (year, 2));
+    let year_2024 = (1..=12).filter(|&month| month != 2).map(|month| (2024, month));
+
+    february
+        .into_iter()
+        .chain(year_2024)
+        .map(|(year, month)| MonthDays {
+            year,
+            month,
+            days: PeriodPasswordGenerator::get_month_days(year, month).unwrap(),
+        })
+        .collect()
+}
+
+/// 按当前实现生成完整的测试向量文件
+pub fn generate_vector_file() -> Result<VectorFile, String> {
+    Ok(VectorFile {
+        version: VECTOR_FILE_VERSION,
+        month_days: month_days(),
+        vectors: vector_cases().iter().map(VectorCase::generate).collect::<Result<_, _>>()?,
+    })
+}
+
+impl VectorFile {
+    /// 序列化为带缩进的JSON，以换行结尾
+    pub fn to_json(&self) -> String {
+        serde_json::to_string_pretty(self).unwrap() + "\n"
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::gen_password::PasswordInspection;
+
+    fn load_vector_file() -> VectorFile {
+        serde_json::from_str(include_str!("testdata/password_vectors.json")).unwrap()
+    }
+
+    #[test]
+    fn test_vectors_match_implementation() {
+        let file = load_vector_file();
+        assert_eq!(file.version, VECTOR_FILE_VERSION);
+
+        for expected in &file.month_days {
+            assert_eq!(
+                PeriodPasswordGenerator::get_month_days(expected.year, expected.month),
+                Ok(expected.days),
+                "{}-{:02}",
+                expected.year,
+                expected.month
+            );
+        }
+
+        for expected in &file.vectors {
+            assert_eq!(&expected.case.generate().unwrap(), expected, "测试向量 {} 与实现不一致", expected.case.name);
+        }
+
+        // 文件与用例列表同步，新增或修改用例后需要重新生成
+        assert_eq!(file, generate_vector_file().unwrap(), "测试向量文件需要重新生成");
+    }
+
+    #[test]
+    fn test_vectors_verify_and_inspect() {
+        for vector in load_vector_file().vectors {
+            let case = &vector.case;
+            let generator = case.generator();
+
+            // 密码为 5000000000 加上32位加密代码，固定10位
+            let number: u64 = vector.password.parse().unwrap();
+            assert!((5_000_000_000..=5_000_000_000 + u32::MAX as u64).contains(&number), "{}", case.name);
+            assert_eq!(vector.password.len(), 10);
+
+            assert!(generator.verify(&vector.password, &case.admin_pwd).is_some(), "{}", case.name);
+
+            let inspection = generator.inspect(&vector.password, &case.admin_pwd, case.now);
+            let PasswordInspection::Valid(result) = inspection else {
+                panic!("{} 检查结果不是有效: {:?}", case.name, inspection);
+            };
+            assert_eq!(
+                (result.password_type, result.window, result.valid_from, result.valid_until),
+                (case.password_type.clone(), vector.window, vector.valid_from, vector.valid_until),
+                "{}",
+                case.name
+            );
+        }
+    }
+
+    #[test]
+    fn test_prefix_edges_covered() {
+        let vectors = load_vector_file().vectors;
+        let temp_passwords: Vec<&str> = vectors
+            .iter()
+            .filter(|vector| vector.case.password_type == PasswordType::Temporary)
+            .map(|vector| vector.password.as_str())
+            .collect();
+
+        // 加密代码有前导零时密码以 50 开头，接近32位上限时以 9 开头
+        assert!(temp_passwords.iter().any(|password| password.starts_with("50")));
+        assert!(temp_passwords.iter().any(|password| password.starts_with('9')));
+    }
+}
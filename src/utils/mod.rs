@@ -1,13 +1,25 @@
 //! 工具模块
 
 pub mod gen_password;
+pub mod qr;
 
 // 重新导出gen_password的主要功能
 pub use gen_password::{
+    Clock,
+    LockTimezone,
+    ManualClock,
+    SharedClock,
+    SystemClock,
     UnifiedPasswordGenerator,
     PasswordType,
     PasswordResult,
+    PasswordInspection,
+    CalibrationSample,
+    OffsetCandidate,
+    MAX_CALIBRATION_DRIFT_SECS,
+    search_offsets,
     generate_password,
     verify_password,
     get_password_remaining_time,
-};
\ No newline at end of file
+};
+pub use qr::{render_qr_png, render_qr_png_scaled};
@@ -0,0 +1,94 @@
+//! 二维码模块 - 在本地将文本（邀请链接、密码等）渲染为PNG二维码图片
+
+use crate::error::{AppError, Result};
+use qrcode::{Color, QrCode};
+
+/// 每个二维码模块默认占用的像素数
+pub const DEFAULT_MODULE_PIXELS: u32 = 8;
+
+/// 二维码四周的静区宽度（模块数），规范要求至少4个模块
+const QUIET_ZONE_MODULES: u32 = 4;
+
+/// 将文本渲染为PNG二维码，使用默认模块大小
+pub fn render_qr_png(data: &str) -> Result<Vec<u8>> {
+    render_qr_png_scaled(data, DEFAULT_MODULE_PIXELS)
+}
+
+/// 将文本渲染为PNG二维码
+///
+/// 输出8位灰度图，黑色模块为0、白色背景为255，四周保留静区方便扫码。
+pub fn render_qr_png_scaled(data: &str, module_pixels: u32) -> Result<Vec<u8>> {
+    if module_pixels == 0 {
+        return Err(AppError::validation("二维码模块像素数必须大于0"));
+    }
+
+    let code = QrCode::new(data.as_bytes())
+        .map_err(|e| AppError::Other(format!("二维码编码失败: {}", e)))?;
+    let modules = code.width() as u32;
+    let colors = code.to_colors();
+
+    let size = (modules + QUIET_ZONE_MODULES * 2) * module_pixels;
+    let mut pixels = vec![255u8; (size * size) as usize];
+
+    for (index, color) in colors.iter().enumerate() {
+        if *color != Color::Dark {
+            continue;
+        }
+
+        let x0 = (index as u32 % modules + QUIET_ZONE_MODULES) * module_pixels;
+        let y0 = (index as u32 / modules + QUIET_ZONE_MODULES) * module_pixels;
+        for y in y0..y0 + module_pixels {
+            let row = (y * size) as usize;
+            pixels[row + x0 as usize..row + (x0 + module_pixels) as usize].fill(0);
+        }
+    }
+
+    let mut png_data = Vec::new();
+    {
+        let mut encoder = png::Encoder::new(&mut png_data, size, size);
+        encoder.set_color(png::ColorType::Grayscale);
+        encoder.set_depth(png::BitDepth::Eight);
+
+        let mut writer = encoder
+            .write_header()
+            .map_err(|e| AppError::Other(format!("二维码图片编码失败: {}", e)))?;
+        writer
+            .write_image_data(&pixels)
+            .map_err(|e| AppError::Other(format!("二维码图片编码失败: {}", e)))?;
+    }
+
+    Ok(png_data)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_render_qr_png() {
+        let data = "https://t.me/nekohouse_bot?start=Ag4hX2zLQb6Gd4nEM5xYvA";
+        let png_data = render_qr_png_scaled(data, 4).unwrap();
+
+        let decoder = png::Decoder::new(png_data.as_slice());
+        let mut reader = decoder.read_info().unwrap();
+        let mut pixels = vec![0; reader.output_buffer_size()];
+        let info = reader.next_frame(&mut pixels).unwrap();
+
+        let modules = QrCode::new(data.as_bytes()).unwrap().width() as u32;
+        let size = (modules + QUIET_ZONE_MODULES * 2) * 4;
+        assert_eq!((info.width, info.height), (size, size));
+        assert_eq!(info.color_type, png::ColorType::Grayscale);
+
+        // 静区为白色，紧随其后是定位图案的黑色边框
+        let quiet = (QUIET_ZONE_MODULES * 4) as usize;
+        assert_eq!(pixels[0], 255);
+        assert_eq!(pixels[quiet * size as usize + quiet - 1], 255);
+        assert_eq!(pixels[quiet * size as usize + quiet], 0);
+    }
+
+    #[test]
+    fn test_render_qr_png_rejects_zero_scale() {
+        assert!(render_qr_png_scaled("nekohouse", 0).is_err());
+        assert!(render_qr_png("nekohouse").is_ok());
+    }
+}